@@ -3,7 +3,10 @@
 #define VERTICES_PER_NODE 2
 
 layout (location = 0) in vec2 position;
+layout (location = 1) in float width;
+
 layout (location = 0) out int node_id;
+layout (location = 1) out float node_width;
 
 layout (push_constant) uniform NodePC {
   mat4 view_transform;
@@ -17,6 +20,7 @@ void main() {
 
   int id = 1 + (gl_VertexIndex / VERTICES_PER_NODE);
   node_id = id;
+  node_width = width;
 
   gl_Position = vec4(position.xy, 0.0, 1.0);
 
@@ -0,0 +1,28 @@
+// bits of the per-node flags in the selection buffer, see
+// `NodeFlag` in src/app/selection.rs
+#define NODE_FLAG_SELECTED 1
+#define NODE_FLAG_HIGHLIGHT 2
+
+// how nodes outside the selection are drawn, see `NodeFocusMode` in
+// src/vulkan/draw_system/nodes.rs
+#define FOCUS_MODE_NORMAL 0
+#define FOCUS_MODE_DIM 1
+#define FOCUS_MODE_HIDE 2
+
+// only the color is changed, so hidden nodes can still be picked
+vec4 unfocused_color(vec4 color, uint focus_mode) {
+  if (focus_mode == FOCUS_MODE_DIM) {
+    return vec4(color.rgb, color.a * 0.15);
+  } else if (focus_mode == FOCUS_MODE_HIDE) {
+    return vec4(color.rgb, 0.0);
+  }
+  return color;
+}
+
+// brightens dark colors and darkens light ones, so the highlighted
+// node stands out whatever its color
+vec4 highlight_color(vec4 color) {
+  float luminance = dot(color.rgb, vec3(0.2126, 0.7152, 0.0722));
+  vec3 target = luminance > 0.5 ? vec3(0.0) : vec3(1.0);
+  return vec4(mix(color.rgb, target, 0.4), color.a);
+}
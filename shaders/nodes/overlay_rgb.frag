@@ -1,5 +1,7 @@
 #version 450
 
+#include "node_flags.glsl"
+
 layout (early_fragment_tests) in;
 
 flat layout (location = 0) in int node_id;
@@ -20,21 +22,39 @@ layout (push_constant) uniform NodePC {
   float scale;
   vec2 viewport_dims;
   uint texture_period;
+  float min_node_px;
+  uint focus_mode;
+  uint id_offset;
+  vec4 tint;
 } node_uniform;
 
 void main() {
 
   uint is_selected = selection.flag[node_id - 1];
 
-  f_id = uint(node_id);
+  f_id = uint(node_id) + node_uniform.id_offset;
 
-  if ((is_selected & 1) == 1) {
+  if ((is_selected & NODE_FLAG_SELECTED) != 0) {
     f_mask = vec4(1.0, 1.0, 1.0, 1.0);
   } else {
     f_mask = vec4(0.0, 0.0, 0.0, 0.0);
   }
 
 
-  int color_u = node_id - 1;
-  f_color = texelFetch(overlay, color_u);
+  // graph layers are drawn with a tint, as their nodes aren't in
+  // the overlays
+  if (node_uniform.tint.a > 0.0) {
+    f_color = node_uniform.tint;
+  } else {
+    int color_u = node_id - 1;
+    f_color = texelFetch(overlay, color_u);
+  }
+
+  if ((is_selected & NODE_FLAG_SELECTED) == 0) {
+    f_color = unfocused_color(f_color, node_uniform.focus_mode);
+  }
+
+  if ((is_selected & NODE_FLAG_HIGHLIGHT) != 0) {
+    f_color = highlight_color(f_color);
+  }
 }
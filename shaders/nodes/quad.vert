@@ -3,6 +3,8 @@
 #define VERTICES_PER_NODE 6
 
 layout (location = 0) in vec2 position;
+layout (location = 1) in float width;
+
 layout (location = 0) out int node_id;
 
 layout (push_constant) uniform NodePC {
@@ -11,6 +13,7 @@ layout (push_constant) uniform NodePC {
   float scale;
   vec2 viewport_dims;
   uint texture_period;
+  float min_node_px;
 } node_uniform;
 
 void main() {
@@ -29,7 +32,10 @@ void main() {
   // 4 -> bottom right
   // 5 -> top right
 
-  float del = 0.01;
+  float del = 0.01 * width;
+
+  del = max(del, node_uniform.min_node_px /
+            max(node_uniform.viewport_dims.x, node_uniform.viewport_dims.y));
 
   vec2 offset;
 
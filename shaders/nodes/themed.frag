@@ -1,5 +1,7 @@
 #version 450
 
+#include "node_flags.glsl"
+
 layout (early_fragment_tests) in;
 
 flat layout (location = 0) in int node_id;
@@ -24,14 +26,18 @@ layout (push_constant) uniform NodePC {
   float scale;
   vec2 viewport_dims;
   uint texture_period;
+  float min_node_px;
+  uint focus_mode;
+  uint id_offset;
+  vec4 tint;
 } node_uniform;
 
 void main() {
   uint is_selected = selection.flag[node_id - 1];
 
-  f_id = uint(node_id);
+  f_id = uint(node_id) + node_uniform.id_offset;
 
-  if ((is_selected & 1) == 1) {
+  if ((is_selected & NODE_FLAG_SELECTED) != 0) {
     f_mask = vec4(1.0, 1.0, 1.0, 1.0);
   } else {
     f_mask = vec4(0.0, 0.0, 0.0, 0.0);
@@ -39,4 +45,12 @@ void main() {
 
   float color_u = float((node_id - 1) % node_uniform.texture_period) / node_uniform.texture_period;
   f_color = texture(theme_sampler, color_u);
+
+  if ((is_selected & NODE_FLAG_SELECTED) == 0) {
+    f_color = unfocused_color(f_color, node_uniform.focus_mode);
+  }
+
+  if ((is_selected & NODE_FLAG_HIGHLIGHT) != 0) {
+    f_color = highlight_color(f_color);
+  }
 }
@@ -8,12 +8,17 @@ layout (push_constant) uniform Dims {
   float width;
   float height;
   bool enabled;
+  vec4 outline_color;
+  float blur_radius;
+  bool vertical;
 } dims;
 
 vec2 uv_coord(vec2 coord) {
   return (coord / vec2(dims.width, dims.height));
 }
 
+#define MAX_RADIUS 16
+
 void main() {
 
   vec2 uv = gl_FragCoord.xy / vec2(dims.width, dims.height);
@@ -21,45 +26,40 @@ void main() {
 
   vec4 color = texture(u_color_sampler, uv);
 
-  float row0[3];
-  row0[0] = 0.077847;
-  row0[1] = 0.123317;
-  row0[2] = 0.077847;
-
-  float row1[3];
-  row1[0] = 0.123317;
-  row1[1] = 0.195346;
-  row1[2] = 0.123317;
-
-  float row2[3];
-  row2[0] = 0.077847;
-  row2[1] = 0.123317;
-  row2[2] = 0.077847;
+  if (dims.enabled) {
+    // one dimension of a separable gaussian kernel with sigma equal
+    // to the radius; the blur is drawn in a horizontal and then a
+    // vertical pass, which is the same as the 2D kernel but only
+    // needs 2 * (2r + 1) samples per pixel instead of (2r + 1)^2.
+    // the radius is scaled by the DPI scale factor, so it can be
+    // fractional; the kernel covers every pixel within it, and sigma
+    // keeps the fraction
+    float sigma = clamp(dims.blur_radius, 1.0, float(MAX_RADIUS));
+    int radius = int(ceil(sigma));
 
+    vec2 dir = dims.vertical ? vec2(0.0, 1.0) : vec2(1.0, 0.0);
 
-  if (dims.enabled) {
-    vec3 result = texture(u_color_sampler, uv).rgb * row1[1];
+    vec4 result = vec4(0.0);
+    float weight_sum = 0.0;
 
-    result += texture(u_color_sampler, uv_coord(fc.xy + vec2(-1.0, -1.0))).rgb * row0[0];
-    result += texture(u_color_sampler, uv_coord(fc.xy + vec2(-1.0, 0.0))).rgb * row0[1];
-    result += texture(u_color_sampler, uv_coord(fc.xy + vec2(-1.0, 1.0))).rgb * row0[2];
+    for (int i = -MAX_RADIUS; i <= MAX_RADIUS; i++) {
+      if (abs(i) > radius) {
+        continue;
+      }
 
-    result += texture(u_color_sampler, uv_coord(fc.xy + vec2(0.0, -1.0))).rgb * row1[0];
+      float x = float(i);
+      float weight = exp(-(x * x) / (2.0 * sigma * sigma));
 
-    result += texture(u_color_sampler, uv_coord(fc.xy + vec2(0.0, 1.0))).rgb * row1[2];
+      result += texture(u_color_sampler, uv_coord(fc.xy + dir * x)) * weight;
+      weight_sum += weight;
+    }
 
-    result += texture(u_color_sampler, uv_coord(fc.xy + vec2(1.0, -1.0))).rgb * row2[0];
-    result += texture(u_color_sampler, uv_coord(fc.xy + vec2(1.0, 0.0))).rgb * row2[1];
-    result += texture(u_color_sampler, uv_coord(fc.xy + vec2(1.0, 1.0))).rgb * row2[2];
+    result /= weight_sum;
 
-    f_color = vec4(result, color.a);
-    // f_color = vec4(1.0, 1.0, 1.0, 1.0);
+    // the blurred alpha lets wider kernels thicken the outline
+    f_color = vec4(result.rgb, max(color.a, result.a));
   } else {
-    // vec3 result = texture(u_color_sampler, uv).rgb;
     f_color = color;
-    // f_color = vec4(result, color.a);
-    // f_color = vec4(0.0, 0.0, 0.0, 1.0);
-    // f_color = vec4(1.0, 1.0, 1.0, 1.0);
   }
 
 }
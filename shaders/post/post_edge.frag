@@ -8,6 +8,9 @@ layout (push_constant) uniform Dims {
   float width;
   float height;
   bool enabled;
+  vec4 outline_color;
+  float blur_radius;
+  bool vertical;
 } dims;
 
 vec2 uv_coord(vec2 coord) {
@@ -84,7 +87,9 @@ void main() {
 
     float result = max(hor, ver);
 
-    f_color = vec4(result, result, result, result);
+    vec4 outline = dims.outline_color;
+
+    f_color = vec4(outline.rgb * result, result * outline.a);
 
   } else {
     f_color = color;
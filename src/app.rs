@@ -1,26 +1,33 @@
 pub mod channels;
+pub mod layers;
 pub mod mainview;
+pub mod path_colors;
 pub mod selection;
 pub mod settings;
 pub mod shared_state;
+pub mod theme;
 
 pub use channels::*;
-use handlegraph::pathhandlegraph::PathId;
+use handlegraph::pathhandlegraph::{
+    GraphPathsSteps, IntoPathIds, PathId, PathStep,
+};
 pub use settings::*;
 pub use shared_state::*;
+pub use theme::*;
 
 use crossbeam::channel::Sender;
 
 use rustc_hash::{FxHashMap, FxHashSet};
 
-use handlegraph::handle::NodeId;
+use handlegraph::handle::{Direction, Handle, NodeId};
+use handlegraph::handlegraph::IntoNeighbors;
 
 use anyhow::Result;
 
 use argh::FromArgs;
 
 use std::any::TypeId;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -52,19 +59,36 @@ pub struct App {
     selected_nodes: FxHashSet<NodeId>,
     selection_changed: bool,
 
+    selection_undo: VecDeque<FxHashSet<NodeId>>,
+    selection_redo: Vec<FxHashSet<NodeId>>,
+
     pub selected_nodes_bounding_box: Option<(Point, Point)>,
 
     pub annotations: Annotations,
 
     pub labels: Labels,
 
+    reload_requested: bool,
+    command_palette_requested: bool,
+    svg_export_requested: bool,
+
     msg_handlers: HashMap<String, Arc<AppMsgHandler>>,
+
+    system_theme: SystemTheme,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum AppInput {
     KeyClearSelection,
     KeyToggleTheme,
+    KeyZoomToSelection,
+    KeyUndoSelection,
+    KeyRedoSelection,
+    KeyReloadGraph,
+    KeyCommandPalette,
+    KeyToggleOverlay,
+    KeyNextOverlay,
+    KeyPrevOverlay,
 }
 
 impl BindableInput for AppInput {
@@ -72,21 +96,66 @@ impl BindableInput for AppInput {
         use winit::event::VirtualKeyCode as Key;
         use AppInput as Input;
 
-        let key_binds: FxHashMap<Key, Vec<KeyBind<Input>>> = [
+        let mut key_binds: FxHashMap<Key, Vec<KeyBind<Input>>> = [
             (Key::Escape, Input::KeyClearSelection),
             (Key::F9, Input::KeyToggleTheme),
+            (Key::F, Input::KeyZoomToSelection),
+            (Key::O, Input::KeyToggleOverlay),
+            (Key::RBracket, Input::KeyNextOverlay),
+            (Key::LBracket, Input::KeyPrevOverlay),
         ]
         .iter()
         .copied()
         .map(|(k, i)| (k, vec![KeyBind::new(i)]))
         .collect::<FxHashMap<_, _>>();
 
+        let ctrl_mod = winit::event::ModifiersState::CTRL;
+        let ctrl_shift_mod = ctrl_mod | winit::event::ModifiersState::SHIFT;
+
+        key_binds.insert(
+            Key::Z,
+            vec![
+                KeyBind::with_modifiers(Input::KeyUndoSelection, ctrl_mod),
+                KeyBind::with_modifiers(
+                    Input::KeyRedoSelection,
+                    ctrl_shift_mod,
+                ),
+            ],
+        );
+
+        key_binds.insert(
+            Key::R,
+            vec![KeyBind::with_modifiers(Input::KeyReloadGraph, ctrl_mod)],
+        );
+
+        key_binds.insert(
+            Key::P,
+            vec![KeyBind::with_modifiers(Input::KeyCommandPalette, ctrl_mod)],
+        );
+
         let mouse_binds = FxHashMap::default();
 
         let wheel_bind = None;
 
         SystemInputBindings::new(key_binds, mouse_binds, wheel_bind)
     }
+
+    fn bindable_actions() -> Vec<(&'static str, Self)> {
+        use AppInput as Input;
+
+        vec![
+            ("clear_selection", Input::KeyClearSelection),
+            ("toggle_theme", Input::KeyToggleTheme),
+            ("zoom_to_selection", Input::KeyZoomToSelection),
+            ("undo_selection", Input::KeyUndoSelection),
+            ("redo_selection", Input::KeyRedoSelection),
+            ("reload_graph", Input::KeyReloadGraph),
+            ("command_palette", Input::KeyCommandPalette),
+            ("toggle_overlay", Input::KeyToggleOverlay),
+            ("next_overlay", Input::KeyNextOverlay),
+            ("prev_overlay", Input::KeyPrevOverlay),
+        ]
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -118,6 +187,34 @@ pub enum AppMsg {
 
     RequestSelection(crossbeam::channel::Sender<(Rect, FxHashSet<NodeId>)>),
 
+    /// Frame the view on the bounding box of the selected nodes, if
+    /// any are selected
+    ZoomToSelection,
+
+    /// Select every node that is not currently selected, and
+    /// deselect the rest
+    InvertSelection,
+
+    /// Add every node adjacent to a selected node to the selection
+    GrowSelection,
+
+    /// Restore the selection from before the latest change
+    UndoSelection,
+
+    /// Reapply the latest undone selection change
+    RedoSelection,
+
+    /// Reload the GFA and layout from disk, resetting overlays and
+    /// the selection
+    ReloadGraph,
+
+    /// Open the searchable list of context actions and commands
+    OpenCommandPalette,
+
+    /// Write the nodes in the current view to an SVG file in the
+    /// working directory
+    ExportSvg,
+
     SetData {
         key: String,
         index: String,
@@ -173,6 +270,16 @@ impl AppMsg {
         Self::raw("goto_rect", Some(rect))
     }
 
+    /// Frame the view on the bounding box of the given nodes
+    pub fn goto_nodes(ids: Vec<NodeId>) -> Self {
+        Self::raw("goto_nodes", ids)
+    }
+
+    /// Frame the view on the bounding box of the nodes on a path
+    pub fn goto_path(path: PathId) -> Self {
+        Self::raw("goto_path", path)
+    }
+
     pub fn goto_selection() -> Self {
         Self::raw::<Option<Rect>>("goto_rect", None)
     }
@@ -227,7 +334,10 @@ impl App {
         graph_query: Arc<GraphQuery>,
         layout_boundary: Rect,
     ) -> Result<Self> {
-        let shared_state = SharedState::new(screen_dims);
+        let path_colors =
+            path_colors::PathColors::new(graph_query.graph().path_ids());
+
+        let shared_state = SharedState::new(screen_dims, path_colors);
 
         let channels = AppChannels::new();
 
@@ -252,15 +362,24 @@ impl App {
             selected_nodes: FxHashSet::default(),
             selection_changed: false,
 
+            selection_undo: VecDeque::new(),
+            selection_redo: Vec::new(),
+
             selected_nodes_bounding_box: None,
 
-            settings: AppSettings::default(),
+            settings: AppSettings::load(),
 
             annotations: Annotations::default(),
 
             labels: Labels::default(),
 
+            reload_requested: false,
+            command_palette_requested: false,
+            svg_export_requested: false,
+
             msg_handlers,
+
+            system_theme: SystemTheme::watch(),
         })
     }
 
@@ -316,6 +435,22 @@ impl App {
         self.selection_changed
     }
 
+    /// Returns true once after a graph reload has been requested
+    pub fn take_reload_request(&mut self) -> bool {
+        std::mem::take(&mut self.reload_requested)
+    }
+
+    /// Returns true once after the command palette has been requested
+    pub fn take_command_palette_request(&mut self) -> bool {
+        std::mem::take(&mut self.command_palette_requested)
+    }
+
+    /// Returns true once after an SVG export of the view has been
+    /// requested
+    pub fn take_svg_export_request(&mut self) -> bool {
+        std::mem::take(&mut self.svg_export_requested)
+    }
+
     pub fn selected_nodes_(&self) -> Option<(Rect, &FxHashSet<NodeId>)> {
         log::warn!(
             "self.selected_nodes.is_empty() = {}",
@@ -333,6 +468,12 @@ impl App {
         }
     }
 
+    /// A copy of the current node selection, empty if nothing is
+    /// selected
+    pub fn selected_nodes_snapshot(&self) -> FxHashSet<NodeId> {
+        self.selected_nodes.clone()
+    }
+
     // not even sure where selection_changed is used anymore, if at all
     pub fn selected_nodes(&mut self) -> Option<&FxHashSet<NodeId>> {
         if self.selected_nodes.is_empty() {
@@ -412,15 +553,34 @@ impl App {
                     .or_else(|| Some(app.selected_nodes_bounding_box?.into()));
 
                 if let Some(rect) = bounds {
-                    let view = View::from_dims_and_target(
-                        app.dims(),
-                        rect.min(),
-                        rect.max(),
-                    );
-                    app.channels
-                        .main_view_tx
-                        .send(MainViewMsg::GotoView(view))
-                        .unwrap();
+                    app.goto_rect(rect);
+                }
+            }),
+        );
+
+        new_handler(
+            "goto_nodes",
+            AppMsgHandler::from_fn(|app, nodes, ids: &Vec<NodeId>| {
+                if let Some(rect) = nodes_bounding_box(nodes, ids) {
+                    app.goto_rect(rect);
+                }
+            }),
+        );
+
+        new_handler(
+            "goto_path",
+            AppMsgHandler::from_fn(|app, nodes, path: &PathId| {
+                let graph = app.reactor.graph_query.graph();
+
+                let ids = match graph.path_steps(*path) {
+                    Some(steps) => steps
+                        .map(|step| step.handle().id())
+                        .collect::<FxHashSet<_>>(),
+                    None => return,
+                };
+
+                if let Some(rect) = nodes_bounding_box(nodes, &ids) {
+                    app.goto_rect(rect);
                 }
             }),
         );
@@ -558,6 +718,37 @@ impl App {
             }));
     }
 
+    /// Store the current selection so that the change about to be
+    /// made can be undone; any undone changes can no longer be redone
+    fn push_selection_history(&mut self) {
+        self.selection_redo.clear();
+
+        if self.selection_undo.back() != Some(&self.selected_nodes) {
+            self.push_undo_entry(self.selected_nodes.clone());
+        }
+    }
+
+    fn push_undo_entry(&mut self, selection: FxHashSet<NodeId>) {
+        if self.selection_undo.len() >= SELECTION_HISTORY_LEN {
+            self.selection_undo.pop_front();
+        }
+        self.selection_undo.push_back(selection);
+    }
+
+    /// Replace the selected nodes, leaving it to the main loop to
+    /// update the selection buffer with the difference
+    fn replace_selection(
+        &mut self,
+        node_positions: &[Node],
+        selection: FxHashSet<NodeId>,
+    ) {
+        self.selected_nodes_bounding_box =
+            nodes_bounding_box(node_positions, &selection)
+                .map(|rect| (rect.min(), rect.max()));
+        self.selected_nodes = selection;
+        self.selection_changed = true;
+    }
+
     pub fn apply_app_msg(
         &mut self,
         console_input_tx: &Sender<String>,
@@ -578,11 +769,13 @@ impl App {
             }
             AppMsg::Selection(sel) => match sel {
                 Select::Clear => {
+                    self.push_selection_history();
                     self.selection_changed = true;
                     self.selected_nodes.clear();
                     self.selected_nodes_bounding_box = None;
                 }
                 Select::One { node, clear } => {
+                    self.push_selection_history();
                     self.selection_changed = true;
                     if clear {
                         self.selected_nodes.clear();
@@ -631,6 +824,7 @@ impl App {
                     }
                 }
                 Select::Many { nodes, clear } => {
+                    self.push_selection_history();
                     self.selection_changed = true;
                     if clear {
                         self.selected_nodes.clear();
@@ -713,6 +907,74 @@ impl App {
                 sender.send((rect, selection)).unwrap();
             }
 
+            AppMsg::ZoomToSelection => {
+                if let Some(rect) =
+                    nodes_bounding_box(node_positions, &self.selected_nodes)
+                {
+                    self.goto_rect(rect);
+                }
+            }
+
+            AppMsg::InvertSelection => {
+                let node_count = node_positions.len() as u64;
+
+                let inverted = (1..=node_count)
+                    .map(NodeId::from)
+                    .filter(|node| !self.selected_nodes.contains(node))
+                    .collect::<FxHashSet<_>>();
+
+                self.push_selection_history();
+                self.replace_selection(node_positions, inverted);
+            }
+
+            AppMsg::GrowSelection => {
+                let graph = self.reactor.graph_query.graph_arc().clone();
+
+                let mut grown = self.selected_nodes.clone();
+
+                for &node in self.selected_nodes.iter() {
+                    let handle = Handle::pack(node, false);
+
+                    grown.extend(
+                        graph
+                            .neighbors(handle, Direction::Left)
+                            .chain(graph.neighbors(handle, Direction::Right))
+                            .map(|h| h.id()),
+                    );
+                }
+
+                self.push_selection_history();
+                self.replace_selection(node_positions, grown);
+            }
+
+            AppMsg::UndoSelection => {
+                if let Some(prev) = self.selection_undo.pop_back() {
+                    let current = std::mem::take(&mut self.selected_nodes);
+                    self.selection_redo.push(current);
+                    self.replace_selection(node_positions, prev);
+                }
+            }
+
+            AppMsg::RedoSelection => {
+                if let Some(next) = self.selection_redo.pop() {
+                    let current = std::mem::take(&mut self.selected_nodes);
+                    self.push_undo_entry(current);
+                    self.replace_selection(node_positions, next);
+                }
+            }
+
+            AppMsg::ReloadGraph => {
+                self.reload_requested = true;
+            }
+
+            AppMsg::OpenCommandPalette => {
+                self.command_palette_requested = true;
+            }
+
+            AppMsg::ExportSvg => {
+                self.svg_export_requested = true;
+            }
+
             AppMsg::SetData { key, index, value } => {
                 self.send_msg(AppMsg::set_data(key, index, value)).unwrap();
             }
@@ -747,18 +1009,51 @@ impl App {
         }
     }
 
+    /// Toggling the mode manually stops following the system theme
     fn toggle_dark_mode(&self) {
-        let prev = self.shared_state.dark_mode.fetch_xor(true);
+        self.settings.follow_system_theme().store(false);
 
-        let msg = if prev {
-            GuiMsg::SetLightMode
-        } else {
+        let dark = !self.shared_state.dark_mode.load();
+        self.set_dark_mode(dark);
+    }
+
+    fn set_dark_mode(&self, dark: bool) {
+        self.shared_state.dark_mode.store(dark);
+
+        let msg = if dark {
             GuiMsg::SetDarkMode
+        } else {
+            GuiMsg::SetLightMode
         };
 
         self.channels.gui_tx.send(msg).unwrap();
     }
 
+    /// Switch to the mode of the system theme if it has changed, and
+    /// the app is set to follow it
+    pub fn update_system_theme(&self) {
+        if !self.settings.follow_system_theme().load() {
+            return;
+        }
+
+        let dark = self.system_theme.is_dark();
+
+        if dark != self.shared_state.dark_mode.load() {
+            log::debug!("Following system theme, dark mode: {}", dark);
+            self.set_dark_mode(dark);
+        }
+    }
+
+    /// Animate the view to frame `rect`
+    fn goto_rect(&self, rect: Rect) {
+        let view =
+            View::from_dims_and_target(self.dims(), rect.min(), rect.max());
+        self.channels
+            .main_view_tx
+            .send(MainViewMsg::GotoView(view))
+            .unwrap();
+    }
+
     pub fn apply_input(
         &mut self,
         input: SystemInput<AppInput>,
@@ -768,6 +1063,7 @@ impl App {
             match payload {
                 AppInput::KeyClearSelection => {
                     if state.pressed() {
+                        self.push_selection_history();
                         self.selection_changed = true;
                         self.selected_nodes.clear();
                         self.selected_nodes_bounding_box = None;
@@ -778,11 +1074,78 @@ impl App {
                         self.toggle_dark_mode();
                     }
                 }
+                AppInput::KeyZoomToSelection => {
+                    if state.pressed() {
+                        self.channels
+                            .app_tx
+                            .send(AppMsg::ZoomToSelection)
+                            .unwrap();
+                    }
+                }
+                AppInput::KeyUndoSelection => {
+                    if state.pressed() {
+                        self.channels
+                            .app_tx
+                            .send(AppMsg::UndoSelection)
+                            .unwrap();
+                    }
+                }
+                AppInput::KeyRedoSelection => {
+                    if state.pressed() {
+                        self.channels
+                            .app_tx
+                            .send(AppMsg::RedoSelection)
+                            .unwrap();
+                    }
+                }
+                AppInput::KeyReloadGraph => {
+                    if state.pressed() {
+                        self.channels.app_tx.send(AppMsg::ReloadGraph).unwrap();
+                    }
+                }
+                AppInput::KeyCommandPalette => {
+                    if state.pressed() {
+                        self.channels
+                            .app_tx
+                            .send(AppMsg::OpenCommandPalette)
+                            .unwrap();
+                    }
+                }
+                AppInput::KeyToggleOverlay => {
+                    if state.pressed() {
+                        self.shared_state.overlay_state.toggle_overlay();
+                    }
+                }
+                AppInput::KeyNextOverlay | AppInput::KeyPrevOverlay => {
+                    if state.pressed() {
+                        let forward =
+                            matches!(payload, AppInput::KeyNextOverlay);
+                        gui_msg.send(GuiMsg::CycleOverlay { forward }).unwrap();
+                    }
+                }
             }
         }
     }
 }
 
+/// The maximum number of selections kept for undo; large selections
+/// can take a lot of memory, so this is kept small
+const SELECTION_HISTORY_LEN: usize = 32;
+
+/// The bounding box of the given nodes, or `None` if none of them
+/// have a position in `nodes`
+fn nodes_bounding_box<'a, I>(nodes: &[Node], ids: I) -> Option<Rect>
+where
+    I: IntoIterator<Item = &'a NodeId>,
+{
+    ids.into_iter()
+        .filter_map(|id| nodes.get((id.0 as usize).checked_sub(1)?))
+        .fold(None, |acc: Option<Rect>, node| {
+            let rect = Rect::new(node.p0, node.p1);
+            Some(acc.map_or(rect, |acc| acc.union(rect)))
+        })
+}
+
 type RefAny<'a> = &'a (dyn std::any::Any + Send + Sync);
 type ArcedAny = Arc<dyn std::any::Any + Send + Sync>;
 type BoxedAny = Box<dyn std::any::Any + Send + Sync>;
@@ -831,9 +1194,25 @@ pub struct Args {
     #[argh(positional)]
     pub gfa: String,
 
-    /// the layout file to use
+    /// the layout file to use; if omitted, a simple layout is generated and saved as GFA.layout
     #[argh(positional)]
-    pub layout: String,
+    pub layout: Option<String>,
+
+    /// optional file mapping layout node IDs to GFA node IDs, one pair per line, for layouts where the nodes were renumbered
+    #[argh(positional)]
+    pub node_map: Option<String>,
+
+    /// don't load or save the binary layout cache, LAYOUT.bin, which is used instead of the text layout if it's newer
+    #[argh(switch)]
+    pub no_layout_cache: bool,
+
+    /// save each created overlay to the cache directory, and restore the saved overlays when the same GFA is loaded with this flag
+    #[argh(switch)]
+    pub save_overlays: bool,
+
+    /// don't keep the node sequences in memory, but read them from the GFA file when needed; only for uncompressed GFA1; scripts and path positions see each node as a single placeholder base
+    #[argh(switch)]
+    pub lazy_sequences: bool,
 
     /// load and run a Rhai script file at startup, e.g. for configuration
     #[argh(option)]
@@ -856,6 +1235,10 @@ pub struct Args {
     #[argh(switch)]
     pub trace: bool,
 
+    /// enable the Vulkan validation layers, also enabled by setting GFAESTUS_VALIDATION=1
+    #[argh(switch)]
+    pub validation: bool,
+
     /*
     /// whether or not to log to a file in the working directory
     #[argh(switch)]
@@ -865,6 +1248,10 @@ pub struct Args {
     #[argh(option)]
     pub force_graphics_device: Option<String>,
 
+    /// the index of the graphics device to use, as listed in the debug log; overrides GFAESTUS_GPU
+    #[argh(option)]
+    pub gpu: Option<usize>,
+
     /// path .gff3 and/or .bed file to load at startup, can be used multiple times to load several files
     #[argh(
         option,
@@ -872,6 +1259,69 @@ pub struct Args {
         from_str_fn(annotation_files_to_str)
     )]
     pub annotation_files: Vec<std::path::PathBuf>,
+
+    /// the number of worker threads to use for layout and overlay computation (default: number of CPUs minus one)
+    #[argh(option)]
+    pub threads: Option<usize>,
+
+    /// render a single frame to the given PNG file and exit, without showing a window
+    #[argh(option)]
+    pub screenshot: Option<std::path::PathBuf>,
+
+    /// record a camera fly-through between the keyframes in the given JSON file, saving each frame as a PNG, and exit
+    #[argh(option)]
+    pub fly_through: Option<std::path::PathBuf>,
+
+    /// the directory to save the --fly-through frames in (default: flythrough)
+    #[argh(option)]
+    pub fly_through_dir: Option<std::path::PathBuf>,
+
+    /// the resolution to use for --screenshot and --fly-through, as WIDTHxHEIGHT (default 800x600)
+    #[argh(option, from_str_fn(parse_dims))]
+    pub size: Option<[u32; 2]>,
+
+    /// the world coordinates to center the initial view on, as X,Y
+    #[argh(option, from_str_fn(parse_point))]
+    pub view_center: Option<Point>,
+
+    /// the scale of the initial view, in world units per pixel
+    #[argh(option)]
+    pub view_scale: Option<f32>,
+
+    /// limit the frame rate to this many frames per second while the view isn't moving, to reduce GPU and power usage
+    #[argh(option)]
+    pub idle_fps: Option<u32>,
+}
+
+fn parse_dims(input: &str) -> Result<[u32; 2], String> {
+    let mut fields = input.trim().split('x');
+
+    let mut next = || -> Option<u32> {
+        let v = fields.next()?.trim().parse::<u32>().ok()?;
+        Some(v).filter(|&v| v > 0)
+    };
+
+    match (next(), next()) {
+        (Some(width), Some(height)) => Ok([width, height]),
+        _ => Err(format!(
+            "Could not parse \"{}\" as dimensions, expected WIDTHxHEIGHT",
+            input
+        )),
+    }
+}
+
+fn parse_point(input: &str) -> Result<Point, String> {
+    let mut fields = input.trim().split(',');
+
+    let mut next = || -> Option<f32> { fields.next()?.trim().parse().ok() };
+
+    match (next(), next()) {
+        (Some(x), Some(y)) => Ok(Point::new(x, y)),
+        _ => Err(format!(
+            "Could not parse \"{}\" as a point, expected X,Y",
+            input
+        )),
+    }
 }
 
 fn annotation_files_to_str(input: &str) -> Result<std::path::PathBuf, String> {
@@ -1,9 +1,10 @@
 use crossbeam::channel::{self, Receiver, Sender};
 use winit::event::VirtualKeyCode;
 
+use crate::app::layers::LayerMsg;
 use crate::app::mainview::MainViewMsg;
 use crate::app::AppMsg;
-use crate::gui::GuiMsg;
+use crate::gui::{toasts::Toast, GuiMsg};
 use crate::overlays::OverlayData;
 
 pub type BindMsg = (
@@ -12,7 +13,39 @@ pub type BindMsg = (
 );
 
 pub enum OverlayCreatorMsg {
-    NewOverlay { name: String, data: OverlayData },
+    NewOverlay {
+        name: String,
+        data: OverlayData,
+    },
+    /// Replace the data of the overlay with the same name and kind,
+    /// or create a new overlay if there is none
+    UpdateOverlay {
+        name: String,
+        data: OverlayData,
+    },
+}
+
+impl OverlayCreatorMsg {
+    pub fn name(&self) -> &str {
+        match self {
+            OverlayCreatorMsg::NewOverlay { name, .. } => name,
+            OverlayCreatorMsg::UpdateOverlay { name, .. } => name,
+        }
+    }
+
+    pub fn data(&self) -> &OverlayData {
+        match self {
+            OverlayCreatorMsg::NewOverlay { data, .. } => data,
+            OverlayCreatorMsg::UpdateOverlay { data, .. } => data,
+        }
+    }
+
+    pub fn into_data(self) -> OverlayData {
+        match self {
+            OverlayCreatorMsg::NewOverlay { data, .. } => data,
+            OverlayCreatorMsg::UpdateOverlay { data, .. } => data,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -31,6 +64,12 @@ pub struct AppChannels {
 
     pub modal_tx: Sender<Box<dyn Fn(&mut egui::Ui) + Send + Sync + 'static>>,
     pub modal_rx: Receiver<Box<dyn Fn(&mut egui::Ui) + Send + Sync + 'static>>,
+
+    pub toast_tx: Sender<Toast>,
+    pub toast_rx: Receiver<Toast>,
+
+    pub layer_tx: Sender<LayerMsg>,
+    pub layer_rx: Receiver<LayerMsg>,
 }
 
 impl AppChannels {
@@ -44,6 +83,10 @@ impl AppChannels {
 
         let (modal_tx, modal_rx) = channel::unbounded();
 
+        let (toast_tx, toast_rx) = channel::unbounded::<Toast>();
+
+        let (layer_tx, layer_rx) = channel::unbounded::<LayerMsg>();
+
         Self {
             app_tx,
             app_rx,
@@ -59,6 +102,12 @@ impl AppChannels {
 
             modal_tx,
             modal_rx,
+
+            toast_tx,
+            toast_rx,
+
+            layer_tx,
+            layer_rx,
         }
     }
 }
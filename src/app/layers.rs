@@ -0,0 +1,425 @@
+use ash::Device;
+use crossbeam::channel::{Receiver, Sender};
+use handlegraph::handle::NodeId;
+use parking_lot::RwLock;
+use rustc_hash::FxHashSet;
+
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::app::selection::SelectionBuffer;
+use crate::geometry::Point;
+use crate::graph_query::GraphQuery;
+use crate::gui::toasts::Toast;
+use crate::universe::Universe;
+use crate::vulkan::{
+    draw_system::{
+        nodes::{LayerDraw, NodeVertices, SelectionDescriptors},
+        Vertex,
+    },
+    GfaestusVk,
+};
+
+/// The part of a graph layer shown in the GUI
+#[derive(Debug, Clone)]
+pub struct LayerInfo {
+    pub id: usize,
+    pub name: String,
+    pub node_count: usize,
+
+    /// Added to the layer's node positions, in world units
+    pub offset: Point,
+    pub tint: rgb::RGBA<f32>,
+    pub visible: bool,
+
+    pub selected_count: usize,
+}
+
+/// A node in a graph layer, identified by the layer ID and the
+/// node's ID in that layer's graph
+pub type LayerNode = (usize, NodeId);
+
+pub enum LayerMsg {
+    Loaded {
+        name: String,
+        vertices: Vec<Vertex>,
+    },
+    SetVisible {
+        layer: usize,
+        visible: bool,
+    },
+    SetOffset {
+        layer: usize,
+        offset: Point,
+    },
+    SetTint {
+        layer: usize,
+        tint: rgb::RGBA<f32>,
+    },
+    /// Draw the layer after the next one, i.e. on top of it
+    Raise {
+        layer: usize,
+    },
+    Lower {
+        layer: usize,
+    },
+    Remove {
+        layer: usize,
+    },
+    ToggleSelect {
+        layer: usize,
+        node: NodeId,
+    },
+    ClearSelection {
+        layer: usize,
+    },
+}
+
+/// Load a GFA and its layout as a new graph layer on the thread
+/// pool; the layer is added by `LayerManager::update` once loaded
+pub fn load_layer(
+    thread_pool: &futures::executor::ThreadPool,
+    layer_tx: Sender<LayerMsg>,
+    toast_tx: Sender<Toast>,
+    gfa_path: String,
+    layout_path: String,
+) {
+    thread_pool.spawn_ok(async move {
+        let result = (|| -> Result<Vec<Vertex>> {
+            let graph_query =
+                GraphQuery::load_gfa_auto(&gfa_path, None, false)?;
+            let universe = Universe::from_laid_out_graph(
+                graph_query.graph(),
+                &layout_path,
+                None,
+            )?;
+            Ok(universe.node_vertices())
+        })();
+
+        match result {
+            Ok(vertices) => {
+                let name = std::path::Path::new(&gfa_path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or(gfa_path);
+
+                let _ = layer_tx.send(LayerMsg::Loaded { name, vertices });
+            }
+            Err(err) => {
+                log::error!("Error loading layer {}: {:?}", gfa_path, err);
+                let toast = Toast::error(format!(
+                    "Error loading layer {}: {}",
+                    gfa_path, err
+                ));
+                let _ = toast_tx.send(toast);
+            }
+        }
+    });
+}
+
+struct GraphLayer {
+    info: LayerInfo,
+
+    /// The node IDs of this layer are written to the node ID buffer
+    /// offset by this much
+    id_offset: u32,
+
+    vertices: NodeVertices,
+
+    selection_buffer: SelectionBuffer,
+    selection_descriptors: SelectionDescriptors,
+}
+
+/// Graph layers drawn on top of the main graph, to compare graphs
+/// side by side or overlaid.
+///
+/// Each layer has its own node vertices and selection, and is drawn
+/// with a single tint color, in order after the main graph. The node
+/// IDs of the layers are offset past the main graph's, and each
+/// other's, in the node ID buffer, so `resolve_id` can tell which
+/// layer a node under the cursor belongs to.
+pub struct LayerManager {
+    base_node_count: usize,
+
+    layers: Vec<GraphLayer>,
+    next_id: usize,
+
+    hover: Option<LayerNode>,
+
+    infos: Arc<RwLock<Vec<LayerInfo>>>,
+    layer_rx: Receiver<LayerMsg>,
+
+    device: Device,
+}
+
+impl LayerManager {
+    const DEFAULT_TINTS: [(f32, f32, f32); 4] = [
+        (0.85, 0.37, 0.01),
+        (0.46, 0.44, 0.70),
+        (0.11, 0.62, 0.47),
+        (0.91, 0.16, 0.54),
+    ];
+
+    pub fn new(
+        device: &Device,
+        base_node_count: usize,
+        infos: Arc<RwLock<Vec<LayerInfo>>>,
+        layer_rx: Receiver<LayerMsg>,
+    ) -> Self {
+        Self {
+            base_node_count,
+
+            layers: Vec::new(),
+            next_id: 0,
+
+            hover: None,
+
+            infos,
+            layer_rx,
+
+            device: device.clone(),
+        }
+    }
+
+    pub fn base_node_count(&self) -> usize {
+        self.base_node_count
+    }
+
+    /// Whether the node ID read from the node ID buffer belongs to
+    /// the main graph
+    pub fn is_base_id(&self, id: u32) -> bool {
+        id != 0 && (id as usize) <= self.base_node_count
+    }
+
+    /// The layer and node a node ID read from the node ID buffer
+    /// belongs to, or `None` if it's in the main graph
+    pub fn resolve_id(&self, id: u32) -> Option<LayerNode> {
+        if id == 0 || self.is_base_id(id) {
+            return None;
+        }
+
+        self.layers.iter().find_map(|layer| {
+            let local = id.checked_sub(layer.id_offset)?;
+            if local >= 1 && (local as usize) <= layer.info.node_count {
+                Some((layer.info.id, NodeId::from(local as u64)))
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn hover(&self) -> Option<LayerNode> {
+        self.hover
+    }
+
+    /// Highlight the hovered layer node, removing the highlight from
+    /// the previous one
+    pub fn set_hover(&mut self, hover: Option<LayerNode>) -> Result<()> {
+        if hover == self.hover {
+            return Ok(());
+        }
+
+        for layer in self.layers.iter_mut() {
+            let node = hover
+                .filter(|(id, _)| *id == layer.info.id)
+                .map(|(_, node)| node);
+            layer.selection_buffer.set_highlight(&self.device, node)?;
+        }
+
+        self.hover = hover;
+
+        Ok(())
+    }
+
+    /// The visible layers, in draw order
+    pub fn layer_draws(&self) -> Vec<LayerDraw<'_>> {
+        self.layers
+            .iter()
+            .filter(|layer| layer.info.visible)
+            .map(|layer| LayerDraw {
+                vertices: &layer.vertices,
+                selection_set: layer.selection_descriptors.descriptor_set(),
+                offset: layer.info.offset,
+                tint: layer.info.tint,
+                id_offset: layer.id_offset,
+            })
+            .collect()
+    }
+
+    /// Apply the queued layer messages, uploading newly loaded layers
+    /// and destroying removed ones
+    pub fn update(&mut self, app: &GfaestusVk) -> Result<()> {
+        if self.layer_rx.is_empty() {
+            return Ok(());
+        }
+
+        while let Ok(msg) = self.layer_rx.try_recv() {
+            if let Err(err) = self.apply_msg(app, msg) {
+                log::error!("Error updating graph layers: {:?}", err);
+            }
+        }
+
+        self.update_id_offsets();
+        self.update_infos();
+
+        Ok(())
+    }
+
+    fn layer_mut(&mut self, id: usize) -> Option<&mut GraphLayer> {
+        self.layers.iter_mut().find(|layer| layer.info.id == id)
+    }
+
+    fn apply_msg(&mut self, app: &GfaestusVk, msg: LayerMsg) -> Result<()> {
+        match msg {
+            LayerMsg::Loaded { name, vertices } => {
+                self.add_layer(app, name, &vertices)?;
+            }
+            LayerMsg::SetVisible { layer, visible } => {
+                if let Some(layer) = self.layer_mut(layer) {
+                    layer.info.visible = visible;
+                }
+            }
+            LayerMsg::SetOffset { layer, offset } => {
+                if let Some(layer) = self.layer_mut(layer) {
+                    layer.info.offset = offset;
+                }
+            }
+            LayerMsg::SetTint { layer, mut tint } => {
+                // a zero alpha would make the shaders read the
+                // overlay, which doesn't cover the layer's nodes
+                tint.a = tint.a.max(0.05);
+                if let Some(layer) = self.layer_mut(layer) {
+                    layer.info.tint = tint;
+                }
+            }
+            LayerMsg::Raise { layer } => {
+                let ix = self.layers.iter().position(|l| l.info.id == layer);
+                if let Some(ix) = ix.filter(|&ix| ix + 1 < self.layers.len()) {
+                    self.layers.swap(ix, ix + 1);
+                }
+            }
+            LayerMsg::Lower { layer } => {
+                let ix = self.layers.iter().position(|l| l.info.id == layer);
+                if let Some(ix) = ix.filter(|&ix| ix > 0) {
+                    self.layers.swap(ix, ix - 1);
+                }
+            }
+            LayerMsg::Remove { layer } => {
+                let ix = self.layers.iter().position(|l| l.info.id == layer);
+                if let Some(ix) = ix {
+                    let mut layer = self.layers.remove(ix);
+                    Self::destroy_layer(app, &mut layer)?;
+
+                    if self.hover.map(|(id, _)| id) == Some(layer.info.id) {
+                        self.hover = None;
+                    }
+                }
+            }
+            LayerMsg::ToggleSelect { layer, node } => {
+                let device = self.device.clone();
+                if let Some(layer) = self.layer_mut(layer) {
+                    let mut selection: FxHashSet<NodeId> =
+                        layer.selection_buffer.selection_set().clone();
+
+                    if !selection.remove(&node) {
+                        selection.insert(node);
+                    }
+
+                    layer
+                        .selection_buffer
+                        .update_selection(&device, &selection)?;
+                    layer.info.selected_count = selection.len();
+                }
+            }
+            LayerMsg::ClearSelection { layer } => {
+                let device = self.device.clone();
+                if let Some(layer) = self.layer_mut(layer) {
+                    layer.selection_buffer.clear();
+                    layer.selection_buffer.clear_buffer(&device)?;
+                    layer.info.selected_count = 0;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_layer(
+        &mut self,
+        app: &GfaestusVk,
+        name: String,
+        vertices: &[Vertex],
+    ) -> Result<()> {
+        let node_count = vertices.len() / 2;
+
+        let mut node_vertices =
+            NodeVertices::new(app.vk_context().renderer_config.nodes);
+        node_vertices.upload_vertices(app, vertices)?;
+
+        let mut selection_buffer = SelectionBuffer::new(app, node_count)?;
+        selection_buffer.clear_buffer(&self.device)?;
+
+        let selection_descriptors =
+            SelectionDescriptors::new(app, selection_buffer.buffer, 1)?;
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let (r, g, b) = Self::DEFAULT_TINTS[id % Self::DEFAULT_TINTS.len()];
+
+        log::info!("Added graph layer {} with {} nodes", name, node_count);
+
+        self.layers.push(GraphLayer {
+            info: LayerInfo {
+                id,
+                name,
+                node_count,
+                offset: Point::ZERO,
+                tint: rgb::RGBA::new(r, g, b, 1.0),
+                visible: true,
+                selected_count: 0,
+            },
+            id_offset: 0,
+            vertices: node_vertices,
+            selection_buffer,
+            selection_descriptors,
+        });
+
+        Ok(())
+    }
+
+    /// Give each layer the range of node IDs following the main
+    /// graph and the layers drawn before it
+    fn update_id_offsets(&mut self) {
+        let mut offset = self.base_node_count;
+
+        for layer in self.layers.iter_mut() {
+            layer.id_offset = offset as u32;
+            offset += layer.info.node_count;
+        }
+    }
+
+    fn update_infos(&self) {
+        let infos = self.layers.iter().map(|l| l.info.clone()).collect();
+        *self.infos.write() = infos;
+    }
+
+    fn destroy_layer(app: &GfaestusVk, layer: &mut GraphLayer) -> Result<()> {
+        let device = app.vk_context().device();
+
+        layer.vertices.destroy(app)?;
+        layer.selection_buffer.destroy(device);
+        layer.selection_descriptors.destroy(device);
+
+        Ok(())
+    }
+
+    pub fn destroy(&mut self, app: &GfaestusVk) -> Result<()> {
+        for mut layer in self.layers.drain(..) {
+            Self::destroy_layer(app, &mut layer)?;
+        }
+        self.update_infos();
+        Ok(())
+    }
+}
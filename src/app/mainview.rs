@@ -11,10 +11,14 @@ use handlegraph::handle::NodeId;
 
 use rustc_hash::{FxHashMap, FxHashSet};
 
-use crate::view::{ScreenDims, View};
+use crate::view::{SavedView, ScreenDims, View};
 use crate::{
-    app::{selection::SelectionBuffer, NodeWidth},
-    context::ContextMgr,
+    app::{
+        layers::{LayerManager, LayerMsg, LayerNode},
+        selection::SelectionBuffer,
+        NodeWidth,
+    },
+    context::{ContextMgr, WorldPoint},
     vulkan::texture::GradientTexture,
 };
 use crate::{geometry::*, vulkan::render_pass::Framebuffers};
@@ -25,12 +29,13 @@ use crate::input::binds::{
 };
 
 use crate::vulkan::{
-    draw_system::nodes::{NodeIdBuffer, NodePipelines},
+    draw_system::nodes::{NodeFocusMode, NodeIdBuffer, NodePipelines},
     GfaestusVk,
 };
 
 use ash::vk;
 
+pub mod flythrough;
 pub mod view;
 
 use view::*;
@@ -42,6 +47,8 @@ pub struct MainView {
     pub node_id_buffer: NodeIdBuffer,
     pub selection_buffer: SelectionBuffer,
 
+    pub layers: LayerManager,
+
     node_width: Arc<NodeWidth>,
 
     anim_handler: AnimHandler,
@@ -53,11 +60,25 @@ pub struct MainView {
     channels: AppChannels,
 
     move_delta: AtomicCell<Option<Point>>,
+
+    /// Where the mouse pan button was pressed, in screen space, to
+    /// tell clicks from drags when it's released
+    press_pos: AtomicCell<Option<Point>>,
+
+    /// The view scale that fits the whole layout on screen, used to
+    /// derive the zoom out limit
+    layout_scale: AtomicCell<Option<f32>>,
+
+    relaxation_active: AtomicCell<bool>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum MainViewMsg {
     GotoView(View),
+    /// Set the width factor of each node, indexed by node ID - 1
+    SetNodeWidths(Vec<f32>),
+    /// Draw all nodes with the global node width again
+    ResetNodeWidths,
 }
 
 impl MainView {
@@ -68,6 +89,15 @@ impl MainView {
         shared_state: SharedState,
         node_count: usize,
     ) -> Result<Self> {
+        if node_count > NodeIdBuffer::MAX_NODE_COUNT {
+            log::warn!(
+                "The graph has {} nodes, but only the first {} can be \
+                 picked with the mouse",
+                node_count,
+                NodeIdBuffer::MAX_NODE_COUNT
+            );
+        }
+
         let selection_buffer = SelectionBuffer::new(app, node_count)?;
 
         let swapchain_props = app.swapchain_props;
@@ -92,6 +122,13 @@ impl MainView {
             app.render_passes.id_format,
         )?;
 
+        let layers = LayerManager::new(
+            app.vk_context().device(),
+            node_count,
+            shared_state.layers.clone(),
+            channels.layer_rx.clone(),
+        );
+
         let anim_handler = AnimHandler::new(
             shared_state.view.clone(),
             Point::ZERO,
@@ -103,6 +140,8 @@ impl MainView {
             node_id_buffer,
             selection_buffer,
 
+            layers,
+
             node_width,
 
             anim_handler,
@@ -111,6 +150,12 @@ impl MainView {
 
             move_delta: AtomicCell::new(None),
 
+            press_pos: AtomicCell::new(None),
+
+            layout_scale: AtomicCell::new(None),
+
+            relaxation_active: AtomicCell::new(false),
+
             settings,
             shared_state,
             channels,
@@ -127,24 +172,40 @@ impl MainView {
         &self.channels.main_view_rx
     }
 
-    pub fn apply_msg(&self, msg: MainViewMsg) {
+    pub fn apply_msg(&mut self, app: &GfaestusVk, msg: MainViewMsg) {
         match msg {
-            MainViewMsg::GotoView(view) => {
-                use std::time::Duration;
-
-                let anim_def = AnimationDef {
-                    kind: AnimationKind::Absolute,
-                    order: AnimationOrder::Transform {
-                        center: view.center,
-                        scale: view.scale,
-                    },
-                    duration: Duration::from_millis(500),
-                };
-                self.anim_handler.send_anim_def(anim_def);
+            MainViewMsg::GotoView(view) => self.animate_to_view(view),
+            MainViewMsg::SetNodeWidths(widths) => {
+                if let Err(err) = self.set_node_widths(app, &widths) {
+                    log::error!("Error setting node widths: {:?}", err);
+                }
+            }
+            MainViewMsg::ResetNodeWidths => {
+                if let Err(err) = self.reset_node_widths(app) {
+                    log::error!("Error resetting node widths: {:?}", err);
+                }
             }
         }
     }
 
+    /// Animate the view to `view`. A new animation replaces the one
+    /// in flight, starting from the current view, so rapid gotos
+    /// retarget rather than queue
+    fn animate_to_view(&self, view: View) {
+        use std::time::Duration;
+
+        let anim_def = AnimationDef {
+            kind: AnimationKind::Absolute,
+            order: AnimationOrder::Transform {
+                center: view.center,
+                scale: view.scale,
+            },
+            duration: Duration::from_millis(300),
+        };
+        self.update_scale_limits();
+        self.anim_handler.send_anim_def(anim_def);
+    }
+
     pub fn view(&self) -> View {
         self.shared_state.view.load()
     }
@@ -162,10 +223,103 @@ impl MainView {
             .store(self.anim_handler.initial_view.load());
     }
 
+    /// Animate the view back to the initial view, framing the whole
+    /// graph unless a different initial view was given
+    pub fn animate_reset_view(&self) {
+        self.animate_to_view(self.anim_handler.initial_view.load());
+    }
+
+    /// Set the bounding box of the layout, which the zoom out limit
+    /// is derived from; must be called again when the screen size
+    /// changes
+    pub fn set_layout_bounds<D: Into<ScreenDims>>(
+        &self,
+        screen_dims: D,
+        top_left: Point,
+        bottom_right: Point,
+    ) {
+        let view =
+            View::from_dims_and_target(screen_dims, top_left, bottom_right);
+        self.layout_scale.store(Some(view.scale));
+    }
+
+    /// The `(min, max)` view scale, i.e. how far the view can zoom in
+    /// and out. The max is relative to the scale that fits the whole
+    /// layout on screen, if the layout bounds are known.
+    pub fn scale_limits(&self) -> (f32, f32) {
+        let controls = self.settings.view_controls().load();
+
+        let min_scale = controls.min_scale;
+        let max_scale = self
+            .layout_scale
+            .load()
+            .map(|scale| scale * controls.max_zoom_out)
+            .unwrap_or(std::f32::MAX);
+
+        (min_scale, max_scale.max(min_scale))
+    }
+
+    fn update_scale_limits(&self) {
+        let (min_scale, max_scale) = self.scale_limits();
+        self.anim_handler.set_scale_limits(min_scale, max_scale);
+    }
+
     pub fn set_view(&self, view: View) {
         self.shared_state.view.store(view);
     }
 
+    /// The file used by the save and restore view keybinds
+    pub const VIEW_FILE: &'static str = "gfaestus_view.json";
+
+    /// If the mouse moves less than this many pixels between the pan
+    /// button being pressed and released, it's treated as a click
+    const CLICK_DRAG_THRESHOLD: f32 = 4.0;
+
+    pub fn save_view_file<D: Into<ScreenDims>>(
+        &self,
+        screen_dims: D,
+        path: &str,
+    ) -> Result<()> {
+        let saved = SavedView::new(self.view(), screen_dims);
+        saved.save_file(path)
+    }
+
+    /// Restores a view saved with `save_view_file`, framing the same
+    /// world region regardless of the current window size.
+    pub fn load_view_file<D: Into<ScreenDims>>(
+        &self,
+        screen_dims: D,
+        path: &str,
+    ) -> Result<()> {
+        let saved = SavedView::load_file(path)?;
+        let view = saved.view_for_dims(screen_dims);
+        self.set_view(view);
+        Ok(())
+    }
+
+    /// If true, the layout is continuously refined by the
+    /// force-directed relaxation compute pass
+    pub fn relaxation_active(&self) -> bool {
+        self.relaxation_active.load()
+    }
+
+    pub fn set_relaxation_active(&self, active: bool) {
+        self.relaxation_active.store(active);
+    }
+
+    pub fn toggle_relaxation(&self) {
+        self.relaxation_active.fetch_xor(true);
+    }
+
+    /// The number of relaxation iterations in each dispatch
+    pub fn relaxation_steps(&self) -> usize {
+        self.settings.relaxation_steps().load().max(1)
+    }
+
+    pub fn set_relaxation_steps(&self, steps: usize) {
+        self.settings.relaxation_steps().store(steps.max(1));
+    }
+
     pub fn node_id_buffer(&self) -> vk::Buffer {
         self.node_id_buffer.buffer
     }
@@ -187,14 +341,44 @@ impl MainView {
 
         let min_y = if y < 40 { 0 } else { y - 40 };
 
-        self.node_id_buffer.read_rect(
+        let mut nodes = self.node_id_buffer.read_rect(
             self.node_draw_system.device(),
             min_x..=(x + 40),
             min_y..=(y + 40),
-        )
+        );
+        nodes.retain(|id| self.layers.is_base_id(id.0 as u32));
+        nodes
     }
 
-    pub fn read_node_id_at(&self, point: Point) -> Option<u32> {
+    /// Return the unique nodes covered by `rect`, given in screen
+    /// pixels like `read_node_id_at`, from the most recently copied
+    /// node ID buffer.
+    pub fn read_node_ids_in_rect(&self, rect: Rect) -> FxHashSet<NodeId> {
+        let min = rect.min();
+        let max = rect.max();
+
+        if max.x < 0.0 || max.y < 0.0 {
+            return FxHashSet::default();
+        }
+
+        let min_x = min.x.max(0.0).floor() as u32;
+        let min_y = min.y.max(0.0).floor() as u32;
+
+        let max_x = max.x.floor() as u32;
+        let max_y = max.y.floor() as u32;
+
+        let mut nodes = self.node_id_buffer.read_rect(
+            self.node_draw_system.device(),
+            min_x..=max_x,
+            min_y..=max_y,
+        );
+        nodes.retain(|id| self.layers.is_base_id(id.0 as u32));
+        nodes
+    }
+
+    /// The ID in the node ID buffer at `point`, including the offset
+    /// IDs of the graph layers
+    fn read_id_at(&self, point: Point) -> Option<u32> {
         let x = point.x as u32;
         let y = point.y as u32;
 
@@ -202,8 +386,89 @@ impl MainView {
             .read(self.node_draw_system.device(), x, y)
     }
 
+    /// The main graph node at `point`, if any
+    pub fn read_node_id_at(&self, point: Point) -> Option<u32> {
+        self.read_id_at(point)
+            .filter(|&id| self.layers.is_base_id(id))
+    }
+
+    /// The graph layer node at `point`, if any
+    pub fn read_layer_node_at(&self, point: Point) -> Option<LayerNode> {
+        self.read_id_at(point)
+            .and_then(|id| self.layers.resolve_id(id))
+    }
+
+    /// Apply the queued graph layer changes; must be called outside
+    /// the frame's command recording
+    pub fn update_layers(&mut self, app: &GfaestusVk) -> Result<()> {
+        self.layers.update(app)
+    }
+
+    /// Highlight the graph layer node under the cursor, if any
+    pub fn set_highlighted_layer_node(
+        &mut self,
+        node: Option<LayerNode>,
+    ) -> Result<()> {
+        self.shared_state.hover_layer_node.store(node);
+        self.layers.set_hover(node)
+    }
+
+    /// Must be called outside the frame's command recording, before
+    /// `draw_nodes` is called with `cull` set
+    pub fn prepare_node_culling(&mut self, app: &GfaestusVk) -> Result<()> {
+        self.node_draw_system.prepare_culling(app)
+    }
+
+    /// The number of nodes drawn by the last culled or reduced
+    /// detail node draw
+    pub fn visible_node_count(&self) -> Option<usize> {
+        self.node_draw_system.visible_node_count()
+    }
+
+    /// Read back the visible node count of the last culled node draw;
+    /// must only be called once the frame has finished executing
+    pub fn read_node_cull_results(&mut self) -> Result<()> {
+        self.node_draw_system.read_cull_results()
+    }
+
+    /// Whether the last node draw used the reduced level of detail
+    pub fn node_lod_active(&self) -> bool {
+        self.node_draw_system.lod_active()
+    }
+
+    /// The global node width the nodes are drawn with at the scale
+    /// of `view`, before the per-node width factors are applied
+    pub fn node_width(&self, view: View) -> f32 {
+        let min = self.node_width.min_node_width();
+        let max = self.node_width.max_node_width();
+
+        let min_scale = self.node_width.min_node_scale();
+        let max_scale = self.node_width.max_node_scale();
+
+        let norm_scale = (view.scale - min_scale) / (max_scale - min_scale);
+
+        let easing_val =
+            EasingExpoOut::value_at_normalized_time(norm_scale as f64) as f32;
+
+        let mut width = min + easing_val * (max - min);
+
+        if view.scale > max_scale {
+            width *= view.scale / (min_scale - max_scale);
+        } else if view.scale < min_scale {
+            width = min
+        }
+        width
+    }
+
+    /// The per-node width factors, indexed by node ID - 1; empty if
+    /// every node uses the global width
+    pub fn node_width_factors(&self) -> &[f32] {
+        self.node_draw_system.vertices.width_factors()
+    }
+
     pub fn draw_nodes(
         &mut self,
+        cull: bool,
         cmd_buf: vk::CommandBuffer,
         render_pass: vk::RenderPass,
         framebuffers: &Framebuffers,
@@ -214,42 +479,35 @@ impl MainView {
     ) -> Result<()> {
         let view = self.shared_state.view();
 
-        let node_width = {
-            let min = self.node_width.min_node_width();
-            let max = self.node_width.max_node_width();
-
-            let min_scale = self.node_width.min_node_scale();
-            let max_scale = self.node_width.max_node_scale();
-
-            let norm_scale = (view.scale - min_scale) / (max_scale - min_scale);
-
-            let easing_val =
-                EasingExpoOut::value_at_normalized_time(norm_scale as f64)
-                    as f32;
+        let node_width = self.node_width(view);
+        let min_node_px = self.node_width.min_node_pixels().unwrap_or(0.0);
 
-            let mut width = min + easing_val * (max - min);
+        let lod_threshold = self.settings.node_lod_threshold().load();
 
-            if view.scale > max_scale {
-                width *= view.scale / (min_scale - max_scale);
-            } else if view.scale < min_scale {
-                width = min
-            }
-            width
-        };
+        let layers = self.layers.layer_draws();
 
-        let background_color = if self.shared_state.dark_mode.load() {
-            self.settings.background_color_dark().load()
+        let focus_mode = if self.selection_buffer.selection_set().is_empty() {
+            NodeFocusMode::Normal
         } else {
-            self.settings.background_color_light().load()
+            self.settings.node_focus_mode().load()
         };
 
+        let background_color = self
+            .settings
+            .background_color(self.shared_state.dark_mode.load());
+
         if let Some(overlay_id) = overlay_id {
             self.node_draw_system.draw(
+                cull,
+                lod_threshold,
+                &layers,
                 cmd_buf,
                 render_pass,
                 framebuffers,
                 screen_dims,
                 node_width,
+                min_node_px,
+                focus_mode,
                 view,
                 offset,
                 background_color,
@@ -266,6 +524,24 @@ impl MainView {
         }
     }
 
+    /// Set the width of each node, indexed by node ID - 1, as a
+    /// factor of the global node width from the settings. `widths`
+    /// must contain one entry per node.
+    pub fn set_node_widths(
+        &mut self,
+        app: &GfaestusVk,
+        widths: &[f32],
+    ) -> Result<()> {
+        self.node_draw_system.vertices.upload_widths(app, widths)
+    }
+
+    /// Draw all nodes with the global node width again
+    pub fn reset_node_widths(&mut self, app: &GfaestusVk) -> Result<()> {
+        let node_count = self.node_draw_system.vertices.node_count();
+        let widths = vec![1.0; node_count];
+        self.set_node_widths(app, &widths)
+    }
+
     pub fn update_node_selection(
         &mut self,
         new_selection: &FxHashSet<NodeId>,
@@ -284,6 +560,13 @@ impl MainView {
         selection.clear_buffer(device)
     }
 
+    /// Highlight `node` in the main view, e.g. the node under the
+    /// cursor, removing the highlight from the previous node
+    pub fn set_highlighted_node(&mut self, node: Option<NodeId>) -> Result<()> {
+        let device = self.node_draw_system.device();
+        self.selection_buffer.set_highlight(device, node)
+    }
+
     pub fn set_view_center(&self, center: Point) {
         let mut view = self.shared_state.view();
         view.center = center;
@@ -291,8 +574,9 @@ impl MainView {
     }
 
     pub fn set_view_scale(&self, scale: f32) {
+        let (min_scale, max_scale) = self.scale_limits();
         let mut view = self.shared_state.view();
-        view.scale = scale;
+        view.scale = scale.max(min_scale).min(max_scale);
         self.shared_state.view.store(view);
     }
 
@@ -312,11 +596,19 @@ impl MainView {
             screen_dims,
             mouse_screen,
             mouse_world,
+            self.settings.view_controls().load(),
         ) {
+            self.update_scale_limits();
             self.anim_handler.send_anim_def(anim_def);
         }
     }
 
+    /// True if the view is animating or being moved by the user, in
+    /// which case frames should be drawn as fast as possible
+    pub fn is_animating(&self) -> bool {
+        self.anim_handler.is_animating() || self.view_input_state.active()
+    }
+
     pub fn produce_context(&self, ctx: &ContextMgr) {
         let mouse_pos = self.shared_state.mouse_pos();
 
@@ -328,6 +620,12 @@ impl MainView {
             ctx.produce_context(|| node);
         }
 
+        ctx.produce_context(|| {
+            let view = self.shared_state.view();
+            let dims = self.shared_state.screen_dims();
+            WorldPoint(view.screen_to_world(mouse_pos, dims))
+        });
+
         // TODO use Arc and Arc::make_mut on the selection_set field
         // to handle this in a much nicer way
         let nodes = self.selection_buffer.selection_set();
@@ -368,7 +666,30 @@ impl MainView {
                     }
                     In::KeyResetView => {
                         if pressed {
-                            self.reset_view();
+                            self.animate_reset_view();
+                        }
+                    }
+                    In::KeySaveView => {
+                        if pressed {
+                            if let Err(err) = self
+                                .save_view_file(screen_dims, Self::VIEW_FILE)
+                            {
+                                log::error!("Error saving view: {:?}", err);
+                            }
+                        }
+                    }
+                    In::KeyRestoreView => {
+                        if pressed {
+                            if let Err(err) = self
+                                .load_view_file(screen_dims, Self::VIEW_FILE)
+                            {
+                                log::error!("Error restoring view: {:?}", err);
+                            }
+                        }
+                    }
+                    In::KeyToggleRelaxation => {
+                        if pressed {
+                            self.toggle_relaxation();
                         }
                     }
                     _ => (),
@@ -379,6 +700,8 @@ impl MainView {
                 match payload {
                     In::ButtonMousePan => {
                         if pressed {
+                            self.press_pos.store(Some(mouse_pos));
+
                             if self.shared_state.hover_node().is_some() {
                                 let view = self.shared_state.view();
                                 let mouse_world = view.screen_point_to_world(
@@ -399,27 +722,60 @@ impl MainView {
                                     .start_click_and_drag_pan(mouse_world);
                             }
                         } else {
-                            if let Some(start) = self.move_delta.load() {
-                                use crate::app::AppMsg;
-
-                                let view = self.shared_state.view();
-                                let mouse_world = view.screen_point_to_world(
-                                    screen_dims,
-                                    mouse_pos,
-                                );
-
-                                let delta = mouse_world - start;
-
-                                self.channels
-                                    .app_tx
-                                    .send(AppMsg::TranslateSelected(delta))
-                                    .unwrap();
+                            use crate::app::{AppMsg, Select};
+
+                            let clicked =
+                                self.press_pos.take().map_or(false, |start| {
+                                    start.dist(mouse_pos)
+                                        < Self::CLICK_DRAG_THRESHOLD
+                                });
+
+                            if let Some(start) = self.move_delta.take() {
+                                if clicked {
+                                    // clicking a node without dragging
+                                    // it selects the node
+                                    if let Some(node) =
+                                        self.shared_state.hover_node()
+                                    {
+                                        let select =
+                                            Select::One { node, clear: true };
+
+                                        self.channels
+                                            .app_tx
+                                            .send(AppMsg::Selection(select))
+                                            .unwrap();
+                                    }
+                                } else {
+                                    let view = self.shared_state.view();
+                                    let mouse_world = view
+                                        .screen_point_to_world(
+                                            screen_dims,
+                                            mouse_pos,
+                                        );
+
+                                    let delta = mouse_world - start;
 
-                                self.move_delta.store(None);
+                                    self.channels
+                                        .app_tx
+                                        .send(AppMsg::TranslateSelected(delta))
+                                        .unwrap();
+                                }
                             }
                             self.view_input_state.mouse_released();
                         }
                     }
+                    In::ButtonDragPan => {
+                        if pressed {
+                            let view = self.shared_state.view();
+                            let mouse_world = view
+                                .screen_point_to_world(screen_dims, mouse_pos);
+
+                            self.view_input_state
+                                .start_click_and_drag_pan(mouse_world);
+                        } else {
+                            self.view_input_state.mouse_released();
+                        }
+                    }
                     In::ButtonSelect => {
                         use crate::app::AppMsg;
                         use crate::app::Select;
@@ -436,6 +792,13 @@ impl MainView {
                                     clear: false,
                                 }))
                                 .unwrap();
+                        } else if let Some((layer, node)) =
+                            self.read_layer_node_at(pos)
+                        {
+                            self.channels
+                                .layer_tx
+                                .send(LayerMsg::ToggleSelect { layer, node })
+                                .unwrap();
                         }
                     }
 
@@ -466,6 +829,8 @@ impl MainView {
                         self.shared_state.view(),
                         mouse_pos,
                         delta,
+                        self.settings.view_controls().load(),
+                        self.scale_limits(),
                     );
                 }
             }
@@ -476,6 +841,7 @@ impl MainView {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum MainViewInput {
     ButtonMousePan,
+    ButtonDragPan,
     ButtonSelect,
     ButtonRectangleSelect,
     KeyPanUp,
@@ -483,6 +849,9 @@ pub enum MainViewInput {
     KeyPanDown,
     KeyPanLeft,
     KeyResetView,
+    KeySaveView,
+    KeyRestoreView,
+    KeyToggleRelaxation,
     WheelZoom,
 }
 
@@ -498,6 +867,10 @@ impl BindableInput for MainViewInput {
             (Key::Left, Input::KeyPanLeft),
             (Key::Right, Input::KeyPanRight),
             (Key::Space, Input::KeyResetView),
+            (Key::Home, Input::KeyResetView),
+            (Key::F5, Input::KeySaveView),
+            (Key::F6, Input::KeyRestoreView),
+            (Key::F7, Input::KeyToggleRelaxation),
         ]
         .iter()
         .copied()
@@ -525,6 +898,10 @@ impl BindableInput for MainViewInput {
                     )
                 ],
             ),
+            (
+                event::MouseButton::Middle,
+                vec![MouseButtonBind::new(Input::ButtonDragPan)],
+            ),
             // (
             //     event::MouseButton::Right,
             //     vec![MouseButtonBind::new(Input::ButtonSelect)],
@@ -538,4 +915,24 @@ impl BindableInput for MainViewInput {
 
         SystemInputBindings::new(key_binds, mouse_binds, wheel_bind)
     }
+
+    // the wheel zoom isn't bindable, as it's the only wheel input
+    fn bindable_actions() -> Vec<(&'static str, Self)> {
+        use MainViewInput as Input;
+
+        vec![
+            ("mouse_pan", Input::ButtonMousePan),
+            ("drag_pan", Input::ButtonDragPan),
+            ("select", Input::ButtonSelect),
+            ("rectangle_select", Input::ButtonRectangleSelect),
+            ("pan_up", Input::KeyPanUp),
+            ("pan_right", Input::KeyPanRight),
+            ("pan_down", Input::KeyPanDown),
+            ("pan_left", Input::KeyPanLeft),
+            ("reset_view", Input::KeyResetView),
+            ("save_view", Input::KeySaveView),
+            ("restore_view", Input::KeyRestoreView),
+            ("toggle_relaxation", Input::KeyToggleRelaxation),
+        ]
+    }
 }
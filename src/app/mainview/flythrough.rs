@@ -0,0 +1,140 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use std::path::{Path, PathBuf};
+
+use crate::view::{SavedView, ScreenDims, View};
+
+use super::view::{EasingCirc, EasingFunction, ViewLerp};
+
+/// A camera keyframe of a fly-through. The view is stored like the
+/// view files, so the saved view keybind can be used to create them.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    #[serde(flatten)]
+    pub view: SavedView,
+    /// Seconds taken to move to this keyframe from the previous one;
+    /// ignored for the first keyframe
+    #[serde(default)]
+    pub duration: f64,
+}
+
+/// The fly-through file format, e.g.
+///
+/// `{ "fps": 30, "keyframes": [ { "center": { "x": 0, "y": 0 },
+///    "scale": 10, "width": 800, "height": 600, "duration": 2 }, ... ] }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlyThroughFile {
+    pub fps: f64,
+    pub keyframes: Vec<Keyframe>,
+}
+
+/// Moves the camera between a list of keyframes, one frame at a
+/// time. Time advances by a fixed step each frame, so the output is
+/// the same no matter how fast the frames are rendered.
+pub struct FlyThrough {
+    fps: f64,
+    keyframes: Vec<Keyframe>,
+
+    out_dir: PathBuf,
+
+    frame: usize,
+    frame_count: usize,
+}
+
+impl FlyThrough {
+    /// Loads the keyframes from the JSON file at `path`; the frames
+    /// will be saved as numbered PNGs in `out_dir`, which is created
+    /// if needed.
+    pub fn load_file<P: AsRef<Path>, Q: AsRef<Path>>(
+        path: P,
+        out_dir: Q,
+    ) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let FlyThroughFile { fps, keyframes } =
+            serde_json::from_reader(std::io::BufReader::new(file))?;
+
+        if keyframes.is_empty() {
+            anyhow::bail!("Fly-through has no keyframes");
+        }
+
+        if fps.is_nan() || fps <= 0.0 {
+            anyhow::bail!("Fly-through FPS must be positive, was {}", fps);
+        }
+
+        if let Some(kf) = keyframes.iter().find(|kf| kf.duration < 0.0) {
+            anyhow::bail!("Negative keyframe duration: {}", kf.duration);
+        }
+
+        let out_dir = out_dir.as_ref().to_owned();
+        std::fs::create_dir_all(&out_dir)?;
+
+        let total: f64 = keyframes.iter().skip(1).map(|kf| kf.duration).sum();
+        let frame_count = (total * fps).ceil() as usize + 1;
+
+        Ok(Self {
+            fps,
+            keyframes,
+
+            out_dir,
+
+            frame: 0,
+            frame_count,
+        })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.frame >= self.frame_count
+    }
+
+    pub fn out_dir(&self) -> &Path {
+        &self.out_dir
+    }
+
+    /// The view `time` seconds into the fly-through, on a screen of
+    /// size `screen_dims`
+    pub fn view_at<D: Into<ScreenDims>>(
+        &self,
+        screen_dims: D,
+        time: f64,
+    ) -> View {
+        let dims = screen_dims.into();
+
+        let mut start = self.keyframes[0].view.view_for_dims(dims);
+        let mut start_time = 0.0;
+
+        for keyframe in self.keyframes.iter().skip(1) {
+            let end = keyframe.view.view_for_dims(dims);
+            let end_time = start_time + keyframe.duration;
+
+            if time < end_time {
+                let t = (time - start_time) / keyframe.duration;
+                let t = EasingCirc::value_at_normalized_time(t);
+                return ViewLerp::new(start, end).lerp(t);
+            }
+
+            start = end;
+            start_time = end_time;
+        }
+
+        start
+    }
+
+    /// The view of the current frame
+    pub fn current_view<D: Into<ScreenDims>>(&self, screen_dims: D) -> View {
+        self.view_at(screen_dims, self.frame as f64 / self.fps)
+    }
+
+    /// The file the current frame should be saved to
+    pub fn frame_path(&self) -> PathBuf {
+        self.out_dir.join(format!("frame_{:05}.png", self.frame))
+    }
+
+    pub fn advance(&mut self) {
+        self.frame += 1;
+    }
+}
@@ -1,3 +1,4 @@
+use crate::app::ViewControls;
 use crate::geometry::*;
 use crate::view::{ScreenDims, View};
 
@@ -245,8 +246,15 @@ where
 }
 
 impl<E: EasingFunction> ViewAnimation<E> {
-    pub fn from_anim_def(start: View, anim: AnimationDef) -> Self {
-        let end = match anim.kind {
+    /// Create the animation from `start` to the view described by
+    /// `anim`, with the scale of the end view clamped to the
+    /// `(min, max)` scale limits
+    pub fn from_anim_def(
+        start: View,
+        anim: AnimationDef,
+        (min_scale, max_scale): (f32, f32),
+    ) -> Self {
+        let mut end = match anim.kind {
             AnimationKind::Absolute => View {
                 center: anim.order.center().unwrap_or(start.center),
                 scale: anim.order.scale().unwrap_or(start.scale),
@@ -258,6 +266,8 @@ impl<E: EasingFunction> ViewAnimation<E> {
             },
         };
 
+        end.scale = end.scale.max(min_scale).min(max_scale);
+
         let view_lerp = ViewLerp::new(start, end);
 
         let now = Duration::new(0, 0);
@@ -328,7 +338,12 @@ pub struct AnimHandler {
     pub initial_view: Arc<AtomicCell<View>>,
     pub mouse_pos: Arc<AtomicCell<Point>>,
 
-    _join_handle: std::thread::JoinHandle<()>,
+    animating: Arc<AtomicCell<bool>>,
+
+    /// The `(min, max)` view scale that animations are clamped to
+    scale_limits: Arc<AtomicCell<(f32, f32)>>,
+
+    join_handle: Option<std::thread::JoinHandle<()>>,
     anim_tx: channel::Sender<AnimationDef>,
 }
 
@@ -350,9 +365,15 @@ impl AnimHandler {
         let mouse_pos_ = Arc::new(AtomicCell::new(mouse_pos));
         let mouse_pos = mouse_pos_.clone();
 
+        let animating_ = Arc::new(AtomicCell::new(false));
+        let animating = animating_.clone();
+
+        let scale_limits_ = Arc::new(AtomicCell::new((0.0, std::f32::MAX)));
+        let scale_limits = scale_limits_.clone();
+
         let (anim_tx, anim_rx) = channel::unbounded::<AnimationDef>();
 
-        let _join_handle = std::thread::spawn(move || {
+        let join_handle = std::thread::spawn(move || {
             let update_delay = std::time::Duration::from_millis(5);
             let sleep_delay = std::time::Duration::from_micros(2500);
 
@@ -365,11 +386,23 @@ impl AnimHandler {
             loop {
                 let cur_view = view.load();
 
-                while let Ok(def) = anim_rx.try_recv() {
+                loop {
+                    let def = match anim_rx.try_recv() {
+                        Ok(def) => def,
+                        Err(channel::TryRecvError::Empty) => break,
+                        // the handler has been dropped
+                        Err(channel::TryRecvError::Disconnected) => return,
+                    };
+
                     let view_anim: ViewAnimation<EasingExpoOut> =
-                        ViewAnimation::from_anim_def(cur_view, def);
+                        ViewAnimation::from_anim_def(
+                            cur_view,
+                            def,
+                            scale_limits_.load(),
+                        );
 
                     animation = Some(view_anim.boxed());
+                    animating_.store(true);
                     last_update = Instant::now();
                 }
 
@@ -393,6 +426,8 @@ impl AnimHandler {
                         animation.take();
                     }
 
+                    animating_.store(animation.is_some());
+
                     last_update = Instant::now();
                 } else {
                     std::thread::sleep(sleep_delay);
@@ -405,12 +440,26 @@ impl AnimHandler {
             initial_view,
             mouse_pos,
 
-            _join_handle,
+            animating,
+
+            scale_limits,
+
+            join_handle: Some(join_handle),
             anim_tx,
         }
     }
 
+    /// True while a view animation is in progress
+    pub fn is_animating(&self) -> bool {
+        self.animating.load()
+    }
+
+    pub fn set_scale_limits(&self, min_scale: f32, max_scale: f32) {
+        self.scale_limits.store((min_scale, max_scale));
+    }
+
     pub fn send_anim_def(&self, anim_def: AnimationDef) {
+        self.animating.store(true);
         self.anim_tx.send(anim_def).unwrap();
     }
 
@@ -440,7 +489,21 @@ impl AnimHandler {
 
         let anim_def = AnimationDef::pan_key(scale, h, v);
 
-        self.anim_tx.send(anim_def).unwrap();
+        self.send_anim_def(anim_def);
+    }
+}
+
+impl Drop for AnimHandler {
+    fn drop(&mut self) {
+        // dropping the only sender stops the animation thread
+        let (anim_tx, _) = channel::unbounded();
+        self.anim_tx = anim_tx;
+
+        if let Some(join_handle) = self.join_handle.take() {
+            if join_handle.join().is_err() {
+                log::error!("View animation thread panicked");
+            }
+        }
     }
 }
 
@@ -474,7 +537,11 @@ impl KeyPanState {
         self.up() || self.right() || self.down() || self.left()
     }
 
-    pub fn animation_def(&self, scale: f32) -> Option<AnimationDef> {
+    pub fn animation_def(
+        &self,
+        scale: f32,
+        pan_speed: f32,
+    ) -> Option<AnimationDef> {
         if !self.active() {
             return None;
         }
@@ -505,7 +572,7 @@ impl KeyPanState {
             _ => 0.0,
         };
 
-        let mult = 10.0;
+        let mult = 10.0 * pan_speed;
 
         let center = Point::new(d_x * mult * scale, d_y * mult * scale);
 
@@ -583,6 +650,7 @@ impl MousePanState {
     pub fn animation_def<D: Into<ScreenDims>>(
         &self,
         scale: f32,
+        pan_speed: f32,
         screen_dims: D,
         cur_mouse_screen: Point,
         cur_mouse_world: Point,
@@ -601,7 +669,7 @@ impl MousePanState {
                     y: mouse_delta.y / dims.height,
                 };
 
-                let center = mouse_norm * scale;
+                let center = mouse_norm * (scale * pan_speed);
 
                 let kind = AnimationKind::Relative;
                 let order = AnimationOrder::Translate { center };
@@ -635,6 +703,7 @@ pub struct ScrollZoomState {
     view_start: View,
     mouse_screen_pos: Point,
     scroll_delta: f32,
+    scale_limits: (f32, f32),
 }
 
 impl ScrollZoomState {
@@ -642,11 +711,13 @@ impl ScrollZoomState {
         view: View,
         mouse_screen_pos: Point,
         scroll_delta: f32,
+        scale_limits: (f32, f32),
     ) -> Self {
         Self {
             view_start: view,
             mouse_screen_pos,
             scroll_delta,
+            scale_limits,
         }
     }
 
@@ -675,12 +746,14 @@ impl ScrollZoomState {
             1.0 + (self.scroll_delta * mult)
         };
 
-        end.scale *= scroll_delta;
+        // clamp here, rather than only in the animation, so the point
+        // under the cursor stays put when zooming past a limit
+        let (min_scale, max_scale) = self.scale_limits;
+        end.scale = (end.scale * scroll_delta).max(min_scale).min(max_scale);
 
         let start_mouse_world =
-            start.screen_point_to_world(dims, self.mouse_screen_pos);
-        let end_mouse_world =
-            end.screen_point_to_world(dims, self.mouse_screen_pos);
+            start.screen_to_world(self.mouse_screen_pos, dims);
+        let end_mouse_world = end.screen_to_world(self.mouse_screen_pos, dims);
 
         let mouse_diff = end_mouse_world - start_mouse_world;
 
@@ -725,6 +798,7 @@ impl ViewInputState {
         screen_dims: D,
         cur_mouse_screen: Point,
         cur_mouse_world: Point,
+        controls: ViewControls,
     ) -> Option<AnimationDef> {
         let mouse_pan = self.mouse_pan.load();
 
@@ -735,15 +809,24 @@ impl ViewInputState {
         } else if mouse_pan.active() {
             mouse_pan.animation_def(
                 view.scale,
+                controls.pan_speed,
                 screen_dims,
                 cur_mouse_screen,
                 cur_mouse_world,
             )
         } else {
-            self.key_pan.animation_def(view.scale)
+            self.key_pan.animation_def(view.scale, controls.pan_speed)
         }
     }
 
+    /// True if the view is being panned or zoomed by the user, i.e.
+    /// if `animation_def` may produce new animations
+    pub fn active(&self) -> bool {
+        self.mouse_pan.load().active()
+            || self.key_pan.active()
+            || self.scroll_zoom.load().is_some()
+    }
+
     pub fn start_mouse_pan(&self, screen_mouse_pos: Point) {
         let pan_state = MousePanState::Continuous {
             mouse_screen_origin: screen_mouse_pos,
@@ -769,11 +852,14 @@ impl ViewInputState {
         view: View,
         cur_mouse_screen: Point,
         scroll_delta: f32,
+        controls: ViewControls,
+        scale_limits: (f32, f32),
     ) {
         let scroll_zoom = ScrollZoomState::zoom_to_cursor(
             view,
             cur_mouse_screen,
-            scroll_delta,
+            controls.wheel_delta(scroll_delta),
+            scale_limits,
         );
         self.scroll_zoom.store(Some(scroll_zoom));
     }
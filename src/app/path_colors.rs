@@ -0,0 +1,136 @@
+use handlegraph::{
+    handlegraph::*, packedgraph::PackedGraph, pathhandlegraph::*,
+};
+
+use crossbeam::atomic::AtomicCell;
+use parking_lot::RwLock;
+use rustc_hash::FxHashMap;
+
+use std::sync::Arc;
+
+use crate::overlays::category_palette;
+
+/// The color of nodes that aren't on any of the paths in the path
+/// membership overlay
+const NO_PATH_COLOR: rgb::RGB<f32> = rgb::RGB {
+    r: 0.3,
+    g: 0.3,
+    b: 0.3,
+};
+
+#[derive(Default)]
+struct PathColorsInner {
+    colors: FxHashMap<PathId, rgb::RGB<f32>>,
+
+    /// The paths in the path membership overlay, in category order,
+    /// i.e. nodes on `overlay_paths[i]` have category `i + 1`
+    overlay_paths: Vec<PathId>,
+}
+
+/// A color for each path, assigned from the qualitative palette when
+/// the graph is loaded, and shared by every overlay that paints path
+/// membership so each path keeps its color across overlays.
+#[derive(Clone, Default)]
+pub struct PathColors {
+    inner: Arc<RwLock<PathColorsInner>>,
+    changed: Arc<AtomicCell<bool>>,
+}
+
+impl PathColors {
+    /// The name of the categorical overlay created from the path list
+    pub const OVERLAY_NAME: &'static str = "Path colors";
+
+    pub fn new<I>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = PathId>,
+    {
+        let mut paths = paths.into_iter().collect::<Vec<_>>();
+        paths.sort();
+
+        let palette = category_palette();
+
+        let colors = paths
+            .into_iter()
+            .enumerate()
+            .map(|(ix, path)| (path, palette[ix % palette.len()]))
+            .collect();
+
+        let inner = PathColorsInner {
+            colors,
+            overlay_paths: Vec::new(),
+        };
+
+        Self {
+            inner: Arc::new(RwLock::new(inner)),
+            changed: Arc::new(false.into()),
+        }
+    }
+
+    pub fn get(&self, path: PathId) -> Option<rgb::RGB<f32>> {
+        self.inner.read().colors.get(&path).copied()
+    }
+
+    /// Change the color of `path`, marking the path membership
+    /// overlay palette as changed if the path is part of it
+    pub fn set(&self, path: PathId, color: rgb::RGB<f32>) {
+        let mut inner = self.inner.write();
+
+        if inner.colors.get(&path) == Some(&color) {
+            return;
+        }
+
+        inner.colors.insert(path, color);
+
+        if inner.overlay_paths.contains(&path) {
+            self.changed.store(true);
+        }
+    }
+
+    /// Returns true, once, if a path's color has changed since the
+    /// path membership overlay palette was last read
+    pub fn take_changed(&self) -> bool {
+        self.changed.swap(false)
+    }
+
+    /// Build the category for each node, and the palette, for the
+    /// path membership overlay of `paths`. Nodes on several of the
+    /// paths get the category of the first of them; nodes on none
+    /// get category 0, drawn in gray.
+    pub fn membership_overlay(
+        &self,
+        graph: &PackedGraph,
+        paths: &[PathId],
+    ) -> (Vec<u32>, Vec<rgb::RGB<f32>>) {
+        let mut categories = vec![0u32; graph.node_count()];
+
+        for (ix, &path) in paths.iter().enumerate().rev() {
+            let category = (ix + 1) as u32;
+
+            if let Some(steps) = graph.path_steps(path) {
+                for step in steps {
+                    let node_ix = (step.handle().id().0 - 1) as usize;
+                    if let Some(cat) = categories.get_mut(node_ix) {
+                        *cat = category;
+                    }
+                }
+            }
+        }
+
+        self.inner.write().overlay_paths = paths.to_vec();
+        self.changed.store(false);
+
+        (categories, self.overlay_palette())
+    }
+
+    /// The palette of the path membership overlay, using the current
+    /// path colors
+    pub fn overlay_palette(&self) -> Vec<rgb::RGB<f32>> {
+        let inner = self.inner.read();
+
+        std::iter::once(NO_PATH_COLOR)
+            .chain(inner.overlay_paths.iter().map(|path| {
+                inner.colors.get(path).copied().unwrap_or(NO_PATH_COLOR)
+            }))
+            .collect()
+    }
+}
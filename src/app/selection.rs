@@ -83,8 +83,25 @@ impl NodeSelection {
     }
 }
 
+/// The bits of the per-node flags stored in the selection buffer,
+/// which the node fragment shaders read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum NodeFlag {
+    Selected = 1,
+    /// Set for the node under the mouse cursor
+    Highlight = 1 << 1,
+}
+
+impl NodeFlag {
+    pub fn bit(self) -> u32 {
+        self as u32
+    }
+}
+
 pub struct SelectionBuffer {
     latest_selection: FxHashSet<NodeId>,
+    highlight: Option<NodeId>,
 
     pub buffer: vk::Buffer,
     memory: vk::DeviceMemory,
@@ -113,6 +130,7 @@ impl SelectionBuffer {
 
         Ok(Self {
             latest_selection,
+            highlight: None,
 
             buffer,
             memory,
@@ -124,6 +142,67 @@ impl SelectionBuffer {
         &self.latest_selection
     }
 
+    pub fn highlight(&self) -> Option<NodeId> {
+        self.highlight
+    }
+
+    /// Moves the highlight flag to `node`, or clears it. Only the
+    /// flags of the previous and new highlighted nodes are written,
+    /// and only if the node changed.
+    pub fn set_highlight(
+        &mut self,
+        device: &Device,
+        node: Option<NodeId>,
+    ) -> Result<()> {
+        if node == self.highlight {
+            return Ok(());
+        }
+
+        let node_count = (self.size / 4) as usize;
+        let in_range = |n: &NodeId| n.0 >= 1 && n.0 as usize <= node_count;
+
+        let prev = self.highlight.filter(in_range);
+        let node = node.filter(in_range);
+
+        unsafe {
+            let data_ptr = device.map_memory(
+                self.memory,
+                0,
+                self.size,
+                vk::MemoryMapFlags::empty(),
+            )?;
+
+            let val_ptr = data_ptr as *mut u32;
+            let flag = NodeFlag::Highlight.bit();
+
+            if let Some(prev) = prev {
+                let ptr = val_ptr.add((prev.0 - 1) as usize);
+                ptr.write(ptr.read() & !flag);
+            }
+
+            if let Some(node) = node {
+                let ptr = val_ptr.add((node.0 - 1) as usize);
+                ptr.write(ptr.read() | flag);
+            }
+
+            device.unmap_memory(self.memory);
+        }
+
+        self.highlight = node;
+
+        Ok(())
+    }
+
+    /// Sets the highlight flag of the highlighted node again, after
+    /// the whole buffer has been overwritten; `val_ptr` must point to
+    /// the mapped buffer memory
+    unsafe fn restore_highlight(&self, val_ptr: *mut u32) {
+        if let Some(node) = self.highlight {
+            let ptr = val_ptr.add((node.0 - 1) as usize);
+            ptr.write(ptr.read() | NodeFlag::Highlight.bit());
+        }
+    }
+
     /// fill `latest_selection` by reading from the buffer
     pub fn fill_selection_set(&mut self, device: &Device) -> Result<()> {
         let node_count = (self.size / 4) as usize;
@@ -141,10 +220,12 @@ impl SelectionBuffer {
             let val_ptr = data_ptr as *const u32;
             let sel_slice = std::slice::from_raw_parts(val_ptr, node_count);
 
+            let selected = NodeFlag::Selected.bit();
+
             self.latest_selection.extend(
                 sel_slice.iter().enumerate().filter_map(|(ix, &val)| {
                     let node_id = NodeId::from((ix + 1) as u64);
-                    if val == 1 {
+                    if val & selected == selected {
                         Some(node_id)
                     } else {
                         None
@@ -152,6 +233,10 @@ impl SelectionBuffer {
                 }),
             );
 
+            // the buffer is filled by copying the GPU selection
+            // results, which don't include the highlight
+            self.restore_highlight(data_ptr as *mut u32);
+
             device.unmap_memory(self.memory);
         }
 
@@ -167,6 +252,7 @@ impl SelectionBuffer {
         }
 
         self.latest_selection.clear();
+        self.highlight = None;
         self.buffer = vk::Buffer::null();
         self.memory = vk::DeviceMemory::null();
         self.size = 0 as vk::DeviceSize;
@@ -188,6 +274,8 @@ impl SelectionBuffer {
             let val_ptr = data_ptr as *mut u32;
             std::ptr::write_bytes(val_ptr, 0u8, (self.size / 4) as usize);
 
+            self.restore_highlight(val_ptr);
+
             device.unmap_memory(self.memory);
         }
 
@@ -217,7 +305,7 @@ impl SelectionBuffer {
 
                 let val_ptr = val_ptr.add(ix);
                 // let val_ptr = val_ptr.add(2);
-                val_ptr.write(1);
+                val_ptr.write(val_ptr.read() | NodeFlag::Selected.bit());
 
                 device.unmap_memory(self.memory);
             }
@@ -263,6 +351,8 @@ impl SelectionBuffer {
         let removed = self.latest_selection.difference(new_selection);
         let added = new_selection.difference(&self.latest_selection);
 
+        let selected = NodeFlag::Selected.bit();
+
         unsafe {
             let data_ptr = device.map_memory(
                 self.memory,
@@ -280,7 +370,7 @@ impl SelectionBuffer {
                 }
 
                 let val_ptr = val_ptr.add(ix);
-                val_ptr.write(0);
+                val_ptr.write(val_ptr.read() & !selected);
             }
 
             for &node in added {
@@ -292,7 +382,7 @@ impl SelectionBuffer {
                 }
 
                 let val_ptr = val_ptr.add(ix);
-                val_ptr.write(1);
+                val_ptr.write(val_ptr.read() | selected);
             }
 
             device.unmap_memory(self.memory);
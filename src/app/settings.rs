@@ -1,18 +1,44 @@
+use anyhow::Result;
+use ash::vk;
 use crossbeam::atomic::AtomicCell;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::{path::PathBuf, sync::Arc};
 
-use crate::vulkan::draw_system::edges::EdgesUBO;
+use crate::vulkan::draw_system::{
+    edges::EdgesUBO, nodes::NodeFocusMode, selection::OutlineStyle,
+};
 
 #[derive(Debug, Clone)]
 pub struct AppSettings {
     node_width: Arc<NodeWidth>,
 
     edge_renderer: Arc<AtomicCell<EdgesUBO>>,
+    edge_cull_scale: Arc<AtomicCell<Option<f32>>>,
+
+    node_culling: Arc<AtomicCell<bool>>,
+    node_lod_threshold: Arc<AtomicCell<Option<f32>>>,
+
+    selection_outline: Arc<AtomicCell<bool>>,
+    selection_outline_style: Arc<AtomicCell<OutlineStyle>>,
+
+    node_focus_mode: Arc<AtomicCell<NodeFocusMode>>,
+
+    view_controls: Arc<AtomicCell<ViewControls>>,
 
     label_radius: Arc<AtomicCell<f32>>,
+    node_id_labels: Arc<AtomicCell<Option<f32>>>,
 
     background_color_light: Arc<AtomicCell<rgb::RGB<f32>>>,
     background_color_dark: Arc<AtomicCell<rgb::RGB<f32>>>,
+    background_color_override: Arc<AtomicCell<Option<rgb::RGB<f32>>>>,
+
+    follow_system_theme: Arc<AtomicCell<bool>>,
+
+    msaa_samples: Arc<AtomicCell<vk::SampleCountFlags>>,
+
+    idle_fps: Arc<AtomicCell<Option<u32>>>,
+
+    relaxation_steps: Arc<AtomicCell<usize>>,
 }
 
 impl std::default::Default for AppSettings {
@@ -20,7 +46,15 @@ impl std::default::Default for AppSettings {
         Self {
             node_width: Default::default(),
             edge_renderer: Default::default(),
+            edge_cull_scale: Arc::new(None.into()),
+            node_culling: Arc::new(true.into()),
+            node_lod_threshold: Arc::new(Some(1.0).into()),
+            selection_outline: Arc::new(true.into()),
+            selection_outline_style: Default::default(),
+            node_focus_mode: Default::default(),
+            view_controls: Default::default(),
             label_radius: Arc::new(50.0.into()),
+            node_id_labels: Arc::new(None.into()),
 
             background_color_light: Arc::new(
                 rgb::RGB::new(1.0, 1.0, 1.0).into(),
@@ -28,11 +62,28 @@ impl std::default::Default for AppSettings {
             background_color_dark: Arc::new(
                 rgb::RGB::new(0.1, 0.1, 0.2).into(),
             ),
+            background_color_override: Arc::new(None.into()),
+
+            follow_system_theme: Arc::new(true.into()),
+
+            msaa_samples: Arc::new(vk::SampleCountFlags::TYPE_1.into()),
+
+            idle_fps: Arc::new(None.into()),
+
+            relaxation_steps: Arc::new(4.into()),
         }
     }
 }
 
 impl AppSettings {
+    /// The default settings, with the parts that are stored in the
+    /// user's config directory loaded from there
+    pub fn load() -> Self {
+        let settings = Self::default();
+        settings.view_controls.store(ViewControls::load_or_default());
+        settings
+    }
+
     pub fn node_width(&self) -> &Arc<NodeWidth> {
         &self.node_width
     }
@@ -45,10 +96,55 @@ impl AppSettings {
         self.edge_renderer.store(conf);
     }
 
+    /// The view scale above which edges are not drawn, if any
+    pub fn edge_cull_scale(&self) -> &Arc<AtomicCell<Option<f32>>> {
+        &self.edge_cull_scale
+    }
+
+    /// Whether nodes outside the viewport are culled on the GPU
+    /// before drawing
+    pub fn node_culling(&self) -> &Arc<AtomicCell<bool>> {
+        &self.node_culling
+    }
+
+    /// If set, only one node per level of detail grid cell is drawn
+    /// while the cells are smaller than this many pixels
+    pub fn node_lod_threshold(&self) -> &Arc<AtomicCell<Option<f32>>> {
+        &self.node_lod_threshold
+    }
+
+    /// Whether the selected nodes are outlined; if false, the
+    /// outline passes are never drawn
+    pub fn selection_outline(&self) -> &Arc<AtomicCell<bool>> {
+        &self.selection_outline
+    }
+
+    /// The color and blur radius of the selection outline, applied
+    /// as push constants when the outline passes are drawn
+    pub fn selection_outline_style(&self) -> &Arc<AtomicCell<OutlineStyle>> {
+        &self.selection_outline_style
+    }
+
+    /// How nodes outside the selection are drawn; ignored when
+    /// nothing is selected
+    pub fn node_focus_mode(&self) -> &Arc<AtomicCell<NodeFocusMode>> {
+        &self.node_focus_mode
+    }
+
+    pub fn view_controls(&self) -> &Arc<AtomicCell<ViewControls>> {
+        &self.view_controls
+    }
+
     pub fn label_radius(&self) -> &Arc<AtomicCell<f32>> {
         &self.label_radius
     }
 
+    /// If set, nodes at least this many pixels long on screen are
+    /// labeled with their IDs
+    pub fn node_id_labels(&self) -> &Arc<AtomicCell<Option<f32>>> {
+        &self.node_id_labels
+    }
+
     pub fn background_color_light(&self) -> &Arc<AtomicCell<rgb::RGB<f32>>> {
         &self.background_color_light
     }
@@ -56,6 +152,50 @@ impl AppSettings {
     pub fn background_color_dark(&self) -> &Arc<AtomicCell<rgb::RGB<f32>>> {
         &self.background_color_dark
     }
+
+    /// A background color used instead of the theme's light or dark
+    /// background, if set
+    pub fn background_color_override(
+        &self,
+    ) -> &Arc<AtomicCell<Option<rgb::RGB<f32>>>> {
+        &self.background_color_override
+    }
+
+    /// The color the main view is cleared to, i.e. the override if
+    /// there is one, otherwise the background of the current mode
+    pub fn background_color(&self, dark_mode: bool) -> rgb::RGB<f32> {
+        if let Some(color) = self.background_color_override.load() {
+            color
+        } else if dark_mode {
+            self.background_color_dark.load()
+        } else {
+            self.background_color_light.load()
+        }
+    }
+
+    /// Whether to switch between light and dark mode with the system
+    /// theme; cleared when the mode or theme is chosen manually
+    pub fn follow_system_theme(&self) -> &Arc<AtomicCell<bool>> {
+        &self.follow_system_theme
+    }
+
+    /// The requested MSAA sample count; the main loop applies it to
+    /// the renderer, and stores the count that was actually used
+    pub fn msaa_samples(&self) -> &Arc<AtomicCell<vk::SampleCountFlags>> {
+        &self.msaa_samples
+    }
+
+    /// If set, frames are drawn at most this many times per second
+    /// while nothing on screen is moving
+    pub fn idle_fps(&self) -> &Arc<AtomicCell<Option<u32>>> {
+        &self.idle_fps
+    }
+
+    /// The number of layout relaxation iterations submitted in each
+    /// compute dispatch while the relaxation is running
+    pub fn relaxation_steps(&self) -> &Arc<AtomicCell<usize>> {
+        &self.relaxation_steps
+    }
 }
 
 #[derive(Debug)]
@@ -65,6 +205,10 @@ pub struct NodeWidth {
 
     min_node_scale: AtomicCell<f32>,
     max_node_scale: AtomicCell<f32>,
+
+    /// If set, nodes are drawn at least this many pixels wide no
+    /// matter how far the view is zoomed out
+    min_node_pixels: AtomicCell<Option<f32>>,
 }
 
 impl NodeWidth {
@@ -83,6 +227,10 @@ impl NodeWidth {
         self.max_node_scale.load()
     }
 
+    pub fn min_node_pixels(&self) -> Option<f32> {
+        self.min_node_pixels.load()
+    }
+
     pub fn set_min_node_width(&self, width: f32) {
         self.min_node_width.store(width);
     }
@@ -98,6 +246,10 @@ impl NodeWidth {
     pub fn set_max_node_scale(&self, width: f32) {
         self.max_node_scale.store(width);
     }
+
+    pub fn set_min_node_pixels(&self, pixels: Option<f32>) {
+        self.min_node_pixels.store(pixels);
+    }
 }
 
 impl std::default::Default for NodeWidth {
@@ -108,6 +260,96 @@ impl std::default::Default for NodeWidth {
 
             min_node_scale: AtomicCell::new(1.0),
             max_node_scale: AtomicCell::new(50.0),
+
+            min_node_pixels: AtomicCell::new(None),
+        }
+    }
+}
+
+/// Pan and zoom behavior of the main view, stored in the user's
+/// config directory so that it's kept across sessions
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewControls {
+    /// Multiplier for keyboard and mouse panning
+    pub pan_speed: f32,
+
+    /// Multiplier for the mouse wheel zoom step
+    pub zoom_speed: f32,
+
+    /// Reverse the mouse wheel zoom direction
+    pub invert_zoom: bool,
+
+    /// The smallest view scale the view can zoom in to
+    pub min_scale: f32,
+
+    /// How far the view can zoom out, as a multiple of the scale that
+    /// fits the whole graph on screen
+    pub max_zoom_out: f32,
+}
+
+impl std::default::Default for ViewControls {
+    fn default() -> Self {
+        Self {
+            pan_speed: 1.0,
+            zoom_speed: 1.0,
+            invert_zoom: false,
+            min_scale: 0.01,
+            max_zoom_out: 2.0,
+        }
+    }
+}
+
+impl ViewControls {
+    fn config_file() -> Option<PathBuf> {
+        let mut path = dirs::config_dir()?;
+        path.push("gfaestus");
+        path.push("view_controls.json");
+        Some(path)
+    }
+
+    pub fn load_or_default() -> Self {
+        let path = if let Some(path) = Self::config_file() {
+            path
+        } else {
+            return Self::default();
+        };
+
+        if !path.exists() {
+            return Self::default();
+        }
+
+        let result = (|| -> Result<Self> {
+            let file = std::fs::File::open(&path)?;
+            let controls =
+                serde_json::from_reader(std::io::BufReader::new(file))?;
+            Ok(controls)
+        })();
+
+        result.unwrap_or_else(|err| {
+            log::warn!("Error loading view controls: {:?}", err);
+            Self::default()
+        })
+    }
+
+    pub fn store(&self) -> Result<()> {
+        if let Some(path) = Self::config_file() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let file = std::fs::File::create(path)?;
+            serde_json::to_writer_pretty(file, self)?;
+        }
+        Ok(())
+    }
+
+    /// Scale a mouse wheel delta by the zoom speed and direction
+    pub fn wheel_delta(&self, delta: f32) -> f32 {
+        let delta = delta * self.zoom_speed;
+        if self.invert_zoom {
+            -delta
+        } else {
+            delta
         }
     }
 }
@@ -3,8 +3,10 @@ use std::sync::Arc;
 use crossbeam::atomic::AtomicCell;
 use handlegraph::handle::NodeId;
 use handlegraph::pathhandlegraph::PathId;
+use parking_lot::RwLock;
 
-use crate::{geometry::*, gui::GuiFocusState};
+use crate::app::layers::{LayerInfo, LayerNode};
+use crate::{app::path_colors::PathColors, geometry::*, gui::GuiFocusState};
 use crate::{view::*, vulkan::texture::GradientName};
 
 #[derive(Clone)]
@@ -16,6 +18,13 @@ pub struct SharedState {
 
     pub hover_node: Arc<AtomicCell<Option<NodeId>>>,
 
+    /// The graph layer node under the cursor, if any
+    pub hover_layer_node: Arc<AtomicCell<Option<LayerNode>>>,
+
+    /// The graph layers drawn on top of the main graph, in draw
+    /// order
+    pub layers: Arc<RwLock<Vec<LayerInfo>>>,
+
     pub mouse_rect: MouseRect,
 
     pub overlay_state: OverlayState,
@@ -27,10 +36,15 @@ pub struct SharedState {
     pub dark_mode: Arc<AtomicCell<bool>>,
 
     pub show_modal: Arc<AtomicCell<bool>>,
+
+    pub path_colors: PathColors,
 }
 
 impl SharedState {
-    pub fn new<Dims: Into<ScreenDims>>(screen_dims: Dims) -> Self {
+    pub fn new<Dims: Into<ScreenDims>>(
+        screen_dims: Dims,
+        path_colors: PathColors,
+    ) -> Self {
         Self {
             mouse_pos: Arc::new(Point::ZERO.into()),
             screen_dims: Arc::new(screen_dims.into().into()),
@@ -39,6 +53,9 @@ impl SharedState {
 
             hover_node: Arc::new(None.into()),
 
+            hover_layer_node: Arc::new(None.into()),
+            layers: Arc::new(RwLock::new(Vec::new())),
+
             mouse_rect: MouseRect::default(),
 
             overlay_state: OverlayState::default(),
@@ -48,6 +65,8 @@ impl SharedState {
             edges_enabled: Arc::new(true.into()),
             dark_mode: Arc::new(false.into()),
             show_modal: Arc::new(false.into()),
+
+            path_colors,
         }
     }
 
@@ -71,6 +90,10 @@ impl SharedState {
         &self.overlay_state
     }
 
+    pub fn path_colors(&self) -> &PathColors {
+        &self.path_colors
+    }
+
     pub fn edges_enabled(&self) -> bool {
         self.edges_enabled.load()
     }
@@ -141,9 +164,18 @@ pub struct OverlayState {
     pub current_overlay: Arc<AtomicCell<Option<usize>>>,
 
     gradient: Arc<AtomicCell<GradientName>>,
+
+    /// If true, value overlays sample the gradient from the end
+    reversed: Arc<AtomicCell<bool>>,
+
+    /// The overlay that was active before it was toggled off
+    toggled_overlay: Arc<AtomicCell<Option<usize>>>,
 }
 
 impl OverlayState {
+    /// The overlay shown when overlays are toggled off
+    pub const DEFAULT_OVERLAY: usize = 0;
+
     pub fn current_overlay(&self) -> Option<usize> {
         self.current_overlay.load()
     }
@@ -154,11 +186,36 @@ impl OverlayState {
 
     pub fn set_current_overlay(&self, overlay_id: Option<usize>) {
         self.current_overlay.store(overlay_id);
+        self.toggled_overlay.store(None);
+    }
+
+    /// Switch between the current overlay and the default overlay,
+    /// i.e. the first one, restoring the previous overlay when
+    /// toggled back on
+    pub fn toggle_overlay(&self) {
+        let current = self.current_overlay.load();
+
+        if current == Some(Self::DEFAULT_OVERLAY) {
+            if let Some(previous) = self.toggled_overlay.take() {
+                self.current_overlay.store(Some(previous));
+            }
+        } else if current.is_some() {
+            self.current_overlay.store(Some(Self::DEFAULT_OVERLAY));
+            self.toggled_overlay.store(current);
+        }
     }
 
     pub fn set_gradient(&self, gradient: GradientName) {
         self.gradient.store(gradient);
     }
+
+    pub fn gradient_reversed(&self) -> bool {
+        self.reversed.load()
+    }
+
+    pub fn set_gradient_reversed(&self, reversed: bool) {
+        self.reversed.store(reversed);
+    }
 }
 
 impl std::default::Default for OverlayState {
@@ -167,9 +224,15 @@ impl std::default::Default for OverlayState {
 
         let gradient = Arc::new(AtomicCell::new(GradientName::Magma));
 
+        let reversed = Arc::new(AtomicCell::new(false));
+
+        let toggled_overlay = Arc::new(AtomicCell::new(None));
+
         Self {
             current_overlay,
             gradient,
+            reversed,
+            toggled_overlay,
         }
     }
 }
@@ -0,0 +1,150 @@
+use anyhow::Result;
+use crossbeam::{atomic::AtomicCell, channel};
+use serde::{Deserialize, Serialize};
+
+use std::sync::Arc;
+
+use crate::vulkan::texture::GradientName;
+
+/// The colors used by the main view and GUI, which can be stored as
+/// and loaded from JSON.
+///
+/// Every part of a theme is read by the renderers each frame, so
+/// switching themes only updates the settings and overlay state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Theme {
+    pub name: String,
+
+    /// Whether the GUI should use the dark style
+    pub dark: bool,
+
+    pub background: [f32; 3],
+    pub edge_color: [f32; 3],
+
+    /// Gradient used by value overlays
+    pub gradient: GradientName,
+}
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            name: "Light".to_string(),
+            dark: false,
+            background: [1.0, 1.0, 1.0],
+            edge_color: [0.1, 0.1, 0.1],
+            gradient: GradientName::Magma,
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            name: "Dark".to_string(),
+            dark: true,
+            background: [0.1, 0.1, 0.2],
+            edge_color: [0.8, 0.8, 0.8],
+            gradient: GradientName::Magma,
+        }
+    }
+
+    /// Uses the Cividis gradient, which reads the same with the
+    /// common forms of color blindness, and a blue from the
+    /// Okabe-Ito palette for edges.
+    pub fn color_blind_safe() -> Self {
+        Self {
+            name: "Color-blind safe".to_string(),
+            dark: false,
+            background: [0.98, 0.98, 0.96],
+            edge_color: [0.0, 0.447, 0.698],
+            gradient: GradientName::Cividis,
+        }
+    }
+
+    pub fn presets() -> Vec<Self> {
+        vec![Self::light(), Self::dark(), Self::color_blind_safe()]
+    }
+
+    pub fn background_rgb(&self) -> rgb::RGB<f32> {
+        let [r, g, b] = self.background;
+        rgb::RGB::new(r, g, b)
+    }
+
+    pub fn edge_color_rgb(&self) -> rgb::RGB<f32> {
+        let [r, g, b] = self.edge_color;
+        rgb::RGB::new(r, g, b)
+    }
+
+    pub fn save_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    pub fn load_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let theme = serde_json::from_reader(std::io::BufReader::new(file))?;
+        Ok(theme)
+    }
+}
+
+/// Whether the operating system prefers a dark theme, checked on a
+/// background thread so that a change of the system theme while the
+/// app is running is picked up. The thread is stopped and joined when
+/// this is dropped.
+pub struct SystemTheme {
+    dark: Arc<AtomicCell<bool>>,
+
+    stop_tx: Option<channel::Sender<()>>,
+    join_handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl SystemTheme {
+    const POLL_INTERVAL: std::time::Duration =
+        std::time::Duration::from_secs(2);
+
+    /// Check the system theme, and start the thread that keeps
+    /// checking it
+    pub fn watch() -> Self {
+        let dark = Arc::new(AtomicCell::new(Self::detect()));
+
+        let dark_ = dark.clone();
+
+        let (stop_tx, stop_rx) = channel::bounded::<()>(0);
+
+        let join_handle = std::thread::spawn(move || {
+            // nothing is sent on the channel, it's disconnected
+            // when the `SystemTheme` is dropped
+            while let Err(channel::RecvTimeoutError::Timeout) =
+                stop_rx.recv_timeout(Self::POLL_INTERVAL)
+            {
+                dark_.store(Self::detect());
+            }
+        });
+
+        Self {
+            dark,
+
+            stop_tx: Some(stop_tx),
+            join_handle: Some(join_handle),
+        }
+    }
+
+    pub fn is_dark(&self) -> bool {
+        self.dark.load()
+    }
+
+    fn detect() -> bool {
+        matches!(dark_light::detect(), dark_light::Mode::Dark)
+    }
+}
+
+impl Drop for SystemTheme {
+    fn drop(&mut self) {
+        self.stop_tx.take();
+
+        if let Some(join_handle) = self.join_handle.take() {
+            if join_handle.join().is_err() {
+                log::error!("System theme thread panicked");
+            }
+        }
+    }
+}
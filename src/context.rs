@@ -25,9 +25,13 @@ use rustc_hash::{FxHashMap, FxHashSet};
 use lazy_static::lazy_static;
 
 use crate::{
-    app::{selection::NodeSelection, App, AppChannels, AppMsg, SharedState},
+    app::{
+        selection::NodeSelection, App, AppChannels, AppMsg, OverlayCreatorMsg,
+        Select, SharedState,
+    },
     geometry::{Point, Rect},
-    gui::console::Console,
+    graph_query::{reverse_complement_seq, GraphQuery},
+    gui::{console::Console, toasts::Toast, GuiMsg},
     reactor::{ModalError, ModalHandler, ModalSuccess, Reactor},
 };
 
@@ -35,6 +39,10 @@ use crate::{
 
 pub struct OverGraph {}
 
+/// The point in the layout under the cursor, in world coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldPoint(pub Point);
+
 #[derive(Default, Clone)]
 pub struct Context {
     values: FxHashMap<TypeId, Arc<rhai::Dynamic>>,
@@ -120,6 +128,40 @@ impl ContextAction {
     }
 }
 
+/// A command that doesn't depend on the context, listed in the
+/// command palette along with the context actions
+#[derive(Clone)]
+pub struct GlobalCommand {
+    action: Arc<dyn Fn(&App) + Send + Sync + 'static>,
+}
+
+impl GlobalCommand {
+    pub fn new(action: impl Fn(&App) + Send + Sync + 'static) -> Self {
+        Self {
+            action: Arc::new(action),
+        }
+    }
+
+    /// A command that sends `msg` to the app
+    pub fn app_msg(msg: impl Fn() -> AppMsg + Send + Sync + 'static) -> Self {
+        Self::new(move |app| app.channels.app_tx.send(msg()).unwrap())
+    }
+}
+
+#[derive(Clone)]
+enum PaletteEntry {
+    Action(ContextAction),
+    Command(GlobalCommand),
+}
+
+#[derive(Default)]
+struct CommandPalette {
+    open: bool,
+    focus_query: bool,
+    query: String,
+    selected: usize,
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum InitState {
     Null,
@@ -141,6 +183,10 @@ pub struct ContextMgr {
     load_context_this_frame: Arc<AtomicCell<bool>>,
     context_menu_open: Arc<AtomicCell<bool>>,
 
+    /// Set when the context menu is (re)opened, so that the click
+    /// that opened it doesn't also count as a click outside the menu
+    just_opened: Arc<AtomicCell<bool>>,
+
     ctx_tx: channel::Sender<(TypeId, rhai::Dynamic)>,
     ctx_rx: channel::Receiver<(TypeId, rhai::Dynamic)>,
 
@@ -149,10 +195,30 @@ pub struct ContextMgr {
 
     // context_order: RwLock<Vec<String>>,
     context_actions: RwLock<HashMap<String, ContextAction>>,
+    global_commands: RwLock<HashMap<String, GlobalCommand>>,
+
+    command_palette: Mutex<CommandPalette>,
 
     position: Arc<AtomicCell<Point>>,
 }
 
+/// Create a context action from a Rhai script.
+///
+/// The script must export two constants and define an `action`
+/// function:
+///
+/// * `name`: the label shown in the context menu
+/// * `context_types`: an array of context type names, e.g.
+///   `["NodeId", "PathId"]`; the action is only offered when all of
+///   them are present in the current context
+/// * `fn action(context)`: called with the current context when the
+///   menu item is clicked
+///
+/// Inside `action`, `context.get("NodeId")` returns the value of the
+/// named type as the corresponding Rhai type (`NodeId`, `PathId`,
+/// `FxHashSet<NodeId>`), and `context.has("PathId")` can be used to
+/// check for optional values. `get` returns `()` if the value is
+/// missing.
 pub fn rhai_context_action(
     context_mgr: &ContextMgr,
     script_path: &str,
@@ -162,54 +228,67 @@ pub fn rhai_context_action(
     engine.register_type_with_name::<Arc<Context>>("Arc<Context>");
 
     let type_names = context_mgr.ctx_type_map.clone();
-
     engine.register_fn(
         "get",
         move |ctx: &mut Arc<Context>, type_name: &str| {
             let name_to_id = type_names.name_to_id.read();
-            let id = name_to_id.get(type_name).unwrap();
-            let val = ctx.get_dyn(*id).unwrap();
-            val
+            name_to_id
+                .get(type_name)
+                .and_then(|id| ctx.get_dyn(*id))
+                .unwrap_or_default()
         },
     );
-    let ast = engine.compile_file(script_path.into());
-    if ast.is_err() {
-        log::warn!("{:?}", ast);
-    }
-    let ast = ast?;
-    let module =
-        rhai::Module::eval_ast_as_new(rhai::Scope::new(), &ast, &engine);
 
-    if module.is_err() {
-        log::warn!("{:?}", module);
-    }
-    let module = module?;
+    let type_names = context_mgr.ctx_type_map.clone();
+    engine.register_fn(
+        "has",
+        move |ctx: &mut Arc<Context>, type_name: &str| {
+            let name_to_id = type_names.name_to_id.read();
+            name_to_id
+                .get(type_name)
+                .map(|id| ctx.values.contains_key(id))
+                .unwrap_or(false)
+        },
+    );
 
-    let mut req: FxHashSet<TypeId> = FxHashSet::default();
+    let ast = engine.compile_file(script_path.into())?;
+    let module =
+        rhai::Module::eval_ast_as_new(rhai::Scope::new(), &ast, &engine)?;
 
     let name_to_id = context_mgr.ctx_type_map.name_to_id.read();
 
+    let mut req: FxHashSet<TypeId> = FxHashSet::default();
+
     if let Some(types) = module.get_var("context_types") {
-        let types: rhai::Array = types.cast();
+        let types: rhai::Array = types.try_cast().ok_or_else(|| {
+            anyhow::anyhow!("`context_types` must be an array of strings")
+        })?;
 
         for t in types {
-            if let Ok(name) = t.into_immutable_string() {
-                if let Some(type_id) = name_to_id.get(name.as_str()) {
-                    req.insert(*type_id);
-                }
-            }
+            let name = t.into_immutable_string().map_err(|_| {
+                anyhow::anyhow!("`context_types` must be an array of strings")
+            })?;
+
+            let type_id = name_to_id.get(name.as_str()).ok_or_else(|| {
+                anyhow::anyhow!("unknown context type \"{}\"", name)
+            })?;
+
+            req.insert(*type_id);
         }
     }
 
-    let action_name = if let Some(name) = module.get_var("name") {
-        if let Ok(name) = name.into_immutable_string() {
-            name.to_string()
-        } else {
-            "something went wrong".to_string()
-        }
-    } else {
-        "something went wrong".to_string()
-    };
+    let action_name = module
+        .get_var("name")
+        .and_then(|name| name.into_immutable_string().ok())
+        .ok_or_else(|| anyhow::anyhow!("missing `name` constant"))?
+        .to_string();
+
+    if !ast
+        .iter_functions()
+        .any(|f| f.name == "action" && f.params.len() == 1)
+    {
+        anyhow::bail!("missing `action(context)` function");
+    }
 
     let reqs: Vec<_> = req.into_iter().collect();
 
@@ -217,10 +296,18 @@ pub fn rhai_context_action(
         engine, ast, "action",
     );
 
+    let script_path = script_path.to_string();
+
     let action = ContextAction::new(
         &reqs,
         Box::new(move |ctx| {
-            action_fn(ctx).unwrap();
+            if let Err(err) = action_fn(ctx) {
+                log::error!(
+                    "Error running context action {}: {:?}",
+                    script_path,
+                    err
+                );
+            }
         }),
     );
 
@@ -267,6 +354,218 @@ pub fn copy_node_id_action(app: &App) -> ContextAction {
     )
 }
 
+/// Summarize the neighborhood of `node_id` in the forward
+/// orientation: its sequence length, and its left and right
+/// neighbors, each with the orientation it's reached in, e.g.:
+///
+/// ```text
+/// Node 12, 34 bp
+/// Left:  5+ 7-
+/// Right: 13+
+/// ```
+pub fn node_neighbors_summary(
+    graph_query: &GraphQuery,
+    node_id: NodeId,
+) -> Option<String> {
+    let graph = graph_query.graph();
+
+    if !graph.has_node(node_id) {
+        return None;
+    }
+
+    let handle = Handle::pack(node_id, false);
+
+    let neighbors = |dir: Direction| {
+        let mut neighbors = graph
+            .neighbors(handle, dir)
+            .map(|h| {
+                let orient = if h.is_reverse() { '-' } else { '+' };
+                format!("{}{}", h.id().0, orient)
+            })
+            .collect::<Vec<_>>();
+
+        if neighbors.is_empty() {
+            neighbors.push("none".to_string());
+        }
+
+        neighbors.join(" ")
+    };
+
+    Some(format!(
+        "Node {}, {} bp\nLeft:  {}\nRight: {}",
+        node_id.0,
+        graph_query.node_len(handle),
+        neighbors(Direction::Left),
+        neighbors(Direction::Right)
+    ))
+}
+
+pub fn copy_node_neighbors_action(app: &App) -> ContextAction {
+    let app_msg_tx = app.channels.app_tx.clone();
+    let graph_query = app.reactor.graph_query.clone();
+
+    let req = [TypeId::of::<NodeId>()];
+
+    ContextAction::new(
+        &req,
+        Box::new(move |ctx| {
+            let node_id = *ctx.read_lock::<NodeId>().unwrap();
+
+            if let Some(contents) =
+                node_neighbors_summary(&graph_query, node_id)
+            {
+                app_msg_tx
+                    .send(AppMsg::set_clipboard_contents(&contents))
+                    .unwrap();
+            }
+        }),
+    )
+}
+
+/// The largest neighborhood `node_neighborhood` will collect, so
+/// that a few hops from a hub don't select most of the graph
+pub const NEIGHBORHOOD_MAX_NODES: usize = 50_000;
+
+/// Collect the nodes within `hops` steps of `node_id`, following
+/// edges in both directions, with a breadth-first search. The search
+/// stops early once `max_nodes` nodes have been found, in which case
+/// the second value is true.
+pub fn node_neighborhood(
+    graph: &PackedGraph,
+    node_id: NodeId,
+    hops: usize,
+    max_nodes: usize,
+) -> (FxHashSet<NodeId>, bool) {
+    let mut nodes: FxHashSet<NodeId> = FxHashSet::default();
+
+    if !graph.has_node(node_id) {
+        return (nodes, false);
+    }
+
+    nodes.insert(node_id);
+
+    let mut frontier = vec![node_id];
+
+    for _ in 0..hops {
+        let mut next = Vec::new();
+
+        for &id in frontier.iter() {
+            let handle = Handle::pack(id, false);
+
+            let neighbors = graph
+                .neighbors(handle, Direction::Left)
+                .chain(graph.neighbors(handle, Direction::Right));
+
+            for other in neighbors {
+                if nodes.len() >= max_nodes {
+                    return (nodes, true);
+                }
+
+                if nodes.insert(other.id()) {
+                    next.push(other.id());
+                }
+            }
+        }
+
+        if next.is_empty() {
+            break;
+        }
+
+        frontier = next;
+    }
+
+    (nodes, false)
+}
+
+/// Ask for a number of hops, then select the nodes within that many
+/// hops of the node, and frame the view on them
+pub fn select_neighborhood_action(app: &App) -> ContextAction {
+    let graph = app.reactor.graph_query.graph_arc().clone();
+    let app_tx = app.channels.app_tx.clone();
+    let toast_tx = app.reactor.toast_tx.clone();
+    let show_modal = app.shared_state.show_modal.clone();
+    let modal_tx = app.channels.modal_tx.clone();
+
+    let futures_tx = app.reactor.future_tx.clone();
+
+    let req = [TypeId::of::<NodeId>()];
+
+    ContextAction::new(
+        &req,
+        Box::new(move |ctx| {
+            let node_id = *ctx.read_lock::<NodeId>().unwrap();
+
+            let (result_tx, mut result_rx) =
+                futures::channel::mpsc::channel::<Option<usize>>(1);
+
+            let callback =
+                move |hops: &mut usize, ui: &mut egui::Ui, force: bool| {
+                    ui.label(format!("Neighborhood of node {}", node_id.0));
+
+                    ui.horizontal(|ui| {
+                        ui.label("Hops");
+                        ui.add(egui::DragValue::new(hops).clamp_range(1..=100));
+                    });
+
+                    if force {
+                        return Ok(ModalSuccess::Success);
+                    }
+
+                    Err(ModalError::Continue)
+                };
+
+            let prepared = ModalHandler::prepare_callback(
+                &show_modal,
+                2,
+                callback,
+                result_tx,
+            );
+
+            modal_tx.send(prepared).unwrap();
+
+            let graph = graph.clone();
+            let app_tx = app_tx.clone();
+            let toast_tx = toast_tx.clone();
+
+            let fut = async move {
+                let hops = match result_rx.next().await.flatten() {
+                    Some(hops) => hops,
+                    None => return,
+                };
+
+                let (nodes, truncated) = node_neighborhood(
+                    &graph,
+                    node_id,
+                    hops,
+                    NEIGHBORHOOD_MAX_NODES,
+                );
+
+                if truncated {
+                    let msg = format!(
+                        "Neighborhood of node {} cut off at {} nodes",
+                        node_id.0,
+                        nodes.len()
+                    );
+                    log::warn!("{}", msg);
+                    toast_tx.send(Toast::warning(msg)).unwrap();
+                }
+
+                let ids = nodes.iter().copied().collect::<Vec<_>>();
+
+                app_tx
+                    .send(AppMsg::Selection(Select::Many {
+                        nodes,
+                        clear: true,
+                    }))
+                    .unwrap();
+                app_tx.send(AppMsg::goto_nodes(ids)).unwrap();
+            };
+
+            futures_tx.send(Box::pin(fut) as _).unwrap();
+        }),
+    )
+}
+
 pub fn pan_to_node_action(app: &App) -> ContextAction {
     let req = [];
 
@@ -279,6 +578,8 @@ pub fn pan_to_node_action(app: &App) -> ContextAction {
 
     let futures_tx = app.reactor.future_tx.clone();
 
+    let clipboard_ctx = app.reactor.clipboard_ctx.clone();
+
     ContextAction::new(
         &req,
         Box::new(move |ctx| {
@@ -287,15 +588,37 @@ pub fn pan_to_node_action(app: &App) -> ContextAction {
 
             let first_run = AtomicCell::new(true);
 
+            let clipboard_ctx = clipboard_ctx.clone();
+            let modal_graph = graph.clone();
+
             let callback =
                 move |text: &mut String, ui: &mut egui::Ui, force: bool| {
-                    ui.label("Enter node ID");
+                    ui.label("Enter node ID, or a list of IDs");
                     let text_box = ui.text_edit_singleline(text);
 
                     if first_run.fetch_and(false) {
                         text_box.request_focus();
                     }
 
+                    if ui.button("Paste from clipboard").clicked() {
+                        let mut clipboard = clipboard_ctx.lock();
+                        match clipboard.get_contents() {
+                            Ok(contents) => *text = contents.trim().to_string(),
+                            Err(err) => {
+                                log::warn!("Error reading clipboard: {:?}", err)
+                            }
+                        }
+                    }
+
+                    let (ids, invalid) = parse_node_ids(&modal_graph, text);
+                    if ids.len() > 1 || invalid > 0 {
+                        ui.label(format!(
+                            "{} nodes, {} invalid IDs skipped",
+                            ids.len(),
+                            invalid
+                        ));
+                    }
+
                     if text_box.lost_focus()
                         && ui.input().key_pressed(egui::Key::Enter)
                         || force
@@ -321,11 +644,21 @@ pub fn pan_to_node_action(app: &App) -> ContextAction {
             let fut = async move {
                 let value = result_rx.next().await.flatten();
 
-                if let Some(parsed) = value.and_then(|v| v.parse::<u64>().ok())
-                {
-                    let node_id = NodeId::from(parsed);
-                    if graph.has_node(node_id) {
-                        app_tx.send(AppMsg::goto_node(node_id)).unwrap();
+                if let Some(text) = value {
+                    let (ids, invalid) = parse_node_ids(&graph, &text);
+
+                    if invalid > 0 {
+                        log::warn!("Skipped {} invalid node IDs", invalid);
+                    }
+
+                    match ids.as_slice() {
+                        [] => (),
+                        [node_id] => {
+                            app_tx.send(AppMsg::goto_node(*node_id)).unwrap();
+                        }
+                        _ => {
+                            app_tx.send(AppMsg::goto_nodes(ids)).unwrap();
+                        }
                     }
                 }
             };
@@ -335,6 +668,375 @@ pub fn pan_to_node_action(app: &App) -> ContextAction {
     )
 }
 
+/// Parse a comma- or whitespace-separated list of node IDs,
+/// returning the IDs that exist in `graph` and the number of entries
+/// that were skipped
+fn parse_node_ids(graph: &PackedGraph, text: &str) -> (Vec<NodeId>, usize) {
+    let mut invalid = 0;
+
+    let ids = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| {
+            let id = s
+                .parse::<u64>()
+                .ok()
+                .map(NodeId::from)
+                .filter(|id| graph.has_node(*id));
+            if id.is_none() {
+                invalid += 1;
+            }
+            id
+        })
+        .collect();
+
+    (ids, invalid)
+}
+
+/// Write the subgraph induced by `nodes` as GFA 1.0, including the
+/// links between selected nodes, with segments in node ID order.
+///
+/// Self-loops are written with the orientations they have in the
+/// graph, and parallel edges are written once per copy.
+pub fn subgraph_gfa(
+    graph_query: &GraphQuery,
+    nodes: &FxHashSet<NodeId>,
+) -> String {
+    use std::collections::BTreeMap;
+    use std::fmt::Write;
+
+    let graph = graph_query.graph();
+
+    let mut node_ids = nodes.iter().copied().collect::<Vec<_>>();
+    node_ids.sort();
+
+    // the number of times each edge was seen while walking the
+    // neighbors of the selected nodes
+    let mut edges: BTreeMap<(Handle, Handle), usize> = BTreeMap::new();
+
+    let mut gfa = String::from("H\tVN:Z:1.0\n");
+
+    for &node_id in node_ids.iter() {
+        let handle = Handle::pack(node_id, false);
+
+        if !graph.has_node(node_id) {
+            continue;
+        }
+
+        let seq = graph_query.sequence_vec(handle);
+        writeln!(gfa, "S\t{}\t{}", node_id.0, seq.as_bstr()).unwrap();
+
+        for &from in &[handle, handle.flip()] {
+            for to in graph.neighbors(from, Direction::Right) {
+                if nodes.contains(&to.id()) {
+                    // an edge can be traversed from either end, so store
+                    // only one of its two equivalent representations
+                    let edge = (from, to).min((to.flip(), from.flip()));
+                    *edges.entry(edge).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    let orient = |h: Handle| if h.is_reverse() { '-' } else { '+' };
+
+    for ((from, to), seen) in edges {
+        // most edges are seen once from each end, but an edge that
+        // is its own reverse, like the self-loop 1+ -> 1-, is only
+        // seen once
+        let copies = if (from, to) == (to.flip(), from.flip()) {
+            seen
+        } else {
+            (seen / 2).max(1)
+        };
+
+        for _ in 0..copies {
+            writeln!(
+                gfa,
+                "L\t{}\t{}\t{}\t{}\t0M",
+                from.id().0,
+                orient(from),
+                to.id().0,
+                orient(to)
+            )
+            .unwrap();
+        }
+    }
+
+    gfa
+}
+
+pub fn copy_subgraph_gfa_action(app: &App) -> ContextAction {
+    let app_msg_tx = app.channels.app_tx.clone();
+    let graph = app.reactor.graph_query.clone();
+
+    let req = [TypeId::of::<FxHashSet<NodeId>>()];
+
+    ContextAction::new(
+        &req,
+        Box::new(move |ctx| {
+            let nodes = ctx.read_lock::<FxHashSet<NodeId>>().unwrap();
+            let contents = subgraph_gfa(&graph, &nodes);
+            app_msg_tx
+                .send(AppMsg::set_clipboard_contents(&contents))
+                .unwrap();
+        }),
+    )
+}
+
+/// Spell out the sequence of the steps on `path` whose nodes are in
+/// `nodes`. Each contiguous run of selected steps ends up on its own
+/// line.
+pub fn path_sub_sequence(
+    graph_query: &GraphQuery,
+    path: PathId,
+    nodes: &FxHashSet<NodeId>,
+) -> Option<String> {
+    let graph = graph_query.graph();
+    let steps = graph.path_steps(path)?;
+
+    let mut runs: Vec<Vec<u8>> = Vec::new();
+    let mut in_run = false;
+
+    for step in steps {
+        let handle = step.handle();
+
+        if !nodes.contains(&handle.id()) {
+            in_run = false;
+            continue;
+        }
+
+        if !in_run {
+            runs.push(Vec::new());
+            in_run = true;
+        }
+
+        let seq = graph_query.sequence_vec(Handle::pack(handle.id(), false));
+        let run = runs.last_mut().unwrap();
+
+        if handle.is_reverse() {
+            run.extend(reverse_complement_seq(&seq));
+        } else {
+            run.extend(seq);
+        }
+    }
+
+    let runs = runs
+        .iter()
+        .map(|run| run.to_str_lossy())
+        .collect::<Vec<_>>();
+
+    Some(runs.join("\n"))
+}
+
+pub fn copy_path_sub_sequence_action(app: &App) -> ContextAction {
+    let app_msg_tx = app.channels.app_tx.clone();
+    let graph = app.reactor.graph_query.clone();
+
+    let req = [TypeId::of::<PathId>(), TypeId::of::<FxHashSet<NodeId>>()];
+
+    ContextAction::new(
+        &req,
+        Box::new(move |ctx| {
+            let path = *ctx.read_lock::<PathId>().unwrap();
+            let nodes = ctx.read_lock::<FxHashSet<NodeId>>().unwrap();
+
+            if let Some(contents) = path_sub_sequence(&graph, path, &nodes) {
+                app_msg_tx
+                    .send(AppMsg::set_clipboard_contents(&contents))
+                    .unwrap();
+            }
+        }),
+    )
+}
+
+pub fn frame_path_action(app: &App) -> ContextAction {
+    let app_msg_tx = app.channels.app_tx.clone();
+
+    let req = [TypeId::of::<PathId>()];
+
+    ContextAction::new(
+        &req,
+        Box::new(move |ctx| {
+            let path = *ctx.read_lock::<PathId>().unwrap();
+            app_msg_tx.send(AppMsg::goto_path(path)).unwrap();
+        }),
+    )
+}
+
+pub fn add_marker_action(app: &App) -> ContextAction {
+    let gui_tx = app.channels.gui_tx.clone();
+
+    let req = [TypeId::of::<WorldPoint>()];
+
+    ContextAction::new(
+        &req,
+        Box::new(move |ctx| {
+            let WorldPoint(point) = *ctx.read_lock::<WorldPoint>().unwrap();
+            gui_tx.send(GuiMsg::AddMarker { point }).unwrap();
+        }),
+    )
+}
+
+/// A command that scans the graph on the thread pool, and replaces
+/// the selection with the nodes for which `filter` returns true. The
+/// number of nodes found is shown in a toast, with `desc` describing
+/// them.
+pub fn select_nodes_command<F>(desc: &'static str, filter: F) -> GlobalCommand
+where
+    F: Fn(&PackedGraph, Handle) -> bool + Send + Sync + Copy + 'static,
+{
+    GlobalCommand::new(move |app| {
+        let graph = app.reactor.graph_query.graph_arc().clone();
+        let app_msg_tx = app.channels.app_tx.clone();
+        let toast_tx = app.channels.toast_tx.clone();
+
+        let result = app.reactor.spawn_forget(async move {
+            let nodes = graph
+                .handles()
+                .filter(|&handle| filter(&graph, handle))
+                .map(|handle| handle.id())
+                .collect::<FxHashSet<_>>();
+
+            let msg = format!("Selected {} {}", nodes.len(), desc);
+            log::info!("{}", msg);
+            let _ = toast_tx.send(Toast::info(msg));
+
+            app_msg_tx
+                .send(AppMsg::Selection(Select::Many { nodes, clear: true }))
+                .unwrap();
+        });
+
+        if let Err(err) = result {
+            log::error!("Error scanning the graph for {}: {:?}", desc, err);
+        }
+    })
+}
+
+/// A command that asks for a TSV or CSV file of node IDs and values,
+/// and creates a value overlay from it, named after the file. Rows
+/// for nodes that aren't in the graph are skipped, and the nodes
+/// without a value are drawn in gray.
+pub fn load_node_value_table_command() -> GlobalCommand {
+    GlobalCommand::new(move |app| {
+        let graph = app.reactor.graph_query.graph_arc().clone();
+
+        let modal_tx = app.channels.modal_tx.clone();
+        let show_modal = app.shared_state.show_modal.clone();
+
+        let overlay_tx = app.channels.new_overlay_tx.clone();
+        let toast_tx = app.channels.toast_tx.clone();
+
+        let path_future = crate::reactor::file_picker_modal(
+            modal_tx,
+            &show_modal,
+            &["tsv", "csv", "txt"],
+            None,
+        );
+
+        app.reactor.thread_pool.spawn_ok(async move {
+            let path = if let Some(path) = path_future.await {
+                path
+            } else {
+                return;
+            };
+
+            let result = std::fs::File::open(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|file| {
+                    let reader = std::io::BufReader::new(file);
+                    crate::overlays::NodeValueTable::parse(&graph, reader)
+                });
+
+            let table = match result {
+                Ok(table) => table,
+                Err(err) => {
+                    log::error!("Error loading node values: {:?}", err);
+                    let toast = Toast::error(format!(
+                        "Error loading node values: {}",
+                        err
+                    ));
+                    toast_tx.send(toast).unwrap();
+                    return;
+                }
+            };
+
+            if table.values.is_empty() {
+                let toast = Toast::warning(format!(
+                    "No node values found in {}",
+                    path.display()
+                ));
+                toast_tx.send(toast).unwrap();
+                return;
+            }
+
+            let mut problems = Vec::new();
+
+            if table.unknown_nodes > 0 {
+                problems.push(format!(
+                    "{} rows for nodes not in the graph",
+                    table.unknown_nodes
+                ));
+            }
+
+            if table.duplicates > 0 {
+                problems.push(format!(
+                    "{} duplicate rows, the last value was used",
+                    table.duplicates
+                ));
+            }
+
+            if table.invalid_rows > 0 {
+                problems.push(format!(
+                    "{} rows that couldn't be parsed",
+                    table.invalid_rows
+                ));
+            }
+
+            if !problems.is_empty() {
+                let msg = format!(
+                    "Node values from {}: {}",
+                    path.display(),
+                    problems.join(", ")
+                );
+                log::warn!("{}", msg);
+                toast_tx.send(Toast::warning(msg)).unwrap();
+            }
+
+            log::info!(
+                "Loaded values for {} nodes from {}",
+                table.values.len(),
+                path.display()
+            );
+
+            let name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("Node values")
+                .to_string();
+
+            let data = table.to_overlay(graph.node_count());
+
+            overlay_tx
+                .send(OverlayCreatorMsg::NewOverlay { name, data })
+                .unwrap();
+        });
+    })
+}
+
+/// True if no path steps on the node of `handle`
+pub fn node_has_no_paths(graph: &PackedGraph, handle: Handle) -> bool {
+    graph
+        .steps_on_handle(handle)
+        .map_or(true, |mut steps| steps.next().is_none())
+}
+
+/// True if the node of `handle` has no edges
+pub fn node_is_orphan(graph: &PackedGraph, handle: Handle) -> bool {
+    graph.degree(handle, Direction::Left) == 0
+        && graph.degree(handle, Direction::Right) == 0
+}
+
 impl std::default::Default for ContextMgr {
     fn default() -> Self {
         let (ctx_tx, ctx_rx) = channel::unbounded();
@@ -345,10 +1047,13 @@ impl std::default::Default for ContextMgr {
             ctx_rx,
             load_context_this_frame: Arc::new(false.into()),
             context_menu_open: Arc::new(false.into()),
+            just_opened: Arc::new(false.into()),
             frame_context: Arc::new(Context::default()).into(),
             frame_active: false.into(),
             // context_order: RwLock::new(Vec::default()),
             context_actions: RwLock::new(HashMap::default()),
+            global_commands: RwLock::new(HashMap::default()),
+            command_palette: Mutex::new(CommandPalette::default()),
             // type_names: RwLock::new(FxHashMap::default()),
             position: Arc::new(Point::ZERO.into()),
             ctx_type_map: Arc::new(CtxTypeMap::default()),
@@ -366,13 +1071,22 @@ impl ContextMgr {
             let dir = entry?.path();
             if let Some(ext) = dir.extension().and_then(|os| os.to_str()) {
                 if ext == "rhai" {
-                    let (name, action) = rhai_context_action(
+                    match rhai_context_action(
                         self,
                         dir.as_os_str().to_str().unwrap(),
                         console.create_engine(),
-                    )
-                    .unwrap();
-                    self.register_action(&name, action);
+                    ) {
+                        Ok((name, action)) => {
+                            self.register_action(&name, action);
+                        }
+                        Err(err) => {
+                            log::error!(
+                                "Error loading context action {:?}: {:?}",
+                                dir,
+                                err
+                            );
+                        }
+                    }
                 }
             }
         }
@@ -388,6 +1102,14 @@ impl ContextMgr {
         }
     }
 
+    pub fn register_command(&self, name: &str, command: GlobalCommand) {
+        let mut commands = self.global_commands.write();
+
+        if commands.insert(name.to_string(), command).is_some() {
+            log::warn!("global command overwritten: {}", name);
+        }
+    }
+
     pub fn set_type_name_ez<T>(&self)
     where
         T: std::any::Any + Send + Sync + 'static,
@@ -440,13 +1162,14 @@ impl ContextMgr {
         }
     }
 
+    /// Open the context menu, or move it if it's already open, using
+    /// the context at the current mouse position
     pub fn open_context_menu(&self, ctx: &egui::CtxRef) {
         ctx.memory().open_popup(Self::popup_id());
 
-        if !self.context_menu_open.load() {
-            self.load_context_this_frame.store(true);
-        }
+        self.load_context_this_frame.store(true);
         self.context_menu_open.store(true);
+        self.just_opened.store(true);
     }
 
     pub fn set_position(&self, pos: Point) {
@@ -458,6 +1181,11 @@ impl ContextMgr {
         self.context_menu_open.store(false);
     }
 
+    /// Whether the context menu or the command palette is open
+    pub fn is_menu_open(&self) -> bool {
+        self.context_menu_open.load() || self.command_palette.lock().open
+    }
+
     pub fn begin_frame(&mut self) {
         if matches!(self.init.load(), InitState::Null) {
             self.init.store(InitState::Initializing);
@@ -492,12 +1220,166 @@ impl ContextMgr {
 
     const ID: &'static str = "context_menu";
 
+    const PALETTE_ID: &'static str = "command_palette";
+
     const POPUP_ID: &'static str = "context_menu_popup_id";
 
     fn popup_id() -> egui::Id {
         egui::Id::new(Self::POPUP_ID)
     }
 
+    /// Open the command palette, loading the current context so that
+    /// only the applicable context actions can be run
+    pub fn open_command_palette(&self) {
+        let mut palette = self.command_palette.lock();
+
+        if !palette.open {
+            *palette = CommandPalette {
+                open: true,
+                focus_query: true,
+                ..CommandPalette::default()
+            };
+            self.load_context_this_frame.store(true);
+        }
+    }
+
+    /// The global commands and context actions whose names contain
+    /// `query`, ignoring case, with a flag for whether each can be
+    /// run in the current context. Runnable entries are sorted first.
+    fn palette_entries(
+        &self,
+        query: &str,
+    ) -> Vec<(String, PaletteEntry, bool)> {
+        let query = query.to_lowercase();
+        let matches = |name: &str| name.to_lowercase().contains(&query);
+
+        let context = &self.frame_context;
+
+        let commands = self.global_commands.read();
+        let actions = self.context_actions.read();
+
+        let mut entries = commands
+            .iter()
+            .filter(|(name, _)| matches(name))
+            .map(|(name, cmd)| {
+                (name.to_string(), PaletteEntry::Command(cmd.clone()), true)
+            })
+            .chain(actions.iter().filter(|(name, _)| matches(name)).map(
+                |(name, action)| {
+                    let applicable = action.is_applicable(context);
+                    let entry = PaletteEntry::Action(action.clone());
+                    (name.to_string(), entry, applicable)
+                },
+            ))
+            .collect::<Vec<_>>();
+
+        entries.sort_by(|(n0, _, e0), (n1, _, e1)| {
+            e1.cmp(e0).then_with(|| n0.cmp(n1))
+        });
+
+        entries
+    }
+
+    pub fn show_command_palette(&self, egui_ctx: &egui::CtxRef, app: &App) {
+        let mut palette = self.command_palette.lock();
+
+        if !palette.open {
+            return;
+        }
+
+        let entries = self.palette_entries(&palette.query);
+        let runnable = entries.iter().filter(|(_, _, e)| *e).count();
+
+        let (up, down, enter, escape) = {
+            let input = egui_ctx.input();
+            (
+                input.key_pressed(egui::Key::ArrowUp),
+                input.key_pressed(egui::Key::ArrowDown),
+                input.key_pressed(egui::Key::Enter),
+                input.key_pressed(egui::Key::Escape),
+            )
+        };
+
+        // only runnable entries can be selected, and they come first
+        if down && palette.selected + 1 < runnable {
+            palette.selected += 1;
+        }
+        if up && palette.selected > 0 {
+            palette.selected -= 1;
+        }
+
+        let mut to_run: Option<PaletteEntry> = None;
+
+        let screen_rect = egui_ctx.input().screen_rect();
+        let pos = Point::new(screen_rect.center().x - 200.0, 80.0);
+
+        let prev_query = palette.query.clone();
+
+        egui::Window::new("Command palette")
+            .id(egui::Id::new(Self::PALETTE_ID))
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .fixed_pos(pos)
+            .show(egui_ctx, |ui| {
+                ui.set_width(400.0);
+
+                let query_box = ui.text_edit_singleline(&mut palette.query);
+
+                if palette.focus_query {
+                    query_box.request_focus();
+                    palette.focus_query = false;
+                }
+
+                ui.separator();
+
+                if entries.is_empty() {
+                    ui.label("No matching commands");
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for (ix, (name, entry, runnable)) in
+                            entries.iter().enumerate()
+                        {
+                            let selected = *runnable && ix == palette.selected;
+
+                            let label = ui.add_enabled(
+                                *runnable,
+                                egui::SelectableLabel::new(selected, name),
+                            );
+
+                            if label.clicked() {
+                                to_run = Some(entry.clone());
+                            }
+                        }
+                    });
+            });
+
+        if palette.query != prev_query {
+            palette.selected = 0;
+        } else if enter && to_run.is_none() && palette.selected < runnable {
+            to_run = Some(entries[palette.selected].1.clone());
+        }
+
+        if let Some(entry) = to_run {
+            match entry {
+                PaletteEntry::Action(action) => {
+                    action.apply_action(app, &self.frame_context);
+                }
+                PaletteEntry::Command(command) => {
+                    (command.action)(app);
+                }
+            }
+            palette.open = false;
+        }
+
+        if escape {
+            palette.open = false;
+        }
+    }
+
     pub fn show(&self, egui_ctx: &egui::CtxRef, app: &App) {
         if !matches!(self.init.load(), InitState::Ready) {
             return;
@@ -518,22 +1400,31 @@ impl ContextMgr {
 
                         let context = &self.frame_context;
 
+                        let mut any_applicable = false;
+
                         for (name, action) in actions.iter() {
                             if action.is_applicable(context) {
+                                any_applicable = true;
                                 if ui.button(name).clicked() {
                                     action.apply_action(app, &context);
                                     self.close_context_menu();
                                 }
                             }
                         }
+
+                        if !any_applicable {
+                            ui.label("No actions for this context");
+                        }
                     });
                 });
 
             let popup_response = popup_response.response;
 
+            let just_opened = self.just_opened.swap(false);
+
             if egui_ctx.input().key_pressed(egui::Key::Escape)
                 || popup_response.clicked()
-                || popup_response.clicked_elsewhere()
+                || (popup_response.clicked_elsewhere() && !just_opened)
                 || should_close.load()
                 || !self.context_menu_open.load()
             {
@@ -2,9 +2,13 @@ use std::ops::{
     Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign,
 };
 
+use serde::{Deserialize, Serialize};
+
 use crate::vulkan::draw_system::Vertex;
 
-#[derive(Default, Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[derive(
+    Default, Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize,
+)]
 pub struct Point {
     pub x: f32,
     pub y: f32,
@@ -1,4 +1,5 @@
 pub mod load;
+pub mod sequences;
 
 #[allow(unused_imports)]
 use handlegraph::{
@@ -11,3 +12,38 @@ use handlegraph::{
 
 #[allow(unused_imports)]
 use handlegraph::packedgraph::PackedGraph;
+
+use std::path::Path;
+
+/// A 64-bit FNV-1a hash of `bytes`. Unlike `DefaultHasher`, the
+/// result is the same across runs, platforms and compiler versions,
+/// so it can be used to name files that are read by later runs.
+pub fn stable_hash(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(PRIME)
+    })
+}
+
+/// The stable hash of the canonical path to the GFA at `gfa_path`,
+/// which identifies the graph in data stored per GFA file
+pub fn gfa_path_hash(gfa_path: &Path) -> u64 {
+    let gfa_path =
+        std::fs::canonicalize(gfa_path).unwrap_or_else(|_| gfa_path.to_owned());
+
+    stable_hash(gfa_path.to_string_lossy().as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_hash_is_fnv1a() {
+        assert_eq!(stable_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(stable_hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(stable_hash(b"foobar"), 0x85944171f73967e8);
+    }
+}
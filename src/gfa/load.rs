@@ -12,13 +12,116 @@ use gfa::gfa::Line;
 
 use anyhow::Result;
 
+use bstr::ByteSlice;
+
 use rustc_hash::FxHashMap;
 
+use crossbeam::channel::Sender;
+
+use super::sequences::SequenceIndex;
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
 
+/// Progress report sent while a GFA is being loaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadProgress {
+    pub stage: LoadStage,
+    /// Number of lines of the current stage processed so far
+    pub lines_done: usize,
+    /// Total number of lines in the current stage
+    pub lines_total: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadStage {
+    Indexing,
+    Segments,
+    Links,
+    Paths,
+    PathPositions,
+}
+
+impl std::fmt::Display for LoadStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadStage::Indexing => write!(f, "indexing"),
+            LoadStage::Segments => write!(f, "adding nodes"),
+            LoadStage::Links => write!(f, "adding edges"),
+            LoadStage::Paths => write!(f, "adding paths"),
+            LoadStage::PathPositions => write!(f, "indexing paths"),
+        }
+    }
+}
+
+impl LoadProgress {
+    /// Progress of the current stage, in the range 0.0..=1.0
+    pub fn fraction(&self) -> f32 {
+        if self.lines_total == 0 {
+            1.0
+        } else {
+            (self.lines_done as f32 / self.lines_total as f32).min(1.0)
+        }
+    }
+}
+
+/// How many lines to process between each progress report
+const PROGRESS_INTERVAL: usize = 1 << 14;
+
+pub(crate) fn send_progress(
+    progress: Option<&Sender<LoadProgress>>,
+    stage: LoadStage,
+    lines_done: usize,
+    lines_total: usize,
+) {
+    if let Some(tx) = progress {
+        // the receiver may have stopped listening, which is fine
+        let _ = tx.send(LoadProgress {
+            stage,
+            lines_done,
+            lines_total,
+        });
+    }
+}
+
 pub fn packed_graph_from_mmap(mmap_gfa: &mut MmapGFA) -> Result<PackedGraph> {
+    packed_graph_from_mmap_with_progress(mmap_gfa, None)
+}
+
+/// Like `packed_graph_from_mmap`, but sends a `LoadProgress` on
+/// `progress` as each stage proceeds.
+pub fn packed_graph_from_mmap_with_progress(
+    mmap_gfa: &mut MmapGFA,
+    progress: Option<&Sender<LoadProgress>>,
+) -> Result<PackedGraph> {
+    packed_graph_from_mmap_impl(mmap_gfa, progress, None)
+}
+
+/// Like `packed_graph_from_mmap_with_progress`, but the sequences
+/// aren't stored in the graph, which only gets a single `N` for each
+/// node. The location and length of each sequence in the file is
+/// recorded in `sequences` instead, so they can be read on demand.
+pub fn packed_graph_from_mmap_lazy(
+    mmap_gfa: &mut MmapGFA,
+    progress: Option<&Sender<LoadProgress>>,
+    sequences: &mut SequenceIndex,
+) -> Result<PackedGraph> {
+    packed_graph_from_mmap_impl(mmap_gfa, progress, Some(sequences))
+}
+
+fn packed_graph_from_mmap_impl(
+    mmap_gfa: &mut MmapGFA,
+    progress: Option<&Sender<LoadProgress>>,
+    mut sequences: Option<&mut SequenceIndex>,
+) -> Result<PackedGraph> {
+    send_progress(progress, LoadStage::Indexing, 0, 1);
     let indices = mmap_gfa.build_index()?;
+    send_progress(progress, LoadStage::Indexing, 1, 1);
 
     // let mut graph =
     //     PackedGraph::with_expected_node_count(indices.segments.len());
@@ -47,9 +150,48 @@ pub fn packed_graph_from_mmap(mmap_gfa: &mut MmapGFA) -> Result<PackedGraph> {
 
     let id_offset = if min_id == 0 { 1 } else { 0 };
 
+    let seg_count = indices.segments.len();
+
     info!("adding nodes");
-    for &offset in indices.segments.iter() {
-        let _line = mmap_gfa.read_line_at(offset.0)?;
+    for (ix, &offset) in indices.segments.iter().enumerate() {
+        if ix % PROGRESS_INTERVAL == 0 {
+            send_progress(progress, LoadStage::Segments, ix, seg_count);
+        }
+
+        let line = mmap_gfa.read_line_at(offset.0)?;
+
+        if let Some(sequences) = sequences.as_deref_mut() {
+            let fields = line.splitn_str(4, "\t").collect::<Vec<_>>();
+
+            if let [_, name, seq, ..] = fields.as_slice() {
+                let seq = seq.trim_end();
+                // the sequence follows "S\t", the name, and a tab
+                let seq_offset = offset.0 + 2 + name.len() + 1;
+
+                let name = name
+                    .to_str()
+                    .ok()
+                    .and_then(|name| name.parse::<usize>().ok())
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Invalid segment name: {}",
+                            name.as_bstr()
+                        )
+                    })?;
+                let id = (name + id_offset) as u64;
+
+                if seq == b"*" {
+                    graph.create_handle(seq, id);
+                } else {
+                    sequences.insert(id.into(), seq_offset as u64, seq.len());
+                    // nodes can't have empty sequences
+                    graph.create_handle(b"N", id);
+                }
+            }
+
+            continue;
+        }
+
         let segment = mmap_gfa.parse_current_line()?;
 
         if let gfa::gfa::Line::Segment(segment) = segment {
@@ -64,7 +206,15 @@ pub fn packed_graph_from_mmap(mmap_gfa: &mut MmapGFA) -> Result<PackedGraph> {
 
     info!("adding edges");
 
-    let edges_iter = indices.links.iter().filter_map(|&offset| {
+    let link_count = indices.links.len();
+
+    let links = indices.links.iter().enumerate();
+
+    let edges_iter = links.filter_map(|(ix, &offset)| {
+        if ix % PROGRESS_INTERVAL == 0 {
+            send_progress(progress, LoadStage::Links, ix, link_count);
+        }
+
         let _line = mmap_gfa.read_line_at(offset).ok()?;
         let link = mmap_gfa.parse_current_line().ok()?;
 
@@ -88,10 +238,16 @@ pub fn packed_graph_from_mmap(mmap_gfa: &mut MmapGFA) -> Result<PackedGraph> {
     //     graph.total_bytes()
     // );
 
+    // the GFA parser doesn't know about walks, so they're found here
+    let walks = walk_lines(mmap_gfa.get_ref());
+
     let mut path_ids: FxHashMap<PathId, (usize, usize)> = FxHashMap::default();
-    path_ids.reserve(indices.paths.len());
+    path_ids.reserve(indices.paths.len() + walks.len());
+
+    let path_count = indices.paths.len() + walks.len();
 
     info!("adding paths");
+    send_progress(progress, LoadStage::Paths, 0, path_count);
     for &offset in indices.paths.iter() {
         let line = mmap_gfa.read_line_at(offset)?;
         let length = line.len();
@@ -101,8 +257,34 @@ pub fn packed_graph_from_mmap(mmap_gfa: &mut MmapGFA) -> Result<PackedGraph> {
         }
     }
 
+    if !walks.is_empty() {
+        info!("adding {} walks as paths", walks.len());
+    }
+
+    for &(offset, length) in walks.iter() {
+        let line = &mmap_gfa.get_ref()[offset..offset + length];
+
+        let name = walk_path_name(line).ok_or_else(|| {
+            anyhow::anyhow!("Invalid walk line: {}", line.as_bstr())
+        })?;
+
+        match graph.create_path(&name, false) {
+            Some(path_id) => {
+                path_ids.insert(path_id, (offset, length));
+            }
+            None => {
+                warn!(
+                    "skipping walk with duplicate path name {}",
+                    name.as_bstr()
+                );
+            }
+        }
+    }
+
     info!("created path handles");
 
+    let paths_done = AtomicUsize::new(0);
+
     let mmap_gfa_bytes = mmap_gfa.get_ref();
 
     let parser = mmap_gfa.get_parser();
@@ -111,7 +293,17 @@ pub fn packed_graph_from_mmap(mmap_gfa: &mut MmapGFA) -> Result<PackedGraph> {
         let &(offset, length) = path_ids.get(&path_id).unwrap();
         let end = offset + length;
         let line = &mmap_gfa_bytes[offset..end];
-        if let Some(Line::Path(path)) = parser.parse_gfa_line(line).ok() {
+        if line.starts_with(b"W\t") {
+            match walk_steps(line, id_offset) {
+                Some(steps) => {
+                    path_ref.append_handles_iter_chn(sender, steps.into_iter());
+                }
+                None => {
+                    warn!("invalid walk line: {}", line.as_bstr());
+                }
+            }
+        } else if let Some(Line::Path(path)) = parser.parse_gfa_line(line).ok()
+        {
             path_ref.append_handles_iter_chn(
                 sender,
                 path.iter().map(|(node, orient)| {
@@ -120,6 +312,9 @@ pub fn packed_graph_from_mmap(mmap_gfa: &mut MmapGFA) -> Result<PackedGraph> {
                 }),
             );
         }
+
+        let done = paths_done.fetch_add(1, Ordering::Relaxed) + 1;
+        send_progress(progress, LoadStage::Paths, done, path_count);
     });
 
     /*
@@ -154,3 +349,767 @@ pub fn packed_graph_from_mmap(mmap_gfa: &mut MmapGFA) -> Result<PackedGraph> {
 
     Ok(graph)
 }
+
+/// Finds the `W` (walk) lines of a GFA 1.1 file, returning the
+/// offset and length, without the line ending, of each.
+fn walk_lines(gfa: &[u8]) -> Vec<(usize, usize)> {
+    let mut starts = Vec::new();
+
+    if gfa.starts_with(b"W\t") {
+        starts.push(0);
+    }
+
+    starts.extend(gfa.find_iter("\nW\t").map(|ix| ix + 1));
+
+    starts
+        .into_iter()
+        .map(|start| {
+            let line = &gfa[start..];
+            let len = line.find_byte(b'\n').unwrap_or(line.len());
+            if line[..len].ends_with(b"\r") {
+                (start, len - 1)
+            } else {
+                (start, len)
+            }
+        })
+        .collect()
+}
+
+/// The path name used for a walk line, following the PanSN
+/// convention, i.e. `sample#haplotype#contig`. If the walk doesn't
+/// start at the beginning of the contig, the subrange is appended as
+/// `[start-end]`.
+fn walk_path_name(line: &[u8]) -> Option<Vec<u8>> {
+    let mut fields = line.split_str("\t");
+
+    if fields.next()? != b"W" {
+        return None;
+    }
+
+    let sample = fields.next()?;
+    let hap = fields.next()?;
+    let contig = fields.next()?;
+    let start = fields.next()?;
+    let end = fields.next()?;
+
+    let mut name = Vec::with_capacity(
+        sample.len() + hap.len() + contig.len() + start.len() + end.len() + 5,
+    );
+
+    name.extend_from_slice(sample);
+    name.push(b'#');
+    name.extend_from_slice(hap);
+    name.push(b'#');
+    name.extend_from_slice(contig);
+
+    if start != b"*" && start != b"0" {
+        name.push(b'[');
+        name.extend_from_slice(start);
+        if end != b"*" {
+            name.push(b'-');
+            name.extend_from_slice(end);
+        }
+        name.push(b']');
+    }
+
+    Some(name)
+}
+
+/// Parses the steps of a walk line, where the walk is a list of
+/// segment names each prefixed by `>` (forward) or `<` (reverse),
+/// e.g. `>1<2>3`, into each segment name and whether it's reversed.
+fn walk_step_names(line: &[u8]) -> Option<Vec<(&[u8], bool)>> {
+    let walk = line.split_str("\t").nth(6)?;
+
+    let mut steps = Vec::new();
+
+    let starts = walk
+        .iter()
+        .enumerate()
+        .filter(|&(_, &c)| c == b'>' || c == b'<')
+        .map(|(ix, _)| ix);
+
+    let mut starts = starts.chain(std::iter::once(walk.len())).peekable();
+
+    // the walk must begin with an orientation
+    if starts.peek() != Some(&0) || walk.is_empty() {
+        return None;
+    }
+
+    while let Some(start) = starts.next() {
+        let end = match starts.peek() {
+            Some(&end) => end,
+            None => break,
+        };
+
+        let name = &walk[start + 1..end];
+
+        if name.is_empty() {
+            return None;
+        }
+
+        steps.push((name, walk[start] == b'<'));
+    }
+
+    Some(steps)
+}
+
+/// Parses the steps of a walk line with numeric segment names into
+/// handles, with the IDs offset by `id_offset`.
+fn walk_steps(line: &[u8], id_offset: usize) -> Option<Vec<Handle>> {
+    walk_step_names(line)?
+        .into_iter()
+        .map(|(name, rev)| {
+            let id = name.to_str().ok()?.parse::<usize>().ok()?;
+            Some(Handle::pack((id + id_offset) as u64, rev))
+        })
+        .collect()
+}
+
+/// Returns true if the file at `path` starts with the gzip magic
+/// bytes, or has a `.gz` extension.
+pub fn is_gzipped<P: AsRef<Path>>(path: P) -> Result<bool> {
+    let path = path.as_ref();
+
+    if path.extension().map(|ext| ext == "gz").unwrap_or(false) {
+        return Ok(true);
+    }
+
+    let mut file = std::fs::File::open(path)?;
+    let mut magic = [0u8; 2];
+
+    match file.read_exact(&mut magic) {
+        Ok(_) => Ok(magic == [0x1f, 0x8b]),
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+            Ok(false)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Opens a GFA file for reading, transparently decompressing it if
+/// it's gzipped.
+pub fn open_gfa_reader<P: AsRef<Path>>(path: P) -> Result<Box<dyn BufRead>> {
+    let file = std::fs::File::open(path.as_ref())?;
+
+    if is_gzipped(path.as_ref())? {
+        let decoder = flate2::read::MultiGzDecoder::new(file);
+        Ok(Box::new(BufReader::new(decoder)))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// Decompresses a gzipped GFA to a file in the system temporary
+/// directory, so that it can be memory mapped by the GFA1 loader.
+///
+/// A truncated or otherwise corrupt gzip stream results in an error,
+/// and the partially written file is removed.
+pub fn decompress_gfa_to_temp<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
+    let path = path.as_ref();
+
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("graph")
+        .trim_end_matches(".gfa");
+
+    let tmp_path = std::env::temp_dir().join(format!(
+        "gfaestus-{}-{}.gfa",
+        std::process::id(),
+        stem
+    ));
+
+    let file = std::fs::File::open(path)?;
+    let mut decoder = flate2::read::MultiGzDecoder::new(file);
+
+    let mut out = std::fs::File::create(&tmp_path)?;
+
+    if let Err(err) = std::io::copy(&mut decoder, &mut out) {
+        let _ = std::fs::remove_file(&tmp_path);
+        anyhow::bail!("Error decompressing GFA {}: {}", path.display(), err);
+    }
+
+    Ok(tmp_path)
+}
+
+/// The GFA version declared by a file's header, defaulting to GFA1
+/// if there is no `VN` tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GfaVersion {
+    V1,
+    V2,
+}
+
+/// Reads the header lines at the start of a GFA and returns the
+/// version given by the `VN` tag, if any.
+pub fn sniff_gfa_version<R: BufRead>(mut reader: R) -> Result<GfaVersion> {
+    let mut buf: Vec<u8> = Vec::new();
+
+    loop {
+        buf.clear();
+
+        let read = reader.read_until(b'\n', &mut buf)?;
+
+        if read == 0 {
+            break;
+        }
+
+        let line = buf[0..read].trim();
+
+        if line.is_empty() || line[0] == b'#' {
+            continue;
+        }
+
+        if line[0] != b'H' {
+            break;
+        }
+
+        for field in line.split_str("\t").skip(1) {
+            if let Some(version) = field.strip_prefix(b"VN:Z:") {
+                if version.trim().starts_with(b"2") {
+                    return Ok(GfaVersion::V2);
+                } else {
+                    return Ok(GfaVersion::V1);
+                }
+            }
+        }
+    }
+
+    Ok(GfaVersion::V1)
+}
+
+/// Parses a GFA2 reference such as `12+` into the segment name and
+/// whether it's reversed.
+fn parse_gfa2_ref(field: &[u8]) -> Option<(&[u8], bool)> {
+    let (&orient, name) = field.split_last()?;
+
+    match orient {
+        b'+' => Some((name, false)),
+        b'-' => Some((name, true)),
+        _ => None,
+    }
+}
+
+/// Returns true if a GFA2 edge position refers to the end of its
+/// segment, i.e. is suffixed with `$`.
+fn gfa2_pos_is_end(field: &[u8]) -> bool {
+    field.ends_with(b"$")
+}
+
+/// Returns true if a GFA2 edge position is the start of its segment.
+fn gfa2_pos_is_start(field: &[u8]) -> bool {
+    field == b"0" || field == b"0$"
+}
+
+/// Returns true if the alignment `beg..end` covers the end of the
+/// segment as oriented in the edge. Positions are always given on
+/// the forward strand, so the end of a reversed segment is its
+/// forward start.
+fn gfa2_at_oriented_end(rev: bool, beg: &[u8], end: &[u8]) -> bool {
+    if rev {
+        gfa2_pos_is_start(beg)
+    } else {
+        gfa2_pos_is_end(end)
+    }
+}
+
+/// Returns true if the alignment `beg..end` covers the start of the
+/// segment as oriented in the edge.
+fn gfa2_at_oriented_start(rev: bool, beg: &[u8], end: &[u8]) -> bool {
+    if rev {
+        gfa2_pos_is_end(end)
+    } else {
+        gfa2_pos_is_start(beg)
+    }
+}
+
+struct Gfa2Segment {
+    name: Vec<u8>,
+    seq: Vec<u8>,
+}
+
+/// Builds a `PackedGraph` from a GFA2 file.
+///
+/// `S` lines become nodes, with the segment length field treated as
+/// authoritative; segments with no sequence (`*`) get a sequence of
+/// `N`s of that length, and a sequence whose length doesn't match
+/// the field is an error. Dovetail `E` lines and `G` lines become
+/// edges, and `O` groups of segments become paths, as do GFA 1.1 `W`
+/// walks, like in the GFA1 loader.
+///
+/// Records that can't be represented in the graph (e.g. fragments,
+/// containment edges, and unordered groups) result in an error
+/// listing each record type and how many were found.
+pub fn packed_graph_from_gfa2<R: BufRead>(
+    mut reader: R,
+) -> Result<PackedGraph> {
+    let mut segments: Vec<Gfa2Segment> = Vec::new();
+
+    // (from, from_rev, to, to_rev)
+    let mut edges: Vec<(Vec<u8>, bool, Vec<u8>, bool)> = Vec::new();
+    // (record type, name, steps)
+    let mut paths: Vec<(&'static str, Vec<u8>, Vec<(Vec<u8>, bool)>)> =
+        Vec::new();
+
+    let mut unsupported: BTreeMap<&'static str, usize> = BTreeMap::new();
+
+    let mut buf: Vec<u8> = Vec::new();
+
+    let mut line_num = 0;
+
+    loop {
+        buf.clear();
+
+        let read = reader.read_until(b'\n', &mut buf)?;
+
+        if read == 0 {
+            break;
+        }
+
+        line_num += 1;
+
+        let line = buf[0..read].trim();
+
+        if line.is_empty() || line[0] == b'#' {
+            continue;
+        }
+
+        let mut fields = line.split_str("\t");
+
+        let record_type = fields.next().unwrap_or_default();
+
+        match record_type {
+            b"H" => (),
+            b"S" => {
+                let name = fields.next();
+                let len = fields
+                    .next()
+                    .and_then(|f| f.to_str().ok())
+                    .and_then(|f| f.parse::<usize>().ok());
+                let seq = fields.next();
+
+                match (name, len, seq) {
+                    (Some(name), Some(len), Some(seq)) => {
+                        // the length field is authoritative, so a
+                        // sequence that disagrees with it means the
+                        // node widths would be wrong
+                        if seq != b"*" && seq.len() != len {
+                            anyhow::bail!(
+                                "GFA2 segment {} on line {} has length {} \
+                                 but sequence of length {}",
+                                name.as_bstr(),
+                                line_num,
+                                len,
+                                seq.len()
+                            );
+                        }
+
+                        let seq = if seq == b"*" {
+                            vec![b'N'; len]
+                        } else {
+                            seq.to_owned()
+                        };
+
+                        segments.push(Gfa2Segment {
+                            name: name.to_owned(),
+                            seq,
+                        });
+                    }
+                    _ => {
+                        anyhow::bail!(
+                            "Malformed GFA2 segment on line {}",
+                            line_num
+                        );
+                    }
+                }
+            }
+            b"E" => {
+                let _id = fields.next();
+                let refs = (
+                    fields.next().and_then(parse_gfa2_ref),
+                    fields.next().and_then(parse_gfa2_ref),
+                );
+
+                let (from, to) = match refs {
+                    (Some(from), Some(to)) => (from, to),
+                    _ => anyhow::bail!(
+                        "Malformed GFA2 edge on line {}",
+                        line_num
+                    ),
+                };
+
+                let pos = fields.by_ref().take(4).collect::<Vec<_>>();
+
+                if pos.len() != 4 {
+                    anyhow::bail!("Malformed GFA2 edge on line {}", line_num);
+                }
+
+                let (beg1, end1, beg2, end2) = (pos[0], pos[1], pos[2], pos[3]);
+
+                if gfa2_at_oriented_end(from.1, beg1, end1)
+                    && gfa2_at_oriented_start(to.1, beg2, end2)
+                {
+                    edges.push((
+                        from.0.to_owned(),
+                        from.1,
+                        to.0.to_owned(),
+                        to.1,
+                    ));
+                } else if gfa2_at_oriented_start(from.1, beg1, end1)
+                    && gfa2_at_oriented_end(to.1, beg2, end2)
+                {
+                    edges.push((
+                        to.0.to_owned(),
+                        to.1,
+                        from.0.to_owned(),
+                        from.1,
+                    ));
+                } else {
+                    *unsupported.entry("E (containment)").or_default() += 1;
+                }
+            }
+            b"G" => {
+                let _id = fields.next();
+                let from = fields.next().and_then(parse_gfa2_ref);
+                let to = fields.next().and_then(parse_gfa2_ref);
+
+                match (from, to) {
+                    (Some(from), Some(to)) => {
+                        edges.push((
+                            from.0.to_owned(),
+                            from.1,
+                            to.0.to_owned(),
+                            to.1,
+                        ));
+                    }
+                    _ => {
+                        anyhow::bail!("Malformed GFA2 gap on line {}", line_num)
+                    }
+                }
+            }
+            b"O" => {
+                let name = fields.next().unwrap_or_default().to_owned();
+                let steps = fields
+                    .next()
+                    .unwrap_or_default()
+                    .fields()
+                    .map(|r| {
+                        parse_gfa2_ref(r).map(|(n, rev)| (n.to_owned(), rev))
+                    })
+                    .collect::<Option<Vec<_>>>();
+
+                match steps {
+                    Some(steps) => paths.push(("O", name, steps)),
+                    None => anyhow::bail!(
+                        "Malformed GFA2 ordered group on line {}",
+                        line_num
+                    ),
+                }
+            }
+            b"W" => {
+                let name = walk_path_name(line);
+                let steps = walk_step_names(line).map(|steps| {
+                    steps
+                        .into_iter()
+                        .map(|(n, rev)| (n.to_owned(), rev))
+                        .collect::<Vec<_>>()
+                });
+
+                match (name, steps) {
+                    (Some(name), Some(steps)) => paths.push(("W", name, steps)),
+                    _ => anyhow::bail!("Malformed walk on line {}", line_num),
+                }
+            }
+            b"F" => *unsupported.entry("F (fragment)").or_default() += 1,
+            b"U" => *unsupported.entry("U (unordered group)").or_default() += 1,
+            _ => *unsupported.entry("unknown").or_default() += 1,
+        }
+    }
+
+    // GFA2 segment names are arbitrary strings; if they're all
+    // numeric they're used as node IDs like in GFA1, otherwise the
+    // nodes are numbered in the order they appear in the file
+    let numeric_ids = segments
+        .iter()
+        .map(|seg| seg.name.to_str().ok()?.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>();
+
+    let node_ids: Vec<u64> = match numeric_ids {
+        Some(ids) => {
+            let min_id = ids.iter().copied().min().unwrap_or(1);
+            let id_offset = if min_id == 0 { 1 } else { 0 };
+            ids.into_iter().map(|id| id + id_offset).collect()
+        }
+        None => (1..=segments.len() as u64).collect(),
+    };
+
+    let name_map: FxHashMap<&[u8], u64> = segments
+        .iter()
+        .zip(node_ids.iter())
+        .map(|(seg, &id)| (seg.name.as_slice(), id))
+        .collect();
+
+    let get_handle = |name: &[u8], rev: bool| -> Option<Handle> {
+        let id = *name_map.get(name)?;
+        Some(Handle::pack(id, rev))
+    };
+
+    for (kind, name, _) in paths.iter() {
+        if name.is_empty() {
+            let entry = if *kind == "W" {
+                "W (unnamed)"
+            } else {
+                "O (unnamed)"
+            };
+            *unsupported.entry(entry).or_default() += 1;
+        }
+    }
+
+    for (kind, _, steps) in paths.iter() {
+        if steps
+            .iter()
+            .any(|(n, _)| !name_map.contains_key(n.as_slice()))
+        {
+            let entry = if *kind == "W" {
+                "W (reference to non-segment)"
+            } else {
+                "O (reference to non-segment)"
+            };
+            *unsupported.entry(entry).or_default() += 1;
+        }
+    }
+
+    if !unsupported.is_empty() {
+        let list = unsupported
+            .iter()
+            .map(|(kind, count)| format!("{}: {}", kind, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        anyhow::bail!(
+            "GFA2 contains records that can't be represented: {}",
+            list
+        );
+    }
+
+    let mut graph = PackedGraph::default();
+
+    info!(
+        "loading GFA2 with {} nodes, {} edges",
+        segments.len(),
+        edges.len()
+    );
+
+    info!("adding nodes");
+    for (seg, &id) in segments.iter().zip(node_ids.iter()) {
+        graph.create_handle(&seg.seq, id);
+    }
+
+    info!("adding edges");
+    let mut handle_edges = Vec::with_capacity(edges.len());
+    for (from, from_rev, to, to_rev) in edges.iter() {
+        let from_h = get_handle(from, *from_rev);
+        let to_h = get_handle(to, *to_rev);
+
+        match (from_h, to_h) {
+            (Some(from), Some(to)) => handle_edges.push(Edge(from, to)),
+            _ => anyhow::bail!(
+                "GFA2 edge references unknown segment: {} -> {}",
+                from.as_bstr(),
+                to.as_bstr()
+            ),
+        }
+    }
+
+    graph.create_edges_iter(handle_edges.into_iter());
+
+    info!("adding paths");
+    for (_, name, steps) in paths.iter() {
+        let path_id = graph.create_path(name, false).ok_or_else(|| {
+            anyhow::anyhow!("Duplicate GFA2 path name: {}", name.as_bstr())
+        })?;
+
+        for (seg, rev) in steps.iter() {
+            // every reference was checked against `name_map` above
+            let handle = get_handle(seg, *rev).unwrap();
+            graph.path_append_step(path_id, handle);
+        }
+    }
+
+    Ok(graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use handlegraph::{handle::Direction, handlegraph::*};
+
+    fn gfa2(lines: &[&str]) -> Result<PackedGraph> {
+        let gfa = lines.join("\n");
+        packed_graph_from_gfa2(gfa.as_bytes())
+    }
+
+    fn right_of(graph: &PackedGraph, id: u64, rev: bool) -> Vec<Handle> {
+        graph
+            .neighbors(Handle::pack(id, rev), Direction::Right)
+            .collect()
+    }
+
+    const SEGMENTS: [&str; 3] = [
+        "H\tVN:Z:2.0",
+        "S\t1\t8\tACGTACGT",
+        "S\t2\t8\tTTGGCCAA",
+    ];
+
+    #[test]
+    fn gfa2_dovetail_forward() {
+        let mut lines = SEGMENTS.to_vec();
+        lines.push("E\te1\t1+\t2+\t4\t8$\t0\t4\t4M");
+
+        let graph = gfa2(&lines).unwrap();
+        assert_eq!(right_of(&graph, 1, false), vec![Handle::pack(2, false)]);
+    }
+
+    #[test]
+    fn gfa2_dovetail_second_before_first() {
+        let mut lines = SEGMENTS.to_vec();
+        lines.push("E\te1\t1+\t2+\t0\t4\t4\t8$\t4M");
+
+        let graph = gfa2(&lines).unwrap();
+        assert_eq!(right_of(&graph, 2, false), vec![Handle::pack(1, false)]);
+    }
+
+    #[test]
+    fn gfa2_dovetail_reverse_first() {
+        // the end of 1- is the forward start of segment 1
+        let mut lines = SEGMENTS.to_vec();
+        lines.push("E\te1\t1-\t2+\t0\t4\t0\t4\t4M");
+
+        let graph = gfa2(&lines).unwrap();
+        assert_eq!(right_of(&graph, 1, true), vec![Handle::pack(2, false)]);
+    }
+
+    #[test]
+    fn gfa2_dovetail_reverse_second() {
+        // the start of 2- is the forward end of segment 2
+        let mut lines = SEGMENTS.to_vec();
+        lines.push("E\te1\t1+\t2-\t4\t8$\t4\t8$\t4M");
+
+        let graph = gfa2(&lines).unwrap();
+        assert_eq!(right_of(&graph, 1, false), vec![Handle::pack(2, true)]);
+    }
+
+    #[test]
+    fn gfa2_containment_is_unsupported() {
+        let mut lines = SEGMENTS.to_vec();
+        lines.push("E\te1\t1+\t2+\t2\t6\t0\t4\t4M");
+
+        let err = gfa2(&lines).unwrap_err().to_string();
+        assert!(err.contains("E (containment): 1"), "{}", err);
+    }
+
+    #[test]
+    fn gfa2_segment_length_mismatch() {
+        let lines = ["H\tVN:Z:2.0", "S\t1\t10\tACGT"];
+
+        let err = gfa2(&lines).unwrap_err().to_string();
+        assert!(err.contains("has length 10"), "{}", err);
+    }
+
+    #[test]
+    fn gfa2_segment_length_without_sequence() {
+        let lines = ["H\tVN:Z:2.0", "S\t1\t10\t*"];
+
+        let graph = gfa2(&lines).unwrap();
+        assert_eq!(graph.node_len(Handle::pack(1, false)), 10);
+    }
+
+    const WALK: &str = "W\tHG002\t1\tchr1\t0\t12\t>1<2>3\tSN:Z:x";
+
+    #[test]
+    fn walk_steps_orientations() {
+        let steps = walk_steps(WALK.as_bytes(), 0).unwrap();
+        assert_eq!(
+            steps,
+            vec![
+                Handle::pack(1, false),
+                Handle::pack(2, true),
+                Handle::pack(3, false)
+            ]
+        );
+
+        let steps = walk_steps(WALK.as_bytes(), 1).unwrap();
+        assert_eq!(steps[0], Handle::pack(2, false));
+    }
+
+    #[test]
+    fn walk_steps_invalid() {
+        let walk = |w: &str| format!("W\tHG002\t1\tchr1\t0\t12\t{}", w);
+
+        assert!(walk_steps(walk("1>2").as_bytes(), 0).is_none());
+        assert!(walk_steps(walk("").as_bytes(), 0).is_none());
+        assert!(walk_steps(walk(">1<>2").as_bytes(), 0).is_none());
+        assert!(walk_steps(walk(">1>a").as_bytes(), 0).is_none());
+        assert!(walk_steps(b"W\tHG002\t1\tchr1", 0).is_none());
+    }
+
+    #[test]
+    fn walk_names() {
+        assert_eq!(walk_path_name(WALK.as_bytes()).unwrap(), b"HG002#1#chr1");
+
+        let sub = "W\tHG002\t1\tchr1\t5\t12\t>1";
+        assert_eq!(
+            walk_path_name(sub.as_bytes()).unwrap(),
+            b"HG002#1#chr1[5-12]"
+        );
+    }
+
+    #[test]
+    fn gzipped_gfa_with_walks() {
+        use std::io::Write;
+
+        let gfa = "H\tVN:Z:1.1\nS\t1\tACGT\nS\t2\tGG\n\
+                   L\t1\t+\t2\t-\t0M\nW\tHG002\t1\tchr1\t0\t6\t>1<2\n";
+
+        let path = std::env::temp_dir()
+            .join(format!("gfaestus_walks_{}.gfa.gz", std::process::id()));
+
+        let file = std::fs::File::create(&path).unwrap();
+        let mut encoder =
+            flate2::write::GzEncoder::new(file, flate2::Compression::fast());
+        encoder.write_all(gfa.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let tmp_path = decompress_gfa_to_temp(&path).unwrap();
+        let mut mmap = MmapGFA::new(tmp_path.to_str().unwrap()).unwrap();
+        let graph = packed_graph_from_mmap(&mut mmap).unwrap();
+
+        std::fs::remove_file(&tmp_path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let path_id = graph.get_path_id(b"HG002#1#chr1").unwrap();
+        let steps = graph
+            .path_steps(path_id)
+            .unwrap()
+            .map(|step| step.handle())
+            .collect::<Vec<_>>();
+
+        assert_eq!(steps, vec![Handle::pack(1, false), Handle::pack(2, true)]);
+    }
+
+    #[test]
+    fn gfa2_walks_become_paths() {
+        let mut lines = SEGMENTS.to_vec();
+        lines.push("W\tHG002\t1\tchr1\t0\t16\t>1<2");
+
+        let graph = gfa2(&lines).unwrap();
+        let path_id = graph.get_path_id(b"HG002#1#chr1").unwrap();
+        assert_eq!(graph.path_steps(path_id).unwrap().count(), 2);
+
+        lines.push("W\tHG002\t2\tchr1\t0\t8\t>3");
+        let err = gfa2(&lines).unwrap_err().to_string();
+        assert!(err.contains("W (reference to non-segment): 1"), "{}", err);
+    }
+}
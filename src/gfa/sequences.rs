@@ -0,0 +1,240 @@
+use handlegraph::handle::NodeId;
+
+use anyhow::Result;
+
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Index of where each node's sequence is found in a GFA file, so
+/// that sequences can be read from disk on demand instead of being
+/// kept in memory in the graph.
+///
+/// Only the offset and length of each sequence is stored, and reads
+/// don't move a shared file cursor, so the index can be used from
+/// several threads at once.
+pub struct SequenceIndex {
+    gfa_path: PathBuf,
+    file: File,
+    /// The byte offset and length of each node's sequence in the
+    /// file, indexed by node ID; nodes that aren't in the index have
+    /// a length of zero
+    offsets: Vec<(u64, usize)>,
+    count: usize,
+    total_len: usize,
+}
+
+impl SequenceIndex {
+    pub fn new<P: AsRef<Path>>(gfa_path: P) -> Result<Self> {
+        let gfa_path = gfa_path.as_ref().to_owned();
+        let file = File::open(&gfa_path)?;
+
+        Ok(Self {
+            gfa_path,
+            file,
+            offsets: Vec::new(),
+            count: 0,
+            total_len: 0,
+        })
+    }
+
+    pub fn gfa_path(&self) -> &Path {
+        &self.gfa_path
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn contains(&self, node: NodeId) -> bool {
+        self.node_len(node).is_some()
+    }
+
+    /// The total length of the indexed sequences
+    pub fn total_len(&self) -> usize {
+        self.total_len
+    }
+
+    /// The length of the sequence of `node`, without reading it, or
+    /// `None` if the node isn't in the index.
+    pub fn node_len(&self, node: NodeId) -> Option<usize> {
+        let &(_, len) = self.offsets.get(node.0 as usize)?;
+        Some(len).filter(|&len| len > 0)
+    }
+
+    pub(crate) fn insert(&mut self, node: NodeId, offset: u64, len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        let ix = node.0 as usize;
+
+        if ix >= self.offsets.len() {
+            self.offsets.resize(ix + 1, (0, 0));
+        }
+
+        let (_, prev_len) = self.offsets[ix];
+
+        if prev_len == 0 {
+            self.count += 1;
+        }
+
+        self.total_len = self.total_len - prev_len + len;
+        self.offsets[ix] = (offset, len);
+    }
+
+    /// Reads the forward sequence of `node` from the GFA file, or
+    /// returns `None` if the node isn't in the index.
+    pub fn read(&self, node: NodeId) -> Option<Result<Vec<u8>>> {
+        let &(offset, len) = self.offsets.get(node.0 as usize)?;
+
+        if len == 0 {
+            return None;
+        }
+
+        let mut seq = vec![0u8; len];
+
+        Some(read_exact_at(&self.file, &mut seq, offset).map(|_| seq))
+    }
+
+    /// Reads every indexed sequence in a single pass over the file,
+    /// in the order they appear in it, calling `f` with each node and
+    /// its forward sequence. Much faster than calling `read` for
+    /// each node, when all sequences are needed.
+    pub fn for_each_sequence<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(NodeId, &[u8]),
+    {
+        let mut entries = self
+            .offsets
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, len))| *len > 0)
+            .map(|(ix, &(offset, len))| (offset, len, NodeId::from(ix as u64)))
+            .collect::<Vec<_>>();
+
+        entries.sort_unstable();
+
+        let file = File::open(&self.gfa_path)?;
+        let mut reader = BufReader::with_capacity(1 << 20, file);
+        let mut pos = 0u64;
+
+        let mut seq = Vec::new();
+
+        for (offset, len, node) in entries {
+            // skipping within the buffer doesn't touch the file
+            if offset >= pos {
+                reader.seek_relative((offset - pos) as i64)?;
+            } else {
+                reader.seek(SeekFrom::Start(offset))?;
+            }
+
+            seq.resize(len, 0);
+            reader.read_exact(&mut seq)?;
+            pos = offset + len as u64;
+
+            f(node, &seq);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> Result<()> {
+    use std::os::unix::fs::FileExt;
+    file.read_exact_at(buf, offset)?;
+    Ok(())
+}
+
+#[cfg(windows)]
+fn read_exact_at(
+    file: &File,
+    mut buf: &mut [u8],
+    mut offset: u64,
+) -> Result<()> {
+    use std::os::windows::fs::FileExt;
+
+    while !buf.is_empty() {
+        let read = file.seek_read(buf, offset)?;
+        if read == 0 {
+            anyhow::bail!("Unexpected end of file when reading sequence");
+        }
+        buf = &mut std::mem::take(&mut buf)[read..];
+        offset += read as u64;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    fn index_for(name: &str, gfa: &str) -> SequenceIndex {
+        let path = std::env::temp_dir().join(format!(
+            "gfaestus_{}_{}.gfa",
+            name,
+            std::process::id()
+        ));
+
+        let mut file = File::create(&path).unwrap();
+        file.write_all(gfa.as_bytes()).unwrap();
+
+        let mut index = SequenceIndex::new(&path).unwrap();
+
+        for line in gfa.lines() {
+            let offset = gfa.find(line).unwrap();
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let id = fields[1].parse::<u64>().unwrap();
+            let seq_offset = offset + 2 + fields[1].len() + 1;
+            index.insert(id.into(), seq_offset as u64, fields[2].len());
+        }
+
+        index
+    }
+
+    const GFA: &str = "S\t2\tGGTT\nS\t1\tACGTACGT\nS\t3\tA\n";
+
+    #[test]
+    fn read_sequences() {
+        let index = index_for("read", GFA);
+
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.total_len(), 13);
+        assert_eq!(index.node_len(NodeId::from(1)), Some(8));
+        assert_eq!(index.node_len(NodeId::from(4)), None);
+
+        let read = |id: u64| index.read(NodeId::from(id)).unwrap().unwrap();
+
+        assert_eq!(read(1), b"ACGTACGT");
+        assert_eq!(read(2), b"GGTT");
+        assert_eq!(read(3), b"A");
+        assert!(index.read(NodeId::from(4)).is_none());
+    }
+
+    #[test]
+    fn for_each_sequence_in_file_order() {
+        let index = index_for("for_each", GFA);
+
+        let mut seqs = Vec::new();
+        index
+            .for_each_sequence(|node, seq| seqs.push((node.0, seq.to_vec())))
+            .unwrap();
+
+        assert_eq!(
+            seqs,
+            vec![
+                (2, b"GGTT".to_vec()),
+                (1, b"ACGTACGT".to_vec()),
+                (3, b"A".to_vec()),
+            ]
+        );
+    }
+}
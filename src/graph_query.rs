@@ -8,18 +8,21 @@ use handlegraph::{
     pathhandlegraph::*,
 };
 
-use handlegraph::{
-    packedgraph::{paths::StepPtr, PackedGraph},
-    path_position::PathPositionMap,
-};
+use handlegraph::packedgraph::{paths::StepPtr, PackedGraph};
 
-use crossbeam::channel;
+use crossbeam::channel::{self, Sender};
 
 use std::sync::Arc;
 
 use anyhow::Result;
 
 use crate::asynchronous::AsyncResult;
+use crate::gfa::load::{send_progress, LoadProgress, LoadStage};
+use crate::gfa::sequences::SequenceIndex;
+
+mod path_positions;
+
+pub use path_positions::PathPositions;
 
 pub struct GraphQueryWorker {
     graph_query: Arc<GraphQuery>,
@@ -54,16 +57,148 @@ impl GraphQueryWorker {
 
 pub struct GraphQuery {
     pub graph: Arc<PackedGraph>,
-    pub path_positions: Arc<PathPositionMap>,
+    pub path_positions: Arc<PathPositions>,
+    /// If set, the node sequences are read from the GFA file on
+    /// demand, and the graph only holds a one base placeholder for
+    /// each node, so the node lengths must be taken from here
+    sequences: Option<Arc<SequenceIndex>>,
     query_thread: QueryThread,
 }
 
 impl GraphQuery {
     pub fn load_gfa(gfa_path: &str) -> Result<Self> {
+        Self::load_gfa_with_progress(gfa_path, None)
+    }
+
+    /// Like `load_gfa`, but reports the loading progress on
+    /// `progress`, if provided.
+    pub fn load_gfa_with_progress(
+        gfa_path: &str,
+        progress: Option<&Sender<LoadProgress>>,
+    ) -> Result<Self> {
         let mut mmap = gfa::mmap::MmapGFA::new(gfa_path)?;
-        let graph = crate::gfa::load::packed_graph_from_mmap(&mut mmap)?;
-        let path_positions = PathPositionMap::index_paths(&graph);
-        Ok(Self::new(graph, path_positions))
+        let graph = crate::gfa::load::packed_graph_from_mmap_with_progress(
+            &mut mmap, progress,
+        )?;
+        let path_positions = Self::index_path_positions(&graph, None, progress);
+        Ok(Self::new(graph, path_positions, None))
+    }
+
+    /// Like `load_gfa_with_progress`, but the node sequences are left
+    /// in the GFA file, and read from it when needed.
+    pub fn load_gfa_lazy(
+        gfa_path: &str,
+        progress: Option<&Sender<LoadProgress>>,
+    ) -> Result<Self> {
+        let mut sequences = SequenceIndex::new(gfa_path)?;
+        let mut mmap = gfa::mmap::MmapGFA::new(gfa_path)?;
+        let graph = crate::gfa::load::packed_graph_from_mmap_lazy(
+            &mut mmap,
+            progress,
+            &mut sequences,
+        )?;
+        log::info!("indexed {} node sequences on disk", sequences.len());
+        let path_positions =
+            Self::index_path_positions(&graph, Some(&sequences), progress);
+        Ok(Self::new(graph, path_positions, Some(sequences)))
+    }
+
+    /// Indexes the base positions of the path steps, using the node
+    /// lengths from `sequences` for the nodes it holds
+    fn index_path_positions(
+        graph: &PackedGraph,
+        sequences: Option<&SequenceIndex>,
+        progress: Option<&Sender<LoadProgress>>,
+    ) -> PathPositions {
+        send_progress(progress, LoadStage::PathPositions, 0, 1);
+        let path_positions = PathPositions::index_paths(graph, |handle| {
+            node_len(graph, sequences, handle)
+        });
+        send_progress(progress, LoadStage::PathPositions, 1, 1);
+        path_positions
+    }
+
+    /// Loads a gzipped GFA1 file by first decompressing it to a
+    /// temporary file, which is removed once the graph is built.
+    pub fn load_gfa_gzip(
+        gfa_path: &str,
+        progress: Option<&Sender<LoadProgress>>,
+    ) -> Result<Self> {
+        let t = std::time::Instant::now();
+        let tmp_path = crate::gfa::load::decompress_gfa_to_temp(gfa_path)?;
+        log::info!(
+            "decompressed {} in {:.3} sec",
+            gfa_path,
+            t.elapsed().as_secs_f64()
+        );
+
+        let result = tmp_path
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("Invalid temporary file path"))
+            .and_then(|path| Self::load_gfa_with_progress(path, progress));
+
+        if let Err(err) = std::fs::remove_file(&tmp_path) {
+            log::warn!(
+                "Error removing temporary file {:?}: {:?}",
+                tmp_path,
+                err
+            );
+        }
+
+        result
+    }
+
+    pub fn load_gfa2(
+        gfa_path: &str,
+        progress: Option<&Sender<LoadProgress>>,
+    ) -> Result<Self> {
+        let reader = crate::gfa::load::open_gfa_reader(gfa_path)?;
+        let graph = crate::gfa::load::packed_graph_from_gfa2(reader)?;
+        let path_positions = Self::index_path_positions(&graph, None, progress);
+        Ok(Self::new(graph, path_positions, None))
+    }
+
+    /// Loads the GFA at `gfa_path` as GFA1 or GFA2 depending on the
+    /// `VN` tag in its header. Gzipped files are decompressed
+    /// transparently.
+    ///
+    /// If `progress` is provided, `LoadProgress` updates are sent on
+    /// it while loading, so it can be called from a background thread.
+    ///
+    /// If `lazy_sequences` is true, the sequences are read from disk
+    /// on demand; this is only supported for uncompressed GFA1 files,
+    /// which are otherwise loaded as usual.
+    pub fn load_gfa_auto(
+        gfa_path: &str,
+        progress: Option<&Sender<LoadProgress>>,
+        lazy_sequences: bool,
+    ) -> Result<Self> {
+        use crate::gfa::load::{
+            is_gzipped, open_gfa_reader, sniff_gfa_version, GfaVersion,
+        };
+
+        let version = sniff_gfa_version(open_gfa_reader(gfa_path)?)?;
+
+        let gzipped = is_gzipped(gfa_path)?;
+
+        if lazy_sequences {
+            if version == GfaVersion::V1 && !gzipped {
+                return Self::load_gfa_lazy(gfa_path, progress);
+            }
+
+            log::warn!(
+                "Lazy sequence loading is only supported for uncompressed \
+                 GFA1, loading all sequences"
+            );
+        }
+
+        match version {
+            GfaVersion::V1 if gzipped => {
+                Self::load_gfa_gzip(gfa_path, progress)
+            }
+            GfaVersion::V1 => Self::load_gfa_with_progress(gfa_path, progress),
+            GfaVersion::V2 => Self::load_gfa2(gfa_path, progress),
+        }
     }
 
     pub fn node_count(&self) -> usize {
@@ -74,17 +209,70 @@ impl GraphQuery {
         self.graph.edge_count()
     }
 
-    pub fn new(graph: PackedGraph, path_positions: PathPositionMap) -> Self {
+    pub fn new(
+        graph: PackedGraph,
+        path_positions: PathPositions,
+        sequences: Option<SequenceIndex>,
+    ) -> Self {
         let graph = Arc::new(graph);
         let path_positions = Arc::new(path_positions);
-        let query_thread = QueryThread::new(graph.clone());
+        let sequences = sequences.map(Arc::new);
+        let query_thread = QueryThread::new(graph.clone(), sequences.clone());
         Self {
             graph,
             path_positions,
+            sequences,
             query_thread,
         }
     }
 
+    /// True if the node sequences are read from disk on demand
+    pub fn lazy_sequences(&self) -> bool {
+        self.sequences.is_some()
+    }
+
+    /// Returns the sequence of `handle`, reading it from the GFA file
+    /// if sequences are loaded lazily.
+    pub fn sequence_vec(&self, handle: Handle) -> Vec<u8> {
+        node_sequence(&self.graph, self.sequences.as_deref(), handle)
+    }
+
+    /// The sequence length of `handle`; unlike `PackedGraph::node_len`
+    /// this is also correct when sequences are loaded lazily.
+    pub fn node_len(&self, handle: Handle) -> usize {
+        node_len(&self.graph, self.sequences.as_deref(), handle)
+    }
+
+    /// The total sequence length of the graph; see `node_len`.
+    pub fn total_length(&self) -> usize {
+        total_length(&self.graph, self.sequences.as_deref())
+    }
+
+    /// Calls `f` with the forward sequence of every node. With lazy
+    /// sequences, they're read in a single pass over the GFA file,
+    /// rather than one read per node.
+    pub fn for_each_sequence<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(NodeId, &[u8]),
+    {
+        if let Some(sequences) = self.sequences.as_deref() {
+            sequences.for_each_sequence(&mut f)?;
+        }
+
+        for handle in self.graph.handles() {
+            let indexed = self
+                .sequences
+                .as_ref()
+                .map_or(false, |seqs| seqs.contains(handle.id()));
+
+            if !indexed {
+                f(handle.id(), &self.graph.sequence_vec(handle));
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn query_request_blocking(
         &self,
         request: GraphQueryRequest,
@@ -100,11 +288,11 @@ impl GraphQuery {
         &self.graph
     }
 
-    pub fn path_positions_arc(&self) -> &Arc<PathPositionMap> {
+    pub fn path_positions_arc(&self) -> &Arc<PathPositions> {
         &self.path_positions
     }
 
-    pub fn path_positions(&self) -> &PathPositionMap {
+    pub fn path_positions(&self) -> &PathPositions {
         &self.path_positions
     }
 
@@ -125,6 +313,65 @@ impl GraphQuery {
         result
     }
 
+    /// Finds the nodes whose sequence contains `query`, ignoring case,
+    /// returning at most `max_results` matches in node ID order. If
+    /// `reverse_complement` is true, matches of the reverse
+    /// complement of `query` are included as well.
+    ///
+    /// The second element of the returned tuple is true if the
+    /// result set was truncated.
+    pub fn find_sequence(
+        &self,
+        query: &[u8],
+        reverse_complement: bool,
+        max_results: usize,
+    ) -> (Vec<SequenceMatch>, bool) {
+        use bstr::ByteSlice;
+
+        let mut results = Vec::new();
+
+        if query.is_empty() {
+            return (results, false);
+        }
+
+        let query = query.to_ascii_uppercase();
+        let rev_query = reverse_complement_seq(&query);
+        let search_rev = reverse_complement && rev_query != query;
+
+        let mut handles = self.graph.handles().collect::<Vec<_>>();
+        handles.sort();
+
+        for handle in handles {
+            let mut seq = self.sequence_vec(handle);
+            seq.make_ascii_uppercase();
+
+            if let Some(offset) = seq.find(&query) {
+                results.push(SequenceMatch {
+                    node: handle.id(),
+                    offset,
+                    reverse: false,
+                });
+            }
+
+            if search_rev {
+                if let Some(offset) = seq.find(&rev_query) {
+                    results.push(SequenceMatch {
+                        node: handle.id(),
+                        offset,
+                        reverse: true,
+                    });
+                }
+            }
+
+            if results.len() >= max_results {
+                results.truncate(max_results);
+                return (results, true);
+            }
+        }
+
+        (results, false)
+    }
+
     pub fn handle_positions_iter<'a>(
         &'a self,
         handle: Handle,
@@ -207,7 +454,7 @@ impl GraphQuery {
 
         for step in path_steps {
             let handle = step.handle();
-            let len = self.graph.node_len(handle);
+            let len = self.node_len(handle);
 
             base_offset += len;
 
@@ -231,10 +478,86 @@ impl GraphQuery {
     }
 }
 
+/// A match of a sequence query within a node, as produced by
+/// `GraphQuery::find_sequence`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SequenceMatch {
+    pub node: NodeId,
+    /// Offset of the match from the start of the node's forward sequence
+    pub offset: usize,
+    /// True if it was the reverse complement of the query that matched
+    pub reverse: bool,
+}
+
+/// Returns the sequence of `handle` from `sequences` if it's indexed
+/// there, otherwise from the graph. Read errors are logged, and
+/// the graph's placeholder sequence returned instead.
+fn node_sequence(
+    graph: &PackedGraph,
+    sequences: Option<&SequenceIndex>,
+    handle: Handle,
+) -> Vec<u8> {
+    let from_disk = sequences.and_then(|seqs| seqs.read(handle.id()));
+
+    match from_disk {
+        Some(Ok(seq)) if handle.is_reverse() => reverse_complement_seq(&seq),
+        Some(Ok(seq)) => seq,
+        Some(Err(err)) => {
+            log::warn!(
+                "Error reading sequence of node {}: {:?}",
+                handle.id().0,
+                err
+            );
+            graph.sequence_vec(handle)
+        }
+        None => graph.sequence_vec(handle),
+    }
+}
+
+fn node_len(
+    graph: &PackedGraph,
+    sequences: Option<&SequenceIndex>,
+    handle: Handle,
+) -> usize {
+    sequences
+        .and_then(|seqs| seqs.node_len(handle.id()))
+        .unwrap_or_else(|| graph.node_len(handle))
+}
+
+fn total_length(
+    graph: &PackedGraph,
+    sequences: Option<&SequenceIndex>,
+) -> usize {
+    match sequences {
+        // each indexed node has a one base placeholder in the graph
+        Some(seqs) => graph.total_length() - seqs.len() + seqs.total_len(),
+        None => graph.total_length(),
+    }
+}
+
+/// Returns the reverse complement of a nucleotide sequence; bytes
+/// other than `ACGTN` (in either case) are kept as they are.
+pub fn reverse_complement_seq(seq: &[u8]) -> Vec<u8> {
+    seq.iter()
+        .rev()
+        .map(|&b| match b {
+            b'A' => b'T',
+            b'T' => b'A',
+            b'C' => b'G',
+            b'G' => b'C',
+            b'a' => b't',
+            b't' => b'a',
+            b'c' => b'g',
+            b'g' => b'c',
+            b => b,
+        })
+        .collect()
+}
+
 struct QueryThread {
     resp_rx: channel::Receiver<GraphQueryResp>,
     req_tx: channel::Sender<GraphQueryRequest>,
-    _thread_handle: std::thread::JoinHandle<()>,
+    thread_handle: Option<std::thread::JoinHandle<()>>,
 }
 
 impl QueryThread {
@@ -243,11 +566,14 @@ impl QueryThread {
         self.resp_rx.recv().unwrap()
     }
 
-    fn new(graph: Arc<PackedGraph>) -> Self {
+    fn new(
+        graph: Arc<PackedGraph>,
+        sequences: Option<Arc<SequenceIndex>>,
+    ) -> Self {
         let (resp_tx, resp_rx) = channel::bounded::<GraphQueryResp>(0);
         let (req_tx, req_rx) = channel::bounded::<GraphQueryRequest>(0);
 
-        let _thread_handle = std::thread::spawn(move || {
+        let thread_handle = std::thread::spawn(move || {
             use GraphQueryRequest as Req;
             use GraphQueryResp as Resp;
 
@@ -259,7 +585,7 @@ impl QueryThread {
                         node_count: graph.node_count(),
                         edge_count: graph.edge_count(),
                         path_count: graph.path_count(),
-                        total_len: graph.total_length(),
+                        total_len: total_length(&graph, sequences.as_deref()),
                     },
                     Req::NodeStats(node_id) => {
                         let handle = Handle::pack(node_id, false);
@@ -274,7 +600,7 @@ impl QueryThread {
 
                         Resp::NodeStats {
                             node_id,
-                            len: graph.node_len(handle),
+                            len: node_len(&graph, sequences.as_deref(), handle),
                             degree: (deg_l, deg_r),
                             coverage,
                         }
@@ -287,8 +613,11 @@ impl QueryThread {
                         }
                     }
                     Req::NodeSeq(node_id) => {
-                        let seq =
-                            graph.sequence_vec(Handle::pack(node_id, false));
+                        let seq = node_sequence(
+                            &graph,
+                            sequences.as_deref(),
+                            Handle::pack(node_id, false),
+                        );
                         let len = seq.len();
 
                         Resp::NodeSeq { node_id, seq, len }
@@ -302,7 +631,21 @@ impl QueryThread {
         Self {
             resp_rx,
             req_tx,
-            _thread_handle,
+            thread_handle: Some(thread_handle),
+        }
+    }
+}
+
+impl Drop for QueryThread {
+    fn drop(&mut self) {
+        // dropping the only sender stops the query thread
+        let (req_tx, _) = channel::bounded(0);
+        self.req_tx = req_tx;
+
+        if let Some(thread_handle) = self.thread_handle.take() {
+            if thread_handle.join().is_err() {
+                log::error!("Graph query thread panicked");
+            }
         }
     }
 }
@@ -345,3 +688,62 @@ pub enum GraphQueryResp {
     //     right: Vec<NodeId>,
     // },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    const GFA: &str = "H\tVN:Z:1.0
+S\t1\tACGTACGT
+S\t2\tGG
+S\t3\tTTTAC
+L\t1\t+\t2\t+\t0M
+L\t2\t+\t3\t-\t0M
+P\tp1\t1+,2+,3-\t*
+P\tp2\t3+,1-\t*
+";
+
+    #[test]
+    fn lazy_path_positions_use_sequence_lengths() {
+        let gfa_path = std::env::temp_dir().join(format!(
+            "gfaestus_lazy_positions_{}.gfa",
+            std::process::id()
+        ));
+        let mut file = std::fs::File::create(&gfa_path).unwrap();
+        file.write_all(GFA.as_bytes()).unwrap();
+
+        let gfa_path = gfa_path.to_str().unwrap();
+        let eager = GraphQuery::load_gfa(gfa_path).unwrap();
+        let lazy = GraphQuery::load_gfa_lazy(gfa_path, None).unwrap();
+
+        for path in eager.graph().path_ids() {
+            assert_eq!(
+                lazy.path_positions().path_base_len(path),
+                eager.path_positions().path_base_len(path)
+            );
+            assert_eq!(lazy.path_pos_steps(path), eager.path_pos_steps(path));
+        }
+
+        let p1 = lazy.graph().get_path_id(b"p1").unwrap();
+        assert_eq!(lazy.path_positions().path_base_len(p1), Some(15));
+
+        let offsets = lazy
+            .path_pos_steps(p1)
+            .unwrap()
+            .into_iter()
+            .map(|(_, _, pos)| pos)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![0, 8, 10]);
+
+        let step = lazy.find_step_at_base(p1, 9).unwrap();
+        assert_eq!(
+            lazy.graph().path_handle_at_step(p1, step),
+            Some(Handle::pack(2, false))
+        );
+        assert_eq!(lazy.find_step_at_base(p1, 15), None);
+
+        let _ = std::fs::remove_file(gfa_path);
+    }
+}
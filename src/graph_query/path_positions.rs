@@ -0,0 +1,113 @@
+use handlegraph::{
+    handle::Handle,
+    packedgraph::{paths::StepPtr, PackedGraph},
+    pathhandlegraph::*,
+};
+
+use rustc_hash::FxHashMap;
+
+/// Base pair offsets of the steps on each path in a graph.
+///
+/// Unlike handlegraph's `PathPositionMap`, the node lengths are
+/// provided by the caller, so the offsets are also correct when the
+/// graph only holds placeholder sequences, as with lazy loading.
+#[derive(Debug, Clone, Default)]
+pub struct PathPositions {
+    paths: FxHashMap<PathId, PathIndex>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct PathIndex {
+    /// The offset of the first base of each step, in path order
+    steps: Vec<(usize, StepPtr)>,
+    offsets: FxHashMap<StepPtr, usize>,
+    base_len: usize,
+}
+
+impl PathPositions {
+    /// Indexes every path in `graph`, using `node_len` for the length
+    /// of each node.
+    pub fn index_paths<F>(graph: &PackedGraph, node_len: F) -> Self
+    where
+        F: Fn(Handle) -> usize,
+    {
+        let mut paths = FxHashMap::default();
+
+        for path_id in graph.path_ids() {
+            let path_steps = match graph.path_steps(path_id) {
+                Some(steps) => steps,
+                None => continue,
+            };
+
+            let mut index = PathIndex::default();
+
+            for step in path_steps {
+                let step_ptr = step.0;
+
+                index.steps.push((index.base_len, step_ptr));
+                index.offsets.insert(step_ptr, index.base_len);
+
+                index.base_len += node_len(step.handle());
+            }
+
+            paths.insert(path_id, index);
+        }
+
+        Self { paths }
+    }
+
+    /// The total length of `path` in base pairs
+    pub fn path_base_len(&self, path: PathId) -> Option<usize> {
+        self.paths.get(&path).map(|index| index.base_len)
+    }
+
+    /// The offset of the first base of `step` on `path`
+    pub fn path_step_position(
+        &self,
+        path: PathId,
+        step: StepPtr,
+    ) -> Option<usize> {
+        self.paths.get(&path)?.offsets.get(&step).copied()
+    }
+
+    /// The step on `path` that covers the base at offset `pos`
+    pub fn find_step_at_base(
+        &self,
+        path: PathId,
+        pos: usize,
+    ) -> Option<StepPtr> {
+        let index = self.paths.get(&path)?;
+
+        if pos >= index.base_len {
+            return None;
+        }
+
+        let ix = index.steps.partition_point(|&(offset, _)| offset <= pos);
+        let &(_, step) = index.steps.get(ix.checked_sub(1)?)?;
+
+        Some(step)
+    }
+
+    /// The path, step and base offset of every step on `handle`
+    pub fn handle_positions_iter<'a>(
+        &'a self,
+        graph: &'a PackedGraph,
+        handle: Handle,
+    ) -> Option<impl Iterator<Item = (PathId, StepPtr, usize)> + 'a> {
+        let steps = graph.steps_on_handle(handle)?;
+
+        Some(steps.filter_map(move |(path, step)| {
+            let pos = self.path_step_position(path, step)?;
+            Some((path, step, pos))
+        }))
+    }
+
+    pub fn handle_positions(
+        &self,
+        graph: &PackedGraph,
+        handle: Handle,
+    ) -> Option<Vec<(PathId, StepPtr, usize)>> {
+        self.handle_positions_iter(graph, handle)
+            .map(|positions| positions.collect())
+    }
+}
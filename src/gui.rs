@@ -34,7 +34,7 @@ use crate::{
 };
 use crate::{app::OverlayState, geometry::*};
 
-use crate::overlays::OverlayKind;
+use crate::overlays::{OverlayData, OverlayKind};
 
 use crate::graph_query::GraphQuery;
 
@@ -52,13 +52,18 @@ use log::{debug, error, info, trace, warn};
 
 pub mod console;
 pub mod debug;
+pub mod loading;
 pub mod text;
+pub mod toasts;
+pub mod tooltip;
 pub mod util;
 pub mod widgets;
 pub mod windows;
 
 use console::*;
 use debug::*;
+use toasts::Toasts;
+use tooltip::NodeTooltip;
 #[allow(unused_imports)]
 use util::*;
 use widgets::*;
@@ -81,6 +86,8 @@ pub struct Gui {
     view_state: AppViewState,
 
     menu_bar: MenuBar,
+    node_tooltip: NodeTooltip,
+    toasts: Toasts,
 
     dropped_file: Arc<std::sync::Mutex<Option<PathBuf>>>,
 
@@ -94,6 +101,8 @@ pub struct Gui {
 
     windows: GuiWindows,
     gui_channels: GuiChannels,
+
+    needs_repaint: bool,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -107,6 +116,12 @@ pub enum Windows {
     NodeDetails,
 
     Paths,
+    PathComparison,
+    ShortestPath,
+    Bubbles,
+    Layers,
+
+    SequenceSearch,
 
     Themes,
     Overlays,
@@ -168,19 +183,35 @@ where
 pub struct AppViewState {
     settings: SettingsWindow,
     fps: ViewStateChannel<FrameRate, FrameRateMsg>,
+    frame_times: ViewStateChannel<FrameTimes, FrameTimesMsg>,
 
     graph_stats: ViewStateChannel<GraphStats, GraphStatsMsg>,
 
     node_list: ViewStateChannel<NodeList, NodeListMsg>,
     node_details: ViewStateChannel<NodeDetails, NodeDetailsMsg>,
 
-    path_list: ViewStateChannel<PathList, ()>,
+    path_list: ViewStateChannel<PathList, PathListMsg>,
     path_details: ViewStateChannel<PathDetails, ()>,
 
+    path_comparison: PathComparison,
+    shortest_path: ShortestPath,
+    bubble_finder: BubbleFinder,
+    layer_list: LayerList,
+
+    sequence_search: SequenceSearch,
+
+    bookmarks: Bookmarks,
+    markers: Markers,
+
+    minimap: Minimap,
+
+    themes: ThemeList,
+
     // theme_editor: ThemeEditor,
-    // theme_list: ThemeList,
     overlay_creator: ViewStateChannel<OverlayCreator, OverlayCreatorMsg>,
     overlay_list: ViewStateChannel<OverlayList, OverlayListMsg>,
+
+    bed_overlay_loader: BedOverlayLoader,
 }
 
 impl AppViewState {
@@ -198,9 +229,14 @@ impl AppViewState {
             node_count: graph.node_count(),
             edge_count: graph.edge_count(),
             path_count: graph.path_count(),
-            total_len: graph.total_length(),
+            total_len: graph_query.total_length(),
+            visible_nodes: None,
+            lod_active: false,
+            load_progress: None,
         };
 
+        let themes = ThemeList::new(settings, shared_state);
+
         let settings = SettingsWindow::new(settings, shared_state);
 
         let node_details_state = NodeDetails::default();
@@ -219,10 +255,27 @@ impl AppViewState {
         let path_details =
             ViewStateChannel::<PathDetails, ()>::new(path_details_state);
 
-        let path_list_state = PathList::new(&graph_query, path_id_cell);
-        let path_list = ViewStateChannel::<PathList, ()>::new(path_list_state);
+        let path_list_state = PathList::new(
+            reactor,
+            shared_state.path_colors().clone(),
+            path_id_cell.clone(),
+        );
+        let path_list =
+            ViewStateChannel::<PathList, PathListMsg>::new(path_list_state);
+
+        let path_comparison =
+            PathComparison::new(reactor, path_id_cell.clone());
 
-        let overlay_list_state = OverlayList::new(overlay_state);
+        let shortest_path = ShortestPath::new(reactor, node_id_cell.clone());
+
+        let bubble_finder = BubbleFinder::new(reactor);
+
+        let layer_list = LayerList::new(reactor, shared_state);
+
+        let sequence_search = SequenceSearch::new(reactor);
+
+        let overlay_list_state =
+            OverlayList::new(reactor, overlay_state, path_id_cell);
         let overlay_list = ViewStateChannel::<OverlayList, OverlayListMsg>::new(
             overlay_list_state,
         );
@@ -233,10 +286,13 @@ impl AppViewState {
             OverlayCreatorMsg,
         >::new(overlay_creator_state);
 
+        let bed_overlay_loader = BedOverlayLoader::new(reactor).unwrap();
+
         Self {
             settings,
 
             fps: Default::default(),
+            frame_times: Default::default(),
             graph_stats: ViewStateChannel::new(stats),
 
             node_list,
@@ -245,8 +301,24 @@ impl AppViewState {
             path_list,
             path_details,
 
+            path_comparison,
+            shortest_path,
+            bubble_finder,
+            layer_list,
+
+            sequence_search,
+
+            bookmarks: Bookmarks::default(),
+            markers: Markers::default(),
+
+            minimap: Minimap::default(),
+
+            themes,
+
             overlay_list,
             overlay_creator,
+
+            bed_overlay_loader,
         }
     }
 
@@ -254,6 +326,10 @@ impl AppViewState {
         &self.fps
     }
 
+    pub fn frame_times(&self) -> &ViewStateChannel<FrameTimes, FrameTimesMsg> {
+        &self.frame_times
+    }
+
     pub fn graph_stats(&self) -> &ViewStateChannel<GraphStats, GraphStatsMsg> {
         &self.graph_stats
     }
@@ -262,6 +338,10 @@ impl AppViewState {
         &self.node_list
     }
 
+    pub fn path_list(&self) -> &ViewStateChannel<PathList, PathListMsg> {
+        &self.path_list
+    }
+
     pub fn node_details(
         &self,
     ) -> &ViewStateChannel<NodeDetails, NodeDetailsMsg> {
@@ -273,6 +353,10 @@ impl AppViewState {
             *state = FrameRate::apply_msg(state, msg);
         });
 
+        self.frame_times.apply_received(|state, msg| {
+            state.apply_msg(msg);
+        });
+
         self.graph_stats.apply_received(|state, msg| {
             *state = GraphStats::apply_msg(state, msg);
         });
@@ -284,6 +368,10 @@ impl AppViewState {
         self.node_details.apply_received(|state, msg| {
             state.apply_msg(msg);
         });
+
+        self.path_list.apply_received(|state, msg| {
+            state.apply_msg(msg);
+        });
     }
 }
 
@@ -300,10 +388,24 @@ pub struct OpenWindows {
 
     paths: bool,
     path_details: bool,
+    path_comparison: bool,
+    shortest_path: bool,
+    bubbles: bool,
+    layers: bool,
+
+    sequence_search: bool,
+
+    bookmarks: bool,
+    markers: bool,
+
+    minimap: bool,
+
+    performance: bool,
 
     themes: bool,
     overlays: bool,
     overlay_creator: bool,
+    bed_overlay_loader: bool,
 }
 
 impl std::default::Default for OpenWindows {
@@ -320,10 +422,24 @@ impl std::default::Default for OpenWindows {
 
             paths: false,
             path_details: false,
+            path_comparison: false,
+            shortest_path: false,
+            bubbles: false,
+            layers: false,
+
+            sequence_search: false,
+
+            bookmarks: false,
+            markers: false,
+
+            minimap: false,
+
+            performance: false,
 
             themes: false,
             overlays: false,
             overlay_creator: false,
+            bed_overlay_loader: false,
         }
     }
 }
@@ -340,6 +456,11 @@ pub enum GuiMsg {
     Copy,
     Paste,
 
+    CycleOverlay { forward: bool },
+
+    /// Add a marker at a point in world coordinates
+    AddMarker { point: Point },
+
     // TODO this shouldn't really be here, as things like the console
     // will never update the modifiers
     SetModifiers(winit::event::ModifiersState),
@@ -384,24 +505,7 @@ impl Gui {
 
         let draw_system = GuiPipeline::new(gfaestus, render_pass)?;
 
-        let ctx = egui::CtxRef::default();
-
-        Self::dark_mode(&ctx);
-
-        let font_defs = {
-            use egui::FontFamily as Family;
-            use egui::TextStyle as Style;
-
-            let mut font_defs = egui::FontDefinitions::default();
-            let fam_size = &mut font_defs.family_and_size;
-
-            fam_size.insert(Style::Small, (Family::Proportional, 12.0));
-            fam_size.insert(Style::Body, (Family::Proportional, 16.0));
-            fam_size.insert(Style::Button, (Family::Proportional, 18.0));
-            fam_size.insert(Style::Heading, (Family::Proportional, 22.0));
-            font_defs
-        };
-        ctx.set_fonts(font_defs);
+        let ctx = Self::create_context();
 
         let open_windows = OpenWindows::default();
 
@@ -585,6 +689,8 @@ impl Gui {
             view_state,
 
             menu_bar,
+            node_tooltip: NodeTooltip::default(),
+            toasts: Toasts::new(channels.toast_rx.clone()),
 
             dropped_file,
 
@@ -599,6 +705,8 @@ impl Gui {
 
             windows,
             gui_channels: GuiChannels::new(),
+
+            needs_repaint: true,
         };
 
         Ok(gui)
@@ -628,6 +736,38 @@ impl Gui {
         );
     }
 
+    /// Load the view bookmarks stored for the graph at `gfa_path`
+    pub fn load_bookmarks<P: AsRef<std::path::Path>>(&mut self, gfa_path: P) {
+        self.view_state.bookmarks = Bookmarks::load_for_gfa(gfa_path);
+    }
+
+    /// Load the markers stored for the graph at `gfa_path`
+    pub fn load_markers<P: AsRef<std::path::Path>>(&mut self, gfa_path: P) {
+        self.view_state.markers = Markers::load_for_gfa(gfa_path);
+    }
+
+    pub fn set_overlay_data(&mut self, overlay_id: usize, data: OverlayData) {
+        self.view_state
+            .overlay_list
+            .state
+            .set_overlay_data(overlay_id, data);
+    }
+
+    pub fn overlay_data(&self, overlay_id: usize) -> Option<Arc<OverlayData>> {
+        self.view_state.overlay_list.state.overlay_data(overlay_id)
+    }
+
+    pub fn set_overlay_palette(
+        &mut self,
+        overlay_id: usize,
+        palette: Vec<rgb::RGB<f32>>,
+    ) {
+        self.view_state
+            .overlay_list
+            .state
+            .set_overlay_palette(overlay_id, palette);
+    }
+
     pub fn scroll_to_gff_record(
         &mut self,
         records: &Gff3Records,
@@ -653,6 +793,7 @@ impl Gui {
         // ctx_tx: &crossbeam::channel::Sender<ContextEntry>,
         ctx_mgr: &ContextMgr,
         nodes: &[Node],
+        layout_generation: u64,
     ) {
         let App {
             reactor,
@@ -703,8 +844,18 @@ impl Gui {
             &self.windows,
         );
 
+        StatusBar::ui(
+            &self.ctx,
+            self.shared_state.view(),
+            self.shared_state.screen_dims(),
+            self.shared_state.mouse_pos(),
+            self.shared_state.hover_node(),
+        );
+
         self.console.ui(&self.ctx, self.console_down, reactor);
 
+        self.toasts.ui(&self.ctx);
+
         self.view_state.apply_received();
 
         let scr = self.ctx.input().screen_rect();
@@ -726,6 +877,10 @@ impl Gui {
                 .state
                 .ui(&self.ctx, overlay_creator);
 
+            view_state
+                .bed_overlay_loader
+                .ui(&self.ctx, &mut self.open_windows.bed_overlay_loader);
+
             view_state.overlay_list.state.gradient_picker_ui(&self.ctx);
         }
 
@@ -748,6 +903,31 @@ impl Gui {
             paint_area.painter().rect_stroke(rect.into(), 0.0, stroke);
         }
 
+        view_state.markers.draw_pins(
+            &self.ctx,
+            self.shared_state.view(),
+            self.shared_state.screen_dims(),
+        );
+
+        {
+            let hide_tooltip = self.shared_state.show_modal.load()
+                || ctx_mgr.is_menu_open()
+                || self.shared_state.gui_focus_state.mouse_over_gui();
+
+            let hover_node = if hide_tooltip {
+                None
+            } else {
+                self.shared_state.hover_node()
+            };
+
+            self.node_tooltip.ui(
+                &self.ctx,
+                graph_query,
+                hover_node,
+                self.shared_state.mouse_pos(),
+            );
+        }
+
         self.annotation_file_list.ui(
             &self.ctx,
             &mut self.open_windows.annotation_files,
@@ -870,6 +1050,8 @@ impl Gui {
             let path_details = &mut self.open_windows.path_details;
             let path_details_id_cell =
                 view_state.path_details.state.path_details.path_id_cell();
+            let path_details_step_cell =
+                view_state.path_details.state.step_list.jump_to_step_cell();
 
             if *node_list {
                 view_state.node_list.state.ui(
@@ -887,7 +1069,9 @@ impl Gui {
                     graph_query,
                     &self.ctx,
                     path_details_id_cell,
+                    path_details_step_cell,
                     path_details,
+                    &self.channels.app_tx,
                     ctx_mgr,
                 );
             }
@@ -924,6 +1108,100 @@ impl Gui {
             }
         }
 
+        if self.open_windows.path_comparison {
+            view_state.path_comparison.ui(
+                &self.ctx,
+                &mut self.open_windows.path_comparison,
+                graph_query,
+            );
+        }
+
+        if self.open_windows.shortest_path {
+            view_state.shortest_path.ui(
+                &self.ctx,
+                &mut self.open_windows.shortest_path,
+                graph_query,
+                &self.channels.app_tx,
+            );
+        }
+
+        if self.open_windows.bubbles {
+            view_state.bubble_finder.ui(
+                &self.ctx,
+                &mut self.open_windows.bubbles,
+                &self.channels.app_tx,
+                ctx_mgr,
+            );
+        }
+
+        if self.open_windows.layers {
+            view_state.layer_list.ui(
+                &self.ctx,
+                &mut self.open_windows.layers,
+                &self.channels.layer_tx,
+            );
+        }
+
+        if self.open_windows.sequence_search {
+            view_state.sequence_search.ui(
+                &self.ctx,
+                &mut self.open_windows.sequence_search,
+                &self.channels.app_tx,
+                ctx_mgr,
+            );
+        }
+
+        if self.open_windows.performance {
+            view_state
+                .frame_times
+                .state
+                .ui(&self.ctx, &mut self.open_windows.performance);
+        }
+
+        if self.open_windows.bookmarks {
+            view_state.bookmarks.ui(
+                &self.ctx,
+                &mut self.open_windows.bookmarks,
+                self.shared_state.view(),
+                &self.channels.main_view_tx,
+            );
+        }
+
+        if self.open_windows.markers {
+            view_state.markers.ui(
+                &self.ctx,
+                &mut self.open_windows.markers,
+                self.shared_state.view(),
+                &self.channels.main_view_tx,
+            );
+        }
+
+        if self.open_windows.themes {
+            let applied = view_state
+                .themes
+                .ui(&self.ctx, &mut self.open_windows.themes);
+
+            if let Some(theme) = applied {
+                if theme.dark {
+                    Self::dark_mode(&self.ctx);
+                } else {
+                    Self::light_mode(&self.ctx);
+                }
+            }
+        }
+
+        if self.open_windows.minimap {
+            view_state.minimap.ui(
+                &self.ctx,
+                &mut self.open_windows.minimap,
+                nodes,
+                layout_generation,
+                self.shared_state.view(),
+                self.shared_state.screen_dims(),
+                &self.channels.main_view_tx,
+            );
+        }
+
         {
             let debug = &mut view_state.settings.debug;
             let inspection = &mut debug.egui_inspection;
@@ -965,13 +1243,29 @@ impl Gui {
     ) -> Vec<egui::ClippedMesh> {
         let (output, shapes) = self.ctx.end_frame();
 
+        // widgets may have taken or released keyboard focus during
+        // the frame, and the input events that arrive before the next
+        // frame must be routed accordingly
+        self.shared_state
+            .gui_focus_state
+            .wants_keyboard_input
+            .store(self.ctx.wants_keyboard_input());
+
         if !output.copied_text.is_empty() {
             reactor.set_clipboard_contents(&output.copied_text, true);
         }
 
+        self.needs_repaint = output.needs_repaint;
+
         self.ctx.tessellate(shapes)
     }
 
+    /// True if egui is animating, or otherwise asked to be redrawn,
+    /// as of the last frame
+    pub fn needs_repaint(&self) -> bool {
+        self.needs_repaint
+    }
+
     pub fn pointer_over_gui(&self) -> bool {
         self.ctx.is_pointer_over_area()
     }
@@ -1035,6 +1329,17 @@ impl Gui {
                         Windows::Nodes => &mut open_windows.nodes,
                         Windows::NodeDetails => &mut open_windows.node_details,
                         Windows::Paths => &mut open_windows.paths,
+                        Windows::PathComparison => {
+                            &mut open_windows.path_comparison
+                        }
+                        Windows::ShortestPath => {
+                            &mut open_windows.shortest_path
+                        }
+                        Windows::Bubbles => &mut open_windows.bubbles,
+                        Windows::Layers => &mut open_windows.layers,
+                        Windows::SequenceSearch => {
+                            &mut open_windows.sequence_search
+                        }
                         Windows::Themes => &mut open_windows.themes,
                         Windows::Overlays => &mut open_windows.overlays,
                         Windows::EguiInspection => {
@@ -1080,6 +1385,13 @@ impl Gui {
                         self.frame_input.events.push(egui::Event::Text(text));
                     }
                 }
+                GuiMsg::CycleOverlay { forward } => {
+                    self.view_state.overlay_list.state.cycle_overlay(forward);
+                }
+                GuiMsg::AddMarker { point } => {
+                    self.view_state.markers.add(point);
+                    self.open_windows.markers = true;
+                }
                 GuiMsg::SetModifiers(mods) => {
                     let modifiers = egui::Modifiers {
                         alt: mods.alt(),
@@ -1090,6 +1402,16 @@ impl Gui {
                     };
 
                     self.frame_input.modifiers = modifiers;
+
+                    // the walk keys are bound with ctrl, so their
+                    // release isn't seen if ctrl is released first
+                    if !mods.ctrl() {
+                        self.view_state
+                            .path_details
+                            .state
+                            .step_list
+                            .stop_walk();
+                    }
                 }
             }
         }
@@ -1105,6 +1427,18 @@ impl Gui {
 
         match input {
             SystemInput::Keyboard { state, .. } => {
+                if matches!(payload, In::KeyPathStepPrev | In::KeyPathStepNext)
+                {
+                    if self.open_windows.path_details {
+                        let forward = matches!(payload, In::KeyPathStepNext);
+                        self.view_state
+                            .path_details
+                            .state
+                            .step_list
+                            .set_walk_key(forward, state.pressed());
+                    }
+                }
+
                 if state.pressed() {
                     match payload {
                         GuiInput::KeyEguiInspectionUi => {
@@ -1191,6 +1525,31 @@ impl Gui {
         }
     }
 
+    /// Creates an egui context with the dark theme and the font
+    /// sizes used throughout the GUI
+    fn create_context() -> egui::CtxRef {
+        let ctx = egui::CtxRef::default();
+
+        Self::dark_mode(&ctx);
+
+        let font_defs = {
+            use egui::FontFamily as Family;
+            use egui::TextStyle as Style;
+
+            let mut font_defs = egui::FontDefinitions::default();
+            let fam_size = &mut font_defs.family_and_size;
+
+            fam_size.insert(Style::Small, (Family::Proportional, 12.0));
+            fam_size.insert(Style::Body, (Family::Proportional, 16.0));
+            fam_size.insert(Style::Button, (Family::Proportional, 18.0));
+            fam_size.insert(Style::Heading, (Family::Proportional, 22.0));
+            font_defs
+        };
+        ctx.set_fonts(font_defs);
+
+        ctx
+    }
+
     fn set_style(ctx: &egui::CtxRef, visuals: egui::style::Visuals) {
         let mut style: egui::Style = (*ctx.style()).clone();
         style.visuals = visuals;
@@ -1243,6 +1602,8 @@ pub enum GuiInput {
     KeyToggleConsole,
     KeyConsoleDown,
     KeyConsoleUp,
+    KeyPathStepPrev,
+    KeyPathStepNext,
 }
 
 impl BindableInput for GuiInput {
@@ -1251,7 +1612,7 @@ impl BindableInput for GuiInput {
         use winit::event::VirtualKeyCode as Key;
         use GuiInput as Input;
 
-        let key_binds: FxHashMap<Key, Vec<KeyBind<Input>>> = [
+        let mut key_binds: FxHashMap<Key, Vec<KeyBind<Input>>> = [
             (Key::F1, Input::KeyEguiInspectionUi),
             (Key::F2, Input::KeyEguiSettingsUi),
             (Key::F3, Input::KeyEguiMemoryUi),
@@ -1264,6 +1625,18 @@ impl BindableInput for GuiInput {
         .map(|(k, i)| (k, vec![KeyBind::new(i)]))
         .collect::<FxHashMap<_, _>>();
 
+        // the unmodified arrow keys pan the main view
+        let ctrl_mod = event::ModifiersState::CTRL;
+
+        key_binds.insert(
+            Key::Left,
+            vec![KeyBind::with_modifiers(Input::KeyPathStepPrev, ctrl_mod)],
+        );
+        key_binds.insert(
+            Key::Right,
+            vec![KeyBind::with_modifiers(Input::KeyPathStepNext, ctrl_mod)],
+        );
+
         let mouse_binds: FxHashMap<
             event::MouseButton,
             Vec<MouseButtonBind<Input>>,
@@ -1285,4 +1658,20 @@ impl BindableInput for GuiInput {
 
         SystemInputBindings::new(key_binds, mouse_binds, wheel_bind)
     }
+
+    // the mouse buttons are passed on to egui, so they aren't bindable
+    fn bindable_actions() -> Vec<(&'static str, Self)> {
+        use GuiInput as Input;
+
+        vec![
+            ("egui_inspection_ui", Input::KeyEguiInspectionUi),
+            ("egui_settings_ui", Input::KeyEguiSettingsUi),
+            ("egui_memory_ui", Input::KeyEguiMemoryUi),
+            ("toggle_console", Input::KeyToggleConsole),
+            ("console_down", Input::KeyConsoleDown),
+            ("console_up", Input::KeyConsoleUp),
+            ("path_step_prev", Input::KeyPathStepPrev),
+            ("path_step_next", Input::KeyPathStepNext),
+        ]
+    }
 }
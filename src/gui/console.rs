@@ -37,8 +37,8 @@ use crate::{
 };
 use crate::{
     app::{
-        selection::NodeSelection, AppChannels, AppMsg, OverlayCreatorMsg,
-        Select,
+        mainview::MainViewMsg, selection::NodeSelection, AppChannels, AppMsg,
+        OverlayCreatorMsg, Select,
     },
     geometry::*,
     quad_tree::*,
@@ -1170,6 +1170,7 @@ impl ConsoleShared {
         use rhai::plugin::*;
 
         let mut engine = crate::script::create_engine();
+        crate::script::register_sequence_fns(&mut engine, &self.graph);
 
         engine.register_static_module("app", self.app_module());
         engine.register_static_module("msg", self.app_msg_module());
@@ -2177,6 +2178,37 @@ impl ConsoleShared {
             let dims = screen_dims.load();
             view.screen_point_to_world(dims, screen)
         });
+
+        // per-node width factors, e.g. to draw nodes thicker by their
+        // coverage; the array must have one number per node, in node
+        // ID order
+        let main_view_tx = self.channels.main_view_tx.clone();
+        engine.register_result_fn(
+            "set_node_widths",
+            move |widths: rhai::Array| -> std::result::Result<
+                (),
+                Box<EvalAltResult>,
+            > {
+                let widths = widths
+                    .into_iter()
+                    .map(|w| {
+                        w.as_float()
+                            .or_else(|_| w.as_int().map(|i| i as f32))
+                            .map_err(|_| "Node widths must be numbers".into())
+                    })
+                    .collect::<std::result::Result<Vec<f32>, Box<EvalAltResult>>>()?;
+
+                main_view_tx
+                    .send(MainViewMsg::SetNodeWidths(widths))
+                    .unwrap();
+                Ok(())
+            },
+        );
+
+        let main_view_tx = self.channels.main_view_tx.clone();
+        engine.register_fn("reset_node_widths", move || {
+            main_view_tx.send(MainViewMsg::ResetNodeWidths).unwrap();
+        });
     }
 
     fn add_annotation_fns(&self, engine: &mut rhai::Engine) {
@@ -132,9 +132,6 @@ pub(super) fn tsv_wizard_impl(
         }
     };
 
-    let graph = console.graph.clone();
-    let rayon_pool = console.rayon_pool.clone();
-
     let result = thread_pool.spawn(async move {
         if let Some((table, table_path)) = table_future.await {
             let mut node_color_map: FxHashMap<NodeId, rgb::RGBA<f32>> =
@@ -166,31 +163,10 @@ pub(super) fn tsv_wizard_impl(
             let name = table_path.as_os_str().to_str().unwrap();
 
             if !node_color_map.is_empty() {
-                let data = {
-                    use rayon::prelude::*;
-
-                    let mut nodes = graph
-                        .graph
-                        .handles()
-                        .map(|h| h.id())
-                        .collect::<Vec<_>>();
-                    nodes.sort();
-
-                    // TODO gradient support (or rewrite gradient
-                    // logic entirely)
-
-                    let colors = rayon_pool.install(|| {
-                        nodes
-                            .par_iter()
-                            .map(|node| {
-                                node_color_map.get(&node).copied().unwrap_or(
-                                    rgb::RGBA::new(0.5, 0.5, 0.5, 0.4),
-                                )
-                            })
-                            .collect()
-                    });
-
-                    OverlayData::RGB(colors)
+                // only the annotated nodes are written to the overlay
+                let data = OverlayData::SparseRGB {
+                    default: rgb::RGBA::new(0.5, 0.5, 0.5, 0.4),
+                    colors: node_color_map.into_iter().collect(),
                 };
 
                 let msg = OverlayCreatorMsg::NewOverlay {
@@ -0,0 +1,193 @@
+use ash::{version::DeviceV1_0, vk, Device};
+
+use anyhow::Result;
+
+use crate::geometry::*;
+use crate::gfa::load::LoadProgress;
+use crate::vulkan::{draw_system::gui::GuiPipeline, GfaestusVk};
+
+use super::{
+    widgets::{GraphStats, GraphStatsMsg},
+    Gui, ViewStateChannel,
+};
+
+/// Draws the GFA loading progress in a modal, on an otherwise empty
+/// window, before the graph and the rest of the GUI exist
+pub struct LoadingModal {
+    ctx: egui::CtxRef,
+    draw_system: GuiPipeline,
+
+    graph_stats: ViewStateChannel<GraphStats, GraphStatsMsg>,
+}
+
+impl LoadingModal {
+    pub fn new(app: &GfaestusVk) -> Result<Self> {
+        let draw_system = GuiPipeline::new(app, app.render_passes.gui)?;
+
+        Ok(Self {
+            ctx: Gui::create_context(),
+            draw_system,
+            graph_stats: ViewStateChannel::default(),
+        })
+    }
+
+    pub fn set_progress(&self, progress: LoadProgress) {
+        self.graph_stats.send(GraphStatsMsg {
+            load_progress: Some(progress),
+            ..GraphStatsMsg::default()
+        });
+    }
+
+    /// Draws a frame with the latest progress, returning `true` if
+    /// the swapchain must be recreated
+    pub fn draw(
+        &mut self,
+        app: &mut GfaestusVk,
+        window_size: [u32; 2],
+    ) -> Result<bool> {
+        self.graph_stats.apply_received(|state, msg| {
+            *state = GraphStats::apply_msg(state, msg);
+        });
+
+        let screen_dims: [f32; 2] = app.swapchain_dims().into();
+
+        let raw_input = egui::RawInput {
+            screen_rect: Some(egui::Rect {
+                min: Point::ZERO.into(),
+                max: Point::new(screen_dims[0], screen_dims[1]).into(),
+            }),
+            ..egui::RawInput::default()
+        };
+
+        self.ctx.begin_frame(raw_input);
+        self.graph_stats.state.load_progress_ui(&self.ctx);
+        let (_output, shapes) = self.ctx.end_frame();
+        let meshes = self.ctx.tessellate(shapes);
+
+        let egui_tex = self.ctx.texture();
+        if egui_tex.version != self.draw_system.egui_texture_version() {
+            self.draw_system.upload_egui_texture(
+                app,
+                app.transient_command_pool,
+                app.graphics_queue,
+                &egui_tex,
+            )?;
+        }
+
+        self.draw_system.vertices.upload_meshes(app, &meshes)?;
+
+        let node_pass = app.render_passes.nodes;
+        let gui_pass = app.render_passes.gui;
+        let node_id_image = app.node_attachments.id_resolve.image;
+
+        let draw_system = &self.draw_system;
+        let mut result = Ok(());
+
+        let dims = screen_dims;
+
+        let dirty =
+            app.draw_frame_from(window_size, |device, cmd_buf, fbs| {
+                // an empty node pass clears the swapchain image, which the
+                // GUI pass draws on top of
+                clear_frame(device, cmd_buf, node_pass, fbs.nodes, dims);
+                id_image_to_transfer_src(device, cmd_buf, node_id_image);
+
+                result = draw_system.draw(cmd_buf, gui_pass, fbs, dims);
+            })?;
+
+        result?;
+
+        Ok(dirty)
+    }
+
+    pub fn destroy(&mut self, app: &GfaestusVk) {
+        self.draw_system.destroy(&app.allocator);
+    }
+}
+
+fn clear_frame(
+    device: &Device,
+    cmd_buf: vk::CommandBuffer,
+    render_pass: vk::RenderPass,
+    framebuffer: vk::Framebuffer,
+    viewport_dims: [f32; 2],
+) {
+    let clear_values = [
+        vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: [0.1, 0.1, 0.2, 1.0],
+            },
+        },
+        vk::ClearValue {
+            color: vk::ClearColorValue {
+                uint32: [0, 0, 0, 0],
+            },
+        },
+        vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: [0.0, 0.0, 0.0, 1.0],
+            },
+        },
+    ];
+
+    let extent = vk::Extent2D {
+        width: viewport_dims[0] as u32,
+        height: viewport_dims[1] as u32,
+    };
+
+    let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
+        .render_pass(render_pass)
+        .framebuffer(framebuffer)
+        .render_area(vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent,
+        })
+        .clear_values(&clear_values)
+        .build();
+
+    unsafe {
+        device.cmd_begin_render_pass(
+            cmd_buf,
+            &render_pass_begin_info,
+            vk::SubpassContents::INLINE,
+        );
+        device.cmd_end_render_pass(cmd_buf);
+    }
+}
+
+/// The node pass expects the ID image to be ready for copying, as it
+/// is after a regular frame
+fn id_image_to_transfer_src(
+    device: &Device,
+    cmd_buf: vk::CommandBuffer,
+    node_id_image: vk::Image,
+) {
+    let image_memory_barrier = vk::ImageMemoryBarrier::builder()
+        .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+        .dst_access_mask(vk::AccessFlags::SHADER_READ)
+        .old_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(node_id_image)
+        .subresource_range(vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        })
+        .build();
+
+    unsafe {
+        device.cmd_pipeline_barrier(
+            cmd_buf,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::DependencyFlags::BY_REGION,
+            &[],
+            &[],
+            &[image_memory_barrier],
+        );
+    }
+}
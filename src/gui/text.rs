@@ -345,3 +345,234 @@ pub fn draw_text_at_aligned_world_point_offset(
 
     None
 }
+
+/// The most node ID labels drawn in a single frame, as egui slows
+/// down with very many text shapes
+const MAX_NODE_ID_LABELS: usize = 1000;
+
+/// Uniform grid over the node centers, so that drawing the node ID
+/// labels only has to look at the nodes near the visible part of the
+/// world. Rebuilt whenever the layout generation changes.
+#[derive(Debug, Default, Clone)]
+pub struct NodeCenterGrid {
+    layout_generation: Option<u64>,
+    bounds: Option<Rect>,
+    columns: usize,
+    rows: usize,
+    /// The node indices in cell `i` are
+    /// `node_ixs[cell_offsets[i]..cell_offsets[i + 1]]`, with the
+    /// cells in row-major order
+    cell_offsets: Vec<u32>,
+    node_ixs: Vec<u32>,
+}
+
+impl NodeCenterGrid {
+    /// The average number of nodes in each cell
+    const NODES_PER_CELL: usize = 16;
+
+    /// Rebuild the grid from `nodes`, unless it was already built for
+    /// `layout_generation`
+    pub fn update(&mut self, layout_generation: u64, nodes: &[Node]) {
+        if self.layout_generation == Some(layout_generation) {
+            return;
+        }
+
+        self.layout_generation = Some(layout_generation);
+
+        self.bounds = nodes.iter().fold(None, |acc: Option<Rect>, node| {
+            let rect = Rect::new(node.center(), node.center());
+            Some(acc.map_or(rect, |acc| acc.union(rect)))
+        });
+
+        let side = ((nodes.len() / Self::NODES_PER_CELL).max(1) as f32)
+            .sqrt()
+            .ceil() as usize;
+
+        self.columns = side;
+        self.rows = side;
+
+        let cells = nodes
+            .iter()
+            .map(|node| {
+                let (x, y) = self.cell_at(node.center());
+                y * self.columns + x
+            })
+            .collect::<Vec<_>>();
+
+        self.cell_offsets.clear();
+        self.cell_offsets.resize(self.columns * self.rows + 1, 0);
+
+        for &cell in cells.iter() {
+            self.cell_offsets[cell + 1] += 1;
+        }
+
+        for ix in 1..self.cell_offsets.len() {
+            self.cell_offsets[ix] += self.cell_offsets[ix - 1];
+        }
+
+        let mut next = self.cell_offsets.clone();
+
+        self.node_ixs.clear();
+        self.node_ixs.resize(nodes.len(), 0);
+
+        for (node_ix, &cell) in cells.iter().enumerate() {
+            self.node_ixs[next[cell] as usize] = node_ix as u32;
+            next[cell] += 1;
+        }
+    }
+
+    fn cell_at(&self, p: Point) -> (usize, usize) {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return (0, 0),
+        };
+
+        let norm = |v: f32, min: f32, len: f32, cells: usize| -> usize {
+            if len <= 0.0 {
+                return 0;
+            }
+            let t = ((v - min) / len).clamp(0.0, 1.0);
+            ((t * cells as f32) as usize).min(cells - 1)
+        };
+
+        let x = norm(p.x, bounds.min().x, bounds.width(), self.columns);
+        let y = norm(p.y, bounds.min().y, bounds.height(), self.rows);
+
+        (x, y)
+    }
+
+    /// The indices of the nodes in the cells that overlap `rect`,
+    /// which include every node whose center is in `rect`
+    pub fn nodes_near(&self, rect: Rect) -> impl Iterator<Item = usize> + '_ {
+        let overlaps = self.bounds.map_or(false, |b| b.intersects(rect));
+
+        let (x0, y0) = self.cell_at(rect.min());
+        let (x1, y1) = self.cell_at(rect.max());
+
+        let rows = if overlaps { y0..(y1 + 1) } else { 0..0 };
+
+        rows.flat_map(move |y| {
+            let start = self.cell_offsets[y * self.columns + x0] as usize;
+            let end = self.cell_offsets[y * self.columns + x1 + 1] as usize;
+            self.node_ixs[start..end].iter().map(|&ix| ix as usize)
+        })
+    }
+}
+
+/// Draw the ID of every node that is at least `min_node_px` pixels
+/// long on screen, centered on the node. Only the nodes near the
+/// view in `grid`, which must be up to date with `node_positions`,
+/// are considered, and nodes whose centers are outside the visible
+/// part of the world are skipped.
+///
+/// Returns the number of labels drawn.
+pub fn draw_node_id_labels(
+    ctx: &egui::CtxRef,
+    grid: &NodeCenterGrid,
+    node_positions: &[Node],
+    view: View,
+    min_node_px: f32,
+) -> usize {
+    let screen_rect = ctx.input().screen_rect();
+
+    let dims = Point::new(screen_rect.width(), screen_rect.height());
+    let half_world = dims * (view.scale / 2.0);
+
+    let visible = Rect::new(view.center - half_world, view.center + half_world);
+
+    // screen length = world length / scale
+    let min_world_len = min_node_px * view.scale;
+    let min_world_len_sqr = min_world_len * min_world_len;
+
+    let painter = ctx.layer_painter(painter_layer());
+    let color = ctx.style().visuals.text_color();
+
+    let mut count = 0;
+
+    for ix in grid.nodes_near(visible) {
+        if count >= MAX_NODE_ID_LABELS {
+            break;
+        }
+
+        let node = match node_positions.get(ix) {
+            Some(node) => node,
+            None => continue,
+        };
+
+        if node.p0.dist_sqr(node.p1) < min_world_len_sqr {
+            continue;
+        }
+
+        if !visible.contains(node.center()) {
+            continue;
+        }
+
+        let screen_pos = view.world_point_to_screen(node.center()) + dims / 2.0;
+
+        painter.text(
+            screen_pos.into(),
+            egui::Align2::CENTER_CENTER,
+            (ix + 1).to_string(),
+            egui::TextStyle::Button,
+            color,
+        );
+
+        count += 1;
+    }
+
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(x: f32, y: f32) -> Node {
+        Node {
+            p0: Point::new(x - 1.0, y),
+            p1: Point::new(x + 1.0, y),
+        }
+    }
+
+    #[test]
+    fn grid_finds_nodes_in_rect() {
+        let nodes = (0..100)
+            .map(|ix| node((ix % 10) as f32 * 10.0, (ix / 10) as f32 * 10.0))
+            .collect::<Vec<_>>();
+
+        let mut grid = NodeCenterGrid::default();
+        grid.update(0, &nodes);
+
+        let rect = Rect::new(Point::new(15.0, 25.0), Point::new(42.0, 51.0));
+
+        let mut near = grid.nodes_near(rect).collect::<Vec<_>>();
+        near.sort();
+        near.dedup();
+
+        let inside = (0..nodes.len())
+            .filter(|&ix| rect.contains(nodes[ix].center()))
+            .collect::<Vec<_>>();
+
+        assert!(!inside.is_empty());
+        assert!(inside.iter().all(|ix| near.contains(ix)));
+        assert!(near.len() < nodes.len());
+
+        let outside = Rect::new(Point::new(200.0, 0.0), Point::new(300.0, 5.0));
+        assert_eq!(grid.nodes_near(outside).count(), 0);
+    }
+
+    #[test]
+    fn grid_rebuilds_on_new_generation() {
+        let mut grid = NodeCenterGrid::default();
+        grid.update(0, &[node(0.0, 0.0)]);
+
+        let moved = [node(100.0, 100.0), node(110.0, 100.0)];
+        let rect = Rect::new(Point::new(90.0, 90.0), Point::new(120.0, 110.0));
+
+        grid.update(0, &moved);
+        assert_eq!(grid.nodes_near(rect).count(), 0);
+
+        grid.update(1, &moved);
+        assert_eq!(grid.nodes_near(rect).count(), 2);
+    }
+}
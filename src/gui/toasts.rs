@@ -0,0 +1,146 @@
+use crossbeam::channel::Receiver;
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ToastLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl ToastLevel {
+    fn color(&self) -> egui::Color32 {
+        match self {
+            ToastLevel::Info => egui::Color32::from_rgb(100, 160, 230),
+            ToastLevel::Warning => egui::Color32::from_rgb(230, 180, 40),
+            ToastLevel::Error => egui::Color32::from_rgb(230, 70, 60),
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            ToastLevel::Info => "Info",
+            ToastLevel::Warning => "Warning",
+            ToastLevel::Error => "Error",
+        }
+    }
+}
+
+/// A short-lived notification shown in the corner of the window, for
+/// errors and warnings the user should see even if they aren't
+/// looking at the log; send them on `AppChannels::toast_tx`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toast {
+    pub level: ToastLevel,
+    pub message: String,
+}
+
+impl Toast {
+    pub fn info<S: Into<String>>(message: S) -> Self {
+        Self {
+            level: ToastLevel::Info,
+            message: message.into(),
+        }
+    }
+
+    pub fn warning<S: Into<String>>(message: S) -> Self {
+        Self {
+            level: ToastLevel::Warning,
+            message: message.into(),
+        }
+    }
+
+    pub fn error<S: Into<String>>(message: S) -> Self {
+        Self {
+            level: ToastLevel::Error,
+            message: message.into(),
+        }
+    }
+}
+
+/// Receives toasts and displays each for a few seconds, newest at
+/// the bottom.
+pub struct Toasts {
+    toast_rx: Receiver<Toast>,
+    active: VecDeque<(Toast, Instant)>,
+}
+
+impl Toasts {
+    const ID: &'static str = "toasts_area";
+
+    /// How long each toast is shown, by severity
+    fn lifetime(level: ToastLevel) -> Duration {
+        match level {
+            ToastLevel::Info => Duration::from_secs(3),
+            ToastLevel::Warning => Duration::from_secs(5),
+            ToastLevel::Error => Duration::from_secs(8),
+        }
+    }
+
+    /// Older toasts are dropped when there are more than this many
+    const MAX_ACTIVE: usize = 5;
+
+    pub fn new(toast_rx: Receiver<Toast>) -> Self {
+        Self {
+            toast_rx,
+            active: VecDeque::new(),
+        }
+    }
+
+    pub fn ui(&mut self, ctx: &egui::CtxRef) {
+        let now = Instant::now();
+
+        while let Ok(toast) = self.toast_rx.try_recv() {
+            self.active.push_back((toast, now));
+        }
+
+        while self.active.len() > Self::MAX_ACTIVE {
+            self.active.pop_front();
+        }
+
+        self.active.retain(|(toast, shown)| {
+            now.duration_since(*shown) < Self::lifetime(toast.level)
+        });
+
+        if self.active.is_empty() {
+            return;
+        }
+
+        // keep drawing frames until the toasts have expired
+        ctx.request_repaint();
+
+        let mut dismissed = None;
+
+        egui::Area::new(Self::ID)
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -30.0))
+            .show(ctx, |ui| {
+                for (ix, (toast, _)) in self.active.iter().enumerate() {
+                    let color = toast.level.color();
+
+                    let frame = egui::Frame::popup(ui.style())
+                        .stroke(egui::Stroke::new(1.0, color));
+
+                    let resp = frame.show(ui, |ui| {
+                        ui.set_max_width(300.0);
+                        ui.colored_label(color, toast.level.label());
+                        ui.label(&toast.message);
+                    });
+
+                    if resp
+                        .response
+                        .interact(egui::Sense::click())
+                        .on_hover_text("Click to dismiss")
+                        .clicked()
+                    {
+                        dismissed = Some(ix);
+                    }
+                }
+            });
+
+        if let Some(ix) = dismissed {
+            self.active.remove(ix);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+#[allow(unused_imports)]
+use handlegraph::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    packedgraph::PackedGraph,
+    pathhandlegraph::*,
+};
+
+use rustc_hash::FxHashSet;
+
+use crate::geometry::Point;
+use crate::graph_query::GraphQuery;
+
+/// The details shown in the tooltip for a single node
+#[derive(Debug, Clone, Copy)]
+struct NodeDetails {
+    node: NodeId,
+    seq_len: usize,
+    degree: (usize, usize),
+    path_count: usize,
+}
+
+impl NodeDetails {
+    fn fetch(graph_query: &GraphQuery, node: NodeId) -> Option<Self> {
+        let graph = graph_query.graph();
+
+        if !graph.has_node(node) {
+            return None;
+        }
+
+        let handle = Handle::pack(node, false);
+
+        let deg_l = graph.degree(handle, Direction::Left);
+        let deg_r = graph.degree(handle, Direction::Right);
+
+        let path_count = graph
+            .steps_on_handle(handle)
+            .map(|steps| {
+                steps.map(|(path, _)| path).collect::<FxHashSet<_>>().len()
+            })
+            .unwrap_or(0);
+
+        Some(Self {
+            node,
+            seq_len: graph_query.node_len(handle),
+            degree: (deg_l, deg_r),
+            path_count,
+        })
+    }
+}
+
+/// Tooltip next to the cursor with the details of the hovered node.
+///
+/// The details are only looked up in the graph when the hovered node
+/// changes.
+#[derive(Debug, Default)]
+pub struct NodeTooltip {
+    details: Option<NodeDetails>,
+}
+
+impl NodeTooltip {
+    const ID: &'static str = "node_hover_tooltip";
+
+    /// Offset from the cursor, in pixels, so the tooltip doesn't
+    /// cover the node under it
+    const OFFSET: Point = Point { x: 16.0, y: 16.0 };
+
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        graph_query: &GraphQuery,
+        hover_node: Option<NodeId>,
+        mouse_pos: Point,
+    ) {
+        let node = match hover_node {
+            Some(node) => node,
+            None => {
+                self.details = None;
+                return;
+            }
+        };
+
+        if self.details.map(|d| d.node) != Some(node) {
+            self.details = NodeDetails::fetch(graph_query, node);
+        }
+
+        let details = match self.details {
+            Some(details) => details,
+            None => return,
+        };
+
+        egui::show_tooltip_at(
+            ctx,
+            egui::Id::new(Self::ID),
+            Some((mouse_pos + Self::OFFSET).into()),
+            |ui| {
+                egui::Grid::new("node_hover_tooltip_grid").show(ui, |ui| {
+                    ui.label("Node");
+                    ui.label(details.node.0.to_string());
+                    ui.end_row();
+
+                    ui.label("Length");
+                    ui.label(details.seq_len.to_string());
+                    ui.end_row();
+
+                    ui.label("Degree");
+                    ui.label(format!(
+                        "{} left, {} right",
+                        details.degree.0, details.degree.1
+                    ));
+                    ui.end_row();
+
+                    ui.label("Paths");
+                    ui.label(details.path_count.to_string());
+                    ui.end_row();
+                });
+            },
+        );
+    }
+}
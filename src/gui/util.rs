@@ -1,6 +1,8 @@
 use crossbeam::atomic::AtomicCell;
 use std::cell::RefCell;
 use std::rc::Rc;
+
+use rustc_hash::FxHashSet;
 // use parking_lot::RefCell
 
 #[derive(Default)]
@@ -178,6 +180,77 @@ pub fn grid_row_label(
     }
 }
 
+/// Set of selected rows in a list, supporting Ctrl-click to toggle a
+/// single row and Shift-click to select the range from the anchor
+/// row, i.e. the last row clicked without Shift, to the clicked row
+#[derive(Debug, Default, Clone)]
+pub struct RowSelection {
+    selected: FxHashSet<usize>,
+    anchor: Option<usize>,
+}
+
+impl RowSelection {
+    /// Update the selection with a click on `row`; returns true if
+    /// neither Ctrl nor Shift was held, in which case only `row` is
+    /// selected afterward
+    pub fn click(&mut self, row: usize, modifiers: egui::Modifiers) -> bool {
+        let ctrl = modifiers.ctrl || modifiers.command;
+
+        if modifiers.shift {
+            let anchor = self.anchor.unwrap_or(row);
+            let range = anchor.min(row)..=anchor.max(row);
+
+            if !ctrl {
+                self.selected.clear();
+            }
+            self.selected.extend(range);
+            self.anchor = Some(anchor);
+
+            false
+        } else if ctrl {
+            if !self.selected.remove(&row) {
+                self.selected.insert(row);
+            }
+            self.anchor = Some(row);
+
+            false
+        } else {
+            self.selected.clear();
+            self.selected.insert(row);
+            self.anchor = Some(row);
+
+            true
+        }
+    }
+
+    pub fn is_selected(&self, row: usize) -> bool {
+        self.selected.contains(&row)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.selected.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.selected.len()
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.selected.iter().copied()
+    }
+
+    pub fn clear(&mut self) {
+        self.selected.clear();
+        self.anchor = None;
+    }
+}
+
+/// Outline a selected row, as returned by `grid_row_label`
+pub fn highlight_row(ui: &mut egui::Ui, row: &egui::Response) {
+    let stroke = ui.visuals().selection.stroke;
+    ui.painter().rect_stroke(row.rect.expand(1.0), 0.0, stroke);
+}
+
 pub fn add_scroll_buttons(ui: &mut egui::Ui) -> Option<egui::Align> {
     ui.horizontal(|ui| {
         let mut r = None;
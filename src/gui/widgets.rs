@@ -11,7 +11,9 @@ use rustc_hash::FxHashMap;
 
 use crate::{
     app::AppMsg,
+    gfa::load::LoadProgress,
     overlays::OverlayKind,
+    view::{ScreenDims, View},
     window::{GuiId, GuiWindows},
 };
 use crate::{app::OverlayState, geometry::*};
@@ -77,11 +79,21 @@ impl MenuBar {
 
         let nodes = &mut open_windows.nodes;
         let paths = &mut open_windows.paths;
+        let path_comparison = &mut open_windows.path_comparison;
+        let shortest_path = &mut open_windows.shortest_path;
+        let bubbles = &mut open_windows.bubbles;
+        let layers = &mut open_windows.layers;
+        let sequence_search = &mut open_windows.sequence_search;
+        let bookmarks = &mut open_windows.bookmarks;
+        let markers = &mut open_windows.markers;
+        let performance = &mut open_windows.performance;
+        let minimap = &mut open_windows.minimap;
 
         // let path_view = &mut open_windows.path_position_list;
 
-        let _themes = &mut open_windows.themes;
+        let themes = &mut open_windows.themes;
         let overlays = &mut open_windows.overlays;
+        let bed_overlay_loader = &mut open_windows.bed_overlay_loader;
 
         let resp = egui::TopBottomPanel::top(Self::ID).show(ctx, |ui| {
             use egui::menu;
@@ -96,6 +108,51 @@ impl MenuBar {
                         *paths = !*paths;
                     }
 
+                    if ui
+                        .selectable_label(*path_comparison, "Compare paths")
+                        .clicked()
+                    {
+                        *path_comparison = !*path_comparison;
+                    }
+
+                    if ui
+                        .selectable_label(*shortest_path, "Shortest path")
+                        .clicked()
+                    {
+                        *shortest_path = !*shortest_path;
+                    }
+
+                    if ui.selectable_label(*bubbles, "Bubbles").clicked() {
+                        *bubbles = !*bubbles;
+                    }
+
+                    if ui.selectable_label(*layers, "Layers").clicked() {
+                        *layers = !*layers;
+                    }
+
+                    if ui
+                        .selectable_label(*sequence_search, "Sequence search")
+                        .clicked()
+                    {
+                        *sequence_search = !*sequence_search;
+                    }
+
+                    if ui.selectable_label(*bookmarks, "Bookmarks").clicked() {
+                        *bookmarks = !*bookmarks;
+                    }
+
+                    if ui.selectable_label(*markers, "Markers").clicked() {
+                        *markers = !*markers;
+                    }
+
+                    if ui
+                        .button("Reload GFA")
+                        .on_hover_text("Hotkey: <Ctrl-R>")
+                        .clicked()
+                    {
+                        app_msg_tx.send(AppMsg::ReloadGraph).unwrap();
+                    }
+
                     ui.separator();
 
                     let path_view_id = egui::Id::new("path_view_window");
@@ -136,12 +193,38 @@ impl MenuBar {
                     {
                         *overlays = !*overlays;
                     }
+
+                    if ui
+                        .selectable_label(
+                            *bed_overlay_loader,
+                            "Load BED annotation",
+                        )
+                        .clicked()
+                    {
+                        *bed_overlay_loader = !*bed_overlay_loader;
+                    }
                 });
 
                 menu::menu(ui, "View", |ui| {
                     if ui.button("Goto selection").clicked() {
                         app_msg_tx.send(AppMsg::goto_selection()).unwrap();
                     }
+
+                    if ui.selectable_label(*minimap, "Overview").clicked() {
+                        *minimap = !*minimap;
+                    }
+
+                    if ui.selectable_label(*themes, "Themes").clicked() {
+                        *themes = !*themes;
+                    }
+
+                    if ui
+                        .button("Export SVG")
+                        .on_hover_text("Save the current view as an SVG")
+                        .clicked()
+                    {
+                        app_msg_tx.send(AppMsg::ExportSvg).unwrap();
+                    }
                 });
 
                 menu::menu(ui, "Tools", |ui| {
@@ -149,6 +232,13 @@ impl MenuBar {
                         *settings = !*settings;
                     }
 
+                    if ui
+                        .selectable_label(*performance, "Performance")
+                        .clicked()
+                    {
+                        *performance = !*performance;
+                    }
+
                     ui.separator();
 
                     if ui.button("BED Label Wizard").clicked() {
@@ -196,6 +286,41 @@ impl MenuBar {
     }
 }
 
+/// Bar at the bottom of the window with the world coordinates of
+/// the cursor and the node under it
+pub struct StatusBar;
+
+impl StatusBar {
+    pub const ID: &'static str = "app_status_bar";
+
+    pub fn ui(
+        ctx: &egui::CtxRef,
+        view: View,
+        screen_dims: ScreenDims,
+        mouse_pos: Point,
+        hover_node: Option<NodeId>,
+    ) {
+        let world = view.screen_to_world(mouse_pos, screen_dims);
+
+        egui::TopBottomPanel::bottom(Self::ID).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label(format!("World: ({:.2}, {:.2})", world.x, world.y));
+
+                ui.separator();
+
+                let node = hover_node
+                    .map(|node| node.0.to_string())
+                    .unwrap_or_else(|| "-".to_string());
+                ui.label(format!("Node: {}", node));
+
+                ui.separator();
+
+                ui.label(format!("Scale: {:.3}", view.scale));
+            });
+        });
+    }
+}
+
 #[derive(Debug, Default, Clone, Copy)]
 pub struct NodeInfo {
     node_id: NodeId,
@@ -263,6 +388,16 @@ pub struct GraphStats {
     pub edge_count: usize,
     pub path_count: usize,
     pub total_len: usize,
+
+    /// The number of nodes drawn in the last reported frame
+    pub visible_nodes: Option<usize>,
+
+    /// Whether the last reported frame drew the nodes at the reduced
+    /// level of detail
+    pub lod_active: bool,
+
+    /// The latest progress of loading the graph, while it's loading
+    pub load_progress: Option<LoadProgress>,
 }
 
 impl Widget for GraphStats {
@@ -287,6 +422,46 @@ impl Widget for GraphStats {
                 ui.label(format!("Edges: {}", self.edge_count));
                 ui.label(format!("Paths: {}", self.path_count));
                 ui.label(format!("Total length: {}", self.total_len));
+
+                if let Some(visible) = self.visible_nodes {
+                    ui.label(format!(
+                        "Drawn nodes: {} / {} ({} culled)",
+                        visible,
+                        self.node_count,
+                        self.node_count.saturating_sub(visible)
+                    ));
+                }
+
+                let detail = if self.lod_active { "Reduced" } else { "Full" };
+                ui.label(format!("Detail: {}", detail));
+            })
+    }
+}
+
+impl GraphStats {
+    /// Show the loading progress in a modal in the center of the
+    /// screen, if the graph is being loaded
+    pub fn load_progress_ui(
+        &self,
+        ctx: &egui::CtxRef,
+    ) -> Option<egui::InnerResponse<Option<()>>> {
+        let progress = self.load_progress?;
+
+        egui::Window::new("Loading GFA")
+            .id(egui::Id::new("load_progress_modal"))
+            .anchor(egui::Align2::CENTER_CENTER, Point::ZERO)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.set_min_width(300.0);
+
+                ui.add(egui::ProgressBar::new(progress.fraction()).text(
+                    format!(
+                        "{} ({:.0}%)",
+                        progress.stage,
+                        progress.fraction() * 100.0
+                    ),
+                ));
             })
     }
 }
@@ -297,6 +472,9 @@ pub struct GraphStatsMsg {
     pub edge_count: Option<usize>,
     pub path_count: Option<usize>,
     pub total_len: Option<usize>,
+    pub visible_nodes: Option<usize>,
+    pub lod_active: Option<bool>,
+    pub load_progress: Option<LoadProgress>,
 }
 
 impl GraphStats {
@@ -306,6 +484,9 @@ impl GraphStats {
             edge_count: msg.edge_count.unwrap_or(self.edge_count),
             path_count: msg.path_count.unwrap_or(self.path_count),
             total_len: msg.total_len.unwrap_or(self.total_len),
+            visible_nodes: msg.visible_nodes.or(self.visible_nodes),
+            lod_active: msg.lod_active.unwrap_or(self.lod_active),
+            load_progress: msg.load_progress.or(self.load_progress),
         }
     }
 }
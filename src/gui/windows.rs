@@ -1,21 +1,41 @@
 pub mod annotations;
+pub mod bookmarks;
+pub mod bubbles;
 pub mod file;
 pub mod filters;
 pub mod graph_details;
 pub mod graph_picker;
+pub mod layers;
+pub mod markers;
+pub mod minimap;
 pub mod overlays;
+pub mod path_comparison;
 pub mod path_position;
 pub mod paths;
+pub mod performance;
+pub mod sequence_search;
 pub mod settings;
+pub mod shortest_path;
+pub mod themes;
 pub mod util;
 
 pub use annotations::*;
+pub use bookmarks::*;
+pub use bubbles::*;
 pub use file::*;
 pub use filters::*;
 pub use graph_details::*;
 pub use graph_picker::*;
+pub use layers::*;
+pub use markers::*;
+pub use minimap::*;
 pub use overlays::*;
+pub use path_comparison::*;
 pub use path_position::*;
 pub use paths::*;
+pub use performance::*;
+pub use sequence_search::*;
 pub use settings::*;
+pub use shortest_path::*;
+pub use themes::*;
 pub use util::*;
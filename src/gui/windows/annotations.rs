@@ -39,7 +39,7 @@ use crate::{
     app::AppMsg,
     geometry::Point,
     graph_query::GraphQuery,
-    gui::{util::grid_row_label, GuiMsg, Windows},
+    gui::{toasts::Toast, util::grid_row_label, GuiMsg, Windows},
     overlays::OverlayData,
     reactor::{Host, Outbox, Reactor},
 };
@@ -151,6 +151,8 @@ pub struct AnnotationFileList {
     load_host: Host<PathBuf, AnnotResult>,
 
     latest_result: Option<AnnotResult>,
+
+    toast_tx: Sender<Toast>,
 }
 
 impl AnnotationFileList {
@@ -163,15 +165,15 @@ impl AnnotationFileList {
     ) -> Result<Self> {
         let pwd = std::fs::canonicalize("./").unwrap();
 
-        let mut file_picker = FilePicker::new(
+        let extensions: [&str; 2] = ["gff3", "bed"];
+
+        let file_picker = FilePicker::new(
             egui::Id::with(egui::Id::new(Self::ID), "file_picker"),
             pwd,
+            Some(&extensions),
         )
         .unwrap();
 
-        let extensions: [&str; 2] = ["gff3", "bed"];
-        file_picker.set_visible_extensions(&extensions).unwrap();
-
         let load_host = reactor.create_host(
             move |outbox: &Outbox<AnnotResult>, file: PathBuf| {
                 let running_msg = |msg: &str| {
@@ -258,9 +260,34 @@ impl AnnotationFileList {
 
             load_host,
             latest_result: None,
+
+            toast_tx: reactor.toast_tx.clone(),
         })
     }
 
+    fn take_load_result(&mut self) {
+        if let Some(result) = self.load_host.take() {
+            match &result {
+                Ok((file_type, name)) => {
+                    let mut write = self.current_annotation.write();
+                    *write = Some((*file_type, name.to_owned()));
+                }
+                Err(AnnotMsg::IOError(err))
+                | Err(AnnotMsg::ParseError(err)) => {
+                    log::error!("Error loading annotation file: {}", err);
+                    let toast = Toast::error(format!(
+                        "Error loading annotation file: {}",
+                        err
+                    ));
+                    self.toast_tx.send(toast).unwrap();
+                }
+                Err(AnnotMsg::Running(_)) => (),
+            }
+
+            self.latest_result = Some(result);
+        }
+    }
+
     // pub fn current_annotation(&self) -> Option<(AnnotationFileType, &str)> {
     pub fn current_annotation(
         &self,
@@ -278,14 +305,7 @@ impl AnnotationFileList {
         annotations: &Annotations,
         ui: &mut egui::Ui,
     ) {
-        if let Some(result) = self.load_host.take() {
-            if let Ok((file_type, name)) = &result {
-                let mut write = self.current_annotation.write();
-                *write = Some((*file_type, name.to_owned()));
-            }
-
-            self.latest_result = Some(result);
-        }
+        self.take_load_result();
 
         let is_running =
             matches!(self.latest_result, Some(Err(AnnotMsg::Running(_))));
@@ -410,14 +430,7 @@ impl AnnotationFileList {
         gui_msg_tx: &crossbeam::channel::Sender<GuiMsg>,
         annotations: &Annotations,
     ) -> Option<egui::InnerResponse<Option<()>>> {
-        if let Some(result) = self.load_host.take() {
-            if let Ok((file_type, name)) = &result {
-                let mut write = self.current_annotation.write();
-                *write = Some((*file_type, name.to_owned()));
-            }
-
-            self.latest_result = Some(result);
-        }
+        self.take_load_result();
 
         let is_running =
             matches!(self.latest_result, Some(Err(AnnotMsg::Running(_))));
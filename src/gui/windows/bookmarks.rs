@@ -0,0 +1,171 @@
+use std::path::Path;
+
+use crossbeam::channel::Sender;
+
+use crate::app::mainview::MainViewMsg;
+use crate::view::View;
+
+use super::util::PerGraphStore;
+
+/// Named views for a single graph. The bookmarks are stored in the
+/// config directory, in a file keyed by the hash of the GFA path, so
+/// reopening the same graph restores its bookmarks.
+pub struct Bookmarks {
+    entries: PerGraphStore<(String, View)>,
+
+    new_name: String,
+    renaming: Option<(usize, String)>,
+}
+
+impl std::default::Default for Bookmarks {
+    fn default() -> Self {
+        Self {
+            entries: PerGraphStore::empty(Self::STORE),
+
+            new_name: String::new(),
+            renaming: None,
+        }
+    }
+}
+
+impl Bookmarks {
+    const ID: &'static str = "bookmarks_window";
+    const STORE: &'static str = "bookmarks";
+
+    /// Load the bookmarks for the graph at `gfa_path`, starting
+    /// with an empty set if none have been stored yet.
+    pub fn load_for_gfa<P: AsRef<Path>>(gfa_path: P) -> Self {
+        Self {
+            entries: PerGraphStore::load_for_gfa(
+                Self::STORE,
+                gfa_path.as_ref(),
+            ),
+            ..Self::default()
+        }
+    }
+
+    pub fn entries(&self) -> &[(String, View)] {
+        self.entries.entries()
+    }
+
+    pub fn add(&mut self, name: &str, view: View) {
+        self.entries.push((name.to_string(), view));
+    }
+
+    pub fn remove(&mut self, ix: usize) {
+        self.entries.remove(ix);
+    }
+
+    pub fn rename(&mut self, ix: usize, name: &str) {
+        self.entries
+            .update(ix, |(entry_name, _)| *entry_name = name.to_string());
+    }
+
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        open: &mut bool,
+        current_view: View,
+        main_view_tx: &Sender<MainViewMsg>,
+    ) -> Option<egui::InnerResponse<Option<()>>> {
+        let mut goto: Option<View> = None;
+        let mut remove: Option<usize> = None;
+        let mut rename: Option<(usize, String)> = None;
+        let mut cancel_rename = false;
+        let mut add: Option<String> = None;
+
+        let resp = egui::Window::new("Bookmarks")
+            .id(egui::Id::new(Self::ID))
+            .open(open)
+            .show(ctx, |ui| {
+                let new_name = &mut self.new_name;
+
+                ui.horizontal(|ui| {
+                    let text_box = ui.text_edit_singleline(new_name);
+
+                    let add_btn = ui.add_enabled(
+                        !new_name.is_empty(),
+                        egui::Button::new("Add current view"),
+                    );
+
+                    let enter = text_box.lost_focus()
+                        && ui.input().key_pressed(egui::Key::Enter);
+
+                    if !new_name.is_empty() && (add_btn.clicked() || enter) {
+                        add = Some(std::mem::take(new_name));
+                    }
+                });
+
+                ui.separator();
+
+                let entries = self.entries.entries();
+                let renaming = &mut self.renaming;
+
+                egui::Grid::new("bookmarks_list").striped(true).show(
+                    ui,
+                    |ui| {
+                        for (ix, (name, view)) in entries.iter().enumerate() {
+                            match renaming {
+                                Some((rename_ix, text)) if *rename_ix == ix => {
+                                    ui.text_edit_singleline(text);
+
+                                    if ui.button("Ok").clicked()
+                                        && !text.is_empty()
+                                    {
+                                        rename = Some((ix, text.clone()));
+                                    }
+
+                                    if ui.button("Cancel").clicked() {
+                                        cancel_rename = true;
+                                    }
+                                }
+                                _ => {
+                                    if ui.button(name).clicked() {
+                                        goto = Some(*view);
+                                    }
+
+                                    if ui.button("Rename").clicked() {
+                                        *renaming = Some((ix, name.clone()));
+                                    }
+
+                                    if ui.button("Delete").clicked() {
+                                        remove = Some(ix);
+                                    }
+                                }
+                            }
+
+                            ui.end_row();
+                        }
+                    },
+                );
+
+                if self.entries.is_empty() {
+                    ui.label("No bookmarks");
+                }
+            });
+
+        if let Some(name) = add {
+            self.add(&name, current_view);
+        }
+
+        if cancel_rename {
+            self.renaming = None;
+        }
+
+        if let Some((ix, name)) = rename {
+            self.renaming = None;
+            self.rename(ix, &name);
+        }
+
+        if let Some(ix) = remove {
+            self.renaming = None;
+            self.remove(ix);
+        }
+
+        if let Some(view) = goto {
+            main_view_tx.send(MainViewMsg::GotoView(view)).unwrap();
+        }
+
+        resp
+    }
+}
@@ -0,0 +1,516 @@
+use std::sync::Arc;
+
+use handlegraph::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    packedgraph::PackedGraph,
+};
+
+use crossbeam::{
+    atomic::AtomicCell,
+    channel::{self, Receiver, Sender},
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    app::{AppMsg, Select},
+    context::ContextMgr,
+    graph_query::GraphQuery,
+    gui::util::{grid_row_label, ColumnWidths},
+    reactor::Reactor,
+};
+
+/// A superbubble, i.e. a part of the graph that is entered through a
+/// single handle and left through another, such that every walk from
+/// the entrance reaches the exit, with no cycles or tips in between
+#[derive(Debug, Clone)]
+pub struct Bubble {
+    pub entrance: Handle,
+    pub exit: Handle,
+    /// The nodes in the bubble, including the entrance and exit
+    pub nodes: Vec<NodeId>,
+    /// The number of distinct walks from the entrance to the exit,
+    /// saturating at `u64::MAX`
+    pub walk_count: u64,
+}
+
+impl Bubble {
+    /// Find the superbubble entered through `entrance`, if there is
+    /// one with at most `max_size` nodes, using the algorithm from
+    /// Onodera et al., "Detecting superbubbles in assembly graphs"
+    /// (2013). Handles are followed in the direction they're
+    /// traversed in, so a bubble is found from both of its ends: as
+    /// `(s, t)`, and as `(t.flip(), s.flip())`.
+    pub fn find(
+        graph: &PackedGraph,
+        entrance: Handle,
+        max_size: usize,
+    ) -> Option<Self> {
+        let mut stack: Vec<Handle> = vec![entrance];
+
+        let mut visited: FxHashSet<Handle> = FxHashSet::default();
+        let mut seen: FxHashSet<Handle> = FxHashSet::default();
+        seen.insert(entrance);
+
+        // handles are only visited after all of their parents, so
+        // this is a topological order of the bubble
+        let mut order: Vec<Handle> = Vec::new();
+
+        while let Some(handle) = stack.pop() {
+            visited.insert(handle);
+            seen.remove(&handle);
+            order.push(handle);
+
+            if visited.len() > max_size {
+                return None;
+            }
+
+            let mut children =
+                graph.neighbors(handle, Direction::Right).peekable();
+
+            // a tip
+            children.peek()?;
+
+            for child in children {
+                // a cycle, either through the entrance or inside the
+                // bubble
+                if child == entrance || visited.contains(&child) {
+                    return None;
+                }
+
+                seen.insert(child);
+
+                let parents_visited = graph
+                    .neighbors(child, Direction::Left)
+                    .all(|parent| visited.contains(&parent));
+
+                if parents_visited {
+                    stack.push(child);
+                }
+            }
+
+            if stack.len() == 1 && seen.len() == 1 {
+                let exit = stack[0];
+
+                if !seen.contains(&exit) {
+                    continue;
+                }
+
+                // the exit can't lead straight back to the entrance
+                if graph
+                    .neighbors(exit, Direction::Right)
+                    .any(|h| h == entrance)
+                {
+                    return None;
+                }
+
+                return Some(Self::from_order(graph, entrance, exit, order));
+            }
+        }
+
+        None
+    }
+
+    fn from_order(
+        graph: &PackedGraph,
+        entrance: Handle,
+        exit: Handle,
+        order: Vec<Handle>,
+    ) -> Self {
+        let mut walks: FxHashMap<Handle, u64> = FxHashMap::default();
+        walks.insert(entrance, 1);
+
+        for &handle in order.iter() {
+            let count = walks.get(&handle).copied().unwrap_or(0);
+
+            for child in graph.neighbors(handle, Direction::Right) {
+                let child_count = walks.entry(child).or_insert(0);
+                *child_count = child_count.saturating_add(count);
+            }
+        }
+
+        let walk_count = walks.get(&exit).copied().unwrap_or(0);
+
+        let mut nodes = order
+            .iter()
+            .chain(std::iter::once(&exit))
+            .map(|h| h.id())
+            .collect::<Vec<_>>();
+        nodes.sort();
+        nodes.dedup();
+
+        Self {
+            entrance,
+            exit,
+            nodes,
+            walk_count,
+        }
+    }
+
+    /// The entrance and exit of the bubble, in the same orientation
+    /// no matter which end the bubble was found from
+    fn canonical_ends(&self) -> (Handle, Handle) {
+        let fwd = (self.entrance, self.exit);
+        let rev = (self.exit.flip(), self.entrance.flip());
+        fwd.min(rev)
+    }
+}
+
+/// A bubble search running on the thread pool, which sends the
+/// bubbles it finds in batches as it goes
+struct BubbleSearch {
+    found_rx: Receiver<Vec<Bubble>>,
+    /// The number of nodes searched so far
+    progress: Arc<AtomicCell<usize>>,
+    done: Arc<AtomicCell<bool>>,
+    cancel: Arc<AtomicCell<bool>>,
+}
+
+impl BubbleSearch {
+    /// The number of nodes searched between each batch of results
+    const BATCH_NODES: usize = 4096;
+
+    fn spawn(
+        thread_pool: &futures::executor::ThreadPool,
+        graph_query: Arc<GraphQuery>,
+        max_size: usize,
+    ) -> Self {
+        let (found_tx, found_rx) = channel::unbounded::<Vec<Bubble>>();
+
+        let progress = Arc::new(AtomicCell::new(0));
+        let done = Arc::new(AtomicCell::new(false));
+        let cancel = Arc::new(AtomicCell::new(false));
+
+        let search = Self {
+            found_rx,
+            progress: progress.clone(),
+            done: done.clone(),
+            cancel: cancel.clone(),
+        };
+
+        thread_pool.spawn_ok(async move {
+            Self::run(
+                graph_query.graph(),
+                max_size,
+                &found_tx,
+                &progress,
+                &cancel,
+            );
+            done.store(true);
+        });
+
+        search
+    }
+
+    fn run(
+        graph: &PackedGraph,
+        max_size: usize,
+        found_tx: &Sender<Vec<Bubble>>,
+        progress: &AtomicCell<usize>,
+        cancel: &AtomicCell<bool>,
+    ) {
+        let mut handles = graph.handles().collect::<Vec<_>>();
+        handles.sort();
+
+        let mut reported: FxHashSet<(Handle, Handle)> = FxHashSet::default();
+
+        for (ix, chunk) in handles.chunks(Self::BATCH_NODES).enumerate() {
+            if cancel.load() {
+                return;
+            }
+
+            let mut batch = Vec::new();
+
+            for &handle in chunk {
+                for &entrance in [handle, handle.flip()].iter() {
+                    if let Some(bubble) =
+                        Bubble::find(graph, entrance, max_size)
+                    {
+                        if reported.insert(bubble.canonical_ends()) {
+                            batch.push(bubble);
+                        }
+                    }
+                }
+            }
+
+            progress.store(ix * Self::BATCH_NODES + chunk.len());
+
+            // the receiver is gone if the window started a new search
+            if !batch.is_empty() && found_tx.send(batch).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Window for finding the superbubbles in the graph, and stepping
+/// through them. The search runs on the thread pool, and bubbles are
+/// listed as they're found.
+pub struct BubbleFinder {
+    graph_query: Arc<GraphQuery>,
+    thread_pool: futures::executor::ThreadPool,
+
+    max_size: usize,
+
+    search: Option<BubbleSearch>,
+
+    bubbles: Vec<Bubble>,
+    current: Option<usize>,
+
+    page: usize,
+
+    col_widths: ColumnWidths<4>,
+}
+
+impl BubbleFinder {
+    const ID: &'static str = "bubble_finder_window";
+
+    const PAGE_SIZE: usize = 25;
+
+    pub fn new(reactor: &Reactor) -> Self {
+        Self {
+            graph_query: reactor.graph_query.clone(),
+            thread_pool: reactor.thread_pool.clone(),
+
+            max_size: 1000,
+
+            search: None,
+
+            bubbles: Vec::new(),
+            current: None,
+
+            page: 0,
+
+            col_widths: Default::default(),
+        }
+    }
+
+    fn page_count(&self) -> usize {
+        let len = self.bubbles.len();
+        (len / Self::PAGE_SIZE) + (len % Self::PAGE_SIZE != 0) as usize
+    }
+
+    fn start_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.cancel.store(true);
+        }
+
+        self.bubbles.clear();
+        self.current = None;
+        self.page = 0;
+
+        self.search = Some(BubbleSearch::spawn(
+            &self.thread_pool,
+            self.graph_query.clone(),
+            self.max_size,
+        ));
+    }
+
+    /// Select the nodes of the bubble at `ix`, and frame the view on
+    /// them
+    fn show_bubble(&mut self, ix: usize, app_msg_tx: &Sender<AppMsg>) {
+        let bubble = match self.bubbles.get(ix) {
+            Some(bubble) => bubble,
+            None => return,
+        };
+
+        self.current = Some(ix);
+        self.page = ix / Self::PAGE_SIZE;
+
+        let nodes = bubble.nodes.iter().copied().collect();
+        app_msg_tx
+            .send(AppMsg::Selection(Select::Many { nodes, clear: true }))
+            .unwrap();
+
+        app_msg_tx
+            .send(AppMsg::goto_nodes(bubble.nodes.clone()))
+            .unwrap();
+    }
+
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        open: &mut bool,
+        app_msg_tx: &Sender<AppMsg>,
+        ctx_mgr: &ContextMgr,
+    ) -> Option<egui::InnerResponse<Option<()>>> {
+        let mut searching = false;
+
+        if let Some(search) = self.search.as_ref() {
+            // check before draining, so the last batch isn't missed
+            let done = search.done.load();
+
+            while let Ok(batch) = search.found_rx.try_recv() {
+                self.bubbles.extend(batch);
+            }
+
+            if done {
+                self.search = None;
+            } else {
+                searching = true;
+                ctx.request_repaint();
+            }
+        }
+
+        let node_count = self.graph_query.node_count();
+
+        let mut step: Option<usize> = None;
+
+        let resp = egui::Window::new("Bubbles")
+            .id(egui::Id::new(Self::ID))
+            .default_pos(egui::Pos2::new(600.0, 200.0))
+            .open(open)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::DragValue::new(&mut self.max_size)
+                        .clamp_range(2..=usize::MAX)
+                        .speed(10.0)
+                        .prefix("Max. bubble size: "),
+                )
+                .on_hover_text("Skip bubbles with more nodes than this");
+
+                ui.horizontal(|ui| {
+                    if searching {
+                        if ui.button("Cancel").clicked() {
+                            if let Some(search) = self.search.take() {
+                                search.cancel.store(true);
+                            }
+                        }
+                    } else if ui.button("Find bubbles").clicked() {
+                        self.start_search();
+                    }
+                });
+
+                if let Some(search) = self.search.as_ref() {
+                    ui.label(format!(
+                        "Searched {}/{} nodes, {} bubbles found",
+                        search.progress.load(),
+                        node_count,
+                        self.bubbles.len()
+                    ));
+                } else {
+                    ui.label(format!("{} bubbles", self.bubbles.len()));
+                }
+
+                ui.separator();
+
+                let len = self.bubbles.len();
+
+                ui.horizontal(|ui| {
+                    let prev = ui.add_enabled(
+                        len > 0,
+                        egui::Button::new("Previous bubble"),
+                    );
+                    let next = ui
+                        .add_enabled(len > 0, egui::Button::new("Next bubble"));
+
+                    if prev.clicked() {
+                        step = Some(match self.current {
+                            Some(ix) if ix > 0 => ix - 1,
+                            _ => len - 1,
+                        });
+                    }
+
+                    if next.clicked() {
+                        step = Some(match self.current {
+                            Some(ix) if ix + 1 < len => ix + 1,
+                            _ => 0,
+                        });
+                    }
+
+                    if let Some(ix) = self.current {
+                        ui.label(format!("{}/{}", ix + 1, len));
+                    }
+                });
+
+                let page_count = self.page_count();
+
+                ui.horizontal(|ui| {
+                    if ui.button("Prev").clicked() && self.page > 0 {
+                        self.page -= 1;
+                    }
+
+                    if ui.button("Next").clicked() && self.page + 1 < page_count
+                    {
+                        self.page += 1;
+                    }
+
+                    ui.label(format!(
+                        "Page {}/{}",
+                        (self.page + 1).min(page_count),
+                        page_count
+                    ));
+                });
+
+                let [w0, w1, w2, w3] = self.col_widths.get();
+
+                egui::Grid::new("bubble_finder_results").striped(true).show(
+                    ui,
+                    |ui| {
+                        let inner = grid_row_label(
+                            ui,
+                            egui::Id::new("bubble_finder_results_header"),
+                            &["Entrance", "Exit", "Nodes", "Walks"],
+                            false,
+                            Some(&[w0, w1, w2, w3]),
+                        );
+                        self.col_widths.set_hdr(&inner.inner);
+
+                        let page = self
+                            .bubbles
+                            .iter()
+                            .enumerate()
+                            .skip(self.page * Self::PAGE_SIZE)
+                            .take(Self::PAGE_SIZE);
+
+                        for (ix, bubble) in page {
+                            let handle_str = |h: Handle| {
+                                let strand =
+                                    if h.is_reverse() { "-" } else { "+" };
+                                format!("{}{}", h.id().0, strand)
+                            };
+
+                            let entrance = handle_str(bubble.entrance);
+                            let exit = handle_str(bubble.exit);
+                            let nodes = bubble.nodes.len().to_string();
+                            let walks = if bubble.walk_count == u64::MAX {
+                                ">2^64".to_string()
+                            } else {
+                                bubble.walk_count.to_string()
+                            };
+
+                            let fields: [&str; 4] =
+                                [&entrance, &exit, &nodes, &walks];
+
+                            let inner = grid_row_label(
+                                ui,
+                                egui::Id::new(ui.id().with(ix)),
+                                &fields,
+                                false,
+                                Some(&[w0, w1, w2, w3]),
+                            );
+
+                            self.col_widths.set(&inner.inner);
+
+                            let row = inner.response;
+
+                            if row.clicked() {
+                                step = Some(ix);
+                            }
+
+                            if row.hovered() {
+                                let node = bubble.entrance.id();
+                                ctx_mgr.produce_context(|| node);
+                            }
+                        }
+                    },
+                );
+            });
+
+        if let Some(ix) = step {
+            self.show_bubble(ix, app_msg_tx);
+        }
+
+        resp
+    }
+}
@@ -22,12 +22,34 @@ pub struct FilePicker {
     pub history: Vec<PathBuf>,
 
     pub extensions: HashSet<String>,
+    /// If set, only files with this extension (which must also be in
+    /// `extensions`) are shown
+    pub active_extension: Option<String>,
+
+    hidden_count: usize,
+
+    /// The most recent error when navigating to a directory, shown in
+    /// the dialog until the next directory is loaded
+    error: Option<String>,
+
+    /// If set, the picker chooses a file to write to, which doesn't
+    /// need to exist, named by this in the current directory
+    save_name: Option<String>,
 }
 
 impl FilePicker {
-    pub fn new<P: AsRef<Path>>(id: egui::Id, pwd: P) -> Result<Self> {
+    /// Create a file picker starting in `pwd`. If `extensions` is
+    /// provided, only directories and files with one of the given
+    /// extensions are listed.
+    pub fn new<P: AsRef<Path>>(
+        id: egui::Id,
+        pwd: P,
+        extensions: Option<&[&str]>,
+    ) -> Result<Self> {
         let pwd = pwd.as_ref().to_owned();
-        let current_dir = pwd.clone();
+        let current_dir = Self::load_last_dir()
+            .filter(|dir| dir.is_dir())
+            .unwrap_or_else(|| pwd.clone());
         let current_dir_text = current_dir.as_os_str().to_str().unwrap();
         let current_dir_text = current_dir_text.to_owned();
 
@@ -44,24 +66,117 @@ impl FilePicker {
             dir_list: Vec::new(),
             history: Vec::new(),
 
-            extensions: HashSet::default(),
+            extensions: extensions
+                .unwrap_or_default()
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            active_extension: None,
+
+            hidden_count: 0,
+
+            error: None,
+
+            save_name: None,
         };
 
-        result.load_current_dir()?;
+        if result.load_current_dir().is_err() {
+            result.current_dir.clone_from(&result.pwd);
+            result.load_current_dir()?;
+        }
 
         Ok(result)
     }
 
+    /// Path to the file storing the most recently used directory,
+    /// shared by all file pickers
+    fn last_dir_file() -> Option<PathBuf> {
+        let mut path = dirs::config_dir()?;
+        path.push("gfaestus");
+        path.push("last_dir");
+        Some(path)
+    }
+
+    fn load_last_dir() -> Option<PathBuf> {
+        let path = Self::last_dir_file()?;
+        let contents = std::fs::read_to_string(path).ok()?;
+        let dir = contents.trim();
+
+        if dir.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(dir))
+        }
+    }
+
+    fn store_last_dir(&self) -> Result<()> {
+        if let Some(path) = Self::last_dir_file() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let dir = self.current_dir.to_string_lossy();
+            std::fs::write(path, dir.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Make the picker choose a file to write to, rather than an
+    /// existing file, with `file_name` as the suggested name
+    pub fn save_as(mut self, file_name: &str) -> Self {
+        self.save_name = Some(file_name.to_string());
+        self
+    }
+
+    fn choose_path(&mut self, path: PathBuf) {
+        if let Err(err) = self.store_last_dir() {
+            log::warn!("Error storing last used directory: {:?}", err);
+        }
+        self.selected_path = Some(path);
+    }
+
     pub fn set_visible_extensions(
         &mut self,
         extensions: &[&str],
     ) -> Result<()> {
         let extensions = extensions.iter().map(|s| s.to_string()).collect();
         self.extensions = extensions;
+        self.active_extension = None;
         self.load_current_dir()?;
         Ok(())
     }
 
+    /// Directories are always visible, while files are only visible
+    /// if they match the active extension filter. Files without an
+    /// extension are only visible if there is no filter.
+    fn is_visible(&self, path: &Path) -> bool {
+        if path.is_dir() || self.extensions.is_empty() {
+            return true;
+        }
+
+        let file_ext = path.extension().and_then(|f_ext| f_ext.to_str());
+
+        match (file_ext, &self.active_extension) {
+            (Some(file_ext), Some(active)) => file_ext == active,
+            (Some(file_ext), None) => self.extensions.contains(file_ext),
+            (None, _) => false,
+        }
+    }
+
+    pub fn set_active_extension(
+        &mut self,
+        extension: Option<&str>,
+    ) -> Result<()> {
+        self.active_extension = extension
+            .filter(|ext| self.extensions.contains(*ext))
+            .map(String::from);
+        self.load_current_dir()?;
+        Ok(())
+    }
+
+    pub fn hidden_count(&self) -> usize {
+        self.hidden_count
+    }
+
     pub fn selected_path(&self) -> Option<&Path> {
         let path = self.selected_path.as_ref()?;
         Some(path.as_ref())
@@ -86,28 +201,19 @@ impl FilePicker {
         self.selected_path = None;
         self.dir_list.clear();
 
+        self.hidden_count = 0;
+        self.error = None;
+
         let dirs = std::fs::read_dir(&self.current_dir)?;
 
         for dir in dirs {
             let entry = dir?;
             let path = entry.path();
 
-            if self.extensions.is_empty()
-                || self.extensions.iter().any(|ext| {
-                    if path.is_dir() {
-                        return true;
-                    }
-
-                    if let Some(file_ext) =
-                        path.extension().and_then(|f_ext| f_ext.to_str())
-                    {
-                        file_ext == ext
-                    } else {
-                        false
-                    }
-                })
-            {
+            if self.is_visible(&path) {
                 self.dir_list.push(path);
+            } else {
+                self.hidden_count += 1;
             }
         }
 
@@ -157,6 +263,113 @@ impl FilePicker {
         Ok(())
     }
 
+    /// Split `current_dir` into (label, path) pairs, one for each
+    /// ancestor. Any prefix (e.g. a Windows drive) and the root
+    /// directory are merged into a single leading segment.
+    fn breadcrumbs(&self) -> Vec<(String, PathBuf)> {
+        use std::path::Component;
+
+        let mut crumbs: Vec<(String, PathBuf)> = Vec::new();
+        let mut path = PathBuf::new();
+
+        for component in self.current_dir.components() {
+            path.push(component);
+
+            match component {
+                Component::Prefix(_) | Component::RootDir => {
+                    let label = path.to_string_lossy().to_string();
+                    if let Some(last) = crumbs.last_mut() {
+                        *last = (label, path.clone());
+                    } else {
+                        crumbs.push((label, path.clone()));
+                    }
+                }
+                Component::Normal(name) => {
+                    let label = name.to_string_lossy().to_string();
+                    crumbs.push((label, path.clone()));
+                }
+                _ => {
+                    let label = component.as_os_str().to_string_lossy();
+                    crumbs.push((label.to_string(), path.clone()));
+                }
+            }
+        }
+
+        crumbs
+    }
+
+    fn breadcrumbs_ui(&mut self, ui: &mut egui::Ui) {
+        let crumbs = self.breadcrumbs();
+        let last_ix = crumbs.len().saturating_sub(1);
+
+        let mut goto_dir: Option<PathBuf> = None;
+
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 2.0;
+
+            let mut needs_separator = false;
+
+            for (ix, (label, path)) in crumbs.into_iter().enumerate() {
+                if needs_separator {
+                    ui.label(std::path::MAIN_SEPARATOR.to_string());
+                }
+                needs_separator = !label.ends_with(std::path::is_separator);
+
+                if ix == last_ix {
+                    ui.add(egui::Label::new(label).strong());
+                } else if ui.button(label).clicked() {
+                    goto_dir = Some(path);
+                }
+            }
+        });
+
+        if let Some(dir) = goto_dir {
+            if let Err(err) = self.goto_dir(&dir, true) {
+                self.error = Some(format!("Can't open {:?}: {}", dir, err));
+            }
+        }
+    }
+
+    fn extension_filter_ui(&mut self, ui: &mut egui::Ui) {
+        let mut extensions =
+            self.extensions.iter().cloned().collect::<Vec<_>>();
+        extensions.sort();
+
+        let all_label = extensions
+            .iter()
+            .map(|ext| format!("*.{}", ext))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut active = self.active_extension.clone();
+
+        egui::ComboBox::from_id_source(self.id.with("extension_filter"))
+            .selected_text(
+                active
+                    .as_ref()
+                    .map(|ext| format!("*.{}", ext))
+                    .unwrap_or_else(|| all_label.clone()),
+            )
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut active, None, all_label);
+
+                for ext in extensions {
+                    let label = format!("*.{}", ext);
+                    ui.selectable_value(&mut active, Some(ext), label);
+                }
+            });
+
+        if active != self.active_extension {
+            if let Err(err) = self.set_active_extension(active.as_deref()) {
+                log::warn!(
+                    "Error listing {:?} with the new filter: {:?}",
+                    self.current_dir,
+                    err
+                );
+            }
+        }
+    }
+
     pub fn ui_impl(
         &mut self,
         ui: &mut egui::Ui,
@@ -179,6 +392,12 @@ impl FilePicker {
             }
         });
 
+        self.breadcrumbs_ui(ui);
+
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
         ui.horizontal(|ui| {
             if ui.button("Prev").clicked() {
                 self.goto_prev().unwrap();
@@ -187,8 +406,17 @@ impl FilePicker {
             if ui.button("Up").clicked() {
                 self.go_up().unwrap();
             }
+
+            if !self.extensions.is_empty() {
+                ui.separator();
+                self.extension_filter_ui(ui);
+            }
         });
 
+        if self.hidden_count > 0 {
+            ui.label(format!("{} files hidden by filter", self.hidden_count));
+        }
+
         let mut goto_dir: Option<PathBuf> = None;
 
         let mut choose_path: Option<PathBuf> = None;
@@ -215,6 +443,14 @@ impl FilePicker {
                                 if row.clicked() {
                                     self.highlighted_dir =
                                         Some(dir_path.clone());
+
+                                    if let Some(save_name) =
+                                        self.save_name.as_mut()
+                                    {
+                                        if dir_path.is_file() {
+                                            *save_name = name.to_string();
+                                        }
+                                    }
                                 }
 
                                 if row.double_clicked() {
@@ -233,8 +469,20 @@ impl FilePicker {
             },
         );
 
+        if let Some(save_name) = self.save_name.as_mut() {
+            ui.horizontal(|ui| {
+                ui.label("File name");
+                ui.text_edit_singleline(save_name);
+            });
+        }
+
         if force_accept {
-            if let Some(dir_path) = self.highlighted_dir.as_ref() {
+            if let Some(save_name) = self.save_name.as_ref() {
+                let save_name = save_name.trim();
+                if !save_name.is_empty() {
+                    choose_path = Some(self.current_dir.join(save_name));
+                }
+            } else if let Some(dir_path) = self.highlighted_dir.as_ref() {
                 if dir_path.is_dir() {
                     goto_dir = Some(dir_path.to_owned());
                 } else if dir_path.is_file() {
@@ -249,7 +497,7 @@ impl FilePicker {
         }
 
         if let Some(path) = choose_path {
-            self.selected_path = Some(path);
+            self.choose_path(path);
             return Ok(ModalSuccess::Success);
         }
 
@@ -273,7 +521,9 @@ impl FilePicker {
                 let _ = self.ui_impl(ui, false);
 
                 if ui.button("Ok").clicked() {
-                    self.selected_path = self.highlighted_dir.clone();
+                    if let Some(path) = self.highlighted_dir.clone() {
+                        self.choose_path(path);
+                    }
                 }
             })
     }
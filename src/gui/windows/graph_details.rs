@@ -12,9 +12,13 @@ use handlegraph::{
 };
 
 use crossbeam::{atomic::AtomicCell, channel::Sender};
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use anyhow::Result;
+
 use bstr::ByteSlice;
 
 use crate::{
@@ -25,27 +29,80 @@ use crate::gui::util as gui_util;
 
 use crate::{graph_query::GraphQuery, gui::util::grid_row_label};
 
+use super::file::FilePicker;
+
 pub struct NodeDetails {
     node_id: Arc<AtomicCell<Option<NodeId>>>,
     fetched_node: Option<NodeId>,
 
     sequence: Vec<u8>,
+    base_counts: BaseCounts,
+    sequence_page: usize,
+
     degree: (usize, usize),
-    paths: Vec<(PathId, StepPtr, usize)>,
+    paths: Arc<Vec<(PathId, StepPtr, usize)>>,
+
+    /// The path occurrences of recently shown nodes, as finding them
+    /// means scanning the steps on the node
+    path_cache: FxHashMap<NodeId, Arc<Vec<(PathId, StepPtr, usize)>>>,
 
     unique_paths: Vec<PathId>,
 
     col_widths: ColumnWidths<3>,
 }
 
+/// Per-base counts of a sequence; anything that isn't A, C, G, or T,
+/// in either case, is counted as `other`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BaseCounts {
+    pub a: usize,
+    pub c: usize,
+    pub g: usize,
+    pub t: usize,
+    pub other: usize,
+}
+
+impl BaseCounts {
+    pub fn from_seq(seq: &[u8]) -> Self {
+        let mut counts = Self::default();
+
+        for &base in seq {
+            match base {
+                b'A' | b'a' => counts.a += 1,
+                b'C' | b'c' => counts.c += 1,
+                b'G' | b'g' => counts.g += 1,
+                b'T' | b't' => counts.t += 1,
+                _ => counts.other += 1,
+            }
+        }
+
+        counts
+    }
+
+    /// GC content as a percentage of all bases, including `other`
+    pub fn gc_percent(&self) -> f64 {
+        let total = self.a + self.c + self.g + self.t + self.other;
+
+        if total == 0 {
+            0.0
+        } else {
+            100.0 * (self.g + self.c) as f64 / total as f64
+        }
+    }
+}
+
 impl std::default::Default for NodeDetails {
     fn default() -> Self {
         Self {
             node_id: Arc::new(None.into()),
             fetched_node: None,
             sequence: Vec::new(),
+            base_counts: BaseCounts::default(),
+            sequence_page: 0,
+
             degree: (0, 0),
-            paths: Vec::new(),
+            paths: Arc::new(Vec::new()),
+            path_cache: FxHashMap::default(),
             unique_paths: Vec::new(),
 
             col_widths: Default::default(),
@@ -61,6 +118,14 @@ pub enum NodeDetailsMsg {
 impl NodeDetails {
     const ID: &'static str = "node_details_window";
 
+    /// The number of bases shown at a time in the sequence viewer,
+    /// so only a page of the sequence is formatted each frame
+    const SEQUENCE_PAGE_LEN: usize = 1000;
+
+    /// The cache of path occurrences is cleared when it holds more
+    /// nodes than this
+    const PATH_CACHE_LEN: usize = 256;
+
     pub fn node_id_cell(&self) -> &Arc<AtomicCell<Option<NodeId>>> {
         &self.node_id
     }
@@ -73,8 +138,10 @@ impl NodeDetails {
             NodeDetailsMsg::NoNode => {
                 self.node_id.store(None);
                 self.sequence.clear();
+                self.base_counts = BaseCounts::default();
+                self.sequence_page = 0;
                 self.degree = (0, 0);
-                self.paths.clear();
+                self.paths = Arc::new(Vec::new());
             }
         }
     }
@@ -93,44 +160,66 @@ impl NodeDetails {
 
         self.sequence.clear();
         self.degree = (0, 0);
-        self.paths.clear();
         self.unique_paths.clear();
 
         let graph = graph_query.graph();
 
         let handle = Handle::pack(node_id, false);
 
-        self.sequence.extend(graph.sequence(handle));
+        self.sequence = graph_query.sequence_vec(handle);
+        self.base_counts = BaseCounts::from_seq(&self.sequence);
+        self.sequence_page = 0;
 
         let degree_l = graph.neighbors(handle, Direction::Left).count();
         let degree_r = graph.neighbors(handle, Direction::Right).count();
 
         self.degree = (degree_l, degree_r);
 
-        let paths_fwd =
-            graph_query.handle_positions(Handle::pack(node_id, false));
-
-        if let Some(p) = paths_fwd {
-            self.paths.extend_from_slice(&p);
+        self.paths = self.path_occurrences(graph_query, node_id);
 
-            self.unique_paths
-                .extend(self.paths.iter().map(|(path, _, _)| path));
-            self.unique_paths.sort();
-            self.unique_paths.dedup();
-        }
+        self.unique_paths
+            .extend(self.paths.iter().map(|(path, _, _)| path));
+        self.unique_paths.sort();
+        self.unique_paths.dedup();
 
         self.fetched_node = Some(node_id);
 
         Some(())
     }
 
+    /// Every step on `node`, with the step's path and base offset
+    /// on the path, in the order the steps were found
+    fn path_occurrences(
+        &mut self,
+        graph_query: &GraphQuery,
+        node: NodeId,
+    ) -> Arc<Vec<(PathId, StepPtr, usize)>> {
+        if let Some(paths) = self.path_cache.get(&node) {
+            return paths.clone();
+        }
+
+        let paths = graph_query
+            .handle_positions(Handle::pack(node, false))
+            .unwrap_or_default();
+        let paths = Arc::new(paths);
+
+        if self.path_cache.len() >= Self::PATH_CACHE_LEN {
+            self.path_cache.clear();
+        }
+        self.path_cache.insert(node, paths.clone());
+
+        paths
+    }
+
     pub fn ui(
         &mut self,
         open_node_details: &mut bool,
         graph_query: &GraphQuery,
         ctx: &egui::CtxRef,
         path_details_id_cell: &AtomicCell<Option<PathId>>,
+        path_details_step_cell: &AtomicCell<Option<StepPtr>>,
         open_path_details: &mut bool,
+        app_msg_tx: &Sender<AppMsg>,
         ctx_mgr: &ContextMgr,
     ) -> Option<egui::InnerResponse<Option<()>>> {
         if self.need_fetch() {
@@ -161,11 +250,7 @@ impl NodeDetails {
 
                     ui.separator();
 
-                    if self.sequence.len() < 50 {
-                        ui.label(format!("Seq: {}", self.sequence.as_bstr()));
-                    } else {
-                        ui.label(format!("Seq len: {}", self.sequence.len()));
-                    }
+                    self.sequence_ui(ui, app_msg_tx);
 
                     ui.label(format!(
                         "Degree ({}, {})",
@@ -174,6 +259,15 @@ impl NodeDetails {
 
                     ui.separator();
 
+                    ui.label(format!(
+                        "{} steps on {} paths",
+                        self.paths.len(),
+                        self.unique_paths.len()
+                    ))
+                    .on_hover_text(
+                        "Click a step to show it in the path details",
+                    );
+
                     let scroll_align = gui_util::add_scroll_buttons(ui);
 
                     let num_rows = self.paths.len();
@@ -255,6 +349,8 @@ impl NodeDetails {
                                         if row.clicked() {
                                             path_details_id_cell
                                                 .store(Some(*path_id));
+                                            path_details_step_cell
+                                                .store(Some(*step_ptr));
                                             *open_path_details = true;
                                         }
 
@@ -283,6 +379,75 @@ impl NodeDetails {
                 }
             })
     }
+
+    fn sequence_ui(&mut self, ui: &mut egui::Ui, app_msg_tx: &Sender<AppMsg>) {
+        let counts = self.base_counts;
+
+        ui.label(format!("Length: {} bp", self.sequence.len()));
+        ui.label(format!("GC: {:.2}%", counts.gc_percent()));
+        ui.label(format!(
+            "A: {}  C: {}  G: {}  T: {}  Other: {}",
+            counts.a, counts.c, counts.g, counts.t, counts.other
+        ));
+
+        let page_len = Self::SEQUENCE_PAGE_LEN;
+        let page_count = (self.sequence.len() + page_len - 1) / page_len;
+
+        egui::CollapsingHeader::new("Sequence")
+            .id_source("node_details_sequence")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Copy sequence").clicked() {
+                        let contents = self.sequence.to_str_lossy();
+                        app_msg_tx
+                            .send(AppMsg::set_clipboard_contents(&contents))
+                            .unwrap();
+                    }
+
+                    if page_count > 1 {
+                        let prev = ui.add_enabled(
+                            self.sequence_page > 0,
+                            egui::Button::new("<"),
+                        );
+
+                        ui.label(format!(
+                            "{} / {}",
+                            self.sequence_page + 1,
+                            page_count
+                        ));
+
+                        let next = ui.add_enabled(
+                            self.sequence_page + 1 < page_count,
+                            egui::Button::new(">"),
+                        );
+
+                        if prev.clicked() {
+                            self.sequence_page -= 1;
+                        }
+
+                        if next.clicked() {
+                            self.sequence_page += 1;
+                        }
+                    }
+                });
+
+                let start =
+                    (self.sequence_page * page_len).min(self.sequence.len());
+                let end = (start + page_len).min(self.sequence.len());
+
+                let page = self.sequence[start..end].to_str_lossy();
+
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::Label::new(page.as_ref())
+                                .monospace()
+                                .wrap(true),
+                        );
+                    });
+            });
+    }
 }
 
 pub struct NodeList {
@@ -298,6 +463,11 @@ pub struct NodeList {
     range: AtomicCell<(usize, usize)>,
 
     col_widths: ColumnWidths<5>,
+
+    node_file_path: String,
+    node_file_picker: FilePicker,
+    node_file_picker_open: bool,
+    node_file_result: Option<std::result::Result<String, String>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -335,6 +505,15 @@ impl NodeList {
 
         let filtered_nodes: Vec<NodeId> = Vec::new();
 
+        let pwd = std::fs::canonicalize("./").unwrap();
+
+        let node_file_picker = FilePicker::new(
+            egui::Id::with(egui::Id::new(Self::ID), "node_file_picker"),
+            pwd,
+            None,
+        )
+        .unwrap();
+
         Self {
             all_nodes,
             filtered_nodes,
@@ -346,6 +525,167 @@ impl NodeList {
             range: (0, 0).into(),
 
             col_widths: Default::default(),
+
+            node_file_path: String::new(),
+            node_file_picker,
+            node_file_picker_open: false,
+            node_file_result: None,
+        }
+    }
+
+    /// Write the selected nodes to `path`, one ID per line in
+    /// ascending order. If `path` is a directory, the file is
+    /// created in it as `selection.txt`.
+    fn export_selection(&self, path: &str) -> Result<(PathBuf, usize)> {
+        // the filtered nodes are the sorted selection
+        let nodes = &self.filtered_nodes;
+
+        if nodes.is_empty() {
+            anyhow::bail!("No nodes are selected");
+        }
+
+        let mut path = PathBuf::from(path);
+
+        if path.is_dir() {
+            path.push("selection.txt");
+        }
+
+        let file = std::fs::File::create(&path)?;
+        let mut out = BufWriter::new(file);
+
+        for node in nodes {
+            writeln!(out, "{}", node.0)?;
+        }
+
+        out.flush()?;
+
+        Ok((path, nodes.len()))
+    }
+
+    /// Read a file with one node ID per line, as written by
+    /// `export_selection`, returning the IDs that exist in the graph,
+    /// the number of IDs that don't, and the number of lines that
+    /// aren't node IDs at all. Empty lines are skipped.
+    fn import_selection<P: AsRef<Path>>(
+        graph: &PackedGraph,
+        path: P,
+    ) -> Result<(FxHashSet<NodeId>, usize, usize)> {
+        let file = std::fs::File::open(path)?;
+        let reader = BufReader::new(file);
+
+        let mut nodes: FxHashSet<NodeId> = FxHashSet::default();
+        let mut missing = 0;
+        let mut invalid = 0;
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            match line.parse::<u64>().map(NodeId::from) {
+                Ok(node) if graph.has_node(node) => {
+                    nodes.insert(node);
+                }
+                Ok(_) => missing += 1,
+                Err(_) => invalid += 1,
+            }
+        }
+
+        Ok((nodes, missing, invalid))
+    }
+
+    fn node_file_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        app_msg_tx: &Sender<AppMsg>,
+        graph_query: &GraphQuery,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Node list file");
+            ui.text_edit_singleline(&mut self.node_file_path);
+
+            if ui.button("Choose file").clicked() {
+                self.node_file_picker.reset_selection();
+                self.node_file_picker_open = true;
+            }
+        });
+
+        let has_path = !self.node_file_path.is_empty();
+
+        let (export_btn, import_btn) = ui
+            .horizontal(|ui| {
+                let export_btn = ui
+                    .add_enabled(
+                        has_path && !self.filtered_nodes.is_empty(),
+                        egui::Button::new("Export selection"),
+                    )
+                    .on_hover_text("Write the selected node IDs, one per line");
+
+                let import_btn = ui
+                    .add_enabled(has_path, egui::Button::new("Load selection"))
+                    .on_hover_text("Select the node IDs listed in the file");
+
+                (export_btn, import_btn)
+            })
+            .inner;
+
+        if export_btn.clicked() {
+            let result = self
+                .export_selection(&self.node_file_path)
+                .map(|(path, count)| {
+                    format!("Wrote {} node IDs to {:?}", count, path)
+                })
+                .map_err(|err| {
+                    log::error!("Error exporting selection: {:?}", err);
+                    format!("Export failed: {}", err)
+                });
+            self.node_file_result = Some(result);
+        }
+
+        if import_btn.clicked() {
+            let result = Self::import_selection(
+                graph_query.graph(),
+                &self.node_file_path,
+            )
+            .map(|(nodes, missing, invalid)| {
+                let mut msg = format!(
+                    "Selected {} nodes, {} IDs were not in the graph",
+                    nodes.len(),
+                    missing
+                );
+
+                if invalid > 0 {
+                    msg.push_str(&format!(
+                        ", {} lines were not node IDs",
+                        invalid
+                    ));
+                }
+
+                use crate::app::Select;
+                app_msg_tx
+                    .send(AppMsg::Selection(Select::Many {
+                        nodes,
+                        clear: true,
+                    }))
+                    .unwrap();
+
+                msg
+            })
+            .map_err(|err| {
+                log::error!("Error loading selection: {:?}", err);
+                format!("Loading failed: {}", err)
+            });
+            self.node_file_result = Some(result);
+        }
+
+        match &self.node_file_result {
+            Some(Ok(msg)) | Some(Err(msg)) => {
+                ui.label(msg);
+            }
+            None => (),
         }
     }
 
@@ -362,13 +702,20 @@ impl NodeList {
         graph_query: &GraphQuery,
         ctx_mgr: &ContextMgr,
     ) -> Option<egui::InnerResponse<Option<()>>> {
+        if self.node_file_picker.selected_path().is_some() {
+            self.node_file_picker_open = false;
+        }
+
+        self.node_file_picker
+            .ui(ctx, &mut self.node_file_picker_open);
+
+        if let Some(path) = self.node_file_picker.selected_path() {
+            self.node_file_path = path.to_str().unwrap().to_string();
+            self.node_file_picker.reset_selection();
+        }
+
         let filter = self.apply_filter.load();
 
-        let nodes = if !filter || self.filtered_nodes.is_empty() {
-            &self.all_nodes
-        } else {
-            &self.filtered_nodes
-        };
         egui::Window::new("Nodes")
             .id(egui::Id::new(Self::ID))
             .default_pos(egui::Pos2::new(200.0, 200.0))
@@ -388,6 +735,22 @@ impl NodeList {
                             .unwrap();
                     }
 
+                    if ui
+                        .button("Invert")
+                        .on_hover_text("Select all unselected nodes")
+                        .clicked()
+                    {
+                        app_msg_tx.send(AppMsg::InvertSelection).unwrap();
+                    }
+
+                    if ui
+                        .button("Grow")
+                        .on_hover_text("Add the neighbors of selected nodes")
+                        .clicked()
+                    {
+                        app_msg_tx.send(AppMsg::GrowSelection).unwrap();
+                    }
+
                     if ui
                         .selectable_label(*open_node_details, "Node Details")
                         .clicked()
@@ -402,6 +765,14 @@ impl NodeList {
                     apply_filter.store(!filter);
                 }
 
+                self.node_file_ui(ui, app_msg_tx, graph_query);
+
+                let nodes = if !filter || self.filtered_nodes.is_empty() {
+                    &self.all_nodes
+                } else {
+                    &self.filtered_nodes
+                };
+
                 let scroll_align = gui_util::add_scroll_buttons(ui);
 
                 let node_id_cell = &self.node_details_id;
@@ -474,8 +845,10 @@ impl NodeList {
                                     let degree =
                                         format!("({}, {})", deg_l, deg_r);
 
-                                    let seq_len =
-                                        format!("{}", graph.node_len(handle));
+                                    let seq_len = format!(
+                                        "{}",
+                                        graph_query.node_len(handle)
+                                    );
 
                                     let mut path_count = 0;
                                     let mut uniq_count = 0;
@@ -0,0 +1,183 @@
+use std::sync::Arc;
+
+use crossbeam::{atomic::AtomicCell, channel::Sender};
+use parking_lot::RwLock;
+
+use crate::{
+    app::{
+        layers::{load_layer, LayerInfo, LayerMsg, LayerNode},
+        SharedState,
+    },
+    geometry::Point,
+    gui::toasts::Toast,
+    reactor::Reactor,
+};
+
+/// Load additional GFAs with their layouts as graph layers, and
+/// control how each layer is drawn
+pub struct LayerList {
+    thread_pool: futures::executor::ThreadPool,
+    toast_tx: Sender<Toast>,
+
+    layers: Arc<RwLock<Vec<LayerInfo>>>,
+    hover_layer_node: Arc<AtomicCell<Option<LayerNode>>>,
+
+    gfa_path: String,
+    layout_path: String,
+}
+
+impl LayerList {
+    const ID: &'static str = "layer_list_window";
+
+    pub fn new(reactor: &Reactor, shared_state: &SharedState) -> Self {
+        Self {
+            thread_pool: reactor.thread_pool.clone(),
+            toast_tx: reactor.toast_tx.clone(),
+
+            layers: shared_state.layers.clone(),
+            hover_layer_node: shared_state.hover_layer_node.clone(),
+
+            gfa_path: String::new(),
+            layout_path: String::new(),
+        }
+    }
+
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        open: &mut bool,
+        layer_tx: &Sender<LayerMsg>,
+    ) -> Option<egui::InnerResponse<Option<()>>> {
+        let layers = self.layers.read().clone();
+
+        egui::Window::new("Layers")
+            .id(egui::Id::new(Self::ID))
+            .default_pos(egui::Pos2::new(600.0, 200.0))
+            .open(open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("GFA");
+                    ui.text_edit_singleline(&mut self.gfa_path);
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Layout");
+                    ui.text_edit_singleline(&mut self.layout_path);
+                });
+
+                let can_load =
+                    !self.gfa_path.is_empty() && !self.layout_path.is_empty();
+
+                if ui
+                    .add_enabled(can_load, egui::Button::new("Load layer"))
+                    .clicked()
+                {
+                    load_layer(
+                        &self.thread_pool,
+                        layer_tx.clone(),
+                        self.toast_tx.clone(),
+                        self.gfa_path.clone(),
+                        self.layout_path.clone(),
+                    );
+                }
+
+                ui.separator();
+
+                if layers.is_empty() {
+                    ui.label("No layers loaded");
+                }
+
+                // topmost layer first
+                for info in layers.iter().rev() {
+                    Self::layer_ui(ui, info, layer_tx);
+                    ui.separator();
+                }
+
+                if let Some((layer, node)) = self.hover_layer_node.load() {
+                    let name = layers
+                        .iter()
+                        .find(|info| info.id == layer)
+                        .map(|info| info.name.as_str())
+                        .unwrap_or("?");
+
+                    ui.label(format!("Hovered: {} node {}", name, node.0));
+                }
+            })
+    }
+
+    fn layer_ui(
+        ui: &mut egui::Ui,
+        info: &LayerInfo,
+        layer_tx: &Sender<LayerMsg>,
+    ) {
+        let layer = info.id;
+
+        let send = |msg: LayerMsg| {
+            let _ = layer_tx.send(msg);
+        };
+
+        ui.horizontal(|ui| {
+            let mut visible = info.visible;
+            if ui.checkbox(&mut visible, "").changed() {
+                send(LayerMsg::SetVisible { layer, visible });
+            }
+
+            ui.label(format!("{} ({} nodes)", info.name, info.node_count));
+        });
+
+        ui.horizontal(|ui| {
+            let mut offset = info.offset;
+
+            let x = ui.add(egui::DragValue::new(&mut offset.x).prefix("x: "));
+            let y = ui.add(egui::DragValue::new(&mut offset.y).prefix("y: "));
+
+            if x.changed() || y.changed() {
+                send(LayerMsg::SetOffset { layer, offset });
+            }
+
+            if ui.button("Reset offset").clicked() {
+                send(LayerMsg::SetOffset {
+                    layer,
+                    offset: Point::ZERO,
+                });
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let t = info.tint;
+            let mut color = [t.r, t.g, t.b, t.a];
+
+            if ui.color_edit_button_rgba_unmultiplied(&mut color).changed() {
+                let [r, g, b, a] = color;
+                let tint = rgb::RGBA::new(r, g, b, a);
+                send(LayerMsg::SetTint { layer, tint });
+            }
+
+            if ui.button("Raise").clicked() {
+                send(LayerMsg::Raise { layer });
+            }
+
+            if ui.button("Lower").clicked() {
+                send(LayerMsg::Lower { layer });
+            }
+
+            if ui.button("Remove").clicked() {
+                send(LayerMsg::Remove { layer });
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{} selected", info.selected_count));
+
+            if ui
+                .add_enabled(
+                    info.selected_count > 0,
+                    egui::Button::new("Clear selection"),
+                )
+                .clicked()
+            {
+                send(LayerMsg::ClearSelection { layer });
+            }
+        });
+    }
+}
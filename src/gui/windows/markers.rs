@@ -0,0 +1,200 @@
+use std::path::Path;
+
+use crossbeam::channel::Sender;
+
+use crate::app::mainview::MainViewMsg;
+use crate::geometry::Point;
+use crate::view::{ScreenDims, View};
+
+use super::util::PerGraphStore;
+
+/// Labeled pins at points in the layout, for taking notes on a
+/// graph. Like the bookmarks, the markers are stored per graph in the
+/// config directory.
+pub struct Markers {
+    entries: PerGraphStore<(Point, String)>,
+
+    next_label: usize,
+    /// The marker whose label is being edited, and the edited label,
+    /// which is stored once the text box loses focus
+    editing: Option<(usize, String)>,
+}
+
+impl std::default::Default for Markers {
+    fn default() -> Self {
+        Self {
+            entries: PerGraphStore::empty(Self::STORE),
+
+            next_label: 1,
+            editing: None,
+        }
+    }
+}
+
+impl Markers {
+    const ID: &'static str = "markers_window";
+    const STORE: &'static str = "markers";
+
+    /// Load the markers for the graph at `gfa_path`, starting with
+    /// none if none have been stored yet.
+    pub fn load_for_gfa<P: AsRef<Path>>(gfa_path: P) -> Self {
+        let entries =
+            PerGraphStore::load_for_gfa(Self::STORE, gfa_path.as_ref());
+
+        Self {
+            next_label: entries.len() + 1,
+            entries,
+            editing: None,
+        }
+    }
+
+    pub fn entries(&self) -> &[(Point, String)] {
+        self.entries.entries()
+    }
+
+    /// Add a marker at `point`, in world coordinates, with a
+    /// numbered default label
+    pub fn add(&mut self, point: Point) {
+        let label = format!("Marker {}", self.next_label);
+        self.next_label += 1;
+
+        self.entries.push((point, label));
+    }
+
+    pub fn remove(&mut self, ix: usize) {
+        self.editing = None;
+        self.entries.remove(ix);
+    }
+
+    pub fn relabel(&mut self, ix: usize, label: &str) {
+        self.entries
+            .update(ix, |(_, entry_label)| *entry_label = label.to_string());
+    }
+
+    /// Draw the markers at their positions on the screen; clicking a
+    /// marker removes it
+    pub fn draw_pins(
+        &mut self,
+        ctx: &egui::CtxRef,
+        view: View,
+        dims: ScreenDims,
+    ) {
+        let mut remove: Option<usize> = None;
+
+        let stroke = egui::Stroke::new(2.0, ctx.style().visuals.text_color());
+
+        for (ix, (point, label)) in self.entries.entries().iter().enumerate() {
+            let screen = view.world_to_screen(*point, dims);
+
+            let on_screen = screen.x >= 0.0
+                && screen.x <= dims.width
+                && screen.y >= 0.0
+                && screen.y <= dims.height;
+
+            if !on_screen {
+                continue;
+            }
+
+            egui::Area::new(egui::Id::new(Self::ID).with(ix))
+                .order(egui::Order::Background)
+                .fixed_pos(screen + Point::new(6.0, -20.0))
+                .show(ctx, |ui| {
+                    ui.painter().circle_stroke(screen.into(), 4.0, stroke);
+
+                    let pin = ui
+                        .add(egui::Button::new(label).frame(false))
+                        .on_hover_text("Click to remove");
+
+                    if pin.clicked() {
+                        remove = Some(ix);
+                    }
+                });
+        }
+
+        if let Some(ix) = remove {
+            self.remove(ix);
+        }
+    }
+
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        open: &mut bool,
+        current_view: View,
+        main_view_tx: &Sender<MainViewMsg>,
+    ) -> Option<egui::InnerResponse<Option<()>>> {
+        let mut goto: Option<Point> = None;
+        let mut remove: Option<usize> = None;
+        let mut relabel: Option<(usize, String)> = None;
+
+        let resp = egui::Window::new("Markers")
+            .id(egui::Id::new(Self::ID))
+            .open(open)
+            .show(ctx, |ui| {
+                ui.label("Add markers from the context menu");
+
+                ui.separator();
+
+                let entries = self.entries.entries();
+                let editing = &mut self.editing;
+
+                egui::Grid::new("markers_list")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (ix, (point, label)) in entries.iter().enumerate() {
+                            let mut text = match editing {
+                                Some((edit_ix, text)) if *edit_ix == ix => {
+                                    text.clone()
+                                }
+                                _ => label.clone(),
+                            };
+
+                            let text_box = ui.text_edit_singleline(&mut text);
+
+                            // single line text edits also lose focus
+                            // when Enter is pressed
+                            if text_box.lost_focus() {
+                                *editing = None;
+                                if text != *label {
+                                    relabel = Some((ix, text));
+                                }
+                            } else if text_box.changed() {
+                                *editing = Some((ix, text));
+                            }
+
+                            if ui.button("Go to").clicked() {
+                                goto = Some(*point);
+                            }
+
+                            if ui.button("Delete").clicked() {
+                                remove = Some(ix);
+                            }
+
+                            ui.end_row();
+                        }
+                    });
+
+                if self.entries.is_empty() {
+                    ui.label("No markers");
+                }
+            });
+
+        if let Some((ix, label)) = relabel {
+            self.relabel(ix, &label);
+        }
+
+        if let Some(ix) = remove {
+            self.remove(ix);
+        }
+
+        if let Some(center) = goto {
+            let view = View {
+                center,
+                ..current_view
+            };
+            main_view_tx.send(MainViewMsg::GotoView(view)).unwrap();
+        }
+
+        resp
+    }
+}
@@ -0,0 +1,215 @@
+use crossbeam::channel::Sender;
+
+use crate::app::mainview::MainViewMsg;
+use crate::geometry::{Point, Rect};
+use crate::universe::Node;
+use crate::view::{ScreenDims, View};
+
+/// Overview of the entire layout, drawn as a low resolution node
+/// density grid, with the area covered by the current view outlined.
+/// Clicking or dragging in the overview recenters the main view.
+pub struct Minimap {
+    bounds: Option<Rect>,
+    /// The layout generation the density grid was built for
+    layout_generation: Option<u64>,
+
+    cells: Vec<u32>,
+    max_cell: u32,
+}
+
+impl std::default::Default for Minimap {
+    fn default() -> Self {
+        Self {
+            bounds: None,
+            layout_generation: None,
+
+            cells: vec![0; Self::GRID_SIZE * Self::GRID_SIZE],
+            max_cell: 0,
+        }
+    }
+}
+
+impl Minimap {
+    const ID: &'static str = "minimap_window";
+
+    /// The number of density cells along each axis
+    const GRID_SIZE: usize = 96;
+
+    const WIDTH: f32 = 240.0;
+
+    fn update_nodes(&mut self, layout_generation: u64, nodes: &[Node]) {
+        if self.layout_generation == Some(layout_generation) {
+            return;
+        }
+
+        self.layout_generation = Some(layout_generation);
+
+        let bounds = nodes.iter().fold(None, |acc: Option<Rect>, node| {
+            let rect = Rect::new(node.p0, node.p1);
+            Some(acc.map_or(rect, |acc| acc.union(rect)))
+        });
+
+        self.cells.iter_mut().for_each(|c| *c = 0);
+        self.max_cell = 0;
+        self.bounds = bounds;
+
+        let bounds = if let Some(bounds) = bounds {
+            bounds
+        } else {
+            return;
+        };
+
+        let grid = Self::GRID_SIZE;
+
+        for node in nodes {
+            let (x, y) = Self::cell_at(bounds, node.center());
+            let cell = &mut self.cells[y * grid + x];
+            *cell += 1;
+            self.max_cell = self.max_cell.max(*cell);
+        }
+    }
+
+    fn cell_at(bounds: Rect, p: Point) -> (usize, usize) {
+        let grid = Self::GRID_SIZE;
+
+        let norm = |v: f32, min: f32, len: f32| -> usize {
+            if len <= 0.0 {
+                return 0;
+            }
+            let t = ((v - min) / len).clamp(0.0, 1.0);
+            ((t * grid as f32) as usize).min(grid - 1)
+        };
+
+        let x = norm(p.x, bounds.min().x, bounds.width());
+        let y = norm(p.y, bounds.min().y, bounds.height());
+
+        (x, y)
+    }
+
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        open: &mut bool,
+        nodes: &[Node],
+        layout_generation: u64,
+        view: View,
+        screen_dims: ScreenDims,
+        main_view_tx: &Sender<MainViewMsg>,
+    ) -> Option<egui::InnerResponse<Option<()>>> {
+        self.update_nodes(layout_generation, nodes);
+
+        egui::Window::new("Overview")
+            .id(egui::Id::new(Self::ID))
+            .open(open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let bounds = if let Some(bounds) = self.bounds {
+                    bounds
+                } else {
+                    ui.label("Empty layout");
+                    return;
+                };
+
+                let aspect = if bounds.width() > 0.0 {
+                    bounds.height() / bounds.width()
+                } else {
+                    1.0
+                };
+
+                let size = egui::vec2(
+                    Self::WIDTH,
+                    (Self::WIDTH * aspect).clamp(60.0, 320.0),
+                );
+
+                let (response, painter) =
+                    ui.allocate_painter(size, egui::Sense::click_and_drag());
+
+                let rect = response.rect;
+                let painter = painter.sub_region(rect);
+
+                let to_screen = |p: Point| -> egui::Pos2 {
+                    let x = (p.x - bounds.min().x) / bounds.width().max(1.0);
+                    let y = (p.y - bounds.min().y) / bounds.height().max(1.0);
+                    rect.min + egui::vec2(x * rect.width(), y * rect.height())
+                };
+
+                let to_world = |p: egui::Pos2| -> Point {
+                    let x = (p.x - rect.min.x) / rect.width();
+                    let y = (p.y - rect.min.y) / rect.height();
+                    Point::new(
+                        bounds.min().x + x * bounds.width(),
+                        bounds.min().y + y * bounds.height(),
+                    )
+                };
+
+                let visuals = ui.visuals();
+
+                painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+
+                let grid = Self::GRID_SIZE;
+                let cell_size = egui::vec2(
+                    rect.width() / grid as f32,
+                    rect.height() / grid as f32,
+                );
+
+                let node_color = visuals.text_color();
+                let max_cell = self.max_cell.max(1) as f32;
+
+                for (ix, &count) in self.cells.iter().enumerate() {
+                    if count == 0 {
+                        continue;
+                    }
+
+                    let x = (ix % grid) as f32;
+                    let y = (ix / grid) as f32;
+
+                    let min =
+                        rect.min + egui::vec2(x * cell_size.x, y * cell_size.y);
+                    let cell = egui::Rect::from_min_size(min, cell_size);
+
+                    // sqrt so sparse areas are still visible
+                    let density = (count as f32 / max_cell).sqrt();
+                    let alpha = (64.0 + 191.0 * density) as u8;
+
+                    let color = egui::Color32::from_rgba_unmultiplied(
+                        node_color.r(),
+                        node_color.g(),
+                        node_color.b(),
+                        alpha,
+                    );
+
+                    painter.rect_filled(cell, 0.0, color);
+                }
+
+                let half_w = 0.5 * screen_dims.width * view.scale;
+                let half_h = 0.5 * screen_dims.height * view.scale;
+
+                let view_min =
+                    Point::new(view.center.x - half_w, view.center.y - half_h);
+                let view_max =
+                    Point::new(view.center.x + half_w, view.center.y + half_h);
+
+                let view_rect = egui::Rect::from_min_max(
+                    to_screen(view_min),
+                    to_screen(view_max),
+                );
+
+                painter.rect_stroke(
+                    view_rect,
+                    0.0,
+                    egui::Stroke::new(1.5, visuals.selection.stroke.color),
+                );
+
+                if response.clicked() || response.dragged() {
+                    if let Some(pos) = response.interact_pointer_pos() {
+                        let center = to_world(pos);
+                        let view = View {
+                            center,
+                            scale: view.scale,
+                        };
+                        main_view_tx.send(MainViewMsg::GotoView(view)).unwrap();
+                    }
+                }
+            })
+    }
+}
@@ -1,7 +1,8 @@
 use std::io::Read;
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use crossbeam::atomic::AtomicCell;
+use crossbeam::{atomic::AtomicCell, channel::Sender};
 
 use rustc_hash::FxHashMap;
 
@@ -10,46 +11,219 @@ use anyhow::Result;
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
 
-use crate::geometry::Rect;
+use handlegraph::pathhandlegraph::{GraphPathNames, IntoPathIds, PathId};
+
+use crate::annotations::{BedRecord, BedRecords};
+use crate::asynchronous::AsyncResult;
+use crate::graph_query::GraphQuery;
+use crate::gui::toasts::Toast;
 use crate::reactor::{Host, Outbox, Reactor};
-use crate::script::{ScriptConfig, ScriptTarget};
+use crate::script::{ScriptConfig, ScriptTarget, StopCheck};
 use crate::{
     geometry::Point,
-    vulkan::texture::{GradientName, Gradients},
+    vulkan::texture::{sample_gradient, GradientName, Gradients},
 };
 
 use crate::app::{OverlayCreatorMsg, OverlayState};
-use crate::overlays::OverlayKind;
+use crate::overlays::{OverlayData, OverlayKind};
 
 use super::file::FilePicker;
 
 pub struct OverlayList {
     overlay_state: OverlayState,
 
+    graph_query: Arc<GraphQuery>,
+    rayon_pool: Arc<rayon::ThreadPool>,
+    thread_pool: futures::executor::ThreadPool,
+    new_overlay_tx: Sender<OverlayCreatorMsg>,
+
+    path_depth_result: Option<AsyncResult<OverlayData>>,
+
+    /// The path open in the path details window
+    path_id_cell: Arc<AtomicCell<Option<PathId>>>,
+    strand_overlay_enabled: bool,
+    strand_overlay_path: Option<PathId>,
+
     pub(crate) overlay_names: FxHashMap<usize, (OverlayKind, String)>,
 
+    /// The data each overlay was created from, kept so it can be
+    /// exported without rerunning the script
+    overlay_data: FxHashMap<usize, Arc<OverlayData>>,
+
     gradient_picker: GradientPicker,
 
     gradient_picker_open: AtomicCell<bool>,
+
+    export_path: String,
+    export_picker: FilePicker,
+    export_picker_open: bool,
+    export_result: Option<std::result::Result<String, String>>,
 }
 
 impl OverlayList {
     pub const ID: &'static str = "overlay_list_window";
 
-    pub fn new(overlay_state: OverlayState) -> Self {
+    pub fn new(
+        reactor: &Reactor,
+        overlay_state: OverlayState,
+        path_id_cell: Arc<AtomicCell<Option<PathId>>>,
+    ) -> Self {
         let gradient_picker = GradientPicker::new(overlay_state.clone());
 
+        let pwd = std::fs::canonicalize("./").unwrap();
+
+        let export_picker = FilePicker::new(
+            egui::Id::with(egui::Id::new(Self::ID), "export_picker"),
+            pwd,
+            Some(&["tsv"]),
+        )
+        .unwrap();
+
         Self {
             overlay_state,
+
+            graph_query: reactor.graph_query.clone(),
+            rayon_pool: reactor.rayon_pool.clone(),
+            thread_pool: reactor.thread_pool.clone(),
+            new_overlay_tx: reactor.overlay_create_tx.clone(),
+
+            path_depth_result: None,
+
+            path_id_cell,
+            strand_overlay_enabled: false,
+            strand_overlay_path: None,
+
             overlay_names: Default::default(),
+            overlay_data: Default::default(),
 
             gradient_picker,
 
             // gradient_picker_open: AtomicCell::new(true),
             gradient_picker_open: AtomicCell::new(false),
+
+            export_path: String::new(),
+            export_picker,
+            export_picker_open: false,
+            export_result: None,
+        }
+    }
+
+    pub fn set_overlay_data(&mut self, overlay_id: usize, data: OverlayData) {
+        self.overlay_data.insert(overlay_id, Arc::new(data));
+    }
+
+    pub fn overlay_data(&self, overlay_id: usize) -> Option<Arc<OverlayData>> {
+        self.overlay_data.get(&overlay_id).cloned()
+    }
+
+    /// Replace the palette of the stored data of a categorical
+    /// overlay, keeping its categories
+    pub fn set_overlay_palette(
+        &mut self,
+        overlay_id: usize,
+        palette: Vec<rgb::RGB<f32>>,
+    ) {
+        let data = match self.overlay_data.get_mut(&overlay_id) {
+            Some(data) => data,
+            None => return,
+        };
+
+        if let OverlayData::Category { categories, .. } = data.as_ref() {
+            let categories = categories.clone();
+            *data = Arc::new(OverlayData::Category {
+                categories,
+                palette,
+            });
         }
     }
 
+    /// Write the data of the overlay with the given ID to `path`. If
+    /// `path` is a directory, the file is named after the overlay.
+    fn export_overlay(&self, overlay_id: usize, path: &str) -> Result<PathBuf> {
+        let data = self.overlay_data.get(&overlay_id).ok_or_else(|| {
+            anyhow::anyhow!("No data stored for overlay {}", overlay_id)
+        })?;
+
+        let mut path = PathBuf::from(path);
+
+        if path.is_dir() {
+            let name = self
+                .overlay_names
+                .get(&overlay_id)
+                .map(|(_, name)| name.as_str())
+                .unwrap_or("overlay");
+            path.push(format!("{}.tsv", name));
+        }
+
+        let file = std::fs::File::create(&path)?;
+        data.write_tsv(std::io::BufWriter::new(file))?;
+
+        Ok(path)
+    }
+
+    /// Compute the node degree overlay on the thread pool; it's
+    /// added to the overlay list like any other new overlay.
+    fn create_degree_overlay(&self) {
+        let graph = self.graph_query.clone();
+        let tx = self.new_overlay_tx.clone();
+
+        self.rayon_pool.spawn(move || {
+            let data = crate::overlays::compute_degree_overlay(graph.graph());
+            let msg = OverlayCreatorMsg::NewOverlay {
+                name: "Node degree".to_string(),
+                data,
+            };
+            tx.send(msg).unwrap();
+        });
+    }
+
+    /// Start computing the path depth overlay, which is added to the
+    /// overlay list by `ui` once it's ready, as it visits every step
+    /// of every path.
+    fn create_path_depth_overlay(&mut self) {
+        let graph = self.graph_query.clone();
+
+        let future = async move {
+            crate::overlays::compute_path_depth_overlay(graph.graph())
+        };
+
+        self.path_depth_result =
+            Some(AsyncResult::new(&self.thread_pool, future));
+    }
+
+    /// Regenerate the path strand overlay if the open path has
+    /// changed since it was last created
+    fn update_strand_overlay(&mut self) {
+        let path = self.path_id_cell.load();
+
+        if path == self.strand_overlay_path {
+            return;
+        }
+
+        self.strand_overlay_path = path;
+
+        let path = if let Some(path) = path {
+            path
+        } else {
+            return;
+        };
+
+        let graph = self.graph_query.clone();
+        let tx = self.new_overlay_tx.clone();
+
+        self.rayon_pool.spawn(move || {
+            let data = crate::overlays::compute_path_strand_overlay(
+                graph.graph(),
+                path,
+            );
+            let msg = OverlayCreatorMsg::UpdateOverlay {
+                name: "Path strand".to_string(),
+                data,
+            };
+            tx.send(msg).unwrap();
+        });
+    }
+
     pub fn populate_names<'a>(
         &mut self,
         names: impl Iterator<Item = (usize, OverlayKind, &'a str)>,
@@ -59,12 +233,62 @@ impl OverlayList {
             .extend(names.map(|(x, k, n)| (x, (k, n.to_string()))));
     }
 
+    /// Make the overlay after (or before) the current one in the
+    /// list active, wrapping around at the ends
+    pub fn cycle_overlay(&self, forward: bool) {
+        let mut ids = self.overlay_names.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+
+        if ids.is_empty() {
+            return;
+        }
+
+        let current = self
+            .overlay_state
+            .current_overlay()
+            .and_then(|id| ids.iter().position(|&x| x == id));
+
+        let ix = match current {
+            Some(ix) if forward => (ix + 1) % ids.len(),
+            Some(ix) => (ix + ids.len() - 1) % ids.len(),
+            None => 0,
+        };
+
+        self.overlay_state.set_current_overlay(Some(ids[ix]));
+    }
+
     pub fn ui(
-        &self,
+        &mut self,
         ctx: &egui::CtxRef,
         open: &mut bool,
         open_creator: &mut bool,
     ) -> Option<egui::InnerResponse<Option<()>>> {
+        if self.export_picker.selected_path().is_some() {
+            self.export_picker_open = false;
+        }
+
+        self.export_picker.ui(ctx, &mut self.export_picker_open);
+
+        if let Some(result) = self.path_depth_result.as_mut() {
+            if let Some(data) = result.take_result_if_ready() {
+                let msg = OverlayCreatorMsg::NewOverlay {
+                    name: "Path depth".to_string(),
+                    data,
+                };
+                self.new_overlay_tx.send(msg).unwrap();
+                self.path_depth_result = None;
+            }
+        }
+
+        if self.strand_overlay_enabled {
+            self.update_strand_overlay();
+        }
+
+        if let Some(path) = self.export_picker.selected_path() {
+            self.export_path = path.to_str().unwrap().to_string();
+            self.export_picker.reset_selection();
+        }
+
         egui::Window::new("Overlay List")
             .id(egui::Id::new(Self::ID))
             .open(open)
@@ -86,6 +310,58 @@ impl OverlayList {
                     {
                         self.gradient_picker_open.store(!open_gradient_picker);
                     }
+
+                    let gradient = self.overlay_state.gradient();
+                    self.gradient_picker
+                        .swatch_ui(
+                            ui,
+                            gradient,
+                            self.overlay_state.gradient_reversed(),
+                            Point { x: 60.0, y: 12.0 },
+                        )
+                        .on_hover_text(gradient.to_string());
+
+                    if ui
+                        .button("Node degree")
+                        .on_hover_text(
+                            "Create an overlay coloring nodes by their degree",
+                        )
+                        .clicked()
+                    {
+                        self.create_degree_overlay();
+                    }
+
+                    let path_depth_running = self.path_depth_result.is_some();
+
+                    if ui
+                        .add_enabled(
+                            !path_depth_running,
+                            egui::Button::new("Path depth"),
+                        )
+                        .on_hover_text(
+                            "Create an overlay coloring nodes by the number \
+                             of path steps on them",
+                        )
+                        .clicked()
+                    {
+                        self.create_path_depth_overlay();
+                    }
+
+                    if ui
+                        .selectable_label(
+                            self.strand_overlay_enabled,
+                            "Path strand",
+                        )
+                        .on_hover_text(
+                            "Color nodes by the orientation the path open in \
+                             the path details window visits them in",
+                        )
+                        .clicked()
+                    {
+                        self.strand_overlay_enabled =
+                            !self.strand_overlay_enabled;
+                        self.strand_overlay_path = None;
+                    }
                 });
 
                 egui::Grid::new("overlay_list_window_grid").show(
@@ -118,6 +394,48 @@ impl OverlayList {
                         }
                     },
                 );
+
+                ui.separator();
+
+                let current_overlay = self.overlay_state.current_overlay();
+                let can_export = current_overlay
+                    .map(|id| self.overlay_data.contains_key(&id))
+                    .unwrap_or(false);
+
+                ui.horizontal(|ui| {
+                    ui.label("Export path");
+                    ui.text_edit_singleline(&mut self.export_path);
+
+                    if ui.button("Choose file").clicked() {
+                        self.export_picker.reset_selection();
+                        self.export_picker_open = true;
+                    }
+                });
+
+                let export_btn = ui.add_enabled(
+                    can_export && !self.export_path.is_empty(),
+                    egui::Button::new("Export overlay as TSV"),
+                );
+
+                if export_btn.clicked() {
+                    if let Some(id) = current_overlay {
+                        let result = self
+                            .export_overlay(id, &self.export_path)
+                            .map(|path| format!("Exported to {:?}", path))
+                            .map_err(|err| {
+                                error!("Error exporting overlay: {:?}", err);
+                                format!("Export failed: {}", err)
+                            });
+                        self.export_result = Some(result);
+                    }
+                }
+
+                match &self.export_result {
+                    Some(Ok(msg)) | Some(Err(msg)) => {
+                        ui.label(msg);
+                    }
+                    None => (),
+                }
             })
     }
 
@@ -144,12 +462,16 @@ pub struct ScriptInput {
     path: PathBuf,
 
     config: ScriptConfig,
+
+    run: u64,
+    timeout: Option<std::time::Duration>,
 }
 
 pub enum ScriptMsg {
     IOError(String),
     ScriptError(String),
     Running(String),
+    Cancelled,
 }
 
 #[allow(dead_code)]
@@ -178,6 +500,13 @@ pub struct OverlayCreator {
 
     script_results: Host<ScriptInput, ScriptResult>,
     latest_result: Option<ScriptResult>,
+
+    // a running script is stopped when this no longer matches the
+    // run it was started as
+    current_run: Arc<AtomicCell<u64>>,
+    timeout_secs: u32,
+
+    toast_tx: Sender<Toast>,
 }
 
 impl OverlayCreator {
@@ -186,16 +515,22 @@ impl OverlayCreator {
     pub fn new(reactor: &Reactor) -> Result<Self> {
         let pwd = std::fs::canonicalize("./").unwrap();
 
-        let mut file_picker = FilePicker::new(
+        let extensions: [&str; 1] = ["rhai"];
+
+        let file_picker = FilePicker::new(
             egui::Id::with(egui::Id::new(Self::ID), "file_picker"),
             pwd,
+            Some(&extensions),
         )
         .unwrap();
 
+        let current_run = Arc::new(AtomicCell::new(0u64));
+
         let script_results = {
             let tx = reactor.overlay_create_tx.clone();
             let rayon_pool = reactor.rayon_pool.clone();
             let graph = reactor.graph_query.clone();
+            let current_run = current_run.clone();
 
             reactor.create_host(
                 move |outbox: &Outbox<ScriptResult>, input: ScriptInput| {
@@ -205,15 +540,36 @@ impl OverlayCreator {
 
                     running_msg("Loading script");
 
+                    let io_error = |err: std::io::Error| {
+                        ScriptMsg::io_error(&format!(
+                            "error loading script file {:?}: {}",
+                            input.path, err
+                        ))
+                    };
+
                     let mut file =
-                        std::fs::File::open(input.path).map_err(|_| {
-                            ScriptMsg::io_error("error loading script file")
-                        })?;
+                        std::fs::File::open(&input.path).map_err(io_error)?;
 
                     let mut script = String::new();
-                    file.read_to_string(&mut script).map_err(|_| {
-                        ScriptMsg::io_error("error loading script file")
-                    })?;
+                    file.read_to_string(&mut script).map_err(io_error)?;
+
+                    let run = input.run;
+                    let deadline =
+                        input.timeout.map(|t| std::time::Instant::now() + t);
+
+                    let cancelled = {
+                        let current_run = current_run.clone();
+                        move || current_run.load() != run
+                    };
+                    let timed_out = move || {
+                        deadline
+                            .map_or(false, |d| std::time::Instant::now() >= d)
+                    };
+
+                    let stop: StopCheck = {
+                        let cancelled = cancelled.clone();
+                        Arc::new(move || cancelled() || timed_out())
+                    };
 
                     running_msg("Evaluating script");
                     let overlay_data = crate::script::overlay_colors_tgt(
@@ -221,6 +577,7 @@ impl OverlayCreator {
                         &input.config,
                         &graph,
                         &script,
+                        Some(stop),
                     );
 
                     let feedback = match overlay_data {
@@ -232,6 +589,14 @@ impl OverlayCreator {
                             tx.send(msg).unwrap();
                             Ok(())
                         }
+                        Err(_) if cancelled() => Err(ScriptMsg::Cancelled),
+                        Err(_) if timed_out() => {
+                            let secs = input.timeout.unwrap().as_secs();
+                            Err(ScriptMsg::ScriptError(format!(
+                                "timed out after {} seconds",
+                                secs
+                            )))
+                        }
                         Err(err) => {
                             Err(ScriptMsg::ScriptError(format!("{:?}", err)))
                         }
@@ -242,9 +607,6 @@ impl OverlayCreator {
             )
         };
 
-        let extensions: [&str; 1] = ["rhai"];
-        file_picker.set_visible_extensions(&extensions).unwrap();
-
         Ok(Self {
             name: String::new(),
             script_path_input: String::new(),
@@ -254,6 +616,11 @@ impl OverlayCreator {
 
             script_results,
             latest_result: None,
+
+            current_run,
+            timeout_secs: 0,
+
+            toast_tx: reactor.toast_tx.clone(),
         })
     }
 
@@ -273,9 +640,17 @@ impl OverlayCreator {
 
             if let Err(ScriptMsg::IOError(err)) = &result {
                 error!("Overlay script IO error: {:?}", err);
+                let toast = Toast::error(format!("Overlay script: {}", err));
+                self.toast_tx.send(toast).unwrap();
             }
             if let Err(ScriptMsg::ScriptError(err)) = &result {
                 error!("Overlay script execution error: {:?}", err);
+                let toast =
+                    Toast::error(format!("Overlay script failed: {}", err));
+                self.toast_tx.send(toast).unwrap();
+            }
+            if let Err(ScriptMsg::Cancelled) = &result {
+                info!("Overlay script cancelled");
             }
 
             self.latest_result = Some(result);
@@ -309,6 +684,8 @@ impl OverlayCreator {
                 let file_picker_open = &mut self.file_picker_open;
 
                 let script_results = &mut self.script_results;
+                let current_run = &self.current_run;
+                let timeout_secs = &mut self.timeout_secs;
 
                 let _name_box = ui.horizontal(|ui| {
                     ui.label("Overlay name");
@@ -328,6 +705,19 @@ impl OverlayCreator {
                     ui.add(text_edit);
                 });
 
+                ui.horizontal(|ui| {
+                    ui.label("Timeout (seconds)");
+                    ui.separator();
+                    ui.add_enabled(
+                        !is_running,
+                        egui::DragValue::new(timeout_secs)
+                            .clamp_range(0u32..=3600u32),
+                    )
+                    .on_hover_text("0 for no timeout");
+                });
+
+                let mut cancel = false;
+
                 ui.horizontal(|ui| {
                     let file_btn = egui::Button::new("Choose file");
 
@@ -354,16 +744,39 @@ impl OverlayCreator {
                             target,
                         };
 
+                        let run = current_run.fetch_add(1) + 1;
+
+                        let timeout = Some(*timeout_secs)
+                            .filter(|&secs| secs > 0)
+                            .map(|secs| {
+                                std::time::Duration::from_secs(secs as u64)
+                            });
+
                         let script_input = ScriptInput {
                             name: name.to_string(),
                             path,
                             config,
+
+                            run,
+                            timeout,
                         };
 
                         script_results.call(script_input).unwrap();
                     }
+
+                    let cancel_btn =
+                        ui.add_enabled(is_running, egui::Button::new("Cancel"));
+
+                    if cancel_btn.clicked() {
+                        current_run.fetch_add(1);
+                        cancel = true;
+                    }
                 });
 
+                if cancel {
+                    self.latest_result = Some(Err(ScriptMsg::Cancelled));
+                }
+
                 match &self.latest_result {
                     Some(Err(ScriptMsg::IOError(err))) => {
                         ui.label(format!("IO Error: {:?}", err));
@@ -374,6 +787,9 @@ impl OverlayCreator {
                     Some(Err(ScriptMsg::Running(msg))) => {
                         ui.label(msg);
                     }
+                    Some(Err(ScriptMsg::Cancelled)) => {
+                        ui.label("Cancelled");
+                    }
                     Some(Ok(_)) => {
                         ui.label("Created new overlay");
                     }
@@ -386,23 +802,83 @@ impl OverlayCreator {
 pub struct GradientPicker {
     overlay_state: OverlayState,
     gradient_names: Vec<(GradientName, String)>,
+
+    /// Colors sampled from each gradient for the previews; the set
+    /// of gradients is fixed, so they're only computed once
+    swatches: FxHashMap<GradientName, Vec<egui::Color32>>,
 }
 
 impl GradientPicker {
     pub const ID: &'static str = "gradient_picker_window";
 
+    /// The number of colors in each gradient preview
+    const SWATCH_SAMPLES: usize = 32;
+
     pub fn new(overlay_state: OverlayState) -> Self {
         let gradient_names =
             std::array::IntoIter::new(Gradients::GRADIENT_NAMES)
                 .map(|name| (name, name.to_string()))
                 .collect::<Vec<_>>();
 
+        let swatches = std::array::IntoIter::new(Gradients::GRADIENT_NAMES)
+            .map(|name| {
+                let colors = sample_gradient(
+                    name.gradient(),
+                    Self::SWATCH_SAMPLES,
+                    false,
+                )
+                .into_iter()
+                .map(|c| {
+                    let r = (c.r * 255.0) as u8;
+                    let g = (c.g * 255.0) as u8;
+                    let b = (c.b * 255.0) as u8;
+                    egui::Color32::from_rgb(r, g, b)
+                })
+                .collect();
+                (name, colors)
+            })
+            .collect();
+
         Self {
             overlay_state,
             gradient_names,
+            swatches,
         }
     }
 
+    /// Draw a preview of `gradient` as a row of colored rectangles
+    pub fn swatch_ui(
+        &self,
+        ui: &mut egui::Ui,
+        gradient: GradientName,
+        reversed: bool,
+        size: Point,
+    ) -> egui::Response {
+        let (rect, resp) =
+            ui.allocate_exact_size(size.into(), egui::Sense::hover());
+
+        if let Some(colors) = self.swatches.get(&gradient) {
+            let painter = ui.painter();
+
+            let width = rect.width() / colors.len() as f32;
+
+            for (ix, &color) in colors.iter().enumerate() {
+                let ix = if reversed { colors.len() - 1 - ix } else { ix };
+
+                let x0 = rect.left() + width * ix as f32;
+
+                let rect = egui::Rect::from_min_max(
+                    egui::pos2(x0, rect.top()),
+                    egui::pos2(x0 + width, rect.bottom()),
+                );
+
+                painter.rect_filled(rect, 0.0, color);
+            }
+        }
+
+        resp
+    }
+
     pub fn ui(
         &self,
         ctx: &egui::CtxRef,
@@ -412,6 +888,11 @@ impl GradientPicker {
             .id(egui::Id::new(Self::ID))
             .open(open)
             .show(ctx, |ui| {
+                let mut reversed = self.overlay_state.gradient_reversed();
+                if ui.checkbox(&mut reversed, "Reverse gradient").changed() {
+                    self.overlay_state.set_gradient_reversed(reversed);
+                }
+
                 egui::ScrollArea::both().show(ui, |ui| {
                     // egui::ScrollArea::auto_sized().show(ui, |ui| {
                     egui::Grid::new("gradient_picker_list").show(ui, |ui| {
@@ -424,10 +905,7 @@ impl GradientPicker {
                         let mut current_gradient =
                             self.overlay_state.gradient();
 
-                        let dy = 1.0 / 64.0;
-
-                        for (ix, (gradient_name, name)) in
-                            self.gradient_names.iter().enumerate()
+                        for (gradient_name, name) in self.gradient_names.iter()
                         {
                             let gradient_select = ui.selectable_value(
                                 &mut current_gradient,
@@ -440,20 +918,12 @@ impl GradientPicker {
                                 self.overlay_state.set_gradient(*gradient_name);
                             }
 
-                            // let y = oy + (dy * ix as f32);
-
-                            let y = dy * ix as f32;
-
-                            let p0 = Point::new(0.0, y);
-                            let p1 = Point::new(1.0, y);
-
-                            let img = egui::Image::new(
-                                egui::TextureId::User(0),
+                            self.swatch_ui(
+                                ui,
+                                *gradient_name,
+                                reversed,
                                 Point { x: 260.0, y: 25.0 },
-                            )
-                            .uv(Rect::new(p0, p1));
-
-                            ui.add(img);
+                            );
 
                             ui.end_row();
                         }
@@ -462,3 +932,367 @@ impl GradientPicker {
             })
     }
 }
+
+#[derive(Debug, Clone)]
+pub struct BedOverlayInput {
+    name: String,
+    path: PathBuf,
+
+    split_by_name: bool,
+}
+
+/// The outcome of loading a BED file as overlays
+#[derive(Debug, Clone)]
+pub struct BedOverlaySummary {
+    overlay_count: usize,
+    missing_chroms: Vec<String>,
+}
+
+pub type BedOverlayResult =
+    std::result::Result<BedOverlaySummary, super::AnnotMsg>;
+
+/// Window for creating overlays directly from a BED file, coloring
+/// the nodes covered by each interval. Chromosome names are resolved
+/// against the path names, either the full name or the part before a
+/// `:start-end` suffix, in which case the suffix offsets the interval.
+pub struct BedOverlayLoader {
+    name: String,
+    bed_path_input: String,
+
+    split_by_name: bool,
+
+    file_picker: FilePicker,
+    file_picker_open: bool,
+
+    bed_results: Host<BedOverlayInput, BedOverlayResult>,
+    latest_result: Option<BedOverlayResult>,
+
+    toast_tx: Sender<Toast>,
+}
+
+impl BedOverlayLoader {
+    pub const ID: &'static str = "bed_overlay_loader_window";
+
+    pub fn new(reactor: &Reactor) -> Result<Self> {
+        let pwd = std::fs::canonicalize("./").unwrap();
+
+        let extensions: [&str; 1] = ["bed"];
+
+        let file_picker = FilePicker::new(
+            egui::Id::with(egui::Id::new(Self::ID), "file_picker"),
+            pwd,
+            Some(&extensions),
+        )
+        .unwrap();
+
+        let bed_results = {
+            let tx = reactor.overlay_create_tx.clone();
+            let graph = reactor.graph_query.clone();
+
+            reactor.create_host(
+                move |outbox: &Outbox<BedOverlayResult>,
+                      input: BedOverlayInput| {
+                    use super::AnnotMsg;
+
+                    outbox.insert_blocking(Err(AnnotMsg::Running(
+                        "Loading BED".to_string(),
+                    )));
+
+                    let records = BedRecords::parse_bed_file(&input.path)
+                        .map_err(|err| {
+                            AnnotMsg::ParseError(format!(
+                                "Error parsing BED file: {:?}",
+                                err
+                            ))
+                        })?;
+
+                    outbox.insert_blocking(Err(AnnotMsg::Running(
+                        "Resolving intervals".to_string(),
+                    )));
+
+                    let (overlays, missing_chroms) = bed_overlays(
+                        &graph,
+                        &records,
+                        &input.name,
+                        input.split_by_name,
+                    );
+
+                    for chrom in missing_chroms.iter() {
+                        warn!(
+                            "BED chromosome not found among paths: {}",
+                            chrom
+                        );
+                    }
+
+                    let overlay_count = overlays.len();
+
+                    for (name, data) in overlays {
+                        let msg = OverlayCreatorMsg::NewOverlay { name, data };
+                        tx.send(msg).unwrap();
+                    }
+
+                    Ok(BedOverlaySummary {
+                        overlay_count,
+                        missing_chroms,
+                    })
+                },
+            )
+        };
+
+        Ok(Self {
+            name: String::new(),
+            bed_path_input: String::new(),
+
+            split_by_name: false,
+
+            file_picker,
+            file_picker_open: false,
+
+            bed_results,
+            latest_result: None,
+
+            toast_tx: reactor.toast_tx.clone(),
+        })
+    }
+
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        open: &mut bool,
+    ) -> Option<egui::InnerResponse<Option<()>>> {
+        use super::AnnotMsg;
+
+        if let Some(result) = self.bed_results.take() {
+            if let Ok(summary) = &result {
+                info!(
+                    "Created {} overlays from BED file",
+                    summary.overlay_count
+                );
+            }
+
+            if let Err(AnnotMsg::ParseError(err))
+            | Err(AnnotMsg::IOError(err)) = &result
+            {
+                error!("BED overlay error: {}", err);
+                let toast = Toast::error(format!("BED overlay: {}", err));
+                self.toast_tx.send(toast).unwrap();
+            }
+
+            self.latest_result = Some(result);
+        }
+
+        if self.file_picker.selected_path().is_some() {
+            self.file_picker_open = false;
+        }
+
+        self.file_picker.ui(ctx, &mut self.file_picker_open);
+
+        if let Some(path) = self.file_picker.selected_path() {
+            let path_str = path.to_str().unwrap();
+            self.bed_path_input = path_str.to_string();
+        }
+
+        egui::Window::new("Load BED annotation")
+            .id(egui::Id::new(Self::ID))
+            .open(open)
+            .show(ctx, |ui| {
+                let is_running = matches!(
+                    self.latest_result,
+                    Some(Err(AnnotMsg::Running(_)))
+                );
+
+                let name = &mut self.name;
+                let path_str = &mut self.bed_path_input;
+                let split_by_name = &mut self.split_by_name;
+
+                let file_picker = &mut self.file_picker;
+                let file_picker_open = &mut self.file_picker_open;
+
+                let bed_results = &mut self.bed_results;
+
+                ui.horizontal(|ui| {
+                    ui.label("Overlay name");
+                    ui.separator();
+                    let text_edit =
+                        egui::TextEdit::singleline(name).enabled(!is_running);
+                    ui.add(text_edit);
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("BED path");
+                    ui.separator();
+                    let text_edit = egui::TextEdit::singleline(path_str)
+                        .enabled(!is_running);
+                    ui.add(text_edit);
+                });
+
+                ui.checkbox(split_by_name, "One overlay per name column value");
+
+                ui.horizontal(|ui| {
+                    let file_btn = egui::Button::new("Choose file");
+
+                    if ui.add_enabled(!is_running, file_btn).clicked() {
+                        file_picker.reset_selection();
+                        *file_picker_open = true;
+                    }
+
+                    ui.separator();
+
+                    let load_btn = ui.add_enabled(
+                        !is_running && !path_str.is_empty(),
+                        egui::Button::new("Load"),
+                    );
+
+                    if load_btn.clicked() {
+                        file_picker.reset_selection();
+
+                        let name = if name.is_empty() {
+                            let path = std::path::Path::new(path_str.as_str());
+                            path.file_stem()
+                                .and_then(|stem| stem.to_str())
+                                .unwrap_or("BED")
+                                .to_string()
+                        } else {
+                            name.to_string()
+                        };
+
+                        let input = BedOverlayInput {
+                            name,
+                            path: PathBuf::from(path_str.as_str()),
+                            split_by_name: *split_by_name,
+                        };
+
+                        bed_results.call(input).unwrap();
+                    }
+                });
+
+                match &self.latest_result {
+                    Some(Err(AnnotMsg::IOError(err)))
+                    | Some(Err(AnnotMsg::ParseError(err))) => {
+                        ui.label(err);
+                    }
+                    Some(Err(AnnotMsg::Running(msg))) => {
+                        ui.label(msg);
+                    }
+                    Some(Ok(summary)) => {
+                        ui.label(format!(
+                            "Created {} overlays",
+                            summary.overlay_count
+                        ));
+
+                        if !summary.missing_chroms.is_empty() {
+                            ui.label(format!(
+                                "Chromosomes not found among paths: {}",
+                                summary.missing_chroms.join(", ")
+                            ));
+                        }
+                    }
+                    _ => (),
+                }
+            })
+    }
+}
+
+/// Build the overlays for the records in a BED file, returning them
+/// along with the chromosome names that did not match any path.
+///
+/// Covered nodes are colored by the hash of the record's name column,
+/// and all other nodes use the same faded gray as the other overlay
+/// creators.
+fn bed_overlays(
+    graph: &GraphQuery,
+    records: &BedRecords,
+    name: &str,
+    split_by_name: bool,
+) -> (Vec<(String, OverlayData)>, Vec<String>) {
+    use crate::annotations::{
+        path_name_offset, path_name_range, path_step_range,
+        record_column_hash_color, BedColumn,
+    };
+    use bstr::ByteSlice;
+
+    let mut path_map: FxHashMap<Vec<u8>, PathId> = FxHashMap::default();
+
+    for path_id in graph.graph().path_ids() {
+        if let Some(path_name) = graph.graph().get_path_name_vec(path_id) {
+            if let Some((chr, _, _)) = path_name_range(&path_name) {
+                path_map.entry(chr.to_owned()).or_insert(path_id);
+            }
+            path_map.insert(path_name, path_id);
+        }
+    }
+
+    let mut records_by_path: FxHashMap<PathId, Vec<&BedRecord>> =
+        FxHashMap::default();
+    let mut missing_chroms: Vec<String> = Vec::new();
+
+    for record in records.records.iter() {
+        if let Some(path_id) = path_map.get(&record.chr) {
+            records_by_path.entry(*path_id).or_default().push(record);
+        } else {
+            let chr = record.chr.to_str_lossy().into_owned();
+            if !missing_chroms.contains(&chr) {
+                missing_chroms.push(chr);
+            }
+        }
+    }
+
+    let default_color = rgb::RGBA::new(0.3, 0.3, 0.3, 0.3);
+    let node_count = graph.node_count();
+
+    let mut overlays: FxHashMap<String, Vec<rgb::RGBA<f32>>> =
+        FxHashMap::default();
+
+    for (path_id, path_records) in records_by_path {
+        let steps = if let Some(steps) = graph.path_pos_steps(path_id) {
+            steps
+        } else {
+            continue;
+        };
+
+        let offset = graph
+            .graph()
+            .get_path_name_vec(path_id)
+            .and_then(|name| path_name_offset(&name));
+
+        for record in path_records {
+            let range = if let Some(range) =
+                path_step_range(&steps, offset, record.start, record.end)
+            {
+                range
+            } else {
+                continue;
+            };
+
+            let color = record_column_hash_color(record, &BedColumn::Name)
+                .unwrap_or(rgb::RGBA::new(1.0, 0.3, 0.3, 1.0));
+
+            let overlay_name = if split_by_name {
+                let record_name = record
+                    .rest
+                    .get(0)
+                    .map(|n| n.to_str_lossy().into_owned())
+                    .unwrap_or_else(|| "unnamed".to_string());
+                format!("{} - {}", name, record_name)
+            } else {
+                name.to_string()
+            };
+
+            let data = overlays
+                .entry(overlay_name)
+                .or_insert_with(|| vec![default_color; node_count]);
+
+            for (handle, _, _) in range {
+                data[(handle.id().0 - 1) as usize] = color;
+            }
+        }
+    }
+
+    let mut overlays = overlays
+        .into_iter()
+        .map(|(name, data)| (name, OverlayData::RGB(data)))
+        .collect::<Vec<_>>();
+    overlays.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    (overlays, missing_chroms)
+}
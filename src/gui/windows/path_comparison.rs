@@ -0,0 +1,286 @@
+use std::sync::Arc;
+
+#[allow(unused_imports)]
+use handlegraph::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    packedgraph::PackedGraph,
+    pathhandlegraph::*,
+};
+
+use bstr::ByteSlice;
+
+use crossbeam::{atomic::AtomicCell, channel::Sender};
+
+use crate::{
+    app::OverlayCreatorMsg,
+    asynchronous::AsyncResult,
+    graph_query::{GraphQuery, GraphQueryWorker},
+    overlays::{OverlayData, MEMBERSHIP_PALETTE},
+    reactor::Reactor,
+};
+
+/// The nodes visited by two paths, and how much they overlap
+pub struct PathOverlap {
+    pub paths: [PathId; 2],
+
+    /// The number of distinct nodes visited by each path
+    pub node_counts: [usize; 2],
+
+    /// The number of nodes visited by both paths
+    pub shared: usize,
+
+    /// One flag set per node, indexed by node ID - 1, with bit 0 set
+    /// if the first path visits the node, and bit 1 for the second
+    membership: Vec<u32>,
+}
+
+impl PathOverlap {
+    const FIRST: u32 = 1;
+    const SECOND: u32 = 2;
+    const BOTH: u32 = Self::FIRST | Self::SECOND;
+
+    pub fn compute(graph: &PackedGraph, paths: [PathId; 2]) -> Self {
+        let mut membership = vec![0u32; graph.node_count()];
+
+        for (&path, &flag) in paths.iter().zip(&[Self::FIRST, Self::SECOND]) {
+            if let Some(steps) = graph.path_steps(path) {
+                for step in steps {
+                    let ix = (step.handle().id().0 - 1) as usize;
+                    if let Some(member) = membership.get_mut(ix) {
+                        *member |= flag;
+                    }
+                }
+            }
+        }
+
+        let count = |flag: u32| {
+            membership.iter().filter(|&&m| m & flag == flag).count()
+        };
+
+        let node_counts = [count(Self::FIRST), count(Self::SECOND)];
+        let shared = count(Self::BOTH);
+
+        Self {
+            paths,
+            node_counts,
+            shared,
+            membership,
+        }
+    }
+
+    /// The number of nodes visited by either path
+    pub fn union(&self) -> usize {
+        self.node_counts[0] + self.node_counts[1] - self.shared
+    }
+
+    /// The Jaccard index of the two node sets, or 0 if both paths
+    /// are empty
+    pub fn jaccard(&self) -> f64 {
+        let union = self.union();
+        if union == 0 {
+            0.0
+        } else {
+            self.shared as f64 / union as f64
+        }
+    }
+
+    /// A categorical overlay of the nodes on the first path, the
+    /// second, both, or neither, colored the same way as the path
+    /// strand overlay
+    pub fn overlay_data(&self) -> OverlayData {
+        OverlayData::Category {
+            categories: self.membership.clone(),
+            palette: MEMBERSHIP_PALETTE.to_vec(),
+        }
+    }
+}
+
+/// Window for comparing the nodes visited by two paths, picked from
+/// the path list. The comparison runs on the thread pool.
+pub struct PathComparison {
+    worker: GraphQueryWorker,
+
+    /// The path open in the path details window
+    path_details_id: Arc<AtomicCell<Option<PathId>>>,
+
+    paths: [Option<PathId>; 2],
+
+    result: Option<AsyncResult<PathOverlap>>,
+    overlap: Option<PathOverlap>,
+
+    new_overlay_tx: Sender<OverlayCreatorMsg>,
+}
+
+impl PathComparison {
+    const ID: &'static str = "path_comparison_window";
+
+    pub fn new(
+        reactor: &Reactor,
+        path_details_id: Arc<AtomicCell<Option<PathId>>>,
+    ) -> Self {
+        let worker = GraphQueryWorker::new(
+            reactor.graph_query.clone(),
+            reactor.thread_pool.clone(),
+        );
+
+        Self {
+            worker,
+
+            path_details_id,
+
+            paths: [None, None],
+
+            result: None,
+            overlap: None,
+
+            new_overlay_tx: reactor.overlay_create_tx.clone(),
+        }
+    }
+
+    fn compare(&mut self, paths: [PathId; 2]) {
+        let result =
+            self.worker
+                .run_query(move |graph: Arc<GraphQuery>| async move {
+                    PathOverlap::compute(graph.graph(), paths)
+                });
+
+        self.result = Some(result);
+    }
+
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        open: &mut bool,
+        graph_query: &GraphQuery,
+    ) -> Option<egui::InnerResponse<Option<()>>> {
+        if let Some(result) = self.result.as_mut() {
+            if let Some(overlap) = result.take_result_if_ready() {
+                self.overlap = Some(overlap);
+                self.result = None;
+            }
+        }
+
+        let graph = graph_query.graph();
+
+        let path_name = |path: Option<PathId>| {
+            path.and_then(|path| graph.get_path_name_vec(path))
+                .map(|name| format!("{}", name.as_bstr()))
+                .unwrap_or_else(|| "None".to_string())
+        };
+
+        let open_path = self.path_details_id.load();
+
+        let mut compare = None;
+        let mut create_overlay = false;
+
+        let resp = egui::Window::new("Compare paths")
+            .id(egui::Id::new(Self::ID))
+            .default_pos(egui::Pos2::new(600.0, 200.0))
+            .open(open)
+            .show(ctx, |ui| {
+                egui::Grid::new("path_comparison_paths").show(ui, |ui| {
+                    for (label, path) in
+                        ["Path A", "Path B"].iter().zip(self.paths.iter_mut())
+                    {
+                        ui.label(*label);
+                        ui.label(path_name(*path));
+
+                        if ui
+                            .add_enabled(
+                                open_path.is_some(),
+                                egui::Button::new("Use open path"),
+                            )
+                            .on_hover_text(
+                                "Use the path picked in the path list",
+                            )
+                            .clicked()
+                        {
+                            *path = open_path;
+                        }
+
+                        ui.end_row();
+                    }
+                });
+
+                let running = self.result.is_some();
+
+                let paths = match self.paths {
+                    [Some(a), Some(b)] => Some([a, b]),
+                    _ => None,
+                };
+
+                if ui
+                    .add_enabled(
+                        paths.is_some() && !running,
+                        egui::Button::new("Compare"),
+                    )
+                    .clicked()
+                {
+                    compare = paths;
+                }
+
+                ui.separator();
+
+                if running {
+                    ui.label("Comparing...");
+                    return;
+                }
+
+                let overlap = match self.overlap.as_ref() {
+                    Some(overlap) => overlap,
+                    None => return,
+                };
+
+                ui.label(format!(
+                    "{} and {}",
+                    path_name(Some(overlap.paths[0])),
+                    path_name(Some(overlap.paths[1]))
+                ));
+
+                egui::Grid::new("path_comparison_result").show(ui, |ui| {
+                    let [a, b] = overlap.node_counts;
+                    let shared = overlap.shared;
+
+                    let rows = [
+                        ("Nodes on A", a.to_string()),
+                        ("Nodes on B", b.to_string()),
+                        ("Shared", shared.to_string()),
+                        ("Only on A", (a - shared).to_string()),
+                        ("Only on B", (b - shared).to_string()),
+                        ("Jaccard index", format!("{:.4}", overlap.jaccard())),
+                    ];
+
+                    for (label, value) in rows.iter() {
+                        ui.label(*label);
+                        ui.label(value);
+                        ui.end_row();
+                    }
+                });
+
+                create_overlay = ui
+                    .button("Create overlay")
+                    .on_hover_text(
+                        "Color nodes only on A blue, only on B orange, \
+                         and on both purple",
+                    )
+                    .clicked();
+            });
+
+        if let Some(paths) = compare {
+            self.compare(paths);
+        }
+
+        if create_overlay {
+            if let Some(overlap) = self.overlap.as_ref() {
+                let msg = OverlayCreatorMsg::UpdateOverlay {
+                    name: "Path overlap".to_string(),
+                    data: overlap.overlay_data(),
+                };
+                self.new_overlay_tx.send(msg).unwrap();
+            }
+        }
+
+        resp
+    }
+}
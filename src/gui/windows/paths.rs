@@ -13,14 +13,15 @@ use handlegraph::{
 
 use crossbeam::{atomic::AtomicCell, channel::Sender};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use bstr::ByteSlice;
 
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{
     context::ContextMgr,
-    gui::util::{grid_row_label, ColumnWidths},
+    gui::util::{grid_row_label, highlight_row, ColumnWidths, RowSelection},
     reactor::{Host, Outbox, Reactor},
 };
 
@@ -28,15 +29,34 @@ use crate::gui::util as gui_util;
 
 use crate::graph_query::GraphQuery;
 use crate::{
-    app::{AppMsg, Select},
+    app::{path_colors::PathColors, AppMsg, OverlayCreatorMsg, Select},
     geometry::*,
+    overlays::OverlayData,
 };
 
 pub struct PathList {
     all_paths: Vec<PathId>,
 
+    selected_nodes: FxHashSet<NodeId>,
+    filtered_paths: Vec<PathId>,
+
+    filter_by_selection: AtomicCell<bool>,
+    update_filter: AtomicCell<bool>,
+
+    /// Only paths whose names match this are shown, if it's not empty
+    name_filter: String,
+    name_filter_regex: bool,
+    name_filter_error: Option<String>,
+
     path_details_id: Arc<AtomicCell<Option<PathId>>>,
 
+    path_colors: PathColors,
+    overlay_tx: Sender<OverlayCreatorMsg>,
+
+    /// Rows selected with Ctrl/Shift-click, as indices into the
+    /// displayed (possibly filtered) list
+    row_selection: RowSelection,
+
     col_widths: ColumnWidths<3>,
 }
 
@@ -138,15 +158,18 @@ impl PathDetails {
                 self.step_list.steps_host.call(path).unwrap();
                 self.step_list.fetched_path_id = Some(path);
                 self.step_list.update_filter = true;
+                self.step_list.walk_cursor = None;
             }
         }
 
+        self.step_list.walk(node_details_id_cell, app_msg_tx);
+
         egui::Window::new("Path details")
             .id(egui::Id::new(Self::ID))
             .default_pos(egui::Pos2::new(600.0, 200.0))
             .open(open_path_details)
             .show(ctx, |ui| {
-                if let Some(_path_id) = self.path_details.path_id.load() {
+                if let Some(path_id) = self.path_details.path_id.load() {
                     ui.label(format!(
                         "Path name: {}",
                         self.path_details.path_name.as_bstr()
@@ -184,6 +207,43 @@ impl PathDetails {
                         ));
                     });
 
+                    if let Some(stats) = self.step_list.step_stats(path_id) {
+                        ui.collapsing("Step summary", |ui| {
+                            stats.ui(ui);
+                        });
+                    }
+
+                    ui.horizontal(|ui| {
+                        if ui
+                            .button("Select all nodes on path")
+                            .on_hover_text(
+                                "Replace the selection with every node \
+                                 the path visits",
+                            )
+                            .clicked()
+                        {
+                            let nodes =
+                                self.step_list.path_nodes(graph_query, path_id);
+                            let selection = AppMsg::Selection(Select::Many {
+                                nodes,
+                                clear: true,
+                            });
+                            app_msg_tx.send(selection).unwrap();
+                        }
+
+                        if ui
+                            .button("Frame path")
+                            .on_hover_text(
+                                "Fit the view to every node the path visits",
+                            )
+                            .clicked()
+                        {
+                            app_msg_tx
+                                .send(AppMsg::goto_path(path_id))
+                                .unwrap();
+                        }
+                    });
+
                     self.step_list.ui(
                         ui,
                         app_msg_tx,
@@ -201,41 +261,271 @@ impl PathDetails {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathListMsg {
+    SetSelection(Vec<NodeId>),
+}
+
 impl PathList {
     const ID: &'static str = "path_list_window";
 
+    pub fn apply_msg(&mut self, msg: PathListMsg) {
+        match msg {
+            PathListMsg::SetSelection(nodes) => {
+                self.selected_nodes.clear();
+                self.selected_nodes.extend(nodes);
+                self.update_filter.store(true);
+            }
+        }
+    }
+
+    fn filter_by_selection_active(&self) -> bool {
+        self.filter_by_selection.load() && !self.selected_nodes.is_empty()
+    }
+
+    fn filter_active(&self) -> bool {
+        self.filter_by_selection_active() || !self.name_filter.is_empty()
+    }
+
+    /// The case-insensitive matcher for the name filter; the filter
+    /// is matched as a substring unless regex mode is enabled
+    fn name_matcher(&self) -> Result<regex::bytes::Regex, regex::Error> {
+        let pattern = if self.name_filter_regex {
+            self.name_filter.clone()
+        } else {
+            regex::escape(&self.name_filter)
+        };
+
+        regex::bytes::RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+    }
+
+    /// Find the paths whose names match the name filter, and that
+    /// pass through at least one selected node if filtering by
+    /// selection; only done when the selection or the filters change,
+    /// as every step of every path may be visited
+    fn update_filtered_paths(&mut self, graph_query: &GraphQuery) {
+        self.update_filter.store(false);
+        self.filtered_paths.clear();
+        self.row_selection.clear();
+        self.name_filter_error = None;
+
+        if !self.filter_active() {
+            return;
+        }
+
+        let graph = graph_query.graph();
+
+        let matcher = if self.name_filter.is_empty() {
+            None
+        } else {
+            match self.name_matcher() {
+                Ok(matcher) => Some(matcher),
+                Err(err) => {
+                    // show no paths rather than all of them
+                    self.name_filter_error = Some(err.to_string());
+                    return;
+                }
+            }
+        };
+
+        let selected = if self.filter_by_selection_active() {
+            Some(&self.selected_nodes)
+        } else {
+            None
+        };
+
+        self.filtered_paths
+            .extend(self.all_paths.iter().copied().filter(|&path| {
+                let name_matches = matcher.as_ref().map_or(true, |matcher| {
+                    graph
+                        .get_path_name_vec(path)
+                        .map_or(false, |name| matcher.is_match(&name))
+                });
+
+                name_matches
+                    && selected.map_or(true, |selected| {
+                        graph.path_steps(path).map_or(false, |mut steps| {
+                            steps.any(|step| {
+                                selected.contains(&step.handle().id())
+                            })
+                        })
+                    })
+            }));
+    }
+
     pub fn ui(
         &mut self,
         ctx: &egui::CtxRef,
-        _app_msg_tx: &Sender<AppMsg>,
+        app_msg_tx: &Sender<AppMsg>,
         open_path_details: &mut bool,
         graph_query: &GraphQuery,
         ctx_mgr: &ContextMgr,
     ) -> Option<egui::InnerResponse<Option<()>>> {
-        let paths = &self.all_paths;
+        if self.update_filter.load() {
+            self.update_filtered_paths(graph_query);
+        }
+
+        let filter_by_selection = self.filter_by_selection.load();
+
+        let paths = if self.filter_active() {
+            &self.filtered_paths
+        } else {
+            &self.all_paths
+        };
+
+        let name_filter = &mut self.name_filter;
+        let name_filter_regex = &mut self.name_filter_regex;
+        let name_filter_error = &self.name_filter_error;
+
+        let row_selection = &mut self.row_selection;
+        let col_widths = &self.col_widths;
+        let update_filter = &self.update_filter;
+        let filter_by_selection_cell = &self.filter_by_selection;
+        let path_details_id = &self.path_details_id;
+        let path_colors = &self.path_colors;
+        let overlay_tx = &self.overlay_tx;
 
         egui::Window::new("Paths")
             .id(egui::Id::new(Self::ID))
             .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .selectable_label(*open_path_details, "Path Details")
+                        .clicked()
+                    {
+                        *open_path_details = !*open_path_details;
+                    }
+
+                    if ui
+                        .selectable_label(
+                            filter_by_selection,
+                            "Filter by node selection",
+                        )
+                        .on_hover_text(
+                            "Show only paths that pass through a selected node",
+                        )
+                        .clicked()
+                    {
+                        filter_by_selection_cell.store(!filter_by_selection);
+                        update_filter.store(true);
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Filter by name");
+
+                    let text_box = ui.text_edit_singleline(name_filter);
+
+                    let regex_box =
+                        ui.checkbox(name_filter_regex, "Regex").on_hover_text(
+                            "Match path names against the filter as a \
+                             regular expression instead of a substring",
+                        );
+
+                    if text_box.changed() || regex_box.changed() {
+                        update_filter.store(true);
+                    }
+                });
+
+                if let Some(error) = name_filter_error {
+                    ui.label(error);
+                }
+
+                ui.horizontal(|ui| {
+                    let select_btn = ui.add_enabled(
+                        !row_selection.is_empty(),
+                        egui::Button::new("Select nodes on selected paths"),
+                    );
+
+                    if select_btn
+                        .on_hover_text(
+                            "Ctrl-click to toggle a path, \
+                             Shift-click to select a range of paths",
+                        )
+                        .clicked()
+                    {
+                        let graph = graph_query.graph();
+
+                        let mut nodes: FxHashSet<NodeId> = FxHashSet::default();
+
+                        for &path in
+                            row_selection.rows().filter_map(|ix| paths.get(ix))
+                        {
+                            if let Some(steps) = graph.path_steps(path) {
+                                nodes.extend(
+                                    steps.map(|step| step.handle().id()),
+                                );
+                            }
+                        }
+
+                        let selection = AppMsg::Selection(Select::Many {
+                            nodes,
+                            clear: true,
+                        });
+                        app_msg_tx.send(selection).unwrap();
+                    }
+
+                    if !row_selection.is_empty() {
+                        ui.label(format!(
+                            "{} paths selected",
+                            row_selection.len()
+                        ));
+                    }
+                });
+
                 if ui
-                    .selectable_label(*open_path_details, "Path Details")
+                    .button("Color nodes by path")
+                    .on_hover_text(
+                        "Create an overlay coloring each node by the first \
+                         of the selected paths, or of the listed paths if \
+                         none are selected, that it's on",
+                    )
                     .clicked()
                 {
-                    *open_path_details = !*open_path_details;
+                    let overlay_paths = if row_selection.is_empty() {
+                        paths.clone()
+                    } else {
+                        row_selection
+                            .rows()
+                            .filter_map(|ix| paths.get(ix).copied())
+                            .collect::<Vec<_>>()
+                    };
+
+                    let (categories, palette) = path_colors.membership_overlay(
+                        graph_query.graph(),
+                        &overlay_paths,
+                    );
+
+                    let msg = OverlayCreatorMsg::UpdateOverlay {
+                        name: PathColors::OVERLAY_NAME.to_string(),
+                        data: OverlayData::Category {
+                            categories,
+                            palette,
+                        },
+                    };
+                    overlay_tx.send(msg).unwrap();
                 }
 
                 let scroll_align = gui_util::add_scroll_buttons(ui);
 
-                let path_id_cell = &self.path_details_id;
+                let path_id_cell = path_details_id;
 
                 let num_rows = paths.len();
                 let text_style = egui::TextStyle::Body;
                 let row_height = ui.fonts()[text_style].row_height();
 
-                let [w0, w1, w2] = self.col_widths.get();
+                let [w0, w1, w2] = col_widths.get();
 
                 let header =
                     egui::Grid::new("path_list_grid_header").show(ui, |ui| {
+                        // space for the color swatch column
+                        ui.allocate_exact_size(
+                            ui.spacing().interact_size,
+                            egui::Sense::hover(),
+                        );
+
                         let inner = grid_row_label(
                             ui,
                             egui::Id::new("path_list_grid_header__"),
@@ -243,7 +533,7 @@ impl PathList {
                             false,
                             Some(&[w0, w1, w2]),
                         );
-                        self.col_widths.set_hdr(&inner.inner);
+                        col_widths.set_hdr(&inner.inner);
                     });
 
                 gui_util::scrolled_area(ui, num_rows, scroll_align).show_rows(
@@ -294,6 +584,23 @@ impl PathList {
                                     let fields: [&str; 3] =
                                         [&path_name, &step_count, &base_count];
 
+                                    if let Some(color) =
+                                        path_colors.get(path_id)
+                                    {
+                                        let mut swatch =
+                                            [color.r, color.g, color.b];
+                                        if ui
+                                            .color_edit_button_rgb(&mut swatch)
+                                            .changed()
+                                        {
+                                            let [r, g, b] = swatch;
+                                            path_colors.set(
+                                                path_id,
+                                                rgb::RGB::new(r, g, b),
+                                            );
+                                        }
+                                    }
+
                                     let inner = grid_row_label(
                                         ui,
                                         egui::Id::new(ui.id().with(ix)),
@@ -302,13 +609,21 @@ impl PathList {
                                         Some(&[w0, w1, w2]),
                                     );
 
-                                    self.col_widths.set(&inner.inner);
+                                    col_widths.set(&inner.inner);
 
                                     let row = inner.response;
 
                                     if row.clicked() {
-                                        path_id_cell.store(Some(path_id));
-                                        *open_path_details = true;
+                                        let modifiers = ui.input().modifiers;
+
+                                        if row_selection.click(ix, modifiers) {
+                                            path_id_cell.store(Some(path_id));
+                                            *open_path_details = true;
+                                        }
+                                    }
+
+                                    if row_selection.is_selected(ix) {
+                                        highlight_row(ui, &row);
                                     }
 
                                     if row.hovered() {
@@ -333,10 +648,11 @@ impl PathList {
     }
 
     pub fn new(
-        graph_query: &GraphQuery,
+        reactor: &Reactor,
+        path_colors: PathColors,
         path_details_id: Arc<AtomicCell<Option<PathId>>>,
     ) -> Self {
-        let graph = graph_query.graph();
+        let graph = reactor.graph_query.graph();
 
         let mut all_paths = graph.path_ids().collect::<Vec<_>>();
         all_paths.sort();
@@ -344,8 +660,23 @@ impl PathList {
         Self {
             all_paths,
 
+            selected_nodes: FxHashSet::default(),
+            filtered_paths: Vec::new(),
+
+            filter_by_selection: false.into(),
+            update_filter: false.into(),
+
+            name_filter: String::new(),
+            name_filter_regex: false,
+            name_filter_error: None,
+
             path_details_id,
 
+            path_colors,
+            overlay_tx: reactor.overlay_create_tx.clone(),
+
+            row_selection: RowSelection::default(),
+
             col_widths: Default::default(),
         }
     }
@@ -383,6 +714,73 @@ impl StepRange {
             path_base_len,
         }
     }
+
+    /// Cover the entire path again
+    fn reset(&mut self, step_count: usize) {
+        self.from_pos = 0;
+        self.to_pos = self.path_base_len;
+        self.from_ix = 0;
+        self.to_ix = step_count;
+    }
+}
+
+/// Aggregate statistics over the steps of a path
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PathStepStats {
+    distinct_nodes: usize,
+    repeated_nodes: usize,
+
+    min_node: Option<NodeId>,
+    max_node: Option<NodeId>,
+}
+
+impl PathStepStats {
+    fn from_steps(steps: &[(Handle, StepPtr, usize)]) -> Self {
+        let mut visits: FxHashMap<NodeId, usize> = FxHashMap::default();
+
+        for (handle, _, _) in steps {
+            *visits.entry(handle.id()).or_default() += 1;
+        }
+
+        let distinct_nodes = visits.len();
+        let repeated_nodes = visits.values().filter(|&&c| c > 1).count();
+
+        let min_node = visits.keys().min().copied();
+        let max_node = visits.keys().max().copied();
+
+        Self {
+            distinct_nodes,
+            repeated_nodes,
+
+            min_node,
+            max_node,
+        }
+    }
+
+    fn ui(&self, ui: &mut egui::Ui) {
+        let node_str = |node: Option<NodeId>| {
+            node.map(|n| n.0.to_string())
+                .unwrap_or_else(|| "-".to_string())
+        };
+
+        egui::Grid::new("path_details_step_stats").show(ui, |ui| {
+            ui.label("Distinct nodes");
+            ui.label(self.distinct_nodes.to_string());
+            ui.end_row();
+
+            ui.label("Nodes visited more than once");
+            ui.label(self.repeated_nodes.to_string());
+            ui.end_row();
+
+            ui.label("Min node ID");
+            ui.label(node_str(self.min_node));
+            ui.end_row();
+
+            ui.label("Max node ID");
+            ui.label(node_str(self.max_node));
+            ui.end_row();
+        });
+    }
 }
 
 type StepsResult =
@@ -394,14 +792,43 @@ pub struct StepList {
     steps_host: Host<PathId, StepsResult>,
     latest_result: Option<StepsResult>,
 
+    step_stats: Option<(PathId, PathStepStats)>,
+
     range_filter: StepRange,
 
     update_filter: bool,
 
+    jump_pos_text: String,
+    jump_note: Option<String>,
+
+    /// Index of the step the path walk is currently on
+    walk_cursor: Option<usize>,
+    walk_wrap: bool,
+    walk_key: Option<StepWalk>,
+    walk_jump: Option<usize>,
+
+    /// Step to scroll to once the steps of the path being shown are
+    /// loaded, e.g. when a path is opened from the node details
+    jump_to_step: Arc<AtomicCell<Option<StepPtr>>>,
+
+    /// Steps selected with Ctrl/Shift-click, as indices into the
+    /// path's steps; mirrored in the main node selection
+    row_selection: RowSelection,
+
     col_widths: ColumnWidths<3>,
 }
 
+/// A held walk key, used to step along the path at a fixed rate
+/// rather than on every key repeat event
+#[derive(Debug, Clone, Copy)]
+struct StepWalk {
+    forward: bool,
+    last_step: Option<Instant>,
+}
+
 impl StepList {
+    const WALK_INTERVAL: Duration = Duration::from_millis(150);
+
     fn new(reactor: &Reactor, page_size: usize) -> Self {
         let graph_query = reactor.graph_query.clone();
 
@@ -440,14 +867,190 @@ impl StepList {
             steps_host,
             latest_result: None,
 
+            step_stats: None,
+
             range_filter: StepRange::default(),
 
             update_filter: false,
 
+            jump_pos_text: String::new(),
+            jump_note: None,
+
+            walk_cursor: None,
+            walk_wrap: false,
+            walk_key: None,
+            walk_jump: None,
+
+            jump_to_step: Arc::new(None.into()),
+
+            row_selection: RowSelection::default(),
+
             col_widths: Default::default(),
         }
     }
 
+    pub fn jump_to_step_cell(&self) -> &Arc<AtomicCell<Option<StepPtr>>> {
+        &self.jump_to_step
+    }
+
+    /// Start or stop walking along the path in the given direction.
+    /// Repeated presses while the key is held are ignored.
+    pub(crate) fn set_walk_key(&mut self, forward: bool, pressed: bool) {
+        match self.walk_key {
+            Some(walk) if walk.forward == forward => {
+                if !pressed {
+                    self.walk_key = None;
+                }
+            }
+            _ => {
+                if pressed {
+                    self.walk_key = Some(StepWalk {
+                        forward,
+                        last_step: None,
+                    });
+                }
+            }
+        }
+    }
+
+    pub(crate) fn stop_walk(&mut self) {
+        self.walk_key = None;
+    }
+
+    /// Advance the walk cursor if a walk key is held and enough time
+    /// has passed since the last step, showing the new step's node in
+    /// the node details window and panning the view to it
+    fn walk(
+        &mut self,
+        node_details_id_cell: &AtomicCell<Option<NodeId>>,
+        app_msg_tx: &Sender<AppMsg>,
+    ) {
+        let walk = if let Some(walk) = self.walk_key.as_mut() {
+            walk
+        } else {
+            return;
+        };
+
+        let now = Instant::now();
+
+        if let Some(last) = walk.last_step {
+            if now.duration_since(last) < Self::WALK_INTERVAL {
+                return;
+            }
+        }
+
+        let steps = match &self.latest_result {
+            Some(Ok((_, _, steps))) if !steps.is_empty() => steps,
+            _ => return,
+        };
+
+        walk.last_step = Some(now);
+
+        let last_ix = steps.len() - 1;
+
+        let next = match self.walk_cursor {
+            None if walk.forward => 0,
+            None => last_ix,
+            Some(ix) if walk.forward => {
+                if ix < last_ix {
+                    ix + 1
+                } else if self.walk_wrap {
+                    0
+                } else {
+                    last_ix
+                }
+            }
+            Some(ix) => {
+                if ix > 0 {
+                    ix - 1
+                } else if self.walk_wrap {
+                    last_ix
+                } else {
+                    0
+                }
+            }
+        };
+
+        if self.walk_cursor == Some(next) {
+            return;
+        }
+
+        self.walk_cursor = Some(next);
+        self.walk_jump = Some(next);
+
+        let node = steps[next].0.id();
+        node_details_id_cell.store(Some(node));
+        app_msg_tx.send(AppMsg::goto_node(node)).unwrap();
+    }
+
+    /// Find the index of the step covering the base position `pos`,
+    /// clamping to the last step if `pos` is past the end of the path.
+    /// The second element is true if the position had to be clamped.
+    fn step_ix_at_base(
+        steps: &[(Handle, StepPtr, usize)],
+        path_base_len: usize,
+        pos: usize,
+    ) -> Option<(usize, bool)> {
+        if steps.is_empty() {
+            return None;
+        }
+
+        if pos >= path_base_len {
+            return Some((steps.len() - 1, true));
+        }
+
+        let ix = match steps.binary_search_by_key(&pos, |(_, _, p)| *p) {
+            Ok(x) => x,
+            Err(x) => x.saturating_sub(1),
+        };
+
+        Some((ix, false))
+    }
+
+    /// Summary of the steps in the most recently loaded path, only
+    /// recomputed when a different path has been loaded
+    fn step_stats(&mut self, path: PathId) -> Option<PathStepStats> {
+        if self.fetched_path_id != Some(path) {
+            return None;
+        }
+
+        let (result_path, _, steps) =
+            self.latest_result.as_ref()?.as_ref().ok()?;
+
+        if *result_path != path {
+            return None;
+        }
+
+        match self.step_stats {
+            Some((stats_path, stats)) if stats_path == path => Some(stats),
+            _ => {
+                let stats = PathStepStats::from_steps(steps);
+                self.step_stats = Some((path, stats));
+                Some(stats)
+            }
+        }
+    }
+
+    /// The distinct nodes visited by `path`, taken from the loaded
+    /// steps if they belong to the path, or by scanning the path in
+    /// the graph otherwise
+    fn path_nodes(
+        &self,
+        graph_query: &GraphQuery,
+        path: PathId,
+    ) -> FxHashSet<NodeId> {
+        match &self.latest_result {
+            Some(Ok((result_path, _, steps))) if *result_path == path => {
+                steps.iter().map(|(h, _, _)| h.id()).collect()
+            }
+            _ => graph_query
+                .graph()
+                .path_steps(path)
+                .map(|steps| steps.map(|step| step.handle().id()).collect())
+                .unwrap_or_default(),
+        }
+    }
+
     pub fn ui(
         &mut self,
         ui: &mut egui::Ui,
@@ -468,6 +1071,7 @@ impl StepList {
             }
 
             self.latest_result = Some(result);
+            self.row_selection.clear();
         }
 
         let steps = if let Some(Ok((_, len, steps))) = &self.latest_result {
@@ -539,17 +1143,103 @@ impl StepList {
             }
         });
 
-        let steps = {
+        let mut jump_to_row: Option<usize> = None;
+
+        let walk_wrap = &mut self.walk_wrap;
+
+        ui.horizontal(|ui| {
+            ui.label("Ctrl + Left/Right walks the path");
+            ui.checkbox(walk_wrap, "Wrap around");
+        });
+
+        ui.label("Ctrl-click or Shift-click steps to select their nodes");
+
+        if let Some(ix) = self.walk_cursor {
+            ui.label(format!("Walking, at step {} of {}", ix + 1, steps.len()));
+        }
+
+        let steps_loaded = matches!(
+            &self.latest_result,
+            Some(Ok((path, _, _))) if Some(*path) == self.fetched_path_id
+        );
+
+        if steps_loaded {
+            if let Some(step) = self.jump_to_step.take() {
+                if let Some(ix) = steps.iter().position(|(_, s, _)| *s == step)
+                {
+                    // continue walking from the step
+                    self.walk_cursor = Some(ix);
+                    self.walk_jump = Some(ix);
+                }
+            }
+        }
+
+        if let Some(ix) = self.walk_jump.take() {
+            // the row index must match the step index
+            self.range_filter.reset(steps.len());
+            jump_to_row = Some(ix);
+        }
+
+        let jump_pos_text = &mut self.jump_pos_text;
+        let jump_note = &mut self.jump_note;
+        let range_filter = &mut self.range_filter;
+
+        ui.horizontal(|ui| {
+            ui.label("Go to base pos");
+            let text_box = ui.text_edit_singleline(jump_pos_text);
+
+            if ui.button("Go").clicked()
+                || (text_box.lost_focus()
+                    && ui.input().key_pressed(egui::Key::Enter))
+            {
+                let path_base_len = range_filter.path_base_len;
+
+                match jump_pos_text.trim().parse::<usize>() {
+                    Ok(pos) => {
+                        if let Some((ix, clamped)) =
+                            Self::step_ix_at_base(steps, path_base_len, pos)
+                        {
+                            // the row index must match the step index
+                            range_filter.reset(steps.len());
+
+                            jump_to_row = Some(ix);
+
+                            *jump_note = clamped.then(|| {
+                                format!(
+                                    "Position is past the end of the path \
+                                     ({} bp), showing the last step",
+                                    path_base_len
+                                )
+                            });
+                        }
+                    }
+                    Err(_) => {
+                        *jump_note =
+                            Some("Base position must be a number".to_string());
+                    }
+                }
+            }
+        });
+
+        if let Some(note) = jump_note {
+            ui.label(note.as_str());
+        }
+
+        let all_steps = steps;
+
+        let (step_offset, steps) = {
             let from = self.range_filter.from_ix;
             let to = self.range_filter.to_ix;
 
             let from = from.min(to);
             let to = to.min(steps.len());
 
-            &steps[from..to]
+            (from, &steps[from..to])
         };
 
-        let select_path = ui.button("Select nodes in path");
+        let select_path = ui
+            .button("Select nodes in range")
+            .on_hover_text("Add the nodes in the step range to the selection");
 
         if select_path.clicked() {
             let nodes = steps
@@ -567,7 +1257,10 @@ impl StepList {
         let text_style = egui::TextStyle::Body;
         let row_height = ui.fonts()[text_style].row_height();
 
-        let [w0, w1, w2] = self.col_widths.get();
+        let row_selection = &mut self.row_selection;
+        let col_widths = &self.col_widths;
+
+        let [w0, w1, w2] = col_widths.get();
 
         let header =
             egui::Grid::new("path_details_step_list_header").show(ui, |ui| {
@@ -578,73 +1271,97 @@ impl StepList {
                     false,
                     Some(&[w0, w1, w2]),
                 );
-                self.col_widths.set_hdr(&inner.inner);
+                col_widths.set_hdr(&inner.inner);
             });
 
-        gui_util::scrolled_area(ui, num_rows, scroll_align).show_rows(
-            ui,
-            row_height,
-            num_rows,
-            |ui, range| {
-                ui.set_min_width(header.response.rect.width());
-
-                egui::Grid::new("path_details_step_list")
-                    .spacing(Point { x: 10.0, y: 5.0 })
-                    .striped(true)
-                    .show(ui, |ui| {
-                        let take_n = range.start.max(range.end) - range.start;
-
-                        for (slot_ix, (handle, step_ptr, pos)) in steps
-                            .iter()
-                            .enumerate()
-                            .skip(range.start)
-                            .take(take_n)
-                        {
-                            let node_id = handle.id();
+        let mut scroll_area =
+            gui_util::scrolled_area(ui, num_rows, scroll_align);
 
-                            let handle_str = if handle.is_reverse() {
-                                format!("{}-", node_id.0)
-                            } else {
-                                format!("{}+", node_id.0)
-                            };
+        if let Some(row) = jump_to_row {
+            let spacing = ui.style().spacing.item_spacing.y;
+            scroll_area =
+                scroll_area.scroll_offset((row_height + spacing) * row as f32);
+        }
+
+        scroll_area.show_rows(ui, row_height, num_rows, |ui, range| {
+            ui.set_min_width(header.response.rect.width());
+
+            egui::Grid::new("path_details_step_list")
+                .spacing(Point { x: 10.0, y: 5.0 })
+                .striped(true)
+                .show(ui, |ui| {
+                    let take_n = range.start.max(range.end) - range.start;
+
+                    for (slot_ix, (handle, step_ptr, pos)) in
+                        steps.iter().enumerate().skip(range.start).take(take_n)
+                    {
+                        let node_id = handle.id();
 
-                            let step_ptr_str =
-                                format!("{}", step_ptr.to_vector_value());
+                        let handle_str = if handle.is_reverse() {
+                            format!("{}-", node_id.0)
+                        } else {
+                            format!("{}+", node_id.0)
+                        };
 
-                            let pos_str = format!("{}", pos);
+                        let step_ptr_str =
+                            format!("{}", step_ptr.to_vector_value());
 
-                            let fields: [&str; 3] =
-                                [&handle_str, &step_ptr_str, &pos_str];
+                        let pos_str = format!("{}", pos);
 
-                            let inner = grid_row_label(
-                                ui,
-                                egui::Id::new(ui.id().with(slot_ix)),
-                                &fields,
-                                false,
-                                Some(&[w0, w1, w2]),
-                            );
+                        let fields: [&str; 3] =
+                            [&handle_str, &step_ptr_str, &pos_str];
 
-                            self.col_widths.set(&inner.inner);
+                        let inner = grid_row_label(
+                            ui,
+                            egui::Id::new(ui.id().with(slot_ix)),
+                            &fields,
+                            false,
+                            Some(&[w0, w1, w2]),
+                        );
+
+                        col_widths.set(&inner.inner);
+
+                        let row = inner.response;
+
+                        let step_ix = step_offset + slot_ix;
 
-                            let row = inner.response;
+                        if row.clicked() {
+                            let modifiers = ui.input().modifiers;
 
-                            if row.clicked() {
+                            if row_selection.click(step_ix, modifiers) {
                                 node_details_id_cell.store(Some(handle.id()));
                                 *open_node_details = true;
+                            } else {
+                                let nodes = row_selection
+                                    .rows()
+                                    .filter_map(|ix| all_steps.get(ix))
+                                    .map(|(h, _, _)| h.id())
+                                    .collect::<FxHashSet<_>>();
+
+                                let selection =
+                                    AppMsg::Selection(Select::Many {
+                                        nodes,
+                                        clear: true,
+                                    });
+                                app_msg_tx.send(selection).unwrap();
                             }
+                        }
 
-                            if row.hovered() {
-                                ctx_mgr.produce_context(|| handle.id())
-                            }
+                        if row_selection.is_selected(step_ix) {
+                            highlight_row(ui, &row);
+                        }
 
-                            // if row.clicked_by(egui::PointerButton::Secondary) {
-                            //     ctx_tx
-                            //         .send(ContextEntry::Node(handle.id()))
-                            //         .unwrap();
-                            // }
+                        if row.hovered() {
+                            ctx_mgr.produce_context(|| handle.id())
                         }
-                    })
-            },
-        )
+
+                        // if row.clicked_by(egui::PointerButton::Secondary) {
+                        //     ctx_tx
+                        //         .send(ContextEntry::Node(handle.id()))
+                        //         .unwrap();
+                        // }
+                    }
+                })
+        })
     }
 }
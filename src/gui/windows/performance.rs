@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+
+use egui::plot::{Line, Plot, Value, Values};
+
+use crate::vulkan::timestamps::{GpuPass, PassTimes};
+
+/// A single frame time, in seconds, and whether the selection
+/// outline passes were drawn in that frame
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FrameSample {
+    pub time: f32,
+    pub selection_outline: bool,
+
+    /// The GPU time of each render pass, if timestamp queries are
+    /// supported; these lag a couple of frames behind the frame time
+    pub pass_times: Option<PassTimes>,
+}
+
+/// A batch of consecutive frame samples, oldest first
+#[derive(Debug, Default, Clone)]
+pub struct FrameTimesMsg(pub Vec<FrameSample>);
+
+/// Window plotting the most recent frame times, to make stutters
+/// visible, along with summary statistics over the same samples.
+pub struct FrameTimes {
+    samples: VecDeque<FrameSample>,
+}
+
+impl std::default::Default for FrameTimes {
+    fn default() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(Self::HISTORY_LEN),
+        }
+    }
+}
+
+impl FrameTimes {
+    const ID: &'static str = "frame_times_window";
+
+    /// The number of frames that are plotted
+    pub const HISTORY_LEN: usize = 300;
+
+    pub fn apply_msg(&mut self, msg: FrameTimesMsg) {
+        for sample in msg.0 {
+            if self.samples.len() == Self::HISTORY_LEN {
+                self.samples.pop_front();
+            }
+            self.samples.push_back(sample);
+        }
+    }
+
+    /// The mean time of the recorded frames with and without the
+    /// selection outline passes, if there are frames of that kind
+    fn outline_mean_times(&self) -> (Option<f32>, Option<f32>) {
+        let mean = |outline: bool| {
+            let (sum, count) = self
+                .samples
+                .iter()
+                .filter(|s| s.selection_outline == outline)
+                .fold((0.0, 0), |(sum, count), s| (sum + s.time, count + 1));
+
+            if count > 0 {
+                Some(sum / count as f32)
+            } else {
+                None
+            }
+        };
+
+        (mean(true), mean(false))
+    }
+
+    /// The mean GPU time of each pass, over the frames it was drawn in
+    fn pass_mean_times(&self) -> Vec<(GpuPass, f32)> {
+        GpuPass::ALL
+            .iter()
+            .filter_map(|&pass| {
+                let (sum, count) = self
+                    .samples
+                    .iter()
+                    .filter_map(|s| s.pass_times?.get(pass))
+                    .fold((0.0, 0), |(sum, count), t| (sum + t, count + 1));
+
+                if count > 0 {
+                    Some((pass, sum / count as f32))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Nearest-rank percentile of an already sorted slice
+    fn percentile(sorted: &[f32], p: f32) -> f32 {
+        if sorted.is_empty() {
+            return 0.0;
+        }
+        let rank = ((p / 100.0) * sorted.len() as f32).ceil() as usize;
+        sorted[rank.clamp(1, sorted.len()) - 1]
+    }
+
+    pub fn ui(
+        &self,
+        ctx: &egui::CtxRef,
+        open: &mut bool,
+    ) -> Option<egui::InnerResponse<Option<()>>> {
+        egui::Window::new("Performance")
+            .id(egui::Id::new(Self::ID))
+            .open(open)
+            .show(ctx, |ui| {
+                if self.samples.is_empty() {
+                    ui.label("No frames recorded yet");
+                    return;
+                }
+
+                let mut sorted =
+                    self.samples.iter().map(|s| s.time).collect::<Vec<_>>();
+                sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+                let ms = |t: f32| t * 1000.0;
+
+                egui::Grid::new("frame_times_stats").show(ui, |ui| {
+                    ui.label("Min");
+                    ui.label(format!("{:.2} ms", ms(sorted[0])));
+                    ui.end_row();
+
+                    ui.label("Max");
+                    ui.label(format!("{:.2} ms", ms(sorted[sorted.len() - 1])));
+                    ui.end_row();
+
+                    for &p in &[50.0, 95.0, 99.0] {
+                        ui.label(format!("p{}", p));
+                        let val = Self::percentile(&sorted, p);
+                        ui.label(format!("{:.2} ms", ms(val)));
+                        ui.end_row();
+                    }
+                });
+
+                let values =
+                    self.samples.iter().enumerate().map(|(ix, s)| {
+                        Value::new(ix as f64, ms(s.time) as f64)
+                    });
+
+                let line = Line::new(Values::from_values_iter(values));
+
+                let plot = Plot::new("frame_times_plot")
+                    .line(line)
+                    .include_x(0.0)
+                    .include_x(Self::HISTORY_LEN as f64)
+                    .include_y(0.0)
+                    .height(150.0);
+
+                ui.add(plot);
+
+                ui.label(format!("Last {} frames, in ms", self.samples.len()));
+
+                ui.separator();
+
+                let (with_outline, without_outline) = self.outline_mean_times();
+
+                egui::Grid::new("frame_times_outline").show(ui, |ui| {
+                    let mean_label = |mean: Option<f32>| {
+                        mean.map(|t| format!("{:.2} ms", ms(t)))
+                            .unwrap_or_else(|| "-".to_string())
+                    };
+
+                    ui.label("Mean with selection outline");
+                    ui.label(mean_label(with_outline));
+                    ui.end_row();
+
+                    ui.label("Mean without selection outline");
+                    ui.label(mean_label(without_outline));
+                    ui.end_row();
+
+                    if let (Some(with), Some(without)) =
+                        (with_outline, without_outline)
+                    {
+                        ui.label("Saved by skipping the outline");
+                        ui.label(format!("{:.2} ms", ms(with - without)));
+                        ui.end_row();
+                    }
+                });
+
+                let pass_times = self.pass_mean_times();
+
+                if !pass_times.is_empty() {
+                    ui.separator();
+
+                    ui.label("Mean GPU time per pass");
+
+                    // the pass times are already in ms
+                    egui::Grid::new("frame_times_passes").show(ui, |ui| {
+                        for (pass, time) in pass_times {
+                            ui.label(pass.name());
+                            ui.label(format!("{:.2} ms", time));
+                            ui.end_row();
+                        }
+                    });
+                }
+            })
+    }
+}
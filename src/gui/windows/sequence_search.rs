@@ -0,0 +1,223 @@
+#[allow(unused_imports)]
+use handlegraph::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    mutablehandlegraph::*,
+    packed::*,
+    pathhandlegraph::*,
+};
+
+use crossbeam::channel::Sender;
+
+use crate::{
+    app::AppMsg,
+    context::ContextMgr,
+    graph_query::SequenceMatch,
+    gui::util::{grid_row_label, ColumnWidths},
+    reactor::{Host, Outbox, Reactor},
+};
+
+#[derive(Debug, Clone)]
+struct SearchQuery {
+    query: Vec<u8>,
+    reverse_complement: bool,
+}
+
+type SearchResult = (Vec<SequenceMatch>, bool);
+
+/// Window for finding the nodes whose sequences contain a given
+/// substring. The search runs on the reactor's thread pool.
+pub struct SequenceSearch {
+    query: String,
+    reverse_complement: bool,
+
+    search_host: Host<SearchQuery, SearchResult>,
+    searching: bool,
+
+    results: Vec<SequenceMatch>,
+    truncated: bool,
+
+    page: usize,
+
+    col_widths: ColumnWidths<3>,
+}
+
+impl SequenceSearch {
+    const ID: &'static str = "sequence_search_window";
+
+    /// Searches are capped to this many results, so that a query that
+    /// matches (nearly) every node doesn't exhaust memory
+    pub const MAX_RESULTS: usize = 10_000;
+
+    const PAGE_SIZE: usize = 25;
+
+    pub fn new(reactor: &Reactor) -> Self {
+        let graph_query = reactor.graph_query.clone();
+
+        let search_host = reactor.create_host(
+            move |_outbox: &Outbox<SearchResult>, query: SearchQuery| {
+                graph_query.find_sequence(
+                    &query.query,
+                    query.reverse_complement,
+                    Self::MAX_RESULTS,
+                )
+            },
+        );
+
+        Self {
+            query: String::new(),
+            reverse_complement: false,
+
+            search_host,
+            searching: false,
+
+            results: Vec::new(),
+            truncated: false,
+
+            page: 0,
+
+            col_widths: Default::default(),
+        }
+    }
+
+    fn page_count(&self) -> usize {
+        let len = self.results.len();
+        (len / Self::PAGE_SIZE) + (len % Self::PAGE_SIZE != 0) as usize
+    }
+
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        open: &mut bool,
+        app_msg_tx: &Sender<AppMsg>,
+        ctx_mgr: &ContextMgr,
+    ) -> Option<egui::InnerResponse<Option<()>>> {
+        if let Some((results, truncated)) = self.search_host.take() {
+            self.results = results;
+            self.truncated = truncated;
+            self.page = 0;
+            self.searching = false;
+        }
+
+        egui::Window::new("Sequence search")
+            .id(egui::Id::new(Self::ID))
+            .default_pos(egui::Pos2::new(300.0, 200.0))
+            .open(open)
+            .show(ctx, |ui| {
+                let search = ui.horizontal(|ui| {
+                    let text_box = ui.text_edit_singleline(&mut self.query);
+                    let button = ui.button("Search");
+
+                    (text_box.lost_focus()
+                        && ui.input().key_pressed(egui::Key::Enter))
+                        || button.clicked()
+                });
+
+                ui.checkbox(
+                    &mut self.reverse_complement,
+                    "Include reverse complement",
+                );
+
+                let query = self.query.trim();
+
+                if search.inner && !query.is_empty() && !self.searching {
+                    let query = SearchQuery {
+                        query: query.as_bytes().to_vec(),
+                        reverse_complement: self.reverse_complement,
+                    };
+
+                    if self.search_host.call(query).is_ok() {
+                        self.searching = true;
+                    }
+                }
+
+                ui.separator();
+
+                if self.searching {
+                    ui.label("Searching...");
+                    return;
+                }
+
+                if self.truncated {
+                    ui.label(format!(
+                        "Showing the first {} matches",
+                        self.results.len()
+                    ));
+                } else {
+                    ui.label(format!("{} matches", self.results.len()));
+                }
+
+                let page_count = self.page_count();
+
+                ui.horizontal(|ui| {
+                    if ui.button("Prev").clicked() && self.page > 0 {
+                        self.page -= 1;
+                    }
+
+                    if ui.button("Next").clicked() && self.page + 1 < page_count
+                    {
+                        self.page += 1;
+                    }
+
+                    ui.label(format!(
+                        "Page {}/{}",
+                        (self.page + 1).min(page_count),
+                        page_count
+                    ));
+                });
+
+                let [w0, w1, w2] = self.col_widths.get();
+
+                egui::Grid::new("sequence_search_results")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let inner = grid_row_label(
+                            ui,
+                            egui::Id::new("sequence_search_results_header"),
+                            &["Node", "Offset", "Strand"],
+                            false,
+                            Some(&[w0, w1, w2]),
+                        );
+                        self.col_widths.set_hdr(&inner.inner);
+
+                        let page = self
+                            .results
+                            .iter()
+                            .enumerate()
+                            .skip(self.page * Self::PAGE_SIZE)
+                            .take(Self::PAGE_SIZE);
+
+                        for (ix, result) in page {
+                            let node = format!("{}", result.node.0);
+                            let offset = format!("{}", result.offset);
+                            let strand = if result.reverse { "-" } else { "+" };
+
+                            let fields: [&str; 3] = [&node, &offset, strand];
+
+                            let inner = grid_row_label(
+                                ui,
+                                egui::Id::new(ui.id().with(ix)),
+                                &fields,
+                                false,
+                                Some(&[w0, w1, w2]),
+                            );
+
+                            self.col_widths.set(&inner.inner);
+
+                            let row = inner.response;
+
+                            if row.clicked() {
+                                app_msg_tx
+                                    .send(AppMsg::goto_node(result.node))
+                                    .unwrap();
+                            }
+
+                            if row.hovered() {
+                                let node = result.node;
+                                ctx_mgr.produce_context(|| node);
+                            }
+                        }
+                    });
+            })
+    }
+}
@@ -3,10 +3,12 @@ use crate::{
     geometry::Point,
 };
 
+pub mod controls;
 pub mod debug;
 pub mod gui;
 pub mod main_view;
 
+use controls::*;
 use debug::*;
 use gui::*;
 use main_view::*;
@@ -17,11 +19,13 @@ pub struct SettingsWindow {
     pub(crate) debug: DebugSettings,
     pub(crate) gui: GuiSettings,
     pub(crate) main_view: MainViewSettings,
+    pub(crate) controls: ControlsSettings,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
 enum SettingsTab {
     MainView,
+    Controls,
     Debug,
     Gui,
 }
@@ -41,6 +45,7 @@ impl SettingsWindow {
             debug: Default::default(),
             gui: Default::default(),
             main_view,
+            controls: ControlsSettings::new(settings),
         }
     }
 
@@ -61,6 +66,11 @@ impl SettingsWindow {
                         SettingsTab::MainView,
                         "Main View",
                     );
+                    ui.selectable_value(
+                        &mut self.current_tab,
+                        SettingsTab::Controls,
+                        "Controls",
+                    );
                     ui.selectable_value(
                         &mut self.current_tab,
                         SettingsTab::Gui,
@@ -77,6 +87,9 @@ impl SettingsWindow {
                     SettingsTab::MainView => {
                         self.main_view.ui(ui);
                     }
+                    SettingsTab::Controls => {
+                        self.controls.ui(ui);
+                    }
                     SettingsTab::Debug => {
                         self.debug.ui(ui);
                     }
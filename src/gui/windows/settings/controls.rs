@@ -0,0 +1,67 @@
+use crossbeam::atomic::AtomicCell;
+use std::sync::Arc;
+
+use crate::app::{AppSettings, ViewControls};
+
+pub struct ControlsSettings {
+    view_controls: Arc<AtomicCell<ViewControls>>,
+}
+
+impl ControlsSettings {
+    pub fn new(settings: &AppSettings) -> Self {
+        Self {
+            view_controls: settings.view_controls().clone(),
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let prev_controls = self.view_controls.load();
+        let mut controls = prev_controls;
+
+        ui.add(
+            egui::Slider::new::<f32>(&mut controls.pan_speed, 0.1..=10.0)
+                .logarithmic(true)
+                .text("Pan speed"),
+        )
+        .on_hover_text("Multiplier for keyboard and mouse panning");
+
+        ui.add(
+            egui::Slider::new::<f32>(&mut controls.zoom_speed, 0.1..=10.0)
+                .logarithmic(true)
+                .text("Zoom speed"),
+        )
+        .on_hover_text("Multiplier for the mouse wheel zoom step");
+
+        ui.checkbox(&mut controls.invert_zoom, "Invert zoom direction")
+            .on_hover_text("Reverse the mouse wheel, e.g. for trackpads");
+
+        ui.add(
+            egui::Slider::new::<f32>(&mut controls.min_scale, 0.001..=1.0)
+                .logarithmic(true)
+                .text("Min view scale"),
+        )
+        .on_hover_text("The closest the view can zoom in");
+
+        ui.add(
+            egui::Slider::new::<f32>(&mut controls.max_zoom_out, 1.0..=100.0)
+                .logarithmic(true)
+                .text("Max zoom out"),
+        )
+        .on_hover_text(
+            "How far the view can zoom out, relative to fitting the whole \
+             graph on screen",
+        );
+
+        if ui.button("Reset to defaults").clicked() {
+            controls = ViewControls::default();
+        }
+
+        if controls != prev_controls {
+            self.view_controls.store(controls);
+
+            if let Err(err) = controls.store() {
+                log::warn!("Error storing view controls: {:?}", err);
+            }
+        }
+    }
+}
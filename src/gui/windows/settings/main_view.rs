@@ -1,17 +1,38 @@
+use ash::vk;
 use crossbeam::atomic::AtomicCell;
 use std::sync::Arc;
 
 use crate::{
     app::{AppSettings, NodeWidth},
-    vulkan::draw_system::edges::EdgesUBO,
+    vulkan::draw_system::{
+        edges::EdgesUBO, nodes::NodeFocusMode, selection::OutlineStyle,
+    },
 };
 
 pub struct MainViewSettings {
     node_width: Arc<NodeWidth>,
     label_radius: Arc<AtomicCell<f32>>,
+    node_id_labels: Arc<AtomicCell<Option<f32>>>,
 
     edges_enabled: Arc<AtomicCell<bool>>,
     edges_ubo: Arc<AtomicCell<EdgesUBO>>,
+    edge_cull_scale: Arc<AtomicCell<Option<f32>>>,
+
+    node_culling: Arc<AtomicCell<bool>>,
+    node_lod_threshold: Arc<AtomicCell<Option<f32>>>,
+    selection_outline: Arc<AtomicCell<bool>>,
+    selection_outline_style: Arc<AtomicCell<OutlineStyle>>,
+
+    node_focus_mode: Arc<AtomicCell<NodeFocusMode>>,
+
+    background_color_override: Arc<AtomicCell<Option<rgb::RGB<f32>>>>,
+    follow_system_theme: Arc<AtomicCell<bool>>,
+
+    msaa_samples: Arc<AtomicCell<vk::SampleCountFlags>>,
+
+    idle_fps: Arc<AtomicCell<Option<u32>>>,
+
+    relaxation_steps: Arc<AtomicCell<usize>>,
 }
 
 impl MainViewSettings {
@@ -21,15 +42,133 @@ impl MainViewSettings {
     ) -> Self {
         let node_width = settings.node_width().clone();
         let label_radius = settings.label_radius().clone();
+        let node_id_labels = settings.node_id_labels().clone();
 
         let edges_ubo = settings.edge_renderer().clone();
+        let edge_cull_scale = settings.edge_cull_scale().clone();
+
+        let node_culling = settings.node_culling().clone();
+        let node_lod_threshold = settings.node_lod_threshold().clone();
+        let selection_outline = settings.selection_outline().clone();
+        let selection_outline_style =
+            settings.selection_outline_style().clone();
+
+        let node_focus_mode = settings.node_focus_mode().clone();
+
+        let background_color_override =
+            settings.background_color_override().clone();
+        let follow_system_theme = settings.follow_system_theme().clone();
+
+        let msaa_samples = settings.msaa_samples().clone();
+
+        let idle_fps = settings.idle_fps().clone();
+
+        let relaxation_steps = settings.relaxation_steps().clone();
 
         Self {
             node_width,
             label_radius,
+            node_id_labels,
 
             edges_enabled,
             edges_ubo,
+            edge_cull_scale,
+
+            node_culling,
+            node_lod_threshold,
+            selection_outline,
+            selection_outline_style,
+
+            node_focus_mode,
+
+            background_color_override,
+            follow_system_theme,
+
+            msaa_samples,
+
+            idle_fps,
+
+            relaxation_steps,
+        }
+    }
+
+    /// Colors closer than this in relative luminance are considered
+    /// too similar to tell apart
+    const MIN_LUMINANCE_DIFF: f32 = 0.15;
+
+    fn luminance(color: rgb::RGB<f32>) -> f32 {
+        0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b
+    }
+
+    fn background_color_ui(&self, ui: &mut egui::Ui, outline: OutlineStyle) {
+        let mut follow_system = self.follow_system_theme.load();
+
+        if ui
+            .checkbox(&mut follow_system, "Follow system theme")
+            .on_hover_text(
+                "Switch between light and dark mode with the system \
+                 theme; choosing a mode or theme manually turns this off",
+            )
+            .changed()
+        {
+            self.follow_system_theme.store(follow_system);
+        }
+
+        let mut bg_override = self.background_color_override.load();
+
+        let mut use_override = bg_override.is_some();
+
+        let mut color = bg_override
+            .map(|c| [c.r, c.g, c.b])
+            .unwrap_or([0.5, 0.5, 0.5]);
+
+        let (checkbox, picker) = ui
+            .horizontal(|ui| {
+                let checkbox = ui
+                    .checkbox(&mut use_override, "Custom background")
+                    .on_hover_text(
+                        "Use this background color in both light and \
+                         dark mode, instead of the theme's",
+                    );
+
+                let picker =
+                    use_override.then(|| ui.color_edit_button_rgb(&mut color));
+
+                (checkbox, picker)
+            })
+            .inner;
+
+        let picker_changed = picker.map_or(false, |p| p.changed());
+
+        if checkbox.changed() || picker_changed {
+            let [r, g, b] = color;
+            bg_override = use_override.then(|| rgb::RGB::new(r, g, b));
+            self.background_color_override.store(bg_override);
+        }
+
+        if let Some(bg) = bg_override {
+            let bg_lum = Self::luminance(bg);
+
+            let outline_lum = Self::luminance(outline.color.rgb());
+            let edge_lum = Self::luminance(self.edges_ubo.load().edge_color);
+
+            let too_close =
+                |lum: f32| (bg_lum - lum).abs() < Self::MIN_LUMINANCE_DIFF;
+
+            if too_close(outline_lum) {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    "The selection outline is hard to see \
+                     on this background",
+                );
+            }
+
+            if too_close(edge_lum) {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    "Edges are hard to see on this background",
+                );
+            }
         }
     }
 
@@ -100,6 +239,133 @@ impl MainViewSettings {
             self.edges_enabled.store(!edges_enabled);
         }
 
+        let cull_scale = self.edge_cull_scale.load();
+
+        let mut cull_enabled = cull_scale.is_some();
+        let mut max_scale = cull_scale.unwrap_or(100.0);
+
+        let cull_checkbox = ui
+            .checkbox(&mut cull_enabled, "Hide edges when zoomed out")
+            .on_hover_text(
+                "Skip drawing edges when the view scale is above the limit",
+            );
+
+        let cull_slider = ui.add_enabled(
+            cull_enabled,
+            egui::Slider::new::<f32>(&mut max_scale, 1.0..=10000.0)
+                .logarithmic(true)
+                .text("Edge scale limit"),
+        );
+
+        if cull_checkbox.changed() || cull_slider.changed() {
+            self.edge_cull_scale
+                .store(Some(max_scale).filter(|_| cull_enabled));
+        }
+
+        let mut node_culling = self.node_culling.load();
+
+        let node_culling_checkbox = ui
+            .checkbox(&mut node_culling, "Cull off-screen nodes")
+            .on_hover_text(
+                "Skip nodes outside the view in a compute pass before drawing",
+            );
+
+        if node_culling_checkbox.changed() {
+            self.node_culling.store(node_culling);
+        }
+
+        let lod_threshold = self.node_lod_threshold.load();
+
+        let mut lod_enabled = lod_threshold.is_some();
+        let mut lod_px = lod_threshold.unwrap_or(1.0);
+
+        let lod_checkbox = ui
+            .checkbox(&mut lod_enabled, "Reduce detail when zoomed out")
+            .on_hover_text(
+                "Draw one node per grid cell while the cells are smaller \
+                 than the threshold on screen",
+            );
+
+        let lod_slider = ui.add_enabled(
+            lod_enabled,
+            egui::Slider::new::<f32>(&mut lod_px, 0.25..=16.0)
+                .logarithmic(true)
+                .text("Detail threshold (px)"),
+        );
+
+        if lod_checkbox.changed() || lod_slider.changed() {
+            self.node_lod_threshold
+                .store(Some(lod_px).filter(|_| lod_enabled));
+        }
+
+        let mut selection_outline = self.selection_outline.load();
+
+        let selection_outline_checkbox = ui
+            .checkbox(&mut selection_outline, "Outline selected nodes")
+            .on_hover_text(
+                "Draw the selection outline passes; they are always \
+                 skipped when nothing is selected",
+            );
+
+        if selection_outline_checkbox.changed() {
+            self.selection_outline.store(selection_outline);
+        }
+
+        let mut outline_style = self.selection_outline_style.load();
+
+        let mut outline_color = [
+            outline_style.color.r,
+            outline_style.color.g,
+            outline_style.color.b,
+            outline_style.color.a,
+        ];
+
+        let outline_color_picker = ui
+            .horizontal(|ui| {
+                ui.label("Outline color");
+                ui.color_edit_button_rgba_unmultiplied(&mut outline_color)
+            })
+            .inner;
+
+        let outline_radius_slider = ui
+            .add(
+                egui::Slider::new::<f32>(
+                    &mut outline_style.radius,
+                    1.0..=OutlineStyle::MAX_RADIUS,
+                )
+                .step_by(1.0)
+                .text("Outline blur radius"),
+            )
+            .on_hover_text("Wider blurs give a thicker outline. Default: 1");
+
+        if outline_color_picker.changed() || outline_radius_slider.changed() {
+            let [r, g, b, a] = outline_color;
+            outline_style.color = rgb::RGBA::new(r, g, b, a);
+
+            self.selection_outline_style.store(outline_style);
+        }
+
+        let mut focus_mode = self.node_focus_mode.load();
+
+        ui.horizontal(|ui| {
+            ui.label("Unselected nodes")
+                .on_hover_text("Dim or hide the nodes outside the selection");
+
+            for &mode in [
+                NodeFocusMode::Normal,
+                NodeFocusMode::DimOthers,
+                NodeFocusMode::HideOthers,
+            ]
+            .iter()
+            {
+                ui.radio_value(&mut focus_mode, mode, mode.name());
+            }
+        });
+
+        self.node_focus_mode.store(focus_mode);
+
+        self.background_color_ui(ui, outline_style);
+
         if min_node_width_slider.changed() {
             self.node_width.set_min_node_width(min_width);
         }
@@ -116,6 +382,29 @@ impl MainViewSettings {
             self.node_width.set_max_node_scale(max_scale);
         }
 
+        let min_pixels = self.node_width.min_node_pixels();
+
+        let mut fixed_width = min_pixels.is_some();
+        let mut pixels = min_pixels.unwrap_or(2.0);
+
+        let fixed_width_checkbox = ui
+            .checkbox(&mut fixed_width, "Minimum node width in pixels")
+            .on_hover_text(
+                "Keep nodes visible when zoomed out by never drawing them \
+                 thinner than the given width",
+            );
+
+        let pixels_slider = ui.add_enabled(
+            fixed_width,
+            egui::Slider::new::<f32>(&mut pixels, 1.0..=10.0)
+                .text("Min node width (px)"),
+        );
+
+        if fixed_width_checkbox.changed() || pixels_slider.changed() {
+            self.node_width
+                .set_min_node_pixels(Some(pixels).filter(|_| fixed_width));
+        }
+
         let mut label_radius = self.label_radius.load();
 
         let label_radius_slider = ui.add(
@@ -126,5 +415,83 @@ impl MainViewSettings {
         if label_radius_slider.changed() {
             self.label_radius.store(label_radius);
         }
+
+        let id_labels = self.node_id_labels.load();
+
+        let mut id_labels_enabled = id_labels.is_some();
+        let mut min_node_px = id_labels.unwrap_or(50.0);
+
+        let id_labels_checkbox = ui
+            .checkbox(&mut id_labels_enabled, "Show node IDs when zoomed in")
+            .on_hover_text(
+                "Draw the ID of each node that is long enough on screen",
+            );
+
+        let id_labels_slider = ui.add_enabled(
+            id_labels_enabled,
+            egui::Slider::new::<f32>(&mut min_node_px, 10.0..=1000.0)
+                .logarithmic(true)
+                .text("Min node length for IDs (px)"),
+        );
+
+        if id_labels_checkbox.changed() || id_labels_slider.changed() {
+            self.node_id_labels
+                .store(Some(min_node_px).filter(|_| id_labels_enabled));
+        }
+
+        let mut msaa_samples = self.msaa_samples.load();
+
+        egui::ComboBox::from_label("Antialiasing (MSAA)")
+            .selected_text(format!("{}x", msaa_samples.as_raw()))
+            .show_ui(ui, |ui| {
+                for &count in &[1, 2, 4, 8, 16] {
+                    ui.selectable_value(
+                        &mut msaa_samples,
+                        vk::SampleCountFlags::from_raw(count),
+                        format!("{}x", count),
+                    );
+                }
+            });
+
+        if msaa_samples != self.msaa_samples.load() {
+            self.msaa_samples.store(msaa_samples);
+        }
+
+        let idle_fps = self.idle_fps.load();
+
+        let mut cap_enabled = idle_fps.is_some();
+        let mut fps = idle_fps.unwrap_or(30);
+
+        let cap_checkbox = ui
+            .checkbox(&mut cap_enabled, "Limit frame rate when idle")
+            .on_hover_text(
+                "Draw fewer frames while the view isn't moving, to reduce \
+                 GPU and power usage",
+            );
+
+        let fps_slider = ui.add_enabled(
+            cap_enabled,
+            egui::Slider::new::<u32>(&mut fps, 1..=120).text("Idle FPS"),
+        );
+
+        if cap_checkbox.changed() || fps_slider.changed() {
+            self.idle_fps.store(Some(fps).filter(|_| cap_enabled));
+        }
+
+        let mut relaxation_steps = self.relaxation_steps.load();
+
+        let relaxation_slider = ui
+            .add(
+                egui::Slider::new::<usize>(&mut relaxation_steps, 1..=64)
+                    .text("Layout relaxation steps"),
+            )
+            .on_hover_text(
+                "The number of relaxation iterations run per dispatch \
+                 while the layout relaxation is active. Default: 4",
+            );
+
+        if relaxation_slider.changed() {
+            self.relaxation_steps.store(relaxation_steps.max(1));
+        }
     }
 }
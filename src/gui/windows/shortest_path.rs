@@ -0,0 +1,314 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+#[allow(unused_imports)]
+use handlegraph::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    packedgraph::PackedGraph,
+};
+
+use crossbeam::{atomic::AtomicCell, channel::Sender};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    app::{AppMsg, Select},
+    asynchronous::AsyncResult,
+    graph_query::{GraphQuery, GraphQueryWorker},
+    reactor::Reactor,
+};
+
+/// The outcome of a shortest path search between two nodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSearch {
+    /// The nodes on the path, from the start to the end node, and
+    /// their total sequence length
+    Found { nodes: Vec<NodeId>, base_len: usize },
+    /// The nodes are in different components
+    NoPath { visited: usize },
+    /// The search visited `visited` nodes without reaching the end
+    BudgetExceeded { visited: usize },
+}
+
+impl PathSearch {
+    /// Find a path with the fewest nodes from `from` to `to` with a
+    /// breadth-first search, giving up after visiting `max_visits`
+    /// nodes. Edges are followed in either direction, ignoring the
+    /// node orientations.
+    pub fn run(
+        graph_query: &GraphQuery,
+        from: NodeId,
+        to: NodeId,
+        max_visits: usize,
+    ) -> Self {
+        let graph = graph_query.graph();
+
+        // the node each visited node was first reached from
+        let mut parents: FxHashMap<NodeId, NodeId> = FxHashMap::default();
+        parents.insert(from, from);
+
+        let mut queue: VecDeque<NodeId> = VecDeque::new();
+        queue.push_back(from);
+
+        while let Some(node) = queue.pop_front() {
+            if node == to {
+                return Self::trace_back(graph_query, &parents, from, to);
+            }
+
+            if parents.len() >= max_visits {
+                return Self::BudgetExceeded {
+                    visited: parents.len(),
+                };
+            }
+
+            let handle = Handle::pack(node, false);
+
+            let neighbors = graph
+                .neighbors(handle, Direction::Left)
+                .chain(graph.neighbors(handle, Direction::Right));
+
+            for other in neighbors {
+                let other = other.id();
+
+                if !parents.contains_key(&other) {
+                    parents.insert(other, node);
+                    queue.push_back(other);
+                }
+            }
+        }
+
+        Self::NoPath {
+            visited: parents.len(),
+        }
+    }
+
+    fn trace_back(
+        graph_query: &GraphQuery,
+        parents: &FxHashMap<NodeId, NodeId>,
+        from: NodeId,
+        to: NodeId,
+    ) -> Self {
+        let mut nodes = vec![to];
+        let mut node = to;
+
+        while node != from {
+            node = parents[&node];
+            nodes.push(node);
+        }
+
+        nodes.reverse();
+
+        let base_len = nodes
+            .iter()
+            .map(|&id| graph_query.node_len(Handle::pack(id, false)))
+            .sum();
+
+        Self::Found { nodes, base_len }
+    }
+}
+
+/// Window for finding the shortest path between two nodes, which is
+/// then selected. The search runs on the thread pool.
+pub struct ShortestPath {
+    worker: GraphQueryWorker,
+
+    /// The node open in the node details window
+    node_details_id: Arc<AtomicCell<Option<NodeId>>>,
+
+    endpoints: [String; 2],
+    max_visits: usize,
+
+    result: Option<AsyncResult<PathSearch>>,
+    search: Option<PathSearch>,
+    error: Option<String>,
+}
+
+impl ShortestPath {
+    const ID: &'static str = "shortest_path_window";
+
+    pub fn new(
+        reactor: &Reactor,
+        node_details_id: Arc<AtomicCell<Option<NodeId>>>,
+    ) -> Self {
+        let worker = GraphQueryWorker::new(
+            reactor.graph_query.clone(),
+            reactor.thread_pool.clone(),
+        );
+
+        Self {
+            worker,
+
+            node_details_id,
+
+            endpoints: [String::new(), String::new()],
+            max_visits: 1_000_000,
+
+            result: None,
+            search: None,
+            error: None,
+        }
+    }
+
+    fn parse_endpoints(
+        &self,
+        graph: &PackedGraph,
+    ) -> std::result::Result<[NodeId; 2], String> {
+        let parse = |text: &str| {
+            let text = text.trim();
+            text.parse::<u64>()
+                .ok()
+                .map(NodeId::from)
+                .filter(|&id| graph.has_node(id))
+                .ok_or_else(|| {
+                    format!("\"{}\" is not a node in the graph", text)
+                })
+        };
+
+        Ok([parse(&self.endpoints[0])?, parse(&self.endpoints[1])?])
+    }
+
+    fn search(&mut self, from: NodeId, to: NodeId) {
+        let max_visits = self.max_visits;
+
+        let result =
+            self.worker
+                .run_query(move |graph: Arc<GraphQuery>| async move {
+                    PathSearch::run(&graph, from, to, max_visits)
+                });
+
+        self.search = None;
+        self.result = Some(result);
+    }
+
+    pub fn ui(
+        &mut self,
+        ctx: &egui::CtxRef,
+        open: &mut bool,
+        graph_query: &GraphQuery,
+        app_msg_tx: &Sender<AppMsg>,
+    ) -> Option<egui::InnerResponse<Option<()>>> {
+        if let Some(result) = self.result.as_mut() {
+            if let Some(search) = result.take_result_if_ready() {
+                if let PathSearch::Found { nodes, .. } = &search {
+                    let nodes = nodes.iter().copied().collect();
+                    app_msg_tx
+                        .send(AppMsg::Selection(Select::Many {
+                            nodes,
+                            clear: true,
+                        }))
+                        .unwrap();
+                }
+
+                self.search = Some(search);
+                self.result = None;
+            }
+        }
+
+        let open_node = self.node_details_id.load();
+
+        let mut find = false;
+
+        let resp = egui::Window::new("Shortest path")
+            .id(egui::Id::new(Self::ID))
+            .default_pos(egui::Pos2::new(600.0, 200.0))
+            .open(open)
+            .show(ctx, |ui| {
+                egui::Grid::new("shortest_path_endpoints").show(ui, |ui| {
+                    for (label, text) in
+                        ["From", "To"].iter().zip(self.endpoints.iter_mut())
+                    {
+                        ui.label(*label);
+                        ui.text_edit_singleline(text);
+
+                        if ui
+                            .add_enabled(
+                                open_node.is_some(),
+                                egui::Button::new("Use open node"),
+                            )
+                            .on_hover_text(
+                                "Use the node shown in the node details window",
+                            )
+                            .clicked()
+                        {
+                            if let Some(node) = open_node {
+                                *text = node.0.to_string();
+                            }
+                        }
+
+                        ui.end_row();
+                    }
+                });
+
+                ui.add(
+                    egui::DragValue::new(&mut self.max_visits)
+                        .clamp_range(1..=usize::MAX)
+                        .speed(1000.0)
+                        .prefix("Max. nodes visited: "),
+                )
+                .on_hover_text("Stop searching after visiting this many nodes");
+
+                let running = self.result.is_some();
+
+                find = ui
+                    .add_enabled(!running, egui::Button::new("Find path"))
+                    .clicked();
+
+                ui.separator();
+
+                if running {
+                    ui.label("Searching...");
+                    return;
+                }
+
+                if let Some(error) = &self.error {
+                    ui.label(error);
+                    return;
+                }
+
+                match &self.search {
+                    Some(PathSearch::Found { nodes, base_len }) => {
+                        ui.label(format!(
+                            "{} hops, {} nodes, {} bp",
+                            nodes.len() - 1,
+                            nodes.len(),
+                            base_len
+                        ));
+
+                        if ui.button("Zoom to path").clicked() {
+                            app_msg_tx
+                                .send(AppMsg::goto_nodes(nodes.clone()))
+                                .unwrap();
+                        }
+                    }
+                    Some(PathSearch::NoPath { visited }) => {
+                        ui.label(format!(
+                            "No path, the nodes are in different \
+                             components ({} nodes visited)",
+                            visited
+                        ));
+                    }
+                    Some(PathSearch::BudgetExceeded { visited }) => {
+                        ui.label(format!(
+                            "Gave up after visiting {} nodes",
+                            visited
+                        ));
+                    }
+                    None => (),
+                }
+            });
+
+        if find {
+            match self.parse_endpoints(graph_query.graph()) {
+                Ok([from, to]) => {
+                    self.error = None;
+                    self.search(from, to);
+                }
+                Err(error) => {
+                    self.error = Some(error);
+                }
+            }
+        }
+
+        resp
+    }
+}
@@ -0,0 +1,189 @@
+use crossbeam::atomic::AtomicCell;
+use std::sync::Arc;
+
+use crate::{
+    app::{AppSettings, OverlayState, SharedState, Theme},
+    vulkan::draw_system::edges::EdgesUBO,
+};
+
+/// Lists the built-in and imported themes, and applies the selected
+/// one by updating the shared settings that the renderers read each
+/// frame.
+pub struct ThemeList {
+    themes: Vec<Theme>,
+
+    dark_mode: Arc<AtomicCell<bool>>,
+    follow_system_theme: Arc<AtomicCell<bool>>,
+    background_color_light: Arc<AtomicCell<rgb::RGB<f32>>>,
+    background_color_dark: Arc<AtomicCell<rgb::RGB<f32>>>,
+    edges_ubo: Arc<AtomicCell<EdgesUBO>>,
+    overlay_state: OverlayState,
+
+    path_input: String,
+    file_result: Option<Result<String, String>>,
+}
+
+impl ThemeList {
+    pub const ID: &'static str = "theme_list_window";
+
+    pub fn new(settings: &AppSettings, shared_state: &SharedState) -> Self {
+        Self {
+            themes: Theme::presets(),
+
+            dark_mode: shared_state.dark_mode().clone(),
+            follow_system_theme: settings.follow_system_theme().clone(),
+            background_color_light: settings.background_color_light().clone(),
+            background_color_dark: settings.background_color_dark().clone(),
+            edges_ubo: settings.edge_renderer().clone(),
+            overlay_state: shared_state.overlay_state().clone(),
+
+            path_input: String::new(),
+            file_result: None,
+        }
+    }
+
+    /// The theme matching the current settings
+    pub fn current_theme(&self, name: &str) -> Theme {
+        let dark = self.dark_mode.load();
+
+        let bg = if dark {
+            self.background_color_dark.load()
+        } else {
+            self.background_color_light.load()
+        };
+
+        let edge = self.edges_ubo.load().edge_color;
+
+        Theme {
+            name: name.to_string(),
+            dark,
+            background: [bg.r, bg.g, bg.b],
+            edge_color: [edge.r, edge.g, edge.b],
+            gradient: self.overlay_state.gradient(),
+        }
+    }
+
+    /// Applying a theme stops following the system theme, as it
+    /// picks light or dark mode
+    pub fn apply_theme(&self, theme: &Theme) {
+        self.follow_system_theme.store(false);
+        self.dark_mode.store(theme.dark);
+
+        if theme.dark {
+            self.background_color_dark.store(theme.background_rgb());
+        } else {
+            self.background_color_light.store(theme.background_rgb());
+        }
+
+        let mut edges = self.edges_ubo.load();
+        edges.edge_color = theme.edge_color_rgb();
+        self.edges_ubo.store(edges);
+
+        self.overlay_state.set_gradient(theme.gradient);
+    }
+
+    /// Load a theme from the file in the path input, adding it to
+    /// the list, or replacing the theme with the same name
+    fn import_theme(&mut self) -> Result<Theme, String> {
+        let theme = Theme::load_file(&self.path_input)
+            .map_err(|err| format!("Error loading theme: {}", err))?;
+
+        if let Some(existing) =
+            self.themes.iter_mut().find(|t| t.name == theme.name)
+        {
+            *existing = theme.clone();
+        } else {
+            self.themes.push(theme.clone());
+        }
+
+        Ok(theme)
+    }
+
+    fn export_theme(&self) -> Result<String, String> {
+        let path = std::path::Path::new(&self.path_input);
+
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("Exported");
+
+        let theme = self.current_theme(name);
+
+        theme
+            .save_file(path)
+            .map_err(|err| format!("Error saving theme: {}", err))?;
+
+        Ok(format!("Saved theme to {}", self.path_input))
+    }
+
+    /// Returns the theme that was applied, if any, so that the GUI
+    /// style can be updated to match
+    pub fn ui(&mut self, ctx: &egui::CtxRef, open: &mut bool) -> Option<Theme> {
+        let mut applied: Option<Theme> = None;
+        let mut import = false;
+        let mut export = false;
+
+        egui::Window::new("Themes")
+            .id(egui::Id::new(Self::ID))
+            .open(open)
+            .show(ctx, |ui| {
+                for theme in self.themes.iter() {
+                    if ui.button(&theme.name).clicked() {
+                        applied = Some(theme.clone());
+                    }
+                }
+
+                ui.separator();
+
+                let path_input = &mut self.path_input;
+
+                ui.horizontal(|ui| {
+                    ui.label("Theme file");
+                    ui.text_edit_singleline(path_input);
+                });
+
+                ui.horizontal(|ui| {
+                    let has_path = !path_input.is_empty();
+
+                    import = ui
+                        .add_enabled(has_path, egui::Button::new("Import"))
+                        .clicked();
+
+                    export = ui
+                        .add_enabled(has_path, egui::Button::new("Export"))
+                        .on_hover_text("Save the current colors as a theme")
+                        .clicked();
+                });
+
+                match &self.file_result {
+                    Some(Ok(msg)) | Some(Err(msg)) => {
+                        ui.label(msg);
+                    }
+                    None => (),
+                }
+            });
+
+        if import {
+            match self.import_theme() {
+                Ok(theme) => {
+                    self.file_result =
+                        Some(Ok(format!("Loaded theme {}", theme.name)));
+                    applied = Some(theme);
+                }
+                Err(err) => {
+                    self.file_result = Some(Err(err));
+                }
+            }
+        }
+
+        if export {
+            self.file_result = Some(self.export_theme());
+        }
+
+        if let Some(theme) = applied.as_ref() {
+            self.apply_theme(theme);
+        }
+
+        applied
+    }
+}
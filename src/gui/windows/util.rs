@@ -1,3 +1,11 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::gfa::gfa_path_hash;
+
 pub struct SlotList<T> {
     // display: Box<for<'a> FnMut(&'a egui::Ui, T) -> egui::Response>
     display: Box<dyn Fn(&mut egui::Ui, &T) -> egui::Response>,
@@ -92,3 +100,127 @@ pub fn popup_below_widget(
         }
     }
 }
+
+/// The JSON file in the `dir` subdirectory of the config directory
+/// that stores data for the graph at `gfa_path`, keyed by the stable
+/// hash of the canonical GFA path
+fn gfa_config_file(dir: &str, gfa_path: &Path) -> Option<PathBuf> {
+    let mut path = dirs::config_dir()?;
+    path.push("gfaestus");
+    path.push(dir);
+    path.push(format!("{:016x}.json", gfa_path_hash(gfa_path)));
+    Some(path)
+}
+
+/// A list of entries stored per graph in the config directory, so
+/// that reopening the same graph restores them. Every change is
+/// written to disk immediately.
+pub struct PerGraphStore<T> {
+    entries: Vec<T>,
+    file: Option<PathBuf>,
+    name: &'static str,
+}
+
+impl<T> PerGraphStore<T> {
+    /// An empty store that isn't backed by a file
+    pub fn empty(name: &'static str) -> Self {
+        Self {
+            entries: Vec::new(),
+            file: None,
+            name,
+        }
+    }
+
+    pub fn entries(&self) -> &[T] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<T> PerGraphStore<T>
+where
+    T: Serialize + DeserializeOwned,
+{
+    /// Load the `name` entries stored for the graph at `gfa_path`,
+    /// starting with none if none have been stored yet. `name` is
+    /// also the subdirectory of the config directory they're stored
+    /// in.
+    pub fn load_for_gfa(name: &'static str, gfa_path: &Path) -> Self {
+        let file = gfa_config_file(name, gfa_path);
+
+        let entries = file
+            .as_ref()
+            .filter(|path| path.exists())
+            .and_then(|path| match Self::load_file(path) {
+                Ok(entries) => Some(entries),
+                Err(err) => {
+                    log::warn!("Error loading {}: {:?}", name, err);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self {
+            entries,
+            file,
+            name,
+        }
+    }
+
+    fn load_file(path: &Path) -> Result<Vec<T>> {
+        let file = std::fs::File::open(path)?;
+        let entries = serde_json::from_reader(std::io::BufReader::new(file))?;
+        Ok(entries)
+    }
+
+    fn store(&self) {
+        let path = if let Some(path) = self.file.as_ref() {
+            path
+        } else {
+            return;
+        };
+
+        let result = (|| -> Result<()> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let file = std::fs::File::create(path)?;
+            serde_json::to_writer_pretty(file, &self.entries)?;
+            Ok(())
+        })();
+
+        if let Err(err) = result {
+            log::warn!("Error storing {}: {:?}", self.name, err);
+        }
+    }
+
+    pub fn push(&mut self, entry: T) {
+        self.entries.push(entry);
+        self.store();
+    }
+
+    pub fn remove(&mut self, ix: usize) {
+        if ix < self.entries.len() {
+            self.entries.remove(ix);
+            self.store();
+        }
+    }
+
+    /// Modify the entry at `ix`, if it exists, and store the result
+    pub fn update<F>(&mut self, ix: usize, f: F)
+    where
+        F: FnOnce(&mut T),
+    {
+        if let Some(entry) = self.entries.get_mut(ix) {
+            f(entry);
+            self.store();
+        }
+    }
+}
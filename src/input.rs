@@ -16,8 +16,10 @@ use crate::{app::AppInput, reactor::Reactor};
 use crate::{app::SharedState, geometry::*};
 
 pub mod binds;
+pub mod keymap;
 
 pub use binds::{BindableInput, DigitalState, SystemInputBindings};
+pub use keymap::Keymap;
 
 use binds::*;
 
@@ -172,6 +174,22 @@ impl InputManager {
                             .unwrap();
                     }
                 }
+            } else if let event::WindowEvent::KeyboardInput { input, .. } =
+                winit_ev
+            {
+                // escape also closes popups and menus when no text box
+                // has focus
+                if input.virtual_keycode == Some(VirtualKeyCode::Escape) {
+                    if let Some(event) = winit_to_egui_text_event(
+                        modifiers,
+                        input.state,
+                        VirtualKeyCode::Escape,
+                    ) {
+                        gui_msg_tx
+                            .send(crate::gui::GuiMsg::EguiEvent(event))
+                            .unwrap();
+                    }
+                }
             }
 
             if let Some(app_inputs) =
@@ -188,7 +206,18 @@ impl InputManager {
                 self.gui.bindings.apply(&winit_ev, modifiers, mouse_pos)
             {
                 for input in gui_inputs {
-                    self.gui.tx.send(input).unwrap();
+                    // keys that also type or move the text cursor are
+                    // left to the focused text box, except releases
+                    let text_key = matches!(
+                        input.payload(),
+                        GuiInput::KeyConsoleDown
+                            | GuiInput::KeyPathStepPrev
+                            | GuiInput::KeyPathStepNext
+                    );
+
+                    if !(text_key && gui_wants_keyboard && !input.is_key_up()) {
+                        self.gui.tx.send(input).unwrap();
+                    }
                 }
             }
 
@@ -198,9 +227,13 @@ impl InputManager {
                 .apply(&winit_ev, modifiers, mouse_pos)
             {
                 for input in main_view_inputs {
+                    // releases are always passed on, so that e.g. a
+                    // pan key held while a text box gains focus
+                    // doesn't keep panning
                     if (input.is_keyboard() && !gui_wants_keyboard)
                         || (input.is_mouse() && !mouse_over_gui)
                         || input.is_mouse_up()
+                        || input.is_key_up()
                     {
                         self.main_view.tx.send(input).unwrap();
                     }
@@ -237,9 +270,16 @@ impl InputManager {
 
         let gui_focus_state = shared_state.gui_focus_state.clone();
 
-        let app = SubsystemInput::<AppInput>::from_default_binds();
-        let main_view = SubsystemInput::<MainViewInput>::from_default_binds();
-        let gui = SubsystemInput::<GuiInput>::from_default_binds();
+        let mut app = SubsystemInput::<AppInput>::from_default_binds();
+        let mut main_view =
+            SubsystemInput::<MainViewInput>::from_default_binds();
+        let mut gui = SubsystemInput::<GuiInput>::from_default_binds();
+
+        let keymap = Keymap::load_or_default();
+        keymap.warn_unknown_actions(&Self::bindable_actions());
+        keymap.apply(&mut app.bindings);
+        keymap.apply(&mut main_view.bindings);
+        keymap.apply(&mut gui.bindings);
 
         Self {
             mouse_screen_pos,
@@ -256,6 +296,27 @@ impl InputManager {
             custom_binds: FxHashMap::default(),
         }
     }
+
+    /// The names of all actions that can be bound in the keymap config
+    pub fn bindable_actions() -> Vec<&'static str> {
+        let app = AppInput::bindable_actions().into_iter().map(|(n, _)| n);
+        let main_view = MainViewInput::bindable_actions()
+            .into_iter()
+            .map(|(n, _)| n);
+        let gui = GuiInput::bindable_actions().into_iter().map(|(n, _)| n);
+
+        app.chain(main_view).chain(gui).collect()
+    }
+
+    /// The current bindings of all bindable actions, in the keymap
+    /// config format
+    pub fn keymap_json(&self) -> anyhow::Result<String> {
+        let mut keymap = Keymap::default();
+        keymap.insert_bindings(&self.app.bindings);
+        keymap.insert_bindings(&self.main_view.bindings);
+        keymap.insert_bindings(&self.gui.bindings);
+        keymap.to_json()
+    }
 }
 
 fn received_char_to_egui_text(c: char) -> egui::Event {
@@ -21,6 +21,10 @@ impl<T> InputPayload for T where
 /// Trait for app subsystem inputs that can be bound to keys and other user input
 pub trait BindableInput: InputPayload {
     fn default_binds() -> SystemInputBindings<Self>;
+
+    /// The inputs that can be rebound in the keymap config, with the
+    /// action names used there
+    fn bindable_actions() -> Vec<(&'static str, Self)>;
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
@@ -200,6 +204,14 @@ impl<T: InputPayload> SystemInput<T> {
             SystemInput::Wheel { .. } => false,
         }
     }
+
+    pub fn is_key_up(&self) -> bool {
+        match self {
+            SystemInput::Keyboard { state, .. } => state.released(),
+            SystemInput::MouseButton { .. } => false,
+            SystemInput::Wheel { .. } => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -228,6 +240,67 @@ impl<Inputs: InputPayload> SystemInputBindings<Inputs> {
         }
     }
 
+    /// Remove every key and mouse button bind to `payload`
+    pub fn unbind(&mut self, payload: Inputs) {
+        for binds in self.key_binds.values_mut() {
+            binds.retain(|bind| bind.payload != payload);
+        }
+        self.key_binds.retain(|_, binds| !binds.is_empty());
+
+        for binds in self.mouse_binds.values_mut() {
+            binds.retain(|bind| bind.payload != payload);
+        }
+        self.mouse_binds.retain(|_, binds| !binds.is_empty());
+    }
+
+    pub fn bind_key(
+        &mut self,
+        key: event::VirtualKeyCode,
+        bind: KeyBind<Inputs>,
+    ) {
+        self.key_binds.entry(key).or_default().push(bind);
+    }
+
+    pub fn bind_mouse_button(
+        &mut self,
+        button: event::MouseButton,
+        bind: MouseButtonBind<Inputs>,
+    ) {
+        self.mouse_binds.entry(button).or_default().push(bind);
+    }
+
+    /// The keys bound to `payload`, and their modifiers
+    pub fn key_binds_for(
+        &self,
+        payload: Inputs,
+    ) -> Vec<(event::VirtualKeyCode, event::ModifiersState)> {
+        self.key_binds
+            .iter()
+            .flat_map(|(key, binds)| {
+                binds
+                    .iter()
+                    .filter(move |bind| bind.payload == payload)
+                    .map(move |bind| (*key, bind.modifiers))
+            })
+            .collect()
+    }
+
+    /// The mouse buttons bound to `payload`, and their modifiers
+    pub fn mouse_binds_for(
+        &self,
+        payload: Inputs,
+    ) -> Vec<(event::MouseButton, event::ModifiersState)> {
+        self.mouse_binds
+            .iter()
+            .flat_map(|(button, binds)| {
+                binds
+                    .iter()
+                    .filter(move |bind| bind.payload == payload)
+                    .map(move |bind| (*button, bind.modifiers))
+            })
+            .collect()
+    }
+
     pub fn apply(
         &self,
         // input_state: &mut InputState<Inputs>,
@@ -0,0 +1,426 @@
+use winit::event::{ModifiersState, MouseButton, VirtualKeyCode};
+
+use anyhow::Result;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use std::path::PathBuf;
+
+use super::binds::{
+    BindableInput, KeyBind, MouseButtonBind, SystemInputBindings,
+};
+
+/// A key or mouse button, with the modifiers that must be held, as
+/// written in the keymap config, e.g. `Ctrl+Shift+Z` or `MouseMiddle`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Binding {
+    pub trigger: Trigger,
+    pub modifiers: ModifiersState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trigger {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+}
+
+const MODIFIER_NAMES: [(&str, ModifiersState); 4] = [
+    ("Ctrl", ModifiersState::CTRL),
+    ("Shift", ModifiersState::SHIFT),
+    ("Alt", ModifiersState::ALT),
+    ("Logo", ModifiersState::LOGO),
+];
+
+const MOUSE_NAMES: [(&str, MouseButton); 3] = [
+    ("MouseLeft", MouseButton::Left),
+    ("MouseMiddle", MouseButton::Middle),
+    ("MouseRight", MouseButton::Right),
+];
+
+/// The names of the keys that can be used in the keymap config,
+/// mostly matching the `VirtualKeyCode` variants
+const KEY_NAMES: &[(&str, VirtualKeyCode)] = {
+    use VirtualKeyCode as K;
+    &[
+        ("0", K::Key0),
+        ("1", K::Key1),
+        ("2", K::Key2),
+        ("3", K::Key3),
+        ("4", K::Key4),
+        ("5", K::Key5),
+        ("6", K::Key6),
+        ("7", K::Key7),
+        ("8", K::Key8),
+        ("9", K::Key9),
+        ("A", K::A),
+        ("B", K::B),
+        ("C", K::C),
+        ("D", K::D),
+        ("E", K::E),
+        ("F", K::F),
+        ("G", K::G),
+        ("H", K::H),
+        ("I", K::I),
+        ("J", K::J),
+        ("K", K::K),
+        ("L", K::L),
+        ("M", K::M),
+        ("N", K::N),
+        ("O", K::O),
+        ("P", K::P),
+        ("Q", K::Q),
+        ("R", K::R),
+        ("S", K::S),
+        ("T", K::T),
+        ("U", K::U),
+        ("V", K::V),
+        ("W", K::W),
+        ("X", K::X),
+        ("Y", K::Y),
+        ("Z", K::Z),
+        ("F1", K::F1),
+        ("F2", K::F2),
+        ("F3", K::F3),
+        ("F4", K::F4),
+        ("F5", K::F5),
+        ("F6", K::F6),
+        ("F7", K::F7),
+        ("F8", K::F8),
+        ("F9", K::F9),
+        ("F10", K::F10),
+        ("F11", K::F11),
+        ("F12", K::F12),
+        ("Escape", K::Escape),
+        ("Insert", K::Insert),
+        ("Home", K::Home),
+        ("Delete", K::Delete),
+        ("End", K::End),
+        ("PageDown", K::PageDown),
+        ("PageUp", K::PageUp),
+        ("Left", K::Left),
+        ("Up", K::Up),
+        ("Right", K::Right),
+        ("Down", K::Down),
+        ("Back", K::Back),
+        ("Return", K::Return),
+        ("Space", K::Space),
+        ("Tab", K::Tab),
+        ("Numpad0", K::Numpad0),
+        ("Numpad1", K::Numpad1),
+        ("Numpad2", K::Numpad2),
+        ("Numpad3", K::Numpad3),
+        ("Numpad4", K::Numpad4),
+        ("Numpad5", K::Numpad5),
+        ("Numpad6", K::Numpad6),
+        ("Numpad7", K::Numpad7),
+        ("Numpad8", K::Numpad8),
+        ("Numpad9", K::Numpad9),
+        ("NumpadAdd", K::NumpadAdd),
+        ("NumpadSubtract", K::NumpadSubtract),
+        ("NumpadMultiply", K::NumpadMultiply),
+        ("NumpadDivide", K::NumpadDivide),
+        ("NumpadEnter", K::NumpadEnter),
+        ("Apostrophe", K::Apostrophe),
+        ("Backslash", K::Backslash),
+        ("Comma", K::Comma),
+        ("Equals", K::Equals),
+        ("Grave", K::Grave),
+        ("LBracket", K::LBracket),
+        ("Minus", K::Minus),
+        ("Period", K::Period),
+        ("RBracket", K::RBracket),
+        ("Semicolon", K::Semicolon),
+        ("Slash", K::Slash),
+    ]
+};
+
+impl Binding {
+    /// Parse a binding such as `Ctrl+Shift+Z`; names are case
+    /// insensitive
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.split('+').map(|s| s.trim()).collect::<Vec<_>>();
+
+        let trigger_name = parts.pop()?;
+
+        let mut modifiers = ModifiersState::empty();
+
+        for part in parts {
+            let (_, modifier) = MODIFIER_NAMES
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(part))?;
+            modifiers |= *modifier;
+        }
+
+        let key = KEY_NAMES
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(trigger_name))
+            .map(|(_, key)| Trigger::Key(*key));
+
+        let trigger = key.or_else(|| {
+            MOUSE_NAMES
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(trigger_name))
+                .map(|(_, button)| Trigger::Mouse(*button))
+        })?;
+
+        Some(Self { trigger, modifiers })
+    }
+}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, modifier) in MODIFIER_NAMES.iter() {
+            if self.modifiers.contains(*modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        let name = match self.trigger {
+            Trigger::Key(key) => KEY_NAMES
+                .iter()
+                .find(|(_, k)| *k == key)
+                .map(|(name, _)| name.to_string())
+                .unwrap_or_else(|| format!("{:?}", key)),
+            Trigger::Mouse(button) => MOUSE_NAMES
+                .iter()
+                .find(|(_, b)| *b == button)
+                .map(|(name, _)| name.to_string())
+                .unwrap_or_else(|| format!("{:?}", button)),
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// One or more bindings for an action in the keymap config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+enum ConfigBinds {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Key and mouse button bindings for the actions named in
+/// `BindableInput::bindable_actions`, loaded from `keymap.json` in
+/// the user's config directory. Actions that aren't in the keymap
+/// keep their default bindings.
+#[derive(Debug, Default, Clone)]
+pub struct Keymap {
+    binds: FxHashMap<String, Vec<Binding>>,
+}
+
+impl Keymap {
+    fn config_file() -> Option<PathBuf> {
+        let mut path = dirs::config_dir()?;
+        path.push("gfaestus");
+        path.push("keymap.json");
+        Some(path)
+    }
+
+    /// Load the keymap config, or an empty keymap if there is none
+    /// or it can't be read
+    pub fn load_or_default() -> Self {
+        let path = match Self::config_file() {
+            Some(path) if path.exists() => path,
+            _ => return Self::default(),
+        };
+
+        let result = (|| -> Result<Self> {
+            let file = std::fs::File::open(&path)?;
+            Self::from_reader(std::io::BufReader::new(file))
+        })();
+
+        match result {
+            Ok(keymap) => {
+                log::info!("Loaded keymap from {:?}", path);
+                keymap
+            }
+            Err(err) => {
+                log::warn!("Error loading keymap {:?}: {:?}", path, err);
+                Self::default()
+            }
+        }
+    }
+
+    /// Read a keymap from a JSON object mapping action names to a
+    /// binding, or a list of bindings; an empty list unbinds the
+    /// action. Bindings that can't be parsed are skipped with a
+    /// warning, and an action none of whose bindings can be parsed
+    /// keeps its defaults.
+    pub fn from_reader<R: std::io::Read>(reader: R) -> Result<Self> {
+        let config: FxHashMap<String, ConfigBinds> =
+            serde_json::from_reader(reader)?;
+
+        let binds = config
+            .into_iter()
+            .filter_map(|(action, config_binds)| {
+                let texts = match config_binds {
+                    ConfigBinds::One(text) => vec![text],
+                    ConfigBinds::Many(texts) => texts,
+                };
+
+                let binds = texts
+                    .iter()
+                    .filter_map(|text| {
+                        let bind = Binding::parse(text);
+                        if bind.is_none() {
+                            log::warn!(
+                                "Unknown binding \"{}\" for action {}",
+                                text,
+                                action
+                            );
+                        }
+                        bind
+                    })
+                    .collect::<Vec<_>>();
+
+                if binds.is_empty() && !texts.is_empty() {
+                    log::warn!(
+                        "No valid bindings for action {}, keeping defaults",
+                        action
+                    );
+                    return None;
+                }
+
+                Some((action, binds))
+            })
+            .collect();
+
+        Ok(Self { binds })
+    }
+
+    /// Write the keymap as JSON, in the format read by `from_reader`,
+    /// with the actions sorted by name
+    pub fn to_json(&self) -> Result<String> {
+        let binds = self
+            .binds
+            .iter()
+            .map(|(action, binds)| {
+                let texts = binds.iter().map(|b| b.to_string()).collect();
+                (action.as_str(), ConfigBinds::Many(texts))
+            })
+            .collect::<std::collections::BTreeMap<_, _>>();
+
+        Ok(serde_json::to_string_pretty(&binds)?)
+    }
+
+    /// Warn about the actions in the keymap that aren't in `known`
+    pub fn warn_unknown_actions(&self, known: &[&str]) {
+        let mut unknown = self
+            .binds
+            .keys()
+            .filter(|action| !known.contains(&action.as_str()))
+            .collect::<Vec<_>>();
+        unknown.sort();
+
+        for action in unknown {
+            log::warn!("Unknown action \"{}\" in keymap", action);
+        }
+    }
+
+    /// Replace the bindings of the actions of `T` that are in the
+    /// keymap
+    pub fn apply<T: BindableInput>(
+        &self,
+        bindings: &mut SystemInputBindings<T>,
+    ) {
+        for (action, input) in T::bindable_actions() {
+            let binds = match self.binds.get(action) {
+                Some(binds) => binds,
+                None => continue,
+            };
+
+            bindings.unbind(input);
+
+            for bind in binds {
+                match bind.trigger {
+                    Trigger::Key(key) => bindings.bind_key(
+                        key,
+                        KeyBind::with_modifiers(input, bind.modifiers),
+                    ),
+                    Trigger::Mouse(button) => bindings.bind_mouse_button(
+                        button,
+                        MouseButtonBind::with_modifiers(input, bind.modifiers),
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Add the current bindings of the actions of `T` to the keymap
+    pub fn insert_bindings<T: BindableInput>(
+        &mut self,
+        bindings: &SystemInputBindings<T>,
+    ) {
+        for (action, input) in T::bindable_actions() {
+            let keys = bindings.key_binds_for(input).into_iter().map(
+                |(key, modifiers)| Binding {
+                    trigger: Trigger::Key(key),
+                    modifiers,
+                },
+            );
+
+            let buttons = bindings.mouse_binds_for(input).into_iter().map(
+                |(button, modifiers)| Binding {
+                    trigger: Trigger::Mouse(button),
+                    modifiers,
+                },
+            );
+
+            let mut binds = keys.chain(buttons).collect::<Vec<_>>();
+            binds.sort_by_key(|bind| bind.to_string());
+
+            self.binds.insert(action.to_string(), binds);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_bindings() {
+        let bind = Binding::parse("Ctrl+Shift+Z").unwrap();
+        assert_eq!(bind.trigger, Trigger::Key(VirtualKeyCode::Z));
+        assert_eq!(
+            bind.modifiers,
+            ModifiersState::CTRL | ModifiersState::SHIFT
+        );
+
+        let bind = Binding::parse(" alt + mousemiddle ").unwrap();
+        assert_eq!(bind.trigger, Trigger::Mouse(MouseButton::Middle));
+        assert_eq!(bind.modifiers, ModifiersState::ALT);
+
+        assert!(Binding::parse("").is_none());
+        assert!(Binding::parse("Ctrl+").is_none());
+        assert!(Binding::parse("Hyper+A").is_none());
+        assert!(Binding::parse("NotAKey").is_none());
+    }
+
+    #[test]
+    fn bindings_round_trip() {
+        for text in ["Ctrl+Shift+Z", "Alt+MouseLeft", "F5", "Logo+Space"] {
+            let bind = Binding::parse(text).unwrap();
+            assert_eq!(bind.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn unparseable_entries_keep_defaults() {
+        let json = r#"{
+            "pan_up": ["Bogus", "AlsoBogus"],
+            "pan_down": ["Bogus", "S"],
+            "pan_left": []
+        }"#;
+
+        let keymap = Keymap::from_reader(json.as_bytes()).unwrap();
+
+        assert!(!keymap.binds.contains_key("pan_up"));
+        assert_eq!(
+            keymap.binds["pan_down"],
+            vec![Binding::parse("S").unwrap()]
+        );
+        assert!(keymap.binds["pan_left"].is_empty());
+    }
+}
@@ -17,6 +17,8 @@ pub mod universe;
 pub mod input;
 pub mod view;
 
+pub mod svg;
+
 pub mod asynchronous;
 // pub mod gluon;
 pub mod script;
@@ -1,7 +1,14 @@
 #[allow(unused_imports)]
 use compute::EdgePreprocess;
 use crossbeam::atomic::AtomicCell;
-use gfaestus::context::{debug_context_action, pan_to_node_action, ContextMgr};
+use gfaestus::context::{
+    add_marker_action, copy_node_neighbors_action,
+    copy_path_sub_sequence_action, copy_subgraph_gfa_action,
+    debug_context_action, frame_path_action, load_node_value_table_command,
+    node_has_no_paths, node_is_orphan, pan_to_node_action,
+    select_neighborhood_action, select_nodes_command, ContextMgr,
+    GlobalCommand, WorldPoint,
+};
 use gfaestus::quad_tree::QuadTree;
 use gfaestus::reactor::{ModalError, ModalHandler, ModalSuccess, Reactor};
 use gfaestus::script::plugins::colors::{hash_bytes, hash_color};
@@ -13,27 +20,33 @@ use gfaestus::vulkan::texture::{Gradients, Gradients_, Texture};
 use parking_lot::RwLock;
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use winit::event::{ElementState, Event, MouseButton, WindowEvent};
-use winit::event_loop::ControlFlow;
+use winit::event_loop::{ControlFlow, EventLoop};
+use winit::platform::run_return::EventLoopExtRunReturn;
 
 #[allow(unused_imports)]
 use winit::window::{Window, WindowBuilder};
 
 use gfaestus::app::{
-    mainview::*, Args, OverlayCreatorMsg, OverlayState, Select,
+    mainview::*, path_colors::PathColors, Args, OverlayCreatorMsg,
+    OverlayState, Select,
 };
+use gfaestus::app::mainview::flythrough::FlyThrough;
 use gfaestus::app::{App, AppMsg};
 use gfaestus::geometry::*;
+use gfaestus::gfa::stable_hash;
 use gfaestus::graph_query::*;
 use gfaestus::input::*;
 use gfaestus::overlays::*;
 use gfaestus::universe::*;
-use gfaestus::view::View;
+use gfaestus::view::{SavedView, View, ViewLink};
 use gfaestus::vulkan::render_pass::Framebuffers;
 
-use gfaestus::gui::{widgets::*, windows::*, *};
+use gfaestus::gui::{
+    loading::LoadingModal, toasts::Toast, widgets::*, windows::*, *,
+};
 
 use gfaestus::vulkan::debug;
 
@@ -46,8 +59,10 @@ use gfaestus::vulkan::draw_system::selection::{
     SelectionOutlineBlurPipeline, SelectionOutlineEdgePipeline,
 };
 
+use gfaestus::vulkan::timestamps::{GpuPass, PassTimestamps};
+
 use gfaestus::vulkan::compute::{
-    ComputeManager, GpuSelection, NodeTranslation,
+    ComputeManager, GpuSelection, LayoutRelaxation, NodeTranslation,
 };
 
 use anyhow::Result;
@@ -79,24 +94,318 @@ use flexi_logger::{Duplicate, FileSpec, Logger, LoggerHandle};
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
 
+/// The binary layout cache is stored next to the text layout, and is
+/// only used if it's newer than both the layout and the node ID map.
+/// The cache of a layout loaded with a node ID map is named by the
+/// hash of the map's path, so it's not used without that map.
+fn layout_cache_path(
+    layout_path: &str,
+    node_map_path: Option<&str>,
+) -> (PathBuf, bool) {
+    let cache_path = match node_map_path {
+        Some(map_path) => {
+            let map_path = std::fs::canonicalize(map_path)
+                .unwrap_or_else(|_| PathBuf::from(map_path));
+            let map_hash = stable_hash(map_path.to_string_lossy().as_bytes());
+            PathBuf::from(format!("{}.{:016x}.bin", layout_path, map_hash))
+        }
+        None => PathBuf::from(format!("{}.bin", layout_path)),
+    };
+
+    let modified = |path: &Path| std::fs::metadata(path)?.modified();
+
+    let is_fresh = modified(&cache_path).map_or(false, |cache_time| {
+        std::iter::once(layout_path)
+            .chain(node_map_path)
+            .all(|source| {
+                modified(Path::new(source))
+                    .map_or(false, |source_time| cache_time > source_time)
+            })
+    });
+
+    (cache_path, is_fresh)
+}
+
+/// Generate a layout for a GFA given without a layout file, saved
+/// next to the GFA so that it's only generated again if the GFA has
+/// changed. Returns the path to the layout.
+fn generated_layout(
+    gfa_path: &str,
+    graph_query: &GraphQuery,
+) -> Result<String> {
+    let layout_path = format!("{}.layout", gfa_path);
+
+    let modified = |path: &str| std::fs::metadata(path)?.modified();
+
+    let is_fresh = match (modified(&layout_path), modified(gfa_path)) {
+        (Ok(layout_time), Ok(gfa_time)) => layout_time > gfa_time,
+        _ => false,
+    };
+
+    if is_fresh {
+        info!("No layout file given, using {}", layout_path);
+        return Ok(layout_path);
+    }
+
+    info!("No layout file given, generating {}", layout_path);
+
+    let file = std::fs::File::create(&layout_path).map_err(|err| {
+        anyhow::anyhow!("Could not create {}: {}", layout_path, err)
+    })?;
+
+    auto_layout::write_linear_layout_tsv(
+        graph_query,
+        std::io::BufWriter::new(file),
+    )?;
+
+    Ok(layout_path)
+}
+
 fn universe_from_gfa_layout(
     graph_query: &GraphQuery,
     layout_path: &str,
+    node_map_path: Option<&str>,
+    use_layout_cache: bool,
 ) -> Result<(Universe<FlatLayout>, GraphStats)> {
     let graph = graph_query.graph();
 
-    let universe = Universe::from_laid_out_graph(&graph, layout_path)?;
+    let (cache_path, cache_is_fresh) =
+        layout_cache_path(layout_path, node_map_path);
+
+    let cached = if use_layout_cache && cache_is_fresh {
+        match Universe::load_layout_binary(&graph, &cache_path) {
+            Ok(universe) => {
+                info!("Loaded layout from {}", cache_path.display());
+                Some(universe)
+            }
+            Err(err) => {
+                warn!(
+                    "Could not use binary layout {}, loading {}: {}",
+                    cache_path.display(),
+                    layout_path,
+                    err
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let universe = match cached {
+        Some(universe) => universe,
+        None => {
+            let universe = Universe::from_laid_out_graph(
+                &graph,
+                layout_path,
+                node_map_path,
+            )?;
+
+            if use_layout_cache {
+                match universe.save_layout_binary(&cache_path) {
+                    Ok(_) => {
+                        info!("Saved binary layout to {}", cache_path.display())
+                    }
+                    Err(err) => warn!(
+                        "Could not save binary layout to {}: {}",
+                        cache_path.display(),
+                        err
+                    ),
+                }
+            }
+
+            universe
+        }
+    };
 
     let stats = GraphStats {
         node_count: graph.node_count(),
         edge_count: graph.edge_count(),
         path_count: graph.path_count(),
-        total_len: graph.total_length(),
+        total_len: graph_query.total_length(),
+        visible_nodes: None,
+        lod_active: false,
+        load_progress: None,
     };
 
     Ok((universe, stats))
 }
 
+/// The view used when first showing the graph, framing the entire
+/// layout unless overridden by the `--view-center` and `--view-scale`
+/// arguments.
+fn initial_view_from_args<D: Into<ScreenDims>>(
+    args: &Args,
+    dims: D,
+    top_left: Point,
+    bottom_right: Point,
+) -> View {
+    let mut view = View::from_dims_and_target(dims, top_left, bottom_right);
+
+    if let Some(center) = args.view_center {
+        view.center = center;
+    }
+
+    if let Some(scale) = args.view_scale {
+        view.scale = scale;
+    }
+
+    view
+}
+
+/// Layouts with every node at the same point, such as a single node
+/// with no length, have an empty bounding box, which would give the
+/// initial view a scale of zero. Such boxes, including ones that are
+/// tiny or not finite, are replaced by a box of a default size around
+/// their center.
+fn framable_bounding_box(
+    (top_left, bottom_right): (Point, Point),
+) -> (Point, Point) {
+    // world units
+    const MIN_EXTENT: f32 = 1.0;
+    const DEFAULT_EXTENT: f32 = 200.0;
+
+    let size = bottom_right - top_left;
+
+    let finite = [top_left.x, top_left.y, bottom_right.x, bottom_right.y]
+        .iter()
+        .all(|v| v.is_finite());
+
+    if finite && size.x.max(size.y) >= MIN_EXTENT {
+        return (top_left, bottom_right);
+    }
+
+    warn!(
+        "degenerate layout bounding box ({}, {}) - ({}, {}), \
+         {} x {}; using a default view",
+        top_left.x, top_left.y, bottom_right.x, bottom_right.y, size.x, size.y
+    );
+
+    let center = if finite {
+        top_left + size * 0.5
+    } else {
+        Point::ZERO
+    };
+
+    let half = Point::new(DEFAULT_EXTENT / 2.0, DEFAULT_EXTENT / 2.0);
+
+    (center - half, center + half)
+}
+
+/// Splits the worker threads between the futures thread pool, used
+/// by `GraphQueryWorker` and other async tasks, and the rayon thread
+/// pool used when computing overlays.
+///
+/// The total defaults to one less than the number of CPUs, leaving
+/// a core for the render thread, and can be set with `--threads`.
+fn thread_pool_sizes(args: &Args) -> (usize, usize) {
+    let total = args
+        .threads
+        .unwrap_or_else(|| num_cpus::get().saturating_sub(1))
+        .max(1);
+
+    if total == 1 {
+        return (1, 1);
+    }
+
+    let futures_cpus = (total / 4).max(1);
+    let rayon_cpus = total - futures_cpus;
+
+    (futures_cpus, rayon_cpus)
+}
+
+/// Loads the GFA on a background thread while pumping the window's
+/// event loop, so that the OS doesn't mark the window as unresponsive
+/// during long loads. The loading progress is shown in a modal.
+///
+/// Returns `None` if the window was closed before the graph was
+/// loaded; the loading thread is then left to be stopped when the
+/// process exits.
+fn load_graph_with_progress(
+    gfa_file: &str,
+    lazy_sequences: bool,
+    gfaestus: &mut GfaestusVk,
+    event_loop: &mut EventLoop<()>,
+    window: &Window,
+) -> Result<Option<GraphQuery>> {
+    use crossbeam::channel::TryRecvError;
+
+    let (progress_tx, progress_rx) = crossbeam::channel::unbounded();
+
+    let gfa_path = gfa_file.to_string();
+    let loader = std::thread::spawn(move || {
+        GraphQuery::load_gfa_auto(&gfa_path, Some(&progress_tx), lazy_sequences)
+    });
+
+    let mut modal = LoadingModal::new(gfaestus)?;
+
+    let mut cancelled = false;
+    let mut dirty_swapchain = false;
+
+    loop {
+        let mut finished = false;
+
+        loop {
+            match progress_rx.try_recv() {
+                Ok(progress) => modal.set_progress(progress),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    finished = true;
+                    break;
+                }
+            }
+        }
+
+        if finished {
+            break;
+        }
+
+        event_loop.run_return(|event, _, control_flow| {
+            *control_flow = ControlFlow::Exit;
+
+            if let Event::WindowEvent { event, .. } = event {
+                match event {
+                    WindowEvent::CloseRequested => cancelled = true,
+                    WindowEvent::Resized(_) => dirty_swapchain = true,
+                    _ => (),
+                }
+            }
+        });
+
+        if cancelled {
+            break;
+        }
+
+        let size = window.inner_size();
+
+        if dirty_swapchain && size.width > 0 && size.height > 0 {
+            gfaestus.recreate_swapchain(Some([size.width, size.height]))?;
+            dirty_swapchain = false;
+        }
+
+        if !dirty_swapchain {
+            dirty_swapchain =
+                modal.draw(gfaestus, [size.width, size.height])?;
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(16));
+    }
+
+    gfaestus.wait_gpu_idle()?;
+    modal.destroy(gfaestus);
+
+    if cancelled {
+        info!("Window closed while loading GFA");
+        return Ok(None);
+    }
+
+    let graph_query = loader
+        .join()
+        .map_err(|_| anyhow::anyhow!("GFA loading thread panicked"))??;
+
+    Ok(Some(graph_query))
+}
+
 fn set_up_logger(args: &Args) -> Result<LoggerHandle> {
     let spec = match (args.trace, args.debug, args.quiet) {
         (true, _, _) => "trace",
@@ -120,11 +429,21 @@ fn main() -> Result<()> {
 
     log::debug!("Logger initalized");
 
-    let gfa_file = &args.gfa;
-    let layout_file = &args.layout;
-    log::debug!("using {} and {}", gfa_file, layout_file);
+    log::debug!(
+        "using {} and {}",
+        args.gfa,
+        args.layout.as_deref().unwrap_or("a generated layout")
+    );
+
+    if args.screenshot.is_some() || args.fly_through.is_some() {
+        if let Err(err) = run_headless(&args) {
+            error!("Error rendering offscreen: {:?}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
-    let (mut gfaestus, event_loop, window) = match GfaestusVk::new(&args) {
+    let (mut gfaestus, mut event_loop, window) = match GfaestusVk::new(&args) {
         Ok(app) => app,
         Err(err) => {
             error!("Error initializing Gfaestus");
@@ -133,53 +452,374 @@ fn main() -> Result<()> {
         }
     };
 
-    let renderer_config = gfaestus.vk_context().renderer_config;
-
-    let num_cpus = num_cpus::get();
-
-    let futures_cpus;
-    let rayon_cpus;
-
-    // TODO this has to be done much more intelligently
-    if num_cpus < 4 {
-        futures_cpus = 1;
-        rayon_cpus = 1;
-    } else if num_cpus == 4 {
-        futures_cpus = 1;
-        rayon_cpus = 2;
-    } else if num_cpus <= 6 {
-        futures_cpus = 2;
-        rayon_cpus = num_cpus - 3;
-    } else {
-        futures_cpus = 3;
-        rayon_cpus = num_cpus - 4;
-    }
+    let (futures_cpus, rayon_cpus) = thread_pool_sizes(&args);
 
     log::debug!("futures thread pool: {}", futures_cpus);
     log::debug!("rayon   thread pool: {}", rayon_cpus);
 
-    // TODO make sure to set thread pool size to less than number of CPUs
     let thread_pool =
         ThreadPoolBuilder::new().pool_size(futures_cpus).create()?;
 
+    let mut prev_view: Option<(View, usize)> = None;
+
+    loop {
+        let (gfaestus_, session_end) = run_graph(
+            &args,
+            gfaestus,
+            &mut event_loop,
+            &window,
+            &thread_pool,
+            rayon_cpus,
+            prev_view.take(),
+        )?;
+
+        gfaestus = gfaestus_;
+
+        match session_end {
+            SessionEnd::Exit => break,
+            SessionEnd::Reload { view, node_count } => {
+                info!("Reloading GFA");
+                prev_view = Some((view, node_count));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders the `--screenshot` and `--fly-through` frames without
+/// creating a window, drawing the nodes and edges colored by the
+/// default overlay. Each frame is waited on before it's read back and
+/// saved, and any error is returned, so the caller can exit with a
+/// failure status.
+fn run_headless(args: &Args) -> Result<()> {
+    let mut gfaestus = GfaestusVk::new_headless(args)?;
+
+    let (futures_cpus, rayon_cpus) = thread_pool_sizes(args);
+
+    let thread_pool =
+        ThreadPoolBuilder::new().pool_size(futures_cpus).create()?;
+    let rayon_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(rayon_cpus)
+        .build()?;
+
+    let gfa_file = &args.gfa;
+
+    info!("Loading GFA");
+    let graph_query = Arc::new(GraphQuery::load_gfa_auto(
+        gfa_file,
+        None,
+        args.lazy_sequences,
+    )?);
+
+    let layout_file = match args.layout.as_ref() {
+        Some(layout) => layout.to_owned(),
+        None => generated_layout(gfa_file, &graph_query)?,
+    };
+
+    let (universe, _stats) = universe_from_gfa_layout(
+        &graph_query,
+        &layout_file,
+        args.node_map.as_deref(),
+        !args.no_layout_cache,
+    )?;
+
+    let (top_left, bottom_right) =
+        framable_bounding_box(universe.layout().bounding_box());
+
+    let screen_dims = gfaestus.swapchain_dims();
+
+    let app = App::new(
+        screen_dims,
+        thread_pool,
+        rayon_pool,
+        graph_query.clone(),
+        Rect::new(top_left, bottom_right),
+    )?;
+
+    app.settings.msaa_samples().store(gfaestus.msaa_samples);
+
+    let mut main_view = MainView::new(
+        &gfaestus,
+        app.clone_channels(),
+        app.settings.clone(),
+        app.shared_state().clone(),
+        graph_query.node_count(),
+    )?;
+
+    main_view
+        .node_draw_system
+        .vertices
+        .upload_vertices(&gfaestus, &universe.node_vertices())?;
+
+    let overlay = create_overlay(
+        app.shared_state().overlay_state(),
+        &gfaestus,
+        &mut main_view,
+        &app.reactor,
+        "Node Seq Hash",
+        NODE_SEQ_HASH_SCRIPT,
+    )?
+    .map(|(overlay_id, _)| overlay_id)
+    .ok_or_else(|| anyhow::anyhow!("Error computing the node overlay"))?;
+
+    let mut edge_renderer = if gfaestus.vk_context().renderer_config.edges
+        == EdgeRendererType::Disabled
+    {
+        None
+    } else {
+        Some(EdgeRenderer::new(
+            &gfaestus,
+            &graph_query.graph_arc(),
+            universe.layout(),
+        )?)
+    };
+
+    let mut gradients = Gradients::initialize(
+        &gfaestus,
+        gfaestus.transient_command_pool,
+        gfaestus.graphics_queue,
+        1024,
+    )?;
+
+    let render_frame = |gfaestus: &mut GfaestusVk,
+                            main_view: &mut MainView,
+                            view: View,
+                            png_path: &Path|
+     -> Result<()> {
+        main_view.set_view(view);
+
+        let node_pass = gfaestus.render_passes.nodes;
+        let edges_pass = gfaestus.render_passes.edges;
+        let node_id_image = gfaestus.node_attachments.id_resolve.image;
+
+        let edge_width = app.settings.edge_renderer().load().edge_width;
+
+        let overlay_state = app.shared_state().overlay_state();
+        let gradient = gradients
+            .gradient_oriented(
+                overlay_state.gradient(),
+                overlay_state.gradient_reversed(),
+            )
+            .unwrap();
+
+        let size = screen_dims;
+        let mut result = Ok(());
+
+        gfaestus.draw_offscreen_frame(|device, cmd_buf, framebuffers| {
+            result = main_view.draw_nodes(
+                false,
+                cmd_buf,
+                node_pass,
+                framebuffers,
+                size.into(),
+                Point::ZERO,
+                Some(overlay),
+                gradient,
+            );
+
+            for er in edge_renderer.iter() {
+                if result.is_ok() {
+                    result = er.draw(
+                        cmd_buf,
+                        edge_width,
+                        &main_view.node_draw_system.vertices,
+                        edges_pass,
+                        framebuffers,
+                        size.into(),
+                        2.0,
+                        view,
+                        Point::ZERO,
+                    );
+                }
+            }
+
+            // the node pass expects the ID image to be ready for
+            // copying, as it is after a regular frame
+            unsafe {
+                let image_memory_barrier = vk::ImageMemoryBarrier::builder()
+                    .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                    .dst_access_mask(vk::AccessFlags::SHADER_READ)
+                    .old_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+                    .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .image(node_id_image)
+                    .subresource_range(vk::ImageSubresourceRange {
+                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                        base_mip_level: 0,
+                        level_count: 1,
+                        base_array_layer: 0,
+                        layer_count: 1,
+                    })
+                    .build();
+
+                device.cmd_pipeline_barrier(
+                    cmd_buf,
+                    vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                    vk::PipelineStageFlags::FRAGMENT_SHADER,
+                    vk::DependencyFlags::BY_REGION,
+                    &[],
+                    &[],
+                    &[image_memory_barrier],
+                );
+            }
+        })?;
+
+        result?;
+
+        let pixels = gfaestus.download_offscreen_frame()?;
+        let [width, height]: [u32; 2] = size.into();
+
+        image::save_buffer(
+            png_path,
+            &pixels,
+            width,
+            height,
+            image::ColorType::Rgba8,
+        )?;
+
+        Ok(())
+    };
+
+    let mut result = Ok(());
+
+    if let Some(png_path) = args.screenshot.as_ref() {
+        let view =
+            initial_view_from_args(args, screen_dims, top_left, bottom_right);
+
+        result = render_frame(&mut gfaestus, &mut main_view, view, png_path);
+
+        if result.is_ok() {
+            info!("Saved screenshot to {:?}", png_path);
+        }
+    }
+
+    if let (Some(path), true) = (args.fly_through.as_ref(), result.is_ok()) {
+        result = (|| -> Result<()> {
+            let out_dir = args
+                .fly_through_dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from("flythrough"));
+            let mut fly_through = FlyThrough::load_file(path, out_dir)?;
+
+            info!(
+                "Recording {} fly-through frames to {:?}",
+                fly_through.frame_count(),
+                fly_through.out_dir()
+            );
+
+            while !fly_through.is_done() {
+                let view = fly_through.current_view(screen_dims);
+                let png_path = fly_through.frame_path();
+
+                render_frame(&mut gfaestus, &mut main_view, view, &png_path)
+                    .map_err(|err| {
+                        err.context(format!("saving frame {:?}", png_path))
+                    })?;
+
+                fly_through.advance();
+            }
+
+            info!(
+                "Saved {} fly-through frames to {:?}",
+                fly_through.frame_count(),
+                fly_through.out_dir()
+            );
+
+            Ok(())
+        })();
+    }
+
+    gfaestus.wait_gpu_idle()?;
+
+    let device = gfaestus.vk_context().device();
+
+    main_view.selection_buffer.destroy(device);
+    main_view.node_id_buffer.destroy(device);
+    main_view.node_draw_system.destroy(&gfaestus);
+    main_view.layers.destroy(&gfaestus)?;
+
+    for er in edge_renderer.iter_mut() {
+        er.destroy();
+    }
+
+    gradients.destroy(device);
+
+    result
+}
+
+/// How the event loop for a loaded graph was ended
+enum SessionEnd {
+    Exit,
+    /// Reload the GFA and layout from disk, keeping the current view
+    /// if the node count is unchanged
+    Reload { view: View, node_count: usize },
+}
+
+/// Load the graph and layout, set up everything that depends on
+/// them, and run the event loop until the window is closed or the
+/// graph should be reloaded. `run_return` sends `LoopDestroyed` in
+/// both cases, which is where the GPU resources created here are
+/// destroyed, after waiting for the GPU to be idle; the `App` and
+/// `MainView` threads are joined when they're dropped on return.
+fn run_graph(
+    args: &Args,
+    mut gfaestus: GfaestusVk,
+    event_loop: &mut EventLoop<()>,
+    window: &Window,
+    thread_pool: &ThreadPool,
+    rayon_cpus: usize,
+    prev_view: Option<(View, usize)>,
+) -> Result<(GfaestusVk, SessionEnd)> {
+    let gfa_file = &args.gfa;
+
+    let renderer_config = gfaestus.vk_context().renderer_config;
+
     let rayon_pool = rayon::ThreadPoolBuilder::new()
         .num_threads(rayon_cpus)
         .build()?;
 
+    let reloading = prev_view.is_some();
+
     info!("Loading GFA");
     let t = std::time::Instant::now();
 
-    let graph_query = Arc::new(GraphQuery::load_gfa(gfa_file)?);
+    let graph_query = match load_graph_with_progress(
+        gfa_file,
+        args.lazy_sequences,
+        &mut gfaestus,
+        event_loop,
+        window,
+    )? {
+        Some(graph_query) => Arc::new(graph_query),
+        None => return Ok((gfaestus, SessionEnd::Exit)),
+    };
+
+    // the view is only kept if the layout is likely the same
+    let preserved_view = prev_view
+        .filter(|(_, node_count)| *node_count == graph_query.node_count())
+        .map(|(view, _)| view);
 
-    let layout_1d = Arc::new(Path1DLayout::new(graph_query.graph()));
+    let layout_1d = Arc::new(Path1DLayout::new(&graph_query));
 
     let graph_query_worker =
         GraphQueryWorker::new(graph_query.clone(), thread_pool.clone());
 
-    let (mut universe, stats) =
-        universe_from_gfa_layout(&graph_query, layout_file)?;
+    let layout_file = match args.layout.as_ref() {
+        Some(layout) => layout.to_owned(),
+        None => generated_layout(gfa_file, &graph_query)?,
+    };
+
+    let (mut universe, stats) = universe_from_gfa_layout(
+        &graph_query,
+        &layout_file,
+        args.node_map.as_deref(),
+        !args.no_layout_cache,
+    )?;
 
-    let (top_left, bottom_right) = universe.layout().bounding_box();
+    let (top_left, bottom_right) =
+        framable_bounding_box(universe.layout().bounding_box());
 
     let tree_bounding_box = {
         let tl = top_left;
@@ -200,6 +840,18 @@ fn main() -> Result<()> {
     )
     .expect("error when creating App");
 
+    app.settings.msaa_samples().store(gfaestus.msaa_samples);
+
+    app.settings
+        .idle_fps()
+        .store(args.idle_fps.filter(|&fps| fps > 0));
+
+    // there's no initial resize event when reloading
+    if reloading {
+        let size = window.inner_size();
+        app.update_dims([size.width as f32, size.height as f32]);
+    }
+
     let _center = Point {
         x: top_left.x + (bottom_right.x - top_left.x) / 2.0,
         y: top_left.y + (bottom_right.y - top_left.y) / 2.0,
@@ -229,16 +881,25 @@ fn main() -> Result<()> {
         gfaestus.graphics_queue,
     )?;
 
-    let gpu_selection = GpuSelection::new(&gfaestus, graph_query.node_count())?;
+    let mut gpu_selection =
+        GpuSelection::new(&gfaestus, graph_query.node_count())?;
 
     let node_translation =
         NodeTranslation::new(&gfaestus, graph_query.node_count())?;
 
     let mut select_fence_id: Option<usize> = None;
     let mut translate_fence_id: Option<usize> = None;
+    let mut relax_fence_id: Option<usize> = None;
+    // the CPU copy of the node positions is only updated once the
+    // relaxation is stopped
+    let mut relaxed_positions_stale = false;
+
+    let mut node_center_grid = gfaestus::gui::text::NodeCenterGrid::default();
 
     let mut prev_overlay: Option<usize> = None;
     let mut prev_gradient = app.shared_state().overlay_state().gradient();
+    let mut prev_reversed =
+        app.shared_state().overlay_state().gradient_reversed();
 
     let (winit_tx, winit_rx) =
         crossbeam::channel::unbounded::<WindowEvent<'static>>();
@@ -260,6 +921,14 @@ fn main() -> Result<()> {
     )
     .unwrap();
 
+    {
+        let view = preserved_view.unwrap_or_else(|| {
+            initial_view_from_args(&args, app.dims(), top_left, bottom_right)
+        });
+        main_view.set_initial_view(Some(view.center), Some(view.scale));
+        main_view.set_layout_bounds(app.dims(), top_left, bottom_right);
+    }
+
     let path_view = Arc::new(
         PathViewRenderer::new(
             &gfaestus,
@@ -279,53 +948,61 @@ fn main() -> Result<()> {
     );
 
     let mut gui = Gui::new(&app, &gfaestus, &path_view)?;
+    gui.load_bookmarks(gfa_file);
+    gui.load_markers(gfa_file);
 
     // create default overlays
     {
-        let node_seq_script = "
-fn node_color(id) {
-  let h = handle(id, false);
-  let seq = graph.sequence(h);
-  let hash = hash_bytes(seq);
-  let color = hash_color(hash);
-  color
-}
-";
-
-        let step_count_script = "
-fn node_color(id) {
-  let h = handle(id, false);
-
-  let steps = graph.steps_on_handle(h);
-  let count = 0.0;
-
-  for step in steps {
-    count += 1.0;
-  }
-
-  count
-}
-";
+        let default_overlays = [
+            ("Node Seq Hash", NODE_SEQ_HASH_SCRIPT),
+            ("Node Step Count", STEP_COUNT_SCRIPT),
+        ];
+
+        for (name, script) in default_overlays.iter().copied() {
+            let created = create_overlay(
+                app.shared_state().overlay_state(),
+                &gfaestus,
+                &mut main_view,
+                &app.reactor,
+                name,
+                script,
+            )
+            .expect("Error creating default overlay");
+
+            if let Some((overlay_id, data)) = created {
+                gui.set_overlay_data(overlay_id, data);
+            }
+        }
+    }
 
-        create_overlay(
-            app.shared_state().overlay_state(),
-            &gfaestus,
-            &mut main_view,
-            &app.reactor,
-            "Node Seq Hash",
-            node_seq_script,
-        )
-        .expect("Error creating node seq hash overlay");
+    let overlay_cache = if args.save_overlays {
+        OverlayCache::for_gfa(Path::new(gfa_file))
+    } else {
+        None
+    };
 
-        create_overlay(
-            app.shared_state().overlay_state(),
-            &gfaestus,
-            &mut main_view,
-            &app.reactor,
-            "Node Step Count",
-            step_count_script,
-        )
-        .expect("Error creating step count overlay");
+    if let Some(cache) = overlay_cache.as_ref() {
+        let node_count = graph_query.node_count();
+
+        for (name, data) in cache.load_all(node_count) {
+            let msg = OverlayCreatorMsg::NewOverlay { name, data };
+
+            match handle_new_overlay(
+                app.shared_state().overlay_state(),
+                &gfaestus,
+                &mut main_view,
+                node_count,
+                &msg,
+            ) {
+                Ok(overlay_id) => {
+                    info!("Restored overlay {}", msg.name());
+                    gui.set_overlay_data(overlay_id, msg.into_data());
+                }
+                Err(err) => {
+                    error!("Error restoring overlay {}: {:?}", msg.name(), err);
+                }
+            }
+        }
     }
 
     app.shared_state()
@@ -345,6 +1022,9 @@ fn node_color(id) {
         edge_count: Some(stats.edge_count),
         path_count: Some(stats.path_count),
         total_len: Some(stats.total_len),
+        visible_nodes: None,
+        lod_active: None,
+        load_progress: None,
     });
 
     main_view
@@ -352,6 +1032,13 @@ fn node_color(id) {
         .vertices
         .upload_vertices(&gfaestus, &node_vertices)?;
 
+    let mut layout_relaxation = LayoutRelaxation::new(
+        &gfaestus,
+        graph_query.graph(),
+        universe.layout().node_ids(),
+        &main_view.node_draw_system.vertices,
+    )?;
+
     let mut edge_renderer = if gfaestus.vk_context().renderer_config.edges
         == EdgeRendererType::Disabled
     {
@@ -369,16 +1056,21 @@ fn node_color(id) {
         Some(edge_renderer)
     };
 
-    let mut dirty_swapchain = false;
+    let mut dirty_swapchain = reloading;
+    let mut dirty_pipelines = false;
+
+    let mut session_end = SessionEnd::Exit;
 
     let mut selection_edge = SelectionOutlineEdgePipeline::new(&gfaestus, 1)?;
 
-    let mut selection_blur = SelectionOutlineBlurPipeline::new(&gfaestus, 1)?;
+    let mut selection_blur = SelectionOutlineBlurPipeline::new(&gfaestus)?;
+
+    let mut pass_timestamps = PassTimestamps::new(&gfaestus)?;
 
     let gui_msg_tx = app.channels().gui_tx.clone();
 
     dbg!();
-    let gradients_ = Gradients_::initialize(
+    let mut gradients_ = Gradients_::initialize(
         &gfaestus,
         gfaestus.transient_command_pool,
         gfaestus.graphics_queue,
@@ -392,7 +1084,7 @@ fn node_color(id) {
 
     let mut upload_path_view_texture = true;
 
-    let gradients = Gradients::initialize(
+    let mut gradients = Gradients::initialize(
         &gfaestus,
         gfaestus.transient_command_pool,
         gfaestus.graphics_queue,
@@ -408,7 +1100,11 @@ fn node_color(id) {
     );
 
     const FRAME_HISTORY_LEN: usize = 10;
-    let mut frame_time_history = [0.0f32; FRAME_HISTORY_LEN];
+    let mut frame_time_history = [FrameSample::default(); FRAME_HISTORY_LEN];
+    // the time between the starts of consecutive frames, which is
+    // what the FPS is based on, as it includes any idle waiting
+    let mut frame_interval_history = [0.0f32; FRAME_HISTORY_LEN];
+    let mut last_frame_start = std::time::Instant::now();
     let mut frame = 0;
 
     // hack to make the initial view correct -- we need to have the
@@ -431,11 +1127,164 @@ fn node_color(id) {
         set_type_name!(NodeId);
         set_type_name!(PathId);
         set_type_name!(FxHashSet<NodeId>);
+        set_type_name!(WorldPoint);
     }
 
     let dbg_action = debug_context_action(&context_mgr);
 
     context_mgr.register_action("Debug print", dbg_action);
+    context_mgr.register_action(
+        "Copy subgraph as GFA",
+        copy_subgraph_gfa_action(&app),
+    );
+    context_mgr.register_action(
+        "Copy path sequence in selection",
+        copy_path_sub_sequence_action(&app),
+    );
+    context_mgr.register_action(
+        "Copy node neighbors",
+        copy_node_neighbors_action(&app),
+    );
+    context_mgr.register_action("Frame path", frame_path_action(&app));
+    context_mgr.register_action(
+        "Select neighborhood",
+        select_neighborhood_action(&app),
+    );
+    context_mgr.register_action("Add marker here", add_marker_action(&app));
+
+    {
+        let app_commands: [(&str, fn() -> AppMsg); 9] = [
+            ("Zoom to selection", || AppMsg::ZoomToSelection),
+            ("Clear selection", AppMsg::clear_selection),
+            ("Invert selection", || AppMsg::InvertSelection),
+            ("Grow selection", || AppMsg::GrowSelection),
+            ("Undo selection", || AppMsg::UndoSelection),
+            ("Redo selection", || AppMsg::RedoSelection),
+            ("Toggle dark mode", AppMsg::toggle_dark_mode),
+            ("Reload GFA", || AppMsg::ReloadGraph),
+            ("Export view as SVG", || AppMsg::ExportSvg),
+        ];
+
+        for (name, msg) in app_commands.iter().copied() {
+            context_mgr.register_command(name, GlobalCommand::app_msg(msg));
+        }
+
+        context_mgr.register_command(
+            "Select nodes with no paths",
+            select_nodes_command("nodes with no paths", node_has_no_paths),
+        );
+        context_mgr.register_command(
+            "Select orphan nodes",
+            select_nodes_command("orphan nodes", node_is_orphan),
+        );
+
+        context_mgr.register_command(
+            "Load node-value table",
+            load_node_value_table_command(),
+        );
+
+        context_mgr.register_command(
+            "Follow system theme",
+            GlobalCommand::new(|app| {
+                app.settings.follow_system_theme().store(true);
+            }),
+        );
+
+        let window_commands = [
+            ("Toggle settings window", Windows::Settings),
+            ("Toggle nodes window", Windows::Nodes),
+            ("Toggle node details window", Windows::NodeDetails),
+            ("Toggle paths window", Windows::Paths),
+            ("Toggle path comparison window", Windows::PathComparison),
+            ("Toggle shortest path window", Windows::ShortestPath),
+            ("Toggle bubbles window", Windows::Bubbles),
+            ("Toggle layers window", Windows::Layers),
+            ("Toggle sequence search window", Windows::SequenceSearch),
+            ("Toggle themes window", Windows::Themes),
+            ("Toggle overlays window", Windows::Overlays),
+            (
+                "Toggle annotation records window",
+                Windows::AnnotationRecords,
+            ),
+        ];
+
+        for (name, window) in window_commands.iter().copied() {
+            let command = GlobalCommand::new(move |app| {
+                let msg = GuiMsg::SetWindowOpen { window, open: None };
+                app.channels().gui_tx.send(msg).unwrap();
+            });
+            context_mgr.register_command(name, command);
+        }
+
+        // the bindings are fixed once the keymap has been loaded, and
+        // the output lists every bindable action, so it also serves
+        // as a starting point for a keymap.json
+        match input_manager.keymap_json() {
+            Ok(keymap) => {
+                context_mgr.register_command(
+                    "Copy key bindings as keymap config",
+                    GlobalCommand::new(move |app| {
+                        app.reactor.set_clipboard_contents(&keymap, true);
+                    }),
+                );
+            }
+            Err(e) => log::warn!("Error serializing key bindings: {:?}", e),
+        }
+
+        let gfa = gfa_file.to_string();
+        context_mgr.register_command(
+            "Copy view link",
+            GlobalCommand::new(move |app| {
+                let view = app.shared_state().view();
+                let saved = SavedView::new(view, app.dims());
+                match ViewLink::new(&gfa, saved).encode() {
+                    Ok(link) => app.reactor.set_clipboard_contents(&link, true),
+                    Err(e) => log::error!("Error encoding view link: {:?}", e),
+                }
+            }),
+        );
+
+        let gfa = gfa_file.to_string();
+        context_mgr.register_command(
+            "Go to view link in clipboard",
+            GlobalCommand::new(move |app| {
+                let text = match app.reactor.get_clipboard_contents(true) {
+                    Some(text) => text,
+                    None => return,
+                };
+
+                let link = match ViewLink::decode(&text) {
+                    Ok(link) => link,
+                    Err(e) => {
+                        log::warn!("Error decoding view link: {:?}", e);
+                        let toast =
+                            Toast::error(format!("Invalid view link: {}", e));
+                        let _ = app.channels().toast_tx.send(toast);
+                        return;
+                    }
+                };
+
+                if !link.matches_gfa(&gfa) {
+                    log::warn!(
+                        "View link is for {}, but {} is loaded",
+                        link.gfa,
+                        gfa
+                    );
+                    let toast = Toast::warning(format!(
+                        "View link is for a different GFA: {}",
+                        link.gfa
+                    ));
+                    let _ = app.channels().toast_tx.send(toast);
+                }
+
+                let view = link.view.view_for_dims(app.dims());
+                app.channels()
+                    .main_view_tx
+                    .send(MainViewMsg::GotoView(view))
+                    .unwrap();
+            }),
+        );
+    }
 
     if let Err(e) = context_mgr
         .load_rhai_modules("./scripts/context_actions/".into(), &gui.console)
@@ -485,6 +1334,11 @@ fn node_color(id) {
                             path_str,
                             e
                         );
+                        let toast = Toast::error(format!(
+                            "Error loading annotation file {}: {}",
+                            path_str, e
+                        ));
+                        app.channels().toast_tx.send(toast)?;
                     }
                 }
             }
@@ -493,7 +1347,7 @@ fn node_color(id) {
 
     let timer = std::time::Instant::now();
 
-    event_loop.run(move |event, _, control_flow| {
+    event_loop.run_return(|event, _, control_flow| {
 
         *control_flow = ControlFlow::Poll;
 
@@ -550,6 +1404,20 @@ fn node_color(id) {
 
                 app.shared_state().hover_node.store(hover_node);
 
+                // nodes under the GUI can't be clicked, so they
+                // shouldn't look like they can
+                let over_gui =
+                    app.shared_state().gui_focus_state.mouse_over_gui();
+                let highlight = hover_node.filter(|_| !over_gui);
+                main_view.set_highlighted_node(highlight).unwrap();
+
+                let hover_layer_node = main_view
+                    .read_layer_node_at(mouse_pos)
+                    .filter(|_| !over_gui);
+                main_view.set_highlighted_layer_node(hover_layer_node).unwrap();
+
+                app.update_system_theme();
+
                 if app.selection_changed() {
                     if let Some(selected) = app.selected_nodes() {
 
@@ -565,6 +1433,10 @@ fn node_color(id) {
                         let mut nodes = selected.iter().copied().collect::<Vec<_>>();
                         nodes.sort();
 
+                        gui.app_view_state()
+                            .path_list()
+                            .send(PathListMsg::SetSelection(nodes.clone()));
+
                         gui.app_view_state()
                             .node_list()
                             .send(NodeListMsg::SetFiltered(nodes));
@@ -577,6 +1449,10 @@ fn node_color(id) {
                             .node_list()
                             .send(NodeListMsg::SetFiltered(Vec::new()));
 
+                        gui.app_view_state()
+                            .path_list()
+                            .send(PathListMsg::SetSelection(Vec::new()));
+
                         main_view.clear_node_selection().unwrap();
                     }
                 }
@@ -612,7 +1488,9 @@ fn node_color(id) {
                     }
 
                     if let AppMsg::TranslateSelected(delta) = &app_msg {
-                        if select_fence_id.is_none() && translate_fence_id.is_none() {
+                        if select_fence_id.is_none()
+                            && translate_fence_id.is_none()
+                            && relax_fence_id.is_none() {
 
                             let fence_id = node_translation
                                 .translate_nodes(
@@ -637,17 +1515,33 @@ fn node_color(id) {
                 gui.apply_received_gui_msgs(&mut app.reactor);
 
                 while let Ok(main_view_msg) = main_view.main_view_msg_rx().try_recv() {
-                    main_view.apply_msg(main_view_msg);
+                    main_view.apply_msg(&gfaestus, main_view_msg);
                 }
 
                 while let Ok(new_overlay) = new_overlay_rx.try_recv() {
-                    if let Ok(_) = handle_new_overlay(
+                    if let Ok(overlay_id) = handle_new_overlay(
                         app.shared_state().overlay_state(),
                         &gfaestus,
                         &mut main_view,
                         graph_query.node_count(),
-                        new_overlay
+                        &new_overlay
                     ) {
+                        let name = new_overlay.name().to_string();
+
+                        // the GUI and cache keep a value for every node,
+                        // so sparse updates are applied to the old data
+                        let old_data = gui.overlay_data(overlay_id);
+                        let data = new_overlay.into_data().into_dense(
+                            old_data.as_deref(),
+                            graph_query.node_count(),
+                        );
+
+                        if let Some(cache) = overlay_cache.as_ref() {
+                            if let Err(err) = cache.save(&name, &data) {
+                                error!("Error saving overlay {}: {:?}", name, err);
+                            }
+                        }
+
                         gui.populate_overlay_list(
                             main_view
                                 .node_draw_system
@@ -655,10 +1549,42 @@ fn node_color(id) {
                                 .overlay_names()
                                 .into_iter(),
                         );
+
+                        gui.set_overlay_data(overlay_id, data);
+                    }
+                }
+
+                if app.shared_state().path_colors().take_changed() {
+                    if let Err(err) = update_path_colors_overlay(
+                        app.shared_state().path_colors(),
+                        &gfaestus,
+                        &mut main_view,
+                        &mut gui,
+                    ) {
+                        error!("Error updating path colors overlay: {:?}", err);
                     }
                 }
             }
             Event::MainEventsCleared => {
+                if app.take_reload_request() {
+                    session_end = SessionEnd::Reload {
+                        view: main_view.view(),
+                        node_count: graph_query.node_count(),
+                    };
+                    *control_flow = ControlFlow::Exit;
+                }
+
+                if app.take_command_palette_request() {
+                    context_mgr.open_command_palette();
+                }
+
+                if app.take_svg_export_request() {
+                    export_view_svg(&app,
+                                    &main_view,
+                                    &gui,
+                                    universe.layout().nodes());
+                }
+
                 let screen_dims = app.dims();
                 let mouse_pos = app.mouse_pos();
                 main_view.update_view_animation(screen_dims, mouse_pos);
@@ -696,17 +1622,20 @@ fn node_color(id) {
                 if timer.elapsed().as_millis() > 400 {
                     let cur_overlay = app.shared_state().overlay_state().current_overlay();
                     let cur_gradient = app.shared_state().overlay_state().gradient();
+                    let cur_reversed = app.shared_state().overlay_state().gradient_reversed();
 
                     if path_view.fence_id().is_none()
                         && (cur_overlay != prev_overlay ||
                             path_view.should_rerender() ||
                             // rerender_path_view ||
-                            cur_gradient != prev_gradient)
+                            cur_gradient != prev_gradient ||
+                            cur_reversed != prev_reversed)
                     {
                         // log::warn!("doing the paths");
 
                         prev_overlay = cur_overlay;
                         prev_gradient = cur_gradient;
+                        prev_reversed = cur_reversed;
 
                         let overlay =
                             app.shared_state().overlay_state().current_overlay().unwrap();
@@ -759,6 +1688,33 @@ fn node_color(id) {
                     }
                 }
 
+                // the relaxation is only dispatched once the previous
+                // dispatch has finished, so the render thread never
+                // has to wait on it
+                if let Some(fid) = relax_fence_id {
+                    if compute_manager.is_fence_ready(fid).unwrap() {
+                        compute_manager.block_on_fence(fid).unwrap();
+                        compute_manager.free_fence(fid, false).unwrap();
+
+                        relaxed_positions_stale = true;
+                        relax_fence_id = None;
+                    }
+                } else if main_view.relaxation_active() && translate_fence_id.is_none() {
+                    let fence_id = layout_relaxation
+                        .relax_nodes(
+                            &mut compute_manager,
+                            &main_view.node_draw_system.vertices,
+                            main_view.relaxation_steps(),
+                        ).unwrap();
+
+                    relax_fence_id = Some(fence_id);
+                } else if relaxed_positions_stale && !main_view.relaxation_active() {
+                    universe.update_positions_from_gpu(&gfaestus,
+                                                       &main_view.node_draw_system.vertices).unwrap();
+
+                    relaxed_positions_stale = false;
+                }
+
 
 
                 if let Some(fid) = path_view.fence_id() {
@@ -824,6 +1780,21 @@ fn node_color(id) {
                 }
 
                 let frame_t = std::time::Instant::now();
+                frame_interval_history[frame % FRAME_HISTORY_LEN] =
+                    frame_t.duration_since(last_frame_start).as_secs_f32();
+                last_frame_start = frame_t;
+
+                let msaa_samples = app.settings.msaa_samples().load();
+                if msaa_samples != gfaestus.msaa_samples {
+                    let prev_samples = gfaestus.msaa_samples;
+                    let chosen = gfaestus.set_msaa_samples(msaa_samples);
+                    app.settings.msaa_samples().store(chosen);
+
+                    if chosen != prev_samples {
+                        dirty_swapchain = true;
+                        dirty_pipelines = true;
+                    }
+                }
 
                 if dirty_swapchain {
                     let size = window.inner_size();
@@ -839,17 +1810,31 @@ fn node_color(id) {
                             gfaestus.node_attachments.mask_resolve,
                         );
 
-                        selection_blur.write_descriptor_set(
+                        selection_blur.write_descriptor_sets(
                             gfaestus.vk_context().device(),
-                            gfaestus.offscreen_attachment.color,
+                            &gfaestus.offscreen_attachment,
                         );
 
                         main_view
                             .recreate_node_id_buffer(&gfaestus, size.width, size.height)
                             .unwrap();
 
-                        let new_initial_view =
-                            View::from_dims_and_target(app.dims(), top_left, bottom_right);
+                        if dirty_pipelines {
+                            main_view
+                                .node_draw_system
+                                .recreate_pipelines(&gfaestus)
+                                .unwrap();
+
+                            for er in edge_renderer.iter_mut() {
+                                er.recreate_pipeline(&gfaestus).unwrap();
+                            }
+
+                            dirty_pipelines = false;
+                        }
+
+                        let new_initial_view = preserved_view.unwrap_or_else(|| {
+                            initial_view_from_args(&args, app.dims(), top_left, bottom_right)
+                        });
                         if initial_view.is_none()
                             && initial_resize_timer.elapsed().as_millis() > 100
                         {
@@ -861,6 +1846,11 @@ fn node_color(id) {
                             Some(new_initial_view.center),
                             Some(new_initial_view.scale),
                         );
+                        main_view.set_layout_bounds(
+                            app.dims(),
+                            top_left,
+                            bottom_right,
+                        );
                     } else {
                         log::debug!("Can't recreate swapchain with a zero resolution");
                         return;
@@ -874,6 +1864,7 @@ fn node_color(id) {
                     &app,
                     &context_mgr,
                     universe.layout().nodes(),
+                    universe.layout_generation(),
                 );
 
 
@@ -901,6 +1892,19 @@ fn node_color(id) {
                                                       app.settings.label_radius().load(),
                                                       view);
                     cluster_tree.draw_labels(labels, &gui.ctx, shared_state);
+
+                    if let Some(min_px) = app.settings.node_id_labels().load() {
+                        node_center_grid.update(universe.layout_generation(),
+                                                universe.layout().nodes());
+
+                        gfaestus::gui::text::draw_node_id_labels(
+                            &gui.ctx,
+                            &node_center_grid,
+                            universe.layout().nodes(),
+                            view,
+                            min_px,
+                        );
+                    }
                 }
 
                 // context_mgr.end_frame();
@@ -908,6 +1912,7 @@ fn node_color(id) {
 
                 context_mgr.begin_frame();
                 context_mgr.show(&gui.ctx, &app);
+                context_mgr.show_command_palette(&gui.ctx, &app);
 
                 let meshes = gui.end_frame(&mut app.reactor);
 
@@ -926,6 +1931,7 @@ fn node_color(id) {
                 let node_id_image = gfaestus.node_attachments.id_resolve.image;
 
                 let offscreen_image = gfaestus.offscreen_attachment.color.image;
+                let offscreen_blur_image = gfaestus.offscreen_attachment.blur.image;
 
                 let overlay =
                     app.shared_state().overlay_state().current_overlay();
@@ -938,6 +1944,27 @@ fn node_color(id) {
                 let edges_enabled = edges_enabled &&
                     !matches!(renderer_config.edges, EdgeRendererType::Disabled);
 
+                // edges are culled when zoomed out past the limit, if set
+                let edges_enabled = edges_enabled &&
+                    app.settings
+                        .edge_cull_scale()
+                        .load()
+                        .map_or(true, |max_scale| current_view.scale <= max_scale);
+
+                let node_culling = app.settings.node_culling().load();
+
+                let selection_outline =
+                    app.settings.selection_outline().load() && app.has_selection();
+
+                let outline_style = app.settings.selection_outline_style().load();
+                let scale_factor = window.scale_factor() as f32;
+
+                if node_culling {
+                    main_view.prepare_node_culling(&gfaestus).unwrap();
+                }
+
+                main_view.update_layers(&gfaestus).unwrap();
+
                 let debug_utils = gfaestus.vk_context().debug_utils().map(|u| u.to_owned());
 
                 let debug_utils = debug_utils.as_ref();
@@ -949,6 +1976,22 @@ fn node_color(id) {
                         log::trace!("In draw_frame_from callback");
                         let size = swapchain_dims;
 
+                        let mut pass_timestamps = pass_timestamps.as_mut();
+
+                        if let Some(timestamps) = pass_timestamps.as_mut() {
+                            timestamps.begin_frame(device, cmd_buf);
+                        }
+
+                        let mut time_pass = |pass: GpuPass, end: bool| {
+                            if let Some(timestamps) = pass_timestamps.as_mut() {
+                                if end {
+                                    timestamps.end_pass(device, cmd_buf, pass);
+                                } else {
+                                    timestamps.begin_pass(device, cmd_buf, pass);
+                                }
+                            }
+                        };
+
                         debug::begin_cmd_buf_label(
                             debug_utils,
                             cmd_buf,
@@ -974,9 +2017,14 @@ fn node_color(id) {
                                 })
                                 .build();
 
+                            let offscreen_blur_barrier = vk::ImageMemoryBarrier {
+                                image: offscreen_blur_image,
+                                ..offscreen_image_barrier
+                            };
+
                             let memory_barriers = [];
                             let buffer_memory_barriers = [];
-                            let image_memory_barriers = [offscreen_image_barrier];
+                            let image_memory_barriers = [offscreen_image_barrier, offscreen_blur_barrier];
                             device.cmd_pipeline_barrier(
                                 cmd_buf,
                                 vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
@@ -997,10 +2045,15 @@ fn node_color(id) {
                         );
 
                         let gradient_name = app.shared_state().overlay_state().gradient();
-                        let gradient = gradients.gradient(gradient_name).unwrap();
+                        let gradient_reversed = app.shared_state().overlay_state().gradient_reversed();
+                        let gradient = gradients
+                            .gradient_oriented(gradient_name, gradient_reversed)
+                            .unwrap();
 
                         log::trace!("Drawing nodes");
+                        time_pass(GpuPass::Nodes, false);
                         main_view.draw_nodes(
+                            node_culling,
                             cmd_buf,
                             node_pass,
                             framebuffers,
@@ -1009,6 +2062,7 @@ fn node_color(id) {
                             overlay,
                             gradient,
                         ).unwrap();
+                        time_pass(GpuPass::Nodes, true);
 
 
                         debug::end_cmd_buf_label(debug_utils, cmd_buf);
@@ -1083,69 +2137,84 @@ fn node_color(id) {
                             );
                         }
 
-                        debug::begin_cmd_buf_label(
-                            debug_utils,
-                            cmd_buf,
-                            "Node selection border",
-                        );
-
-                        log::trace!("Drawing selection border edge detection");
-                        selection_edge
-                            .draw(
-                                &device,
-                                cmd_buf,
-                                edge_pass,
-                                framebuffers,
-                                [size.width as f32, size.height as f32],
-                            )
-                            .unwrap();
-
-                        log::trace!("Selection border edge detection -- image transitions");
-                        unsafe {
-                            let image_memory_barrier = vk::ImageMemoryBarrier::builder()
-                                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
-                                .dst_access_mask(vk::AccessFlags::SHADER_READ)
-                                .old_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-                                .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-                                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-                                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-                                .image(offscreen_image)
-                                .subresource_range(vk::ImageSubresourceRange {
-                                    aspect_mask: vk::ImageAspectFlags::COLOR,
-                                    base_mip_level: 0,
-                                    level_count: 1,
-                                    base_array_layer: 0,
-                                    layer_count: 1,
-                                })
-                                .build();
-
-                            let memory_barriers = [];
-                            let buffer_memory_barriers = [];
-                            let image_memory_barriers = [image_memory_barrier];
-                            // let image_memory_barriers = [];
-                            device.cmd_pipeline_barrier(
+                        // the outline passes only draw the selection
+                        // border, so with nothing selected they'd only
+                        // blend a transparent image onto the frame
+                        if selection_outline {
+                            debug::begin_cmd_buf_label(
+                                debug_utils,
                                 cmd_buf,
-                                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
-                                vk::PipelineStageFlags::FRAGMENT_SHADER,
-                                vk::DependencyFlags::BY_REGION,
-                                &memory_barriers,
-                                &buffer_memory_barriers,
-                                &image_memory_barriers,
+                                "Node selection border",
                             );
-                        }
 
-                        log::trace!("Drawing selection border blur");
-                        selection_blur
-                            .draw(
-                                &device,
-                                cmd_buf,
-                                blur_pass,
-                                framebuffers,
-                                [size.width as f32, size.height as f32],
-                            )
-                            .unwrap();
+                            log::trace!("Drawing selection border edge detection");
+                            time_pass(GpuPass::EdgeDetect, false);
+                            selection_edge
+                                .draw(
+                                    &device,
+                                    cmd_buf,
+                                    edge_pass,
+                                    framebuffers,
+                                    [size.width as f32, size.height as f32],
+                                    outline_style,
+                                    scale_factor,
+                                )
+                                .unwrap();
+                            time_pass(GpuPass::EdgeDetect, true);
+
+                            log::trace!("Selection border edge detection -- image transitions");
+                            unsafe {
+                                let image_memory_barrier = vk::ImageMemoryBarrier::builder()
+                                    .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                                    .dst_access_mask(vk::AccessFlags::SHADER_READ)
+                                    .old_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                                    .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                                    .image(offscreen_image)
+                                    .subresource_range(vk::ImageSubresourceRange {
+                                        aspect_mask: vk::ImageAspectFlags::COLOR,
+                                        base_mip_level: 0,
+                                        level_count: 1,
+                                        base_array_layer: 0,
+                                        layer_count: 1,
+                                    })
+                                    .build();
+
+                                let memory_barriers = [];
+                                let buffer_memory_barriers = [];
+                                let image_memory_barriers = [image_memory_barrier];
+                                // let image_memory_barriers = [];
+                                device.cmd_pipeline_barrier(
+                                    cmd_buf,
+                                    vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                                    vk::PipelineStageFlags::FRAGMENT_SHADER,
+                                    vk::DependencyFlags::BY_REGION,
+                                    &memory_barriers,
+                                    &buffer_memory_barriers,
+                                    &image_memory_barriers,
+                                );
+                            }
 
-                        debug::end_cmd_buf_label(debug_utils, cmd_buf);
+                            log::trace!("Drawing selection border blur");
+                            time_pass(GpuPass::Blur, false);
+                            selection_blur
+                                .draw(
+                                    &device,
+                                    cmd_buf,
+                                    edge_pass,
+                                    blur_pass,
+                                    framebuffers,
+                                    offscreen_blur_image,
+                                    [size.width as f32, size.height as f32],
+                                    outline_style,
+                                    scale_factor,
+                                )
+                                .unwrap();
+                            time_pass(GpuPass::Blur, true);
+
+                            debug::end_cmd_buf_label(debug_utils, cmd_buf);
+                        }
 
                         debug::begin_cmd_buf_label(
                             debug_utils,
@@ -1154,6 +2223,7 @@ fn node_color(id) {
                         );
 
                         log::trace!("Drawing GUI");
+                        time_pass(GpuPass::Gui, false);
                         gui.draw(
                             cmd_buf,
                             gui_pass,
@@ -1161,6 +2231,7 @@ fn node_color(id) {
                             size.into(),
                         )
                         .unwrap();
+                        time_pass(GpuPass::Gui, true);
 
                         debug::end_cmd_buf_label(debug_utils, cmd_buf);
 
@@ -1170,6 +2241,21 @@ fn node_color(id) {
                 let size = window.inner_size();
                 dirty_swapchain = gfaestus.draw_frame_from([size.width, size.height], draw).unwrap();
 
+                // unless the swapchain must be recreated, the frame has
+                // finished executing by now, so the culling results can
+                // be read back
+                if !dirty_swapchain {
+                    if let Err(err) = main_view.read_node_cull_results() {
+                        log::warn!("Error reading back node culling results: {:?}", err);
+                    }
+                }
+
+                // the times are from a couple of frames ago, which
+                // are done by now, so reading them doesn't stall
+                let pass_times = pass_timestamps.as_mut().and_then(|timestamps| {
+                    timestamps.end_frame(gfaestus.vk_context().device())
+                });
+
                 if !dirty_swapchain {
                     let screen_dims = app.dims();
 
@@ -1189,12 +2275,17 @@ fn node_color(id) {
 
                 log::trace!("Calculating FPS");
                 let frame_time = frame_t.elapsed().as_secs_f32();
-                frame_time_history[frame % frame_time_history.len()] = frame_time;
+                frame_time_history[frame % frame_time_history.len()] = FrameSample {
+                    time: frame_time,
+                    selection_outline,
+                    pass_times,
+                };
 
                 if frame > FRAME_HISTORY_LEN && frame % FRAME_HISTORY_LEN == 0 {
-                    let ft_sum: f32 = frame_time_history.iter().sum();
+                    let ft_sum: f32 = frame_time_history.iter().map(|s| s.time).sum();
                     let avg = ft_sum / (FRAME_HISTORY_LEN as f32);
-                    let fps = 1.0 / avg;
+                    let interval_sum: f32 = frame_interval_history.iter().sum();
+                    let fps = (FRAME_HISTORY_LEN as f32) / interval_sum;
                     let avg_ms = avg * 1000.0;
 
                     gui.app_view_state().fps().send(FrameRateMsg(FrameRate {
@@ -1202,6 +2293,54 @@ fn node_color(id) {
                         frame_time: avg_ms,
                         frame,
                     }));
+
+                    // oldest sample first
+                    let start = (frame + 1) % FRAME_HISTORY_LEN;
+                    let samples = frame_time_history[start..]
+                        .iter()
+                        .chain(frame_time_history[..start].iter())
+                        .copied()
+                        .collect::<Vec<_>>();
+                    gui.app_view_state().frame_times().send(FrameTimesMsg(samples));
+
+                    // without culling, every node is drawn
+                    let visible_nodes = main_view
+                        .visible_node_count()
+                        .unwrap_or(stats.node_count);
+
+                    gui.app_view_state().graph_stats().send(GraphStatsMsg {
+                        visible_nodes: Some(visible_nodes),
+                        lod_active: Some(main_view.node_lod_active()),
+                        ..GraphStatsMsg::default()
+                    });
+                }
+
+                if let Some(idle_fps) = app.settings.idle_fps().load() {
+                    // only wait when nothing is moving or about to
+                    // change; any new window event ends the wait early
+                    let idle = initialized_view
+                        && timer.elapsed().as_millis() > 500
+                        && !dirty_swapchain
+                        && !main_view.is_animating()
+                        && !gui.needs_repaint()
+                        && select_fence_id.is_none()
+                        && translate_fence_id.is_none()
+                        && relax_fence_id.is_none()
+                        && path_view.fence_id().is_none()
+                        && app_rx.is_empty()
+                        && gui_rx.is_empty()
+                        && main_view_rx.is_empty()
+                        && app.channels().app_rx.is_empty()
+                        && main_view.main_view_msg_rx().is_empty()
+                        && new_overlay_rx.is_empty();
+
+                    if idle && *control_flow != ControlFlow::Exit {
+                        let period = std::time::Duration::from_secs_f32(
+                            1.0 / idle_fps as f32,
+                        );
+                        *control_flow =
+                            ControlFlow::WaitUntil(frame_t + period);
+                    }
                 }
 
                 frame += 1;
@@ -1227,16 +2366,37 @@ fn node_color(id) {
                 main_view.node_id_buffer.destroy(device);
                 main_view.node_draw_system.destroy(&gfaestus);
 
+                layout_relaxation.destroy(&gfaestus).unwrap();
+
+                main_view.layers.destroy(&gfaestus).unwrap();
+
+                for er in edge_renderer.iter_mut() {
+                    er.destroy();
+                }
+
                 gui.draw_system.destroy(&gfaestus.allocator);
 
                 selection_edge.destroy(device);
                 selection_blur.destroy(device);
+
+                if let Some(timestamps) = pass_timestamps.as_ref() {
+                    timestamps.destroy(device);
+                }
+
+                gpu_selection.destroy(device);
+                node_translation.destroy();
+                path_view.destroy(&gfaestus).unwrap();
+
+                gradients_.destroy(device);
+                gradients.destroy(device);
+
+                compute_manager.destroy();
             }
             _ => (),
         }
     });
 
-    Ok(())
+    Ok((gfaestus, session_end))
 }
 
 fn handle_new_overlay(
@@ -1244,47 +2404,234 @@ fn handle_new_overlay(
     app: &GfaestusVk,
     main_view: &mut MainView,
     node_count: usize,
-    msg: OverlayCreatorMsg,
-) -> Result<()> {
-    let OverlayCreatorMsg::NewOverlay { name, data } = msg;
-
-    let overlay = match data {
-        OverlayData::RGB(data) => {
-            let mut overlay =
-                Overlay::new_empty_rgb(&name, app, node_count).unwrap();
-
-            overlay
-                .update_rgb_overlay(
-                    data.iter()
-                        .enumerate()
-                        .map(|(ix, col)| (NodeId::from((ix as u64) + 1), *col)),
-                )
-                .unwrap();
-
-            overlay
+    msg: &OverlayCreatorMsg,
+) -> Result<usize> {
+    let name = msg.name();
+    let data = msg.data();
+
+    let pipelines = &mut main_view.node_draw_system.pipelines;
+
+    if let OverlayCreatorMsg::UpdateOverlay { .. } = msg {
+        if let Some(id) = pipelines.find_overlay(name, data.kind()) {
+            let overlay = pipelines.overlay_mut(id).unwrap();
+            if let OverlayData::Category { palette, .. } = data {
+                overlay.set_palette(app, palette)?;
+            }
+            // sparse data only covers some nodes, so the previous
+            // values of the rest must be replaced by the background
+            fill_sparse_background(overlay, data)?;
+            write_overlay_data(overlay, data)?;
+            return Ok(id);
         }
-        OverlayData::Value(data) => {
-            let mut overlay =
-                Overlay::new_empty_value(&name, &app, node_count).unwrap();
-
-            overlay
-                .update_value_overlay(
-                    data.iter()
-                        .enumerate()
-                        .map(|(ix, v)| (NodeId::from((ix as u64) + 1), *v)),
-                )
-                .unwrap();
+    }
 
-            overlay
+    let mut overlay = match data {
+        OverlayData::RGB(_) | OverlayData::SparseRGB { .. } => {
+            Overlay::new_empty_rgb(&name, app, node_count)?
+        }
+        OverlayData::Value(_) | OverlayData::SparseValue { .. } => {
+            Overlay::new_empty_value(&name, &app, node_count)?
+        }
+        OverlayData::Category { palette, .. } => {
+            Overlay::new_empty_category(&name, &app, node_count, palette)?
         }
     };
 
-    let id = main_view.node_draw_system.pipelines.create_overlay(overlay);
+    // the buffer is uninitialized, so sparse data needs a background
+    fill_sparse_background(&mut overlay, data)?;
+    write_overlay_data(&mut overlay, data)?;
+
+    let id = pipelines.create_overlay(overlay);
     overlay_state.current_overlay.store(Some(id));
 
+    Ok(id)
+}
+
+/// Swap in the current path colors as the palette of the path
+/// membership overlay, leaving the node categories as they are
+fn update_path_colors_overlay(
+    path_colors: &PathColors,
+    app: &GfaestusVk,
+    main_view: &mut MainView,
+    gui: &mut Gui,
+) -> Result<()> {
+    let pipelines = &mut main_view.node_draw_system.pipelines;
+
+    let overlay_id = match pipelines
+        .find_overlay(PathColors::OVERLAY_NAME, OverlayKind::Category)
+    {
+        Some(id) => id,
+        None => return Ok(()),
+    };
+
+    let palette = path_colors.overlay_palette();
+
+    let overlay = pipelines.overlay_mut(overlay_id).unwrap();
+    overlay.set_palette(app, &palette)?;
+
+    gui.set_overlay_palette(overlay_id, palette);
+
     Ok(())
 }
 
+/// Ask for a file to export the current view to as an SVG, and write
+/// it on the thread pool, reporting the result in a toast
+fn export_view_svg(app: &App, main_view: &MainView, gui: &Gui, nodes: &[Node]) {
+    let view = main_view.view();
+    let screen_dims = app.dims();
+    let node_width = main_view.node_width(view);
+    let width_factors = main_view.node_width_factors().to_vec();
+
+    let dark_mode = app.shared_state().dark_mode().load();
+    let background = app.settings.background_color(dark_mode);
+
+    let overlay_state = app.shared_state().overlay_state();
+    let gradient = overlay_state.gradient();
+    let reversed = overlay_state.gradient_reversed();
+
+    let overlay_data = overlay_state
+        .current_overlay()
+        .and_then(|id| gui.overlay_data(id));
+
+    // nodes without overlay data get the theme's foreground color,
+    // faded like the nodes without a value in the main view
+    let fg = app.settings.edge_renderer().load().edge_color;
+    let default_color = rgb::RGBA::new(fg.r, fg.g, fg.b, 0.3);
+
+    let nodes = nodes.to_vec();
+
+    let path_future = gfaestus::reactor::save_file_picker_modal(
+        app.channels().modal_tx.clone(),
+        &app.shared_state().show_modal,
+        &["svg"],
+        None,
+        "gfaestus_view.svg",
+    );
+
+    let toast_tx = app.channels().toast_tx.clone();
+
+    app.reactor.thread_pool.spawn_ok(async move {
+        let path = if let Some(path) = path_future.await {
+            path
+        } else {
+            return;
+        };
+
+        let node_color = |ix: usize| {
+            overlay_data
+                .as_ref()
+                .and_then(|data| data.node_color(ix, gradient, reversed))
+                .unwrap_or(default_color)
+        };
+
+        let options = gfaestus::svg::SvgViewOptions {
+            view,
+            screen_dims,
+            node_width,
+            width_factors: &width_factors,
+            background,
+        };
+
+        let result = std::fs::File::create(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| {
+                gfaestus::svg::write_view_svg(
+                    std::io::BufWriter::new(file),
+                    &nodes,
+                    &options,
+                    node_color,
+                )
+            });
+
+        let toast = match result {
+            Ok(_) => {
+                log::info!("Exported view to {}", path.display());
+                Toast::info(format!("Exported view to {}", path.display()))
+            }
+            Err(err) => {
+                log::error!("Error exporting view as SVG: {:?}", err);
+                Toast::error(format!("Error exporting view as SVG: {}", err))
+            }
+        };
+
+        let _ = toast_tx.send(toast);
+    });
+}
+
+/// Fill the overlay with the default value of sparse overlay data, so
+/// that nodes without a value get the background; other overlay data
+/// covers every node, and is left alone
+fn fill_sparse_background(
+    overlay: &mut Overlay,
+    data: &OverlayData,
+) -> Result<()> {
+    match data {
+        OverlayData::SparseRGB { default, .. } => {
+            overlay.fill_rgb_overlay(*default)
+        }
+        OverlayData::SparseValue { default, .. } => {
+            overlay.fill_value_overlay(*default)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn write_overlay_data(overlay: &mut Overlay, data: &OverlayData) -> Result<()> {
+    match data {
+        OverlayData::RGB(data) => overlay.update_rgb_overlay(
+            data.iter()
+                .enumerate()
+                .map(|(ix, col)| (NodeId::from((ix as u64) + 1), *col)),
+        ),
+        OverlayData::Value(data) => overlay.update_value_overlay(
+            data.iter()
+                .enumerate()
+                .map(|(ix, v)| (NodeId::from((ix as u64) + 1), *v)),
+        ),
+        OverlayData::Category { categories, .. } => overlay
+            .update_category_overlay(
+                categories
+                    .iter()
+                    .enumerate()
+                    .map(|(ix, c)| (NodeId::from((ix as u64) + 1), *c)),
+            ),
+        OverlayData::SparseRGB { colors, .. } => {
+            overlay.update_rgb_overlay(colors.iter().copied())
+        }
+        OverlayData::SparseValue { values, .. } => {
+            overlay.update_value_overlay(values.iter().copied())
+        }
+    }
+}
+
+const NODE_SEQ_HASH_SCRIPT: &str = "
+fn node_color(id) {
+  let h = handle(id, false);
+  let seq = graph.sequence(h);
+  let hash = hash_bytes(seq);
+  let color = hash_color(hash);
+  color
+}
+";
+
+const STEP_COUNT_SCRIPT: &str = "
+fn node_color(id) {
+  let h = handle(id, false);
+
+  let steps = graph.steps_on_handle(h);
+  let count = 0.0;
+
+  for step in steps {
+    count += 1.0;
+  }
+
+  count
+}
+";
+
+/// Runs an overlay script and creates the overlay from its output,
+/// returning the overlay ID and the data for the GUI, or `None` if
+/// the script failed
 fn create_overlay(
     overlay_state: &OverlayState,
     app: &GfaestusVk,
@@ -1292,7 +2639,7 @@ fn create_overlay(
     reactor: &Reactor,
     name: &str,
     script: &str,
-) -> Result<()> {
+) -> Result<Option<(usize, OverlayData)>> {
     let node_count = reactor.graph_query.graph.node_count();
 
     let script_config = gfaestus::script::ScriptConfig {
@@ -1305,15 +2652,26 @@ fn create_overlay(
         &script_config,
         &reactor.graph_query,
         script,
+        None,
     ) {
         let msg = OverlayCreatorMsg::NewOverlay {
             name: name.to_string(),
             data,
         };
-        handle_new_overlay(overlay_state, app, main_view, node_count, msg)?;
+        let overlay_id = handle_new_overlay(
+            overlay_state,
+            app,
+            main_view,
+            node_count,
+            &msg,
+        )?;
+
+        let data = msg.into_data().into_dense(None, node_count);
+
+        return Ok(Some((overlay_id, data)));
     }
 
-    Ok(())
+    Ok(None)
 }
 
 fn draw_tree<T>(ctx: &egui::CtxRef, tree: &QuadTree<T>, app: &App)
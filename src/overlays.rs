@@ -1,3 +1,18 @@
+use handlegraph::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    packedgraph::PackedGraph,
+    pathhandlegraph::*,
+};
+
+use anyhow::Result;
+use rustc_hash::FxHashMap;
+
+use std::path::{Path, PathBuf};
+
+use crate::gfa::{gfa_path_hash, stable_hash};
+use crate::vulkan::texture::GradientName;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 /// Defines the type of mapping from node ID to colors used by an
 /// overlay script
@@ -8,11 +23,777 @@ pub enum OverlayKind {
     /// that can then be mapped to a color, e.g. using a perceptual
     /// color scheme
     Value,
+    /// Overlay scripts that produce an integer category for each
+    /// node, which is mapped to a color from a qualitative palette
+    Category,
 }
 
+/// The value of the nodes in a value overlay that have no value,
+/// which are drawn in a faded gray instead of a gradient color
+pub const MISSING_VALUE: f32 = std::f32::NAN;
+
 pub enum OverlayData {
     RGB(Vec<rgb::RGBA<f32>>),
     Value(Vec<f32>),
+    Category {
+        categories: Vec<u32>,
+        /// Category `c` is drawn using `palette[c % palette.len()]`
+        palette: Vec<rgb::RGB<f32>>,
+    },
+    /// Colors for only some of the nodes; the rest of the overlay is
+    /// drawn in `default` when the overlay is created, and left as it
+    /// was when an existing overlay is updated
+    SparseRGB {
+        default: rgb::RGBA<f32>,
+        colors: Vec<(NodeId, rgb::RGBA<f32>)>,
+    },
+    /// Values for only some of the nodes, see `SparseRGB`
+    SparseValue {
+        default: f32,
+        values: Vec<(NodeId, f32)>,
+    },
+}
+
+/// The default palette for categorical overlays, d3's category10
+pub fn category_palette() -> Vec<rgb::RGB<f32>> {
+    colorous::CATEGORY10
+        .iter()
+        .map(|color| {
+            let (r, g, b) = color.as_tuple();
+            let r = (r as f32) / 255.0;
+            let g = (g as f32) / 255.0;
+            let b = (b as f32) / 255.0;
+            rgb::RGB::new(r, g, b)
+        })
+        .collect()
+}
+
+impl OverlayData {
+    /// Create a categorical overlay using the default palette. If
+    /// there are more categories than palette entries, the palette
+    /// wraps around.
+    pub fn category(categories: Vec<u32>) -> Self {
+        let palette = category_palette();
+
+        if let Some(max) = categories.iter().max() {
+            let count = *max as usize + 1;
+            if count > palette.len() {
+                log::warn!(
+                    "Overlay has {} categories but the palette only has \
+                     {} colors, some categories will share a color",
+                    count,
+                    palette.len()
+                );
+            }
+        }
+
+        OverlayData::Category {
+            categories,
+            palette,
+        }
+    }
+
+    pub fn kind(&self) -> OverlayKind {
+        match self {
+            OverlayData::RGB(_) => OverlayKind::RGB,
+            OverlayData::Value(_) => OverlayKind::Value,
+            OverlayData::Category { .. } => OverlayKind::Category,
+            OverlayData::SparseRGB { .. } => OverlayKind::RGB,
+            OverlayData::SparseValue { .. } => OverlayKind::Value,
+        }
+    }
+
+    pub fn is_sparse(&self) -> bool {
+        matches!(
+            self,
+            OverlayData::SparseRGB { .. } | OverlayData::SparseValue { .. }
+        )
+    }
+
+    /// Expand sparse data into a value for each of the `node_count`
+    /// nodes, by writing the sparse entries on top of `base` if it's
+    /// dense data of the same kind, or on top of the default value
+    /// otherwise. Dense data is returned as is. Entries for node IDs
+    /// outside the graph are dropped.
+    pub fn into_dense(
+        self,
+        base: Option<&OverlayData>,
+        node_count: usize,
+    ) -> OverlayData {
+        match self {
+            OverlayData::SparseRGB { default, colors } => {
+                let mut dense = match base {
+                    Some(OverlayData::RGB(base))
+                        if base.len() == node_count =>
+                    {
+                        base.clone()
+                    }
+                    _ => vec![default; node_count],
+                };
+
+                for (node, color) in colors {
+                    let ix = (node.0 as usize).checked_sub(1);
+                    if let Some(c) = ix.and_then(|ix| dense.get_mut(ix)) {
+                        *c = color;
+                    }
+                }
+
+                OverlayData::RGB(dense)
+            }
+            OverlayData::SparseValue { default, values } => {
+                let mut dense = match base {
+                    Some(OverlayData::Value(base))
+                        if base.len() == node_count =>
+                    {
+                        base.clone()
+                    }
+                    _ => vec![default; node_count],
+                };
+
+                for (node, value) in values {
+                    let ix = (node.0 as usize).checked_sub(1);
+                    if let Some(v) = ix.and_then(|ix| dense.get_mut(ix)) {
+                        *v = value;
+                    }
+                }
+
+                OverlayData::Value(dense)
+            }
+            dense => dense,
+        }
+    }
+
+    /// The color the node shaders draw the node at index `ix` (node
+    /// ID - 1) with, using `gradient` for value overlays. Values are
+    /// clamped to the gradient, like the gradient texture sampler.
+    /// Returns `None` for nodes with the `MISSING_VALUE`.
+    pub fn node_color(
+        &self,
+        ix: usize,
+        gradient: GradientName,
+        reversed: bool,
+    ) -> Option<rgb::RGBA<f32>> {
+        match self {
+            OverlayData::RGB(colors) => colors.get(ix).copied(),
+            OverlayData::Value(values) => {
+                let val = values.get(ix).filter(|v| !v.is_nan())?;
+                Some(gradient_color(*val, gradient, reversed))
+            }
+            OverlayData::Category {
+                categories,
+                palette,
+            } => {
+                let cat = *categories.get(ix)? as usize;
+                let color = palette.get(cat % palette.len().max(1))?;
+                Some(rgb::RGBA::new(color.r, color.g, color.b, 1.0))
+            }
+            OverlayData::SparseRGB { default, colors } => {
+                let node = NodeId::from((ix as u64) + 1);
+                let color = colors
+                    .iter()
+                    .rev()
+                    .find(|(n, _)| *n == node)
+                    .map(|(_, c)| *c)
+                    .unwrap_or(*default);
+                Some(color)
+            }
+            OverlayData::SparseValue { default, values } => {
+                let node = NodeId::from((ix as u64) + 1);
+                let val = values
+                    .iter()
+                    .rev()
+                    .find(|(n, _)| *n == node)
+                    .map(|(_, v)| *v)
+                    .unwrap_or(*default);
+                if val.is_nan() {
+                    return None;
+                }
+                Some(gradient_color(val, gradient, reversed))
+            }
+        }
+    }
+
+    /// Write the overlay as a TSV with one row per node, with node
+    /// IDs starting at 1. Value overlays produce `node_id\tvalue`
+    /// rows, RGB overlays `node_id\tr\tg\tb`, and categorical
+    /// overlays `node_id\tcategory`. Sparse overlays, and value
+    /// overlays with missing values, only produce rows for the nodes
+    /// they have data for.
+    pub fn write_tsv<W: std::io::Write>(
+        &self,
+        mut out: W,
+    ) -> std::io::Result<()> {
+        match self {
+            OverlayData::RGB(colors) => {
+                for (ix, col) in colors.iter().enumerate() {
+                    writeln!(
+                        out,
+                        "{}\t{}\t{}\t{}",
+                        ix + 1,
+                        col.r,
+                        col.g,
+                        col.b
+                    )?;
+                }
+            }
+            OverlayData::Value(values) => {
+                for (ix, val) in values.iter().enumerate() {
+                    if !val.is_nan() {
+                        writeln!(out, "{}\t{}", ix + 1, val)?;
+                    }
+                }
+            }
+            OverlayData::Category { categories, .. } => {
+                for (ix, cat) in categories.iter().enumerate() {
+                    writeln!(out, "{}\t{}", ix + 1, cat)?;
+                }
+            }
+            OverlayData::SparseRGB { colors, .. } => {
+                for (node, col) in colors.iter() {
+                    writeln!(
+                        out,
+                        "{}\t{}\t{}\t{}",
+                        node.0, col.r, col.g, col.b
+                    )?;
+                }
+            }
+            OverlayData::SparseValue { values, .. } => {
+                for (node, val) in values.iter() {
+                    writeln!(out, "{}\t{}", node.0, val)?;
+                }
+            }
+        }
+        out.flush()
+    }
+
+    /// Identifies the binary overlay format, and its version
+    const BINARY_MAGIC: &'static [u8; 8] = b"GFSOVL01";
+
+    /// Write the overlay and its name as the magic bytes, the kind
+    /// as a byte, the length-prefixed name, and the node count,
+    /// followed by the per-node data. RGB colors are stored as four
+    /// bytes, values as f32s, and categories as u32s followed by the
+    /// length-prefixed palette of f32 RGB colors. Counts are u64s,
+    /// and everything is little-endian. Sparse overlays must be made
+    /// dense with `into_dense` first.
+    pub fn write_binary<W: std::io::Write>(
+        &self,
+        name: &str,
+        out: &mut W,
+    ) -> Result<()> {
+        if self.is_sparse() {
+            anyhow::bail!("Can't write sparse overlay {} as binary", name);
+        }
+
+        out.write_all(Self::BINARY_MAGIC)?;
+
+        let kind: u8 = match self.kind() {
+            OverlayKind::RGB => 0,
+            OverlayKind::Value => 1,
+            OverlayKind::Category => 2,
+        };
+        out.write_all(&[kind])?;
+
+        out.write_all(&(name.len() as u64).to_le_bytes())?;
+        out.write_all(name.as_bytes())?;
+
+        let to_u8 = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        match self {
+            OverlayData::RGB(colors) => {
+                out.write_all(&(colors.len() as u64).to_le_bytes())?;
+                for c in colors.iter() {
+                    out.write_all(&[
+                        to_u8(c.r),
+                        to_u8(c.g),
+                        to_u8(c.b),
+                        to_u8(c.a),
+                    ])?;
+                }
+            }
+            OverlayData::Value(values) => {
+                out.write_all(&(values.len() as u64).to_le_bytes())?;
+                for v in values.iter() {
+                    out.write_all(&v.to_le_bytes())?;
+                }
+            }
+            OverlayData::Category {
+                categories,
+                palette,
+            } => {
+                out.write_all(&(categories.len() as u64).to_le_bytes())?;
+                for cat in categories.iter() {
+                    out.write_all(&cat.to_le_bytes())?;
+                }
+
+                out.write_all(&(palette.len() as u64).to_le_bytes())?;
+                for c in palette.iter() {
+                    for v in [c.r, c.g, c.b].iter() {
+                        out.write_all(&v.to_le_bytes())?;
+                    }
+                }
+            }
+            OverlayData::SparseRGB { .. } | OverlayData::SparseValue { .. } => {
+                unreachable!()
+            }
+        }
+
+        out.flush()?;
+
+        Ok(())
+    }
+
+    /// Read an overlay written by `write_binary`, returning its name
+    /// and data. Fails if the overlay doesn't have `node_count` nodes.
+    pub fn read_binary<R: std::io::Read>(
+        node_count: usize,
+        input: &mut R,
+    ) -> Result<(String, Self)> {
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+
+        if &magic != Self::BINARY_MAGIC {
+            anyhow::bail!("Not a binary overlay file");
+        }
+
+        let mut kind = [0u8; 1];
+        input.read_exact(&mut kind)?;
+
+        let read_u64 = |input: &mut R| -> Result<u64> {
+            let mut buf = [0u8; 8];
+            input.read_exact(&mut buf)?;
+            Ok(u64::from_le_bytes(buf))
+        };
+
+        let read_4 = |input: &mut R| -> Result<[u8; 4]> {
+            let mut buf = [0u8; 4];
+            input.read_exact(&mut buf)?;
+            Ok(buf)
+        };
+
+        let name_len = read_u64(input)? as usize;
+        let mut name = vec![0u8; name_len];
+        input.read_exact(&mut name)?;
+        let name = String::from_utf8(name)?;
+
+        let count = read_u64(input)? as usize;
+
+        if count != node_count {
+            anyhow::bail!(
+                "Overlay {} has {} nodes, but the graph has {}",
+                name,
+                count,
+                node_count
+            );
+        }
+
+        let to_f32 = |c: u8| (c as f32) / 255.0;
+
+        let data = match kind[0] {
+            0 => {
+                let colors = (0..count)
+                    .map(|_| -> Result<_> {
+                        let [r, g, b, a] = read_4(input)?;
+                        Ok(rgb::RGBA::new(
+                            to_f32(r),
+                            to_f32(g),
+                            to_f32(b),
+                            to_f32(a),
+                        ))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                OverlayData::RGB(colors)
+            }
+            1 => {
+                let values = (0..count)
+                    .map(|_| read_4(input).map(f32::from_le_bytes))
+                    .collect::<Result<Vec<_>>>()?;
+                OverlayData::Value(values)
+            }
+            2 => {
+                let categories = (0..count)
+                    .map(|_| read_4(input).map(u32::from_le_bytes))
+                    .collect::<Result<Vec<_>>>()?;
+
+                let palette_len = read_u64(input)? as usize;
+                let palette = (0..palette_len)
+                    .map(|_| -> Result<_> {
+                        let r = f32::from_le_bytes(read_4(input)?);
+                        let g = f32::from_le_bytes(read_4(input)?);
+                        let b = f32::from_le_bytes(read_4(input)?);
+                        Ok(rgb::RGB::new(r, g, b))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                OverlayData::Category {
+                    categories,
+                    palette,
+                }
+            }
+            other => anyhow::bail!("Unknown overlay kind {}", other),
+        };
+
+        Ok((name, data))
+    }
+}
+
+/// The color of the value `val` in `gradient`, clamping the value
+/// to the gradient
+fn gradient_color(
+    val: f32,
+    gradient: GradientName,
+    reversed: bool,
+) -> rgb::RGBA<f32> {
+    let mut t = val.clamp(0.0, 1.0) as f64;
+    if reversed {
+        t = 1.0 - t;
+    }
+
+    let (r, g, b) = gradient.gradient().eval_continuous(t).as_tuple();
+    let to_f32 = |c: u8| (c as f32) / 255.0;
+
+    rgb::RGBA::new(to_f32(r), to_f32(g), to_f32(b), 1.0)
+}
+
+/// A directory of overlays created for a GFA file, so that they can
+/// be restored when the same GFA is loaded again.
+///
+/// The directory is keyed by the stable hash of the canonical GFA
+/// path, and stores the size and modification time of the GFA when
+/// the overlays were saved; if the GFA has changed since, the cached
+/// overlays are discarded.
+pub struct OverlayCache {
+    dir: PathBuf,
+    gfa_stamp: String,
+}
+
+impl OverlayCache {
+    const STAMP_FILE: &'static str = "gfa_stamp";
+
+    /// The cache for the GFA at `gfa_path`, in the user's cache
+    /// directory, or `None` if there is no cache directory or the GFA
+    /// can't be read
+    pub fn for_gfa(gfa_path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(gfa_path).ok()?;
+
+        let mtime = metadata
+            .modified()
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?;
+
+        let gfa_stamp = format!("{} {}", metadata.len(), mtime.as_nanos());
+
+        let mut dir = dirs::cache_dir()?;
+        dir.push("gfaestus");
+        dir.push("overlays");
+        dir.push(format!("{:016x}", gfa_path_hash(gfa_path)));
+
+        let cache = Self { dir, gfa_stamp };
+
+        if cache.dir.exists() && !cache.is_fresh() {
+            log::info!("GFA has changed, discarding cached overlays");
+
+            if let Err(err) = std::fs::remove_dir_all(&cache.dir) {
+                log::warn!("Error clearing overlay cache: {:?}", err);
+            }
+        }
+
+        Some(cache)
+    }
+
+    /// True if the cached overlays were saved for the current
+    /// version of the GFA
+    fn is_fresh(&self) -> bool {
+        std::fs::read_to_string(self.dir.join(Self::STAMP_FILE))
+            .map_or(false, |stamp| stamp == self.gfa_stamp)
+    }
+
+    fn overlay_file(&self, name: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}.bin", stable_hash(name.as_bytes())))
+    }
+
+    /// Store `data` as the overlay `name`, replacing any overlay with
+    /// the same name
+    pub fn save(&self, name: &str, data: &OverlayData) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+
+        if !self.is_fresh() {
+            std::fs::write(self.dir.join(Self::STAMP_FILE), &self.gfa_stamp)?;
+        }
+
+        let file = std::fs::File::create(self.overlay_file(name))?;
+        let mut out = std::io::BufWriter::new(file);
+        data.write_binary(name, &mut out)
+    }
+
+    /// Load all overlays in the cache that have `node_count` nodes;
+    /// files that can't be read are skipped with a warning
+    pub fn load_all(&self, node_count: usize) -> Vec<(String, OverlayData)> {
+        if !self.is_fresh() {
+            return Vec::new();
+        }
+
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut overlays = Vec::new();
+
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+
+            if path.extension().map_or(true, |ext| ext != "bin") {
+                continue;
+            }
+
+            let result = std::fs::File::open(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|file| {
+                    let mut input = std::io::BufReader::new(file);
+                    OverlayData::read_binary(node_count, &mut input)
+                });
+
+            match result {
+                Ok(overlay) => overlays.push(overlay),
+                Err(err) => {
+                    log::warn!(
+                        "Error loading cached overlay {:?}: {:?}",
+                        path,
+                        err
+                    );
+                }
+            }
+        }
+
+        overlays.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        overlays
+    }
+}
+
+/// Create a value overlay from the degree of each node, i.e. the
+/// number of neighbors on both sides.
+pub fn compute_degree_overlay(graph: &PackedGraph) -> OverlayData {
+    let mut handles = graph.handles().collect::<Vec<Handle>>();
+    handles.sort();
+
+    let degrees = handles
+        .into_iter()
+        .map(|handle| {
+            let left = graph.degree(handle, Direction::Left);
+            let right = graph.degree(handle, Direction::Right);
+            (left + right) as f32
+        })
+        .collect::<Vec<_>>();
+
+    normalized_value_overlay(degrees)
+}
+
+/// Create a value overlay from the path depth of each node, i.e. the
+/// number of times the node is visited by any path.
+pub fn compute_path_depth_overlay(graph: &PackedGraph) -> OverlayData {
+    let mut depths = vec![0.0f32; graph.node_count()];
+
+    for path in graph.path_ids() {
+        if let Some(steps) = graph.path_steps(path) {
+            for step in steps {
+                let ix = (step.handle().id().0 - 1) as usize;
+                if let Some(depth) = depths.get_mut(ix) {
+                    *depth += 1.0;
+                }
+            }
+        }
+    }
+
+    normalized_value_overlay(depths)
+}
+
+/// The values read from a table of node IDs and values, along with
+/// the problems found while reading it
+#[derive(Debug, Default, Clone)]
+pub struct NodeValueTable {
+    pub values: FxHashMap<NodeId, f32>,
+
+    /// Rows whose node isn't in the graph
+    pub unknown_nodes: usize,
+    /// Rows for nodes that already had a value; the later row wins
+    pub duplicates: usize,
+    /// Rows that couldn't be parsed, not counting a header row
+    pub invalid_rows: usize,
+}
+
+impl NodeValueTable {
+    /// Read a table with a node ID in the first column and a number
+    /// in the second, separated by tabs, or commas if a row has no
+    /// tabs. Other columns are ignored. Empty lines and lines
+    /// starting with `#` are skipped, and a first row after them that
+    /// can't be parsed is taken to be a header.
+    pub fn parse<R: std::io::BufRead>(
+        graph: &PackedGraph,
+        reader: R,
+    ) -> Result<Self> {
+        let mut table = Self::default();
+
+        let mut first_row = true;
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let is_first_row = std::mem::replace(&mut first_row, false);
+
+            let sep = if line.contains('\t') { '\t' } else { ',' };
+            let mut fields = line.split(sep).map(|f| f.trim());
+
+            let row = (|| {
+                let node = fields.next()?.parse::<u64>().ok()?;
+                let value = fields.next()?.parse::<f32>().ok()?;
+                Some((NodeId::from(node), value))
+            })();
+
+            let (node, value) = match row {
+                Some((node, value)) if value.is_finite() => (node, value),
+                _ => {
+                    if !is_first_row {
+                        table.invalid_rows += 1;
+                    }
+                    continue;
+                }
+            };
+
+            let in_graph = node.0 >= 1
+                && (node.0 as usize) <= graph.node_count()
+                && graph.has_node(node);
+
+            if !in_graph {
+                table.unknown_nodes += 1;
+                continue;
+            }
+
+            if table.values.insert(node, value).is_some() {
+                table.duplicates += 1;
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// A value overlay with the values normalized to the range 0-1,
+    /// and the nodes that aren't in the table set to `MISSING_VALUE`
+    pub fn to_overlay(&self, node_count: usize) -> OverlayData {
+        let min = self.values.values().copied().fold(std::f32::MAX, f32::min);
+        let max = self.values.values().copied().fold(std::f32::MIN, f32::max);
+        let range = max - min;
+
+        log::debug!("Node value table, min: {}, max: {}", min, max);
+
+        let mut values = vec![MISSING_VALUE; node_count];
+
+        for (node, value) in self.values.iter() {
+            let ix = (node.0 - 1) as usize;
+            if let Some(v) = values.get_mut(ix) {
+                *v = if range > 0.0 {
+                    (value - min) / range
+                } else {
+                    0.0
+                };
+            }
+        }
+
+        OverlayData::Value(values)
+    }
+}
+
+/// Colors for nodes by their membership in two sets, indexed by the
+/// membership bits: gray for neither, blue for only the first, orange
+/// for only the second, and purple for both
+pub const MEMBERSHIP_PALETTE: [rgb::RGB<f32>; 4] = [
+    rgb::RGB {
+        r: 0.5,
+        g: 0.5,
+        b: 0.5,
+    },
+    rgb::RGB {
+        r: 0.0,
+        g: 0.447,
+        b: 0.698,
+    },
+    rgb::RGB {
+        r: 0.835,
+        g: 0.369,
+        b: 0.0,
+    },
+    rgb::RGB {
+        r: 0.8,
+        g: 0.475,
+        b: 0.655,
+    },
+];
+
+/// Create an RGB overlay showing the orientation in which `path`
+/// visits each node, colored using `MEMBERSHIP_PALETTE` with the
+/// forward and reverse orientations as the two sets. Nodes that
+/// aren't on the path are drawn half transparent.
+pub fn compute_path_strand_overlay(
+    graph: &PackedGraph,
+    path: PathId,
+) -> OverlayData {
+    const FORWARD: u8 = 1;
+    const REVERSE: u8 = 2;
+
+    let mut strands = vec![0u8; graph.node_count()];
+
+    if let Some(steps) = graph.path_steps(path) {
+        for step in steps {
+            let handle = step.handle();
+            let ix = (handle.id().0 - 1) as usize;
+            if let Some(strand) = strands.get_mut(ix) {
+                *strand |= if handle.is_reverse() {
+                    REVERSE
+                } else {
+                    FORWARD
+                };
+            }
+        }
+    }
+
+    let colors = strands
+        .into_iter()
+        .map(|strand| {
+            let color = MEMBERSHIP_PALETTE[strand as usize];
+            let alpha = if strand == 0 { 0.5 } else { 1.0 };
+            rgb::RGBA::new(color.r, color.g, color.b, alpha)
+        })
+        .collect();
+
+    OverlayData::RGB(colors)
+}
+
+/// Normalize the values to the range 0-1, so the overlay is drawn
+/// using the full gradient
+fn normalized_value_overlay(mut values: Vec<f32>) -> OverlayData {
+    let min = values.iter().copied().fold(std::f32::MAX, f32::min);
+    let max = values.iter().copied().fold(std::f32::MIN, f32::max);
+    let range = max - min;
+
+    log::debug!("Overlay values, min: {}, max: {}", min, max);
+
+    for val in values.iter_mut() {
+        *val = if range > 0.0 {
+            (*val - min) / range
+        } else {
+            0.0
+        };
+    }
+
+    OverlayData::Value(values)
 }
 
 pub fn hash_node_color(hash: u64) -> (f32, f32, f32) {
@@ -26,3 +807,56 @@ pub fn hash_node_color(hash: u64) -> (f32, f32, f32) {
     let b = (b_u16 as f32) / max;
     (r, g, b)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use handlegraph::mutablehandlegraph::*;
+
+    fn graph_with_nodes(count: u64) -> PackedGraph {
+        let mut graph = PackedGraph::default();
+        for id in 1..=count {
+            graph.create_handle(b"A", NodeId::from(id));
+        }
+        graph
+    }
+
+    fn parse(graph: &PackedGraph, table: &str) -> NodeValueTable {
+        NodeValueTable::parse(graph, table.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn header_after_comments_is_skipped() {
+        let graph = graph_with_nodes(3);
+
+        let table =
+            parse(&graph, "# coverage\n\n# more notes\nnode\tvalue\n1\t0.5\n");
+
+        assert_eq!(table.invalid_rows, 0);
+        assert_eq!(table.values.len(), 1);
+        assert_eq!(table.values.get(&NodeId::from(1)), Some(&0.5));
+    }
+
+    #[test]
+    fn later_invalid_rows_are_counted() {
+        let graph = graph_with_nodes(3);
+
+        let table = parse(&graph, "node,value\n1,2.0\nx,1.0\n2,nan\n3,1.5\n");
+
+        assert_eq!(table.invalid_rows, 2);
+        assert_eq!(table.values.len(), 2);
+    }
+
+    #[test]
+    fn unknown_and_duplicate_nodes() {
+        let graph = graph_with_nodes(3);
+
+        let table = parse(&graph, "1\t1.0\n0\t1.0\n4\t2.0\n1\t3.0\n");
+
+        assert_eq!(table.unknown_nodes, 2);
+        assert_eq!(table.duplicates, 1);
+        assert_eq!(table.invalid_rows, 0);
+        assert_eq!(table.values.get(&NodeId::from(1)), Some(&3.0));
+    }
+}
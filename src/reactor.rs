@@ -18,6 +18,7 @@ use parking_lot::Mutex;
 use crate::app::channels::OverlayCreatorMsg;
 use crate::app::AppChannels;
 use crate::graph_query::GraphQuery;
+use crate::gui::toasts::Toast;
 use crate::vulkan::GpuTasks;
 
 pub struct Reactor {
@@ -29,6 +30,8 @@ pub struct Reactor {
     pub overlay_create_tx: Sender<OverlayCreatorMsg>,
     pub overlay_create_rx: Receiver<OverlayCreatorMsg>,
 
+    pub toast_tx: Sender<Toast>,
+
     pub gpu_tasks: Arc<GpuTasks>,
 
     pub clipboard_ctx: Arc<Mutex<ClipboardContext>>,
@@ -39,7 +42,10 @@ pub struct Reactor {
 
     // pub future_tx: Sender<Box<dyn FnOnce() + Send + Sync + 'static>>,
     // pub task_rx: Receiver<Box<dyn FnOnce() + Send + Sync + 'static>>,
-    _task_thread: std::thread::JoinHandle<()>,
+    task_thread: Option<std::thread::JoinHandle<()>>,
+    // clones of `future_tx` can outlive the reactor, so the task
+    // thread is told to stop explicitly
+    task_stop_tx: Sender<()>,
 }
 
 impl Reactor {
@@ -52,14 +58,21 @@ impl Reactor {
         let rayon_pool = Arc::new(rayon_pool);
 
         let (task_tx, task_rx) = crossbeam::channel::unbounded();
+        let (task_stop_tx, task_stop_rx) = crossbeam::channel::bounded(1);
 
         let thread_pool_ = thread_pool.clone();
 
-        let _task_thread = std::thread::spawn(move || {
+        let task_thread = std::thread::spawn(move || {
             let thread_pool = thread_pool_;
 
-            while let Ok(task) = task_rx.recv() {
-                thread_pool.spawn(task).unwrap();
+            loop {
+                crossbeam::channel::select! {
+                    recv(task_rx) -> task => match task {
+                        Ok(task) => thread_pool.spawn(task).unwrap(),
+                        Err(_) => break,
+                    },
+                    recv(task_stop_rx) -> _ => break,
+                }
             }
         });
 
@@ -79,29 +92,50 @@ impl Reactor {
             overlay_create_tx: channels.new_overlay_tx.clone(),
             overlay_create_rx: channels.new_overlay_rx.clone(),
 
+            toast_tx: channels.toast_tx.clone(),
+
             future_tx: task_tx,
             // task_rx,
-            _task_thread,
+            task_thread: Some(task_thread),
+            task_stop_tx,
         }
     }
 
     pub fn set_clipboard_contents(&self, contents: &str, block: bool) {
-        if block {
+        let result = if block {
             let mut ctx = self.clipboard_ctx.lock();
-            ctx.set_contents(contents.to_string()).unwrap();
+            ctx.set_contents(contents.to_string())
         } else if let Some(mut ctx) = self.clipboard_ctx.try_lock() {
-            ctx.set_contents(contents.to_string()).unwrap();
+            ctx.set_contents(contents.to_string())
+        } else {
+            Ok(())
+        };
+
+        if let Err(err) = result {
+            log::error!("Error copying to clipboard: {}", err);
+            let toast = Toast::error(format!("Could not copy: {}", err));
+            let _ = self.toast_tx.send(toast);
         }
     }
 
     pub fn get_clipboard_contents(&self, block: bool) -> Option<String> {
-        if block {
+        let result = if block {
             let mut ctx = self.clipboard_ctx.lock();
-            ctx.get_contents().ok()
+            ctx.get_contents()
         } else if let Some(mut ctx) = self.clipboard_ctx.try_lock() {
-            ctx.get_contents().ok()
+            ctx.get_contents()
         } else {
-            None
+            return None;
+        };
+
+        match result {
+            Ok(contents) => Some(contents),
+            Err(err) => {
+                log::error!("Error reading clipboard: {}", err);
+                let toast = Toast::error(format!("Could not paste: {}", err));
+                let _ = self.toast_tx.send(toast);
+                None
+            }
         }
     }
 
@@ -178,3 +212,15 @@ impl Reactor {
         Ok(())
     }
 }
+
+impl Drop for Reactor {
+    fn drop(&mut self) {
+        let _ = self.task_stop_tx.send(());
+
+        if let Some(task_thread) = self.task_thread.take() {
+            if task_thread.join().is_err() {
+                log::error!("Reactor task thread panicked");
+            }
+        }
+    }
+}
@@ -276,14 +276,51 @@ pub fn file_picker_modal(
 
     let pwd = dir.unwrap_or_else(|| std::fs::canonicalize("./").unwrap());
 
-    let mut file_picker =
-        FilePicker::new(egui::Id::new("_file_picker"), pwd).unwrap();
-    file_picker.set_visible_extensions(extensions).unwrap();
+    let file_picker =
+        FilePicker::new(egui::Id::new("_file_picker"), pwd, Some(extensions))
+            .unwrap();
+
+    run_file_picker_modal(modal_tx, show_modal, file_picker)
+}
+
+/// Like `file_picker_modal`, but the chosen file is one to write to,
+/// and may not exist yet; `file_name` is the suggested name.
+pub fn save_file_picker_modal(
+    modal_tx: crossbeam::channel::Sender<
+        Box<dyn Fn(&mut egui::Ui) + Send + Sync + 'static>,
+    >,
+    show_modal: &Arc<AtomicCell<bool>>,
+    extensions: &[&str],
+    dir: Option<PathBuf>,
+    file_name: &str,
+) -> impl Future<Output = Option<PathBuf>> + Send + Sync + 'static {
+    use crate::gui::windows::file::FilePicker;
+
+    let pwd = dir.unwrap_or_else(|| std::fs::canonicalize("./").unwrap());
+
+    let file_picker =
+        FilePicker::new(egui::Id::new("_file_picker"), pwd, Some(extensions))
+            .unwrap()
+            .save_as(file_name);
+
+    run_file_picker_modal(modal_tx, show_modal, file_picker)
+}
+
+fn run_file_picker_modal(
+    modal_tx: crossbeam::channel::Sender<
+        Box<dyn Fn(&mut egui::Ui) + Send + Sync + 'static>,
+    >,
+    show_modal: &Arc<AtomicCell<bool>>,
+    file_picker: crate::gui::windows::file::FilePicker,
+) -> impl Future<Output = Option<PathBuf>> + Send + Sync + 'static {
+    use crate::gui::windows::file::FilePicker;
 
     let closure =
         move |state: &mut FilePicker, ui: &mut egui::Ui, force: bool| {
             if let Ok(v) = state.ui_impl(ui, force) {
-                state.selected_path = state.highlighted_dir.clone();
+                if state.selected_path.is_none() {
+                    state.selected_path = state.highlighted_dir.clone();
+                }
                 return Ok(v);
             }
             Err(ModalError::Continue)
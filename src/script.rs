@@ -10,6 +10,10 @@ use handlegraph::{
 
 use rustc_hash::FxHashMap;
 
+use handlegraph::packedgraph::PackedGraph;
+
+use std::sync::Arc;
+
 use crate::{app::selection::NodeSelection, graph_query::GraphQuery};
 use crate::{
     app::AppMsg,
@@ -106,6 +110,36 @@ pub fn create_engine() -> Engine {
     engine
 }
 
+/// Registers the graph functions that depend on the node sequences,
+/// `sequence` and `total_length`. They go through `graph_query` when
+/// called on its graph, so that they're correct when sequences are
+/// loaded lazily, and the graph only holds placeholders.
+pub fn register_sequence_fns(
+    engine: &mut Engine,
+    graph_query: &Arc<GraphQuery>,
+) {
+    let query = graph_query.clone();
+    engine.register_fn(
+        "sequence",
+        move |graph: &mut Arc<PackedGraph>, handle: Handle| {
+            if Arc::ptr_eq(graph, &query.graph) {
+                query.sequence_vec(handle)
+            } else {
+                graph.sequence_vec(handle)
+            }
+        },
+    );
+
+    let query = graph_query.clone();
+    engine.register_fn("total_length", move |graph: &mut Arc<PackedGraph>| {
+        if Arc::ptr_eq(graph, &query.graph) {
+            query.total_length()
+        } else {
+            graph.total_length()
+        }
+    });
+}
+
 #[derive(Debug, Clone)]
 pub enum ScriptTarget {
     Nodes,
@@ -118,11 +152,17 @@ pub struct ScriptConfig {
     pub target: ScriptTarget,
 }
 
+/// Checked periodically while a script is being evaluated; if it
+/// returns true, the evaluation is terminated with an error
+pub type StopCheck = std::sync::Arc<dyn Fn() -> bool + Send + Sync>;
+
 pub fn check_overlay_kind(data: rhai::Dynamic) -> Option<OverlayKind> {
     if let Some(_rgb) = data.clone().try_cast::<rgb::RGBA<f32>>() {
         Some(OverlayKind::RGB)
     } else if let Some(_val) = data.clone().try_cast::<f32>() {
         Some(OverlayKind::Value)
+    } else if let Some(_cat) = data.clone().try_cast::<i64>() {
+        Some(OverlayKind::Category)
     } else {
         None
     }
@@ -164,6 +204,14 @@ pub fn cast_overlay_data(data: Vec<rhai::Dynamic>) -> Option<OverlayData> {
         log::debug!("Overlay values, min: {}, max: {}", min, max);
 
         return Some(OverlayData::Value(data));
+    } else if let Some(_cat) = first.try_cast::<i64>() {
+        // negative categories are clamped to the first category
+        let categories = data
+            .into_iter()
+            .map(|v| v.try_cast::<i64>().unwrap().max(0) as u32)
+            .collect::<Vec<_>>();
+
+        return Some(OverlayData::category(categories));
     }
 
     None
@@ -258,42 +306,41 @@ pub fn overlay_colors_tgt_ast(
             let default_color = config.default_color;
 
             match data {
-                OverlayData::RGB(rgb) => {
-                    let node_rgb_map: FxHashMap<NodeId, rgb::RGBA<f32>> = nodes
+                // only the nodes on the path are written, the rest of
+                // the overlay gets the default
+                OverlayData::RGB(rgb) => Ok(OverlayData::SparseRGB {
+                    default: default_color,
+                    colors: nodes.into_iter().zip(rgb.into_iter()).collect(),
+                }),
+                OverlayData::Value(val) => Ok(OverlayData::SparseValue {
+                    default: 0.0,
+                    values: nodes.into_iter().zip(val.into_iter()).collect(),
+                }),
+                OverlayData::Category {
+                    categories,
+                    palette,
+                } => {
+                    let node_cat_map: FxHashMap<NodeId, u32> = nodes
                         .into_iter()
-                        .zip(rgb.into_iter())
+                        .zip(categories.into_iter())
                         .collect::<FxHashMap<_, _>>();
 
-                    let data = node_ids
+                    let categories = node_ids
                         .into_iter()
                         .map(|node_id| {
-                            node_rgb_map
-                                .get(&node_id)
-                                .copied()
-                                .unwrap_or(default_color)
-                        })
-                        .collect();
-
-                    Ok(OverlayData::RGB(data))
-                }
-                OverlayData::Value(val) => {
-                    let node_val_map: FxHashMap<NodeId, f32> = nodes
-                        .into_iter()
-                        .zip(val.into_iter())
-                        .collect::<FxHashMap<_, _>>();
-
-                    let data = node_ids
-                        .into_iter()
-                        .map(|node_id| {
-                            node_val_map
+                            node_cat_map
                                 .get(&node_id)
                                 .copied()
                                 .unwrap_or_default()
                         })
                         .collect();
 
-                    Ok(OverlayData::Value(data))
+                    Ok(OverlayData::Category {
+                        categories,
+                        palette,
+                    })
                 }
+                sparse => Ok(sparse),
             }
         }
     }
@@ -302,8 +349,9 @@ pub fn overlay_colors_tgt_ast(
 pub fn overlay_colors_tgt(
     rayon_pool: &rayon::ThreadPool,
     config: &ScriptConfig,
-    graph: &GraphQuery,
+    graph: &Arc<GraphQuery>,
     script: &str,
+    stop: Option<StopCheck>,
 ) -> std::result::Result<OverlayData, Box<EvalAltResult>> {
     use rhai::Scope;
 
@@ -313,6 +361,17 @@ pub fn overlay_colors_tgt(
         .push("path_pos", graph.path_positions.clone());
 
     let mut engine = create_engine();
+    register_sequence_fns(&mut engine, graph);
+
+    if let Some(stop) = stop {
+        engine.on_progress(move |_ops| {
+            if stop() {
+                Some(rhai::Dynamic::UNIT)
+            } else {
+                None
+            }
+        });
+    }
 
     let graph_ = graph.graph.clone();
 
@@ -327,26 +386,18 @@ pub fn overlay_colors_tgt(
             node_ids.sort();
 
             let values = rayon_pool.install(|| {
-                let mut values: Vec<rhai::Dynamic> =
-                    Vec::with_capacity(node_ids.len());
                 node_ids
                     .into_par_iter()
                     .map_with(scope, |mut thread_scope, node_id| {
-                        let value = engine
-                            .call_fn(
-                                &mut thread_scope,
-                                &node_color_ast,
-                                "node_color",
-                                (node_id,),
-                            )
-                            .unwrap();
-
-                        value
+                        engine.call_fn::<rhai::Dynamic>(
+                            &mut thread_scope,
+                            &node_color_ast,
+                            "node_color",
+                            (node_id,),
+                        )
                     })
-                    .collect_into_vec(&mut values);
-
-                values
-            });
+                    .collect::<std::result::Result<Vec<_>, _>>()
+            })?;
 
             let data = cast_overlay_data(values)
                 .ok_or("Couldn't process overlay data")?;
@@ -363,30 +414,23 @@ pub fn overlay_colors_tgt(
                 graph.path_pos_steps(path_id).ok_or("Path not found")?;
 
             let node_value_map = rayon_pool.install(|| {
-                let mut id_values: Vec<(NodeId, rhai::Dynamic)> =
-                    Vec::with_capacity(steps.len());
-
                 steps
                     .into_par_iter()
                     .map_with(scope, |mut thread_scope, step| {
                         let (handle, _, _pos) = step;
                         let node_id = handle.id();
 
-                        let value = engine
-                            .call_fn(
-                                &mut thread_scope,
-                                &node_color_ast,
-                                "node_color",
-                                (node_id,),
-                            )
-                            .unwrap();
+                        let value = engine.call_fn::<rhai::Dynamic>(
+                            &mut thread_scope,
+                            &node_color_ast,
+                            "node_color",
+                            (node_id,),
+                        )?;
 
-                        value
+                        Ok((node_id, value))
                     })
-                    .collect_into_vec(&mut id_values);
-
-                id_values
-            });
+                    .collect::<std::result::Result<Vec<_>, _>>()
+            })?;
 
             let (nodes, values): (Vec<_>, Vec<_>) =
                 node_value_map.into_iter().unzip();
@@ -401,54 +445,65 @@ pub fn overlay_colors_tgt(
             let default_color = config.default_color;
 
             match data {
-                OverlayData::RGB(rgb) => {
-                    let node_rgb_map: FxHashMap<NodeId, rgb::RGBA<f32>> = nodes
-                        .into_iter()
-                        .zip(rgb.into_iter())
-                        .collect::<FxHashMap<_, _>>();
-
-                    let data = node_ids
-                        .into_iter()
-                        .map(|node_id| {
-                            node_rgb_map
-                                .get(&node_id)
-                                .copied()
-                                .unwrap_or(default_color)
-                        })
-                        .collect();
-
-                    Ok(OverlayData::RGB(data))
-                }
-                OverlayData::Value(val) => {
-                    let node_val_map: FxHashMap<NodeId, f32> = nodes
+                // only the nodes on the path are written, the rest of
+                // the overlay gets the default
+                OverlayData::RGB(rgb) => Ok(OverlayData::SparseRGB {
+                    default: default_color,
+                    colors: nodes.into_iter().zip(rgb.into_iter()).collect(),
+                }),
+                OverlayData::Value(val) => Ok(OverlayData::SparseValue {
+                    default: 0.0,
+                    values: nodes.into_iter().zip(val.into_iter()).collect(),
+                }),
+                OverlayData::Category {
+                    categories,
+                    palette,
+                } => {
+                    let node_cat_map: FxHashMap<NodeId, u32> = nodes
                         .into_iter()
-                        .zip(val.into_iter())
+                        .zip(categories.into_iter())
                         .collect::<FxHashMap<_, _>>();
 
-                    let data = node_ids
+                    let categories = node_ids
                         .into_iter()
                         .map(|node_id| {
-                            node_val_map
+                            node_cat_map
                                 .get(&node_id)
                                 .copied()
                                 .unwrap_or_default()
                         })
                         .collect();
 
-                    Ok(OverlayData::Value(data))
+                    Ok(OverlayData::Category {
+                        categories,
+                        palette,
+                    })
                 }
+                sparse => Ok(sparse),
             }
         }
     }
 }
 
-pub fn hash_node_seq(graph: &GraphQuery, node_id: NodeId) -> u64 {
+/// Hashes the forward sequence of every node. With lazy sequences,
+/// they're read in one pass over the GFA, rather than one read per
+/// node.
+pub fn hash_node_seqs(
+    graph: &GraphQuery,
+) -> anyhow::Result<FxHashMap<NodeId, u64>> {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
-    let mut hasher = DefaultHasher::default();
-    let seq = graph.graph().sequence_vec(Handle::pack(node_id, false));
-    seq.hash(&mut hasher);
-    hasher.finish()
+
+    let mut hashes = FxHashMap::default();
+    hashes.reserve(graph.node_count());
+
+    graph.for_each_sequence(|node_id, seq| {
+        let mut hasher = DefaultHasher::default();
+        seq.hash(&mut hasher);
+        hashes.insert(node_id, hasher.finish());
+    })?;
+
+    Ok(hashes)
 }
 
 pub fn hash_node_paths(graph: &GraphQuery, node_id: NodeId) -> u64 {
@@ -468,3 +523,66 @@ pub fn hash_node_paths(graph: &GraphQuery, node_id: NodeId) -> u64 {
         0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    const GFA: &str = "H\tVN:Z:1.0
+S\t1\tACGTACGT
+S\t2\tGGC
+L\t1\t+\t2\t+\t0M
+P\tp1\t1+,2+\t*
+";
+
+    fn eval_on<T: Clone + Send + Sync + 'static>(
+        graph: &Arc<GraphQuery>,
+        script: &str,
+    ) -> T {
+        let mut engine = create_engine();
+        register_sequence_fns(&mut engine, graph);
+
+        let mut scope = rhai::Scope::new();
+        scope.push("graph", graph.graph.clone());
+
+        engine.eval_with_scope::<T>(&mut scope, script).unwrap()
+    }
+
+    #[test]
+    fn lazy_sequence_fns_match_eager() {
+        let gfa_path = std::env::temp_dir()
+            .join(format!("gfaestus_script_lazy_{}.gfa", std::process::id()));
+        let mut file = std::fs::File::create(&gfa_path).unwrap();
+        file.write_all(GFA.as_bytes()).unwrap();
+
+        let gfa_path = gfa_path.to_str().unwrap();
+        let eager = Arc::new(GraphQuery::load_gfa(gfa_path).unwrap());
+        let lazy = Arc::new(GraphQuery::load_gfa_lazy(gfa_path, None).unwrap());
+
+        for script in &[
+            "graph.sequence(handle(1, false)).to_string()",
+            "graph.sequence(handle(2, true)).to_string()",
+        ] {
+            assert_eq!(
+                eval_on::<String>(&lazy, script),
+                eval_on::<String>(&eager, script)
+            );
+        }
+
+        assert_eq!(
+            eval_on::<String>(
+                &lazy,
+                "graph.sequence(handle(2, true)).to_string()"
+            ),
+            "GCC"
+        );
+
+        let total_length = "graph.total_length()";
+        assert_eq!(eval_on::<usize>(&lazy, total_length), 11);
+        assert_eq!(eval_on::<usize>(&eager, total_length), 11);
+
+        let _ = std::fs::remove_file(gfa_path);
+    }
+}
@@ -296,11 +296,6 @@ pub mod graph_plugin {
         graph.path_count()
     }
 
-    #[rhai_fn(pure)]
-    pub fn total_length(graph: &mut Arc<PackedGraph>) -> usize {
-        graph.total_length()
-    }
-
     #[rhai_fn(pure)]
     pub fn min_node_id(graph: &mut Arc<PackedGraph>) -> NodeId {
         graph.min_node_id()
@@ -311,11 +306,6 @@ pub mod graph_plugin {
         graph.max_node_id()
     }
 
-    #[rhai_fn(pure)]
-    pub fn sequence(graph: &mut Arc<PackedGraph>, handle: Handle) -> Vec<u8> {
-        graph.sequence_vec(handle)
-    }
-
     // `PathId` can't (and shouldn't be able to) be created in
     // isolation by the console, meaning all instances of `path` here
     // must be valid path identifiers in a graph, and because we only
@@ -0,0 +1,162 @@
+use std::io::Write;
+
+use anyhow::Result;
+
+use nalgebra_glm as glm;
+
+use crate::geometry::Point;
+use crate::universe::Node;
+use crate::view::{ScreenDims, View};
+use crate::vulkan::draw_system::nodes::NodePushConstants;
+
+/// Nodes narrower than this, in pixels, are written as lines rather
+/// than polygons
+const MIN_POLYGON_WIDTH: f32 = 1.0;
+
+/// How the view written by `write_view_svg` is framed and drawn
+#[derive(Debug, Clone, Copy)]
+pub struct SvgViewOptions<'a> {
+    pub view: View,
+    /// The dimensions of the screen the view is framed for, and of
+    /// the SVG
+    pub screen_dims: ScreenDims,
+    /// The global node width
+    pub node_width: f32,
+    /// The per-node width factors, empty if all nodes use the global
+    /// width
+    pub width_factors: &'a [f32],
+    pub background: rgb::RGB<f32>,
+}
+
+/// Write the nodes visible in `options.view` as an SVG, framed the
+/// same way as the main view on a screen with dimensions
+/// `options.screen_dims`.
+///
+/// The node quads are built the same way as in the node shaders,
+/// using the node pipeline's view transform. `node_color` gives the
+/// color of the node at the given index, i.e. node ID - 1.
+///
+/// Returns the number of nodes that were written.
+pub fn write_view_svg<W: Write>(
+    mut out: W,
+    nodes: &[Node],
+    options: &SvgViewOptions<'_>,
+    node_color: impl Fn(usize) -> rgb::RGBA<f32>,
+) -> Result<usize> {
+    let SvgViewOptions {
+        view,
+        screen_dims,
+        node_width,
+        width_factors,
+        background,
+    } = *options;
+
+    let width = screen_dims.width;
+    let height = screen_dims.height;
+
+    let transform =
+        NodePushConstants::view_transform([0.0, 0.0], [width, height], view);
+
+    let base_width = node_width / (view.scale * width.max(height));
+
+    let to_clip = |p: Point| -> glm::Vec2 {
+        let v = transform * glm::vec4(p.x, p.y, 0.0, 1.0);
+        glm::vec2(v[0], v[1])
+    };
+
+    let to_pixels = |v: glm::Vec2| -> Point {
+        Point::new((v[0] + 1.0) * 0.5 * width, (v[1] + 1.0) * 0.5 * height)
+    };
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    )?;
+
+    writeln!(
+        out,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        svg_color(background)
+    )?;
+
+    let mut count = 0;
+
+    for (ix, node) in nodes.iter().enumerate() {
+        let factor = width_factors.get(ix).copied().unwrap_or(1.0);
+        let half_width = base_width * factor;
+
+        let p = to_clip(node.p0);
+        let q = to_clip(node.p1);
+
+        let diff = q - p;
+
+        let normal = if diff.norm() > 0.0 {
+            let n = diff.normalize();
+            glm::vec2(-n[1], n[0]) * half_width
+        } else {
+            glm::vec2(0.0, 0.0)
+        };
+
+        let corners = [p + normal, p - normal, q - normal, q + normal];
+
+        let outside = corners.iter().all(|c| c[0] < -1.0)
+            || corners.iter().all(|c| c[0] > 1.0)
+            || corners.iter().all(|c| c[1] < -1.0)
+            || corners.iter().all(|c| c[1] > 1.0);
+
+        if outside {
+            continue;
+        }
+
+        let color = node_color(ix);
+        let fill = svg_color(rgb::RGB::new(color.r, color.g, color.b));
+
+        let pixel_width =
+            (to_pixels(corners[0]) - to_pixels(corners[1])).length();
+
+        if pixel_width < MIN_POLYGON_WIDTH {
+            let p = to_pixels(p);
+            let q = to_pixels(q);
+
+            writeln!(
+                out,
+                r#"<line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{}" stroke-opacity="{}"/>"#,
+                p.x, p.y, q.x, q.y, fill, color.a
+            )?;
+        } else {
+            let points = corners
+                .iter()
+                .map(|&c| {
+                    let c = to_pixels(c);
+                    format!("{:.2},{:.2}", c.x, c.y)
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            writeln!(
+                out,
+                r#"<polygon points="{}" fill="{}" fill-opacity="{}"/>"#,
+                points, fill, color.a
+            )?;
+        }
+
+        count += 1;
+    }
+
+    writeln!(out, "</svg>")?;
+    out.flush()?;
+
+    Ok(count)
+}
+
+fn svg_color(color: rgb::RGB<f32>) -> String {
+    let to_u8 = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        to_u8(color.r),
+        to_u8(color.g),
+        to_u8(color.b)
+    )
+}
@@ -18,6 +18,7 @@ use anyhow::Result;
 use crate::vulkan::{draw_system::Vertex, GfaestusVk};
 use crate::{geometry::*, vulkan::draw_system::nodes::NodeVertices};
 
+pub mod auto_layout;
 pub mod config;
 pub mod graph_layout;
 pub mod grid;
@@ -69,6 +70,9 @@ pub struct Universe<G: GraphLayout> {
     // node_ids: Vec<NodeId>,
     pub offset: Point,
     pub angle: f32,
+    /// Incremented whenever the node positions may have changed, so
+    /// that data derived from the layout can tell when it's stale
+    layout_generation: u64,
     // physics_config: PhysicsConfig,
     // layout_config: LayoutConfig,
     // view_config: ViewConfig,
@@ -80,26 +84,69 @@ impl<G: GraphLayout> Universe<G> {
     }
 
     pub fn layout_mut(&mut self) -> &mut G {
+        self.layout_generation += 1;
         &mut self.graph_layout
     }
+
+    pub fn layout_generation(&self) -> u64 {
+        self.layout_generation
+    }
 }
 
 impl Universe<FlatLayout> {
+    /// If `node_map_path` is provided, it's used to map the node
+    /// indices in the layout to the node IDs in the graph, see
+    /// `FlatLayout::from_laid_out_graph`.
     pub fn from_laid_out_graph(
         graph: &PackedGraph,
         layout_path: &str,
+        node_map_path: Option<&str>,
     ) -> Result<Self> {
         let bp_per_world_unit = 1.0;
         let offset = Point::new(0.0, 0.0);
         let angle = 0.0;
 
-        let graph_layout = FlatLayout::from_laid_out_graph(graph, layout_path)?;
+        let graph_layout =
+            FlatLayout::from_laid_out_graph(graph, layout_path, node_map_path)?;
 
         Ok(Self {
             bp_per_world_unit,
             graph_layout,
             offset,
             angle,
+            layout_generation: 0,
+        })
+    }
+
+    /// Write the layout in the binary format read by
+    /// `load_layout_binary`, which is much faster to load than the
+    /// text layout for large graphs.
+    pub fn save_layout_binary<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut writer = std::io::BufWriter::new(file);
+        self.graph_layout.write_binary(&mut writer)
+    }
+
+    /// Load a layout written by `save_layout_binary`. Fails if the
+    /// layout doesn't have exactly the nodes in `graph`.
+    pub fn load_layout_binary<P: AsRef<std::path::Path>>(
+        graph: &PackedGraph,
+        path: P,
+    ) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let mut reader = std::io::BufReader::new(file);
+
+        let graph_layout = FlatLayout::read_binary(graph, &mut reader)?;
+
+        Ok(Self {
+            bp_per_world_unit: 1.0,
+            graph_layout,
+            offset: Point::new(0.0, 0.0),
+            angle: 0.0,
+            layout_generation: 0,
         })
     }
 
@@ -110,6 +157,8 @@ impl Universe<FlatLayout> {
     ) -> Result<()> {
         let node_count = self.graph_layout.nodes.len();
 
+        self.layout_generation += 1;
+
         vertices.download_vertices(
             app,
             node_count,
@@ -221,9 +270,18 @@ impl FlatLayout {
         }
     }
 
+    /// Load the node positions from a layout TSV, as produced by
+    /// `odgi layout`.
+    ///
+    /// By default node N in the layout is assumed to be node N in the
+    /// graph. If the layout tool renumbered the nodes, `node_map_path`
+    /// can point to a file with one `layout_node_id gfa_node_id` pair
+    /// per line, which must map every node in the layout to a
+    /// distinct node in the graph.
     fn from_laid_out_graph(
         graph: &PackedGraph,
         layout_path: &str,
+        node_map_path: Option<&str>,
     ) -> Result<Self> {
         use std::fs::File;
         use std::io::prelude::*;
@@ -296,6 +354,21 @@ impl FlatLayout {
             }
         }
 
+        if let Some(map_path) = node_map_path {
+            let node_map = load_node_id_map(map_path)?;
+            validate_node_id_map(graph, &layout_map, &node_map)?;
+
+            layout_map = layout_map
+                .into_iter()
+                .map(|(layout_id, points)| (node_map[&layout_id], points))
+                .collect();
+
+            component_map = component_map
+                .into_iter()
+                .map(|(layout_id, comp)| (node_map[&layout_id], comp))
+                .collect();
+        }
+
         let mut node_ids = Vec::with_capacity(graph.node_count());
         let mut nodes = Vec::with_capacity(graph.node_count());
 
@@ -311,6 +384,12 @@ impl FlatLayout {
         let mut min_y = std::f32::MAX;
         let mut max_y = std::f32::MIN;
 
+        // the component offsets are the node IDs where each component
+        // starts, so with a node ID map they have to be found again in
+        // graph node ID order
+        let mut mapped_components: Vec<usize> = Vec::new();
+        let mut prev_comp = 0;
+
         for handle in handles {
             let id = handle.id();
 
@@ -318,6 +397,11 @@ impl FlatLayout {
 
             let comp = component_map.get(&id).copied().unwrap_or(0);
 
+            if comp != prev_comp {
+                mapped_components.push(id.0 as usize);
+                prev_comp = comp;
+            }
+
             let delta = Point::new(0.0, (comp as f32) * 10_000.0);
             // let delta = Point::new(0.0, 0.0);
 
@@ -337,12 +421,262 @@ impl FlatLayout {
         let top_left = Point::new(min_x, min_y);
         let bottom_right = Point::new(max_x, max_y);
 
+        let component_offsets = if node_map_path.is_some() {
+            mapped_components
+        } else {
+            components
+        };
+
         Ok(FlatLayout {
             node_ids,
             nodes,
-            component_offsets: components,
+            component_offsets,
             top_left,
             bottom_right,
         })
     }
 }
+
+impl FlatLayout {
+    /// Identifies the binary layout format, and its version
+    const BINARY_MAGIC: &'static [u8; 8] = b"GFSLAY01";
+
+    /// Write the layout as the magic bytes, followed by the node
+    /// count, then each node as its ID and the coordinates of its two
+    /// points, then the component offsets, prefixed by their count.
+    /// Counts and IDs are little-endian u64s, coordinates are
+    /// little-endian f32s.
+    fn write_binary<W: std::io::Write>(&self, out: &mut W) -> Result<()> {
+        out.write_all(Self::BINARY_MAGIC)?;
+
+        out.write_all(&(self.nodes.len() as u64).to_le_bytes())?;
+
+        for (id, node) in self.node_ids.iter().zip(self.nodes.iter()) {
+            out.write_all(&id.0.to_le_bytes())?;
+
+            for v in [node.p0.x, node.p0.y, node.p1.x, node.p1.y].iter() {
+                out.write_all(&v.to_le_bytes())?;
+            }
+        }
+
+        out.write_all(&(self.component_offsets.len() as u64).to_le_bytes())?;
+
+        for offset in self.component_offsets.iter() {
+            out.write_all(&(*offset as u64).to_le_bytes())?;
+        }
+
+        out.flush()?;
+
+        Ok(())
+    }
+
+    fn read_binary<R: std::io::Read>(
+        graph: &PackedGraph,
+        input: &mut R,
+    ) -> Result<Self> {
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+
+        if &magic != Self::BINARY_MAGIC {
+            anyhow::bail!("Not a binary layout file");
+        }
+
+        let read_u64 = |input: &mut R| -> Result<u64> {
+            let mut buf = [0u8; 8];
+            input.read_exact(&mut buf)?;
+            Ok(u64::from_le_bytes(buf))
+        };
+
+        let node_count = read_u64(input)? as usize;
+
+        if node_count != graph.node_count() {
+            anyhow::bail!(
+                "Binary layout has {} nodes, but the graph has {}",
+                node_count,
+                graph.node_count()
+            );
+        }
+
+        let mut node_ids = Vec::with_capacity(node_count);
+        let mut nodes = Vec::with_capacity(node_count);
+
+        let mut min_x = std::f32::MAX;
+        let mut max_x = std::f32::MIN;
+
+        let mut min_y = std::f32::MAX;
+        let mut max_y = std::f32::MIN;
+
+        let mut buf = [0u8; 16];
+
+        for _ in 0..node_count {
+            let id = NodeId::from(read_u64(input)?);
+
+            // nodes must be in ascending ID order, see
+            // `from_laid_out_graph`
+            if node_ids.last().map_or(false, |&prev| prev >= id) {
+                anyhow::bail!("Binary layout nodes are not sorted by ID");
+            }
+
+            if !graph.has_node(id) {
+                anyhow::bail!(
+                    "Binary layout contains node {}, which is not in the graph",
+                    id.0
+                );
+            }
+
+            input.read_exact(&mut buf)?;
+
+            let mut coords = buf.chunks_exact(4).map(|bytes| {
+                f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+            });
+
+            let mut next_point = || {
+                let x = coords.next().unwrap();
+                let y = coords.next().unwrap();
+                Point::new(x, y)
+            };
+
+            let p0 = next_point();
+            let p1 = next_point();
+
+            min_x = min_x.min(p0.x).min(p1.x);
+            max_x = max_x.max(p0.x).max(p1.x);
+
+            min_y = min_y.min(p0.y).min(p1.y);
+            max_y = max_y.max(p0.y).max(p1.y);
+
+            node_ids.push(id);
+            nodes.push(Node { p0, p1 });
+        }
+
+        let component_count = read_u64(input)? as usize;
+
+        let component_offsets = (0..component_count)
+            .map(|_| read_u64(input).map(|offset| offset as usize))
+            .collect::<Result<Vec<_>>>()?;
+
+        let top_left = Point::new(min_x, min_y);
+        let bottom_right = Point::new(max_x, max_y);
+
+        Ok(FlatLayout {
+            node_ids,
+            nodes,
+            component_offsets,
+            top_left,
+            bottom_right,
+        })
+    }
+}
+
+/// Parse a file mapping layout node IDs to GFA node IDs, with one
+/// whitespace-separated pair per line. Empty lines and lines starting
+/// with `#` are skipped.
+fn load_node_id_map(
+    map_path: &str,
+) -> Result<rustc_hash::FxHashMap<NodeId, NodeId>> {
+    use std::io::prelude::*;
+
+    let file = std::fs::File::open(map_path)?;
+    let reader = std::io::BufReader::new(file);
+
+    let mut node_map = rustc_hash::FxHashMap::default();
+
+    for (line_ix, line) in reader.lines().enumerate() {
+        let line = line?;
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = trimmed.split_whitespace();
+
+        let mut next_id = || -> Result<NodeId> {
+            let field = fields.next().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Node ID map {} line {}: expected two node IDs",
+                    map_path,
+                    line_ix + 1
+                )
+            })?;
+
+            let id = field.parse::<u64>().map_err(|_| {
+                anyhow::anyhow!(
+                    "Node ID map {} line {}: invalid node ID \"{}\"",
+                    map_path,
+                    line_ix + 1,
+                    field
+                )
+            })?;
+
+            Ok(NodeId::from(id))
+        };
+
+        let layout_id = next_id()?;
+        let gfa_id = next_id()?;
+
+        if node_map.insert(layout_id, gfa_id).is_some() {
+            anyhow::bail!(
+                "Node ID map {} line {}: layout node {} is mapped twice",
+                map_path,
+                line_ix + 1,
+                layout_id.0
+            );
+        }
+    }
+
+    Ok(node_map)
+}
+
+/// Make sure the node ID map is a bijection between the nodes in the
+/// layout and the nodes in the graph
+fn validate_node_id_map(
+    graph: &PackedGraph,
+    layout_map: &rustc_hash::FxHashMap<NodeId, (Point, Point)>,
+    node_map: &rustc_hash::FxHashMap<NodeId, NodeId>,
+) -> Result<()> {
+    use rustc_hash::FxHashSet;
+
+    if let Some(layout_id) =
+        layout_map.keys().find(|id| !node_map.contains_key(id))
+    {
+        anyhow::bail!("Node ID map is missing layout node {}", layout_id.0);
+    }
+
+    if let Some(layout_id) =
+        node_map.keys().find(|id| !layout_map.contains_key(id))
+    {
+        anyhow::bail!(
+            "Node ID map refers to layout node {}, which is not in the layout",
+            layout_id.0
+        );
+    }
+
+    let mut gfa_ids: FxHashSet<NodeId> = FxHashSet::default();
+
+    for gfa_id in node_map.values() {
+        if !graph.has_node(*gfa_id) {
+            anyhow::bail!(
+                "Node ID map refers to node {}, which is not in the graph",
+                gfa_id.0
+            );
+        }
+
+        if !gfa_ids.insert(*gfa_id) {
+            anyhow::bail!(
+                "Node ID map maps several layout nodes to node {}",
+                gfa_id.0
+            );
+        }
+    }
+
+    if gfa_ids.len() != graph.node_count() {
+        anyhow::bail!(
+            "Node ID map covers {} of the {} nodes in the graph",
+            gfa_ids.len(),
+            graph.node_count()
+        );
+    }
+
+    Ok(())
+}
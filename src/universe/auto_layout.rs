@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+use std::io::Write;
+
+#[allow(unused_imports)]
+use handlegraph::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    packedgraph::PackedGraph,
+};
+
+use anyhow::Result;
+
+use crate::geometry::*;
+use crate::graph_query::GraphQuery;
+
+/// Nodes are laid out on rows of at most this many world units (the
+/// node lengths are in base pairs), wrapping to the next row
+const ROW_LENGTH: f32 = 100_000.0;
+
+/// Distance between two rows
+const ROW_SPACING: f32 = 1_000.0;
+
+/// Space between two consecutive nodes on a row
+const NODE_GAP: f32 = 10.0;
+
+/// A simple layout for graphs without a layout file, with each node
+/// a horizontal line as long as its sequence.
+///
+/// Each connected component is traversed breadth-first from its
+/// lowest node ID, and the nodes are placed left to right in that
+/// order, with each component starting on a new row. The result can
+/// be improved with the layout relaxation in the main view.
+///
+/// Returns the points of each node in ascending node ID order.
+pub fn linear_layout(graph_query: &GraphQuery) -> Vec<(NodeId, Point, Point)> {
+    let graph = graph_query.graph();
+
+    let mut handles = graph.handles().collect::<Vec<_>>();
+    handles.sort();
+
+    let max_id = graph.max_node_id().0 as usize;
+
+    let mut visited = vec![false; max_id + 1];
+    let mut points: Vec<Option<(Point, Point)>> = vec![None; max_id + 1];
+
+    let mut queue: VecDeque<Handle> = VecDeque::new();
+
+    let mut cursor = Point::new(0.0, 0.0);
+
+    for &start in handles.iter() {
+        if visited[start.id().0 as usize] {
+            continue;
+        }
+
+        // start each component on a new row
+        if cursor.x > 0.0 {
+            cursor = Point::new(0.0, cursor.y + ROW_SPACING);
+        }
+
+        visited[start.id().0 as usize] = true;
+        queue.push_back(start);
+
+        while let Some(handle) = queue.pop_front() {
+            let len = graph_query.node_len(handle).max(1) as f32;
+
+            if cursor.x > 0.0 && cursor.x + len > ROW_LENGTH {
+                cursor = Point::new(0.0, cursor.y + ROW_SPACING);
+            }
+
+            let p0 = cursor;
+            let p1 = Point::new(cursor.x + len, cursor.y);
+
+            points[handle.id().0 as usize] = Some((p0, p1));
+
+            cursor.x += len + NODE_GAP;
+
+            let neighbors = graph
+                .neighbors(handle, Direction::Left)
+                .chain(graph.neighbors(handle, Direction::Right));
+
+            for other in neighbors {
+                let other = other.forward();
+                let ix = other.id().0 as usize;
+
+                if !visited[ix] {
+                    visited[ix] = true;
+                    queue.push_back(other);
+                }
+            }
+        }
+    }
+
+    handles
+        .into_iter()
+        .filter_map(|handle| {
+            let id = handle.id();
+            let (p0, p1) = points[id.0 as usize]?;
+            Some((id, p0, p1))
+        })
+        .collect()
+}
+
+/// Write the layout produced by `linear_layout` in the same TSV
+/// format as `odgi layout`, i.e. a header line followed by one line
+/// per node end, so that it can be loaded like any other layout.
+pub fn write_linear_layout_tsv<W: Write>(
+    graph_query: &GraphQuery,
+    mut out: W,
+) -> Result<()> {
+    writeln!(out, "idx\tX\tY")?;
+
+    // node N is on lines 2(N - 1) and 2(N - 1) + 1
+    for (id, p0, p1) in linear_layout(graph_query) {
+        let ix = (id.0 - 1) * 2;
+        writeln!(out, "{}\t{}\t{}", ix, p0.x, p0.y)?;
+        writeln!(out, "{}\t{}\t{}", ix + 1, p1.x, p1.y)?;
+    }
+
+    out.flush()?;
+
+    Ok(())
+}
@@ -2,6 +2,10 @@ use crate::geometry::Point;
 
 use nalgebra_glm as glm;
 
+use serde::{Deserialize, Serialize};
+
+use anyhow::Result;
+
 #[rustfmt::skip]
     #[inline]
 pub fn viewport_scale(width: f32, height: f32) -> glm::Mat4 {
@@ -80,7 +84,7 @@ impl Into<[u32; 2]> for ScreenDims {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct View {
     pub center: Point,
     pub scale: f32,
@@ -202,14 +206,70 @@ impl View {
         view_scale_screen
     }
 
-    #[rustfmt::skip]
-    #[inline]
-    pub fn screen_point_to_world<Dims: Into<ScreenDims>>(&self, dims: Dims, screen_point: Point) -> Point {
-        let to_world_mat = self.screen_to_world_map(dims);
+    /// Map a point in screen space, in pixels with the origin at the
+    /// top left, to world space, using the inverse of the view and
+    /// viewport transforms the nodes are rendered with.
+    ///
+    /// Returns the view center if the screen has no area.
+    pub fn screen_to_world<Dims: Into<ScreenDims>>(
+        &self,
+        point: Point,
+        dims: Dims,
+    ) -> Point {
+        let dims = dims.into();
+
+        let to_clip =
+            viewport_scale(dims.width, dims.height) * self.to_scaled_matrix();
+
+        let to_world = match to_clip.try_inverse() {
+            Some(inverse) => inverse,
+            None => return self.center,
+        };
+
+        let clip = glm::vec4(
+            (point.x / dims.width) * 2.0 - 1.0,
+            (point.y / dims.height) * 2.0 - 1.0,
+            0.0,
+            1.0,
+        );
 
-        let projected = to_world_mat * glm::vec4(screen_point.x, screen_point.y, 0.0, 1.0);
+        let world = to_world * clip;
 
-        Point { x: projected[0], y: projected[1] }
+        Point {
+            x: world[0],
+            y: world[1],
+        }
+    }
+
+    /// Map a point in world space to screen space, in pixels with
+    /// the origin at the top left, using the same view and viewport
+    /// transforms the nodes are rendered with; the inverse of
+    /// `screen_to_world`.
+    pub fn world_to_screen<Dims: Into<ScreenDims>>(
+        &self,
+        point: Point,
+        dims: Dims,
+    ) -> Point {
+        let dims = dims.into();
+
+        let to_clip =
+            viewport_scale(dims.width, dims.height) * self.to_scaled_matrix();
+
+        let clip = to_clip * glm::vec4(point.x, point.y, 0.0, 1.0);
+
+        Point {
+            x: (clip[0] + 1.0) * 0.5 * dims.width,
+            y: (clip[1] + 1.0) * 0.5 * dims.height,
+        }
+    }
+
+    #[inline]
+    pub fn screen_point_to_world<Dims: Into<ScreenDims>>(
+        &self,
+        dims: Dims,
+        screen_point: Point,
+    ) -> Point {
+        self.screen_to_world(screen_point, dims)
     }
 
     pub fn world_point_to_screen(&self, world: Point) -> Point {
@@ -224,6 +284,122 @@ impl View {
     }
 }
 
+/// A view along with the dimensions of the screen it was used with,
+/// so that it can be saved to and restored from a file.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SavedView {
+    pub center: Point,
+    pub scale: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl SavedView {
+    pub fn new<D: Into<ScreenDims>>(view: View, screen_dims: D) -> Self {
+        let dims = screen_dims.into();
+        Self {
+            center: view.center,
+            scale: view.scale,
+            width: dims.width,
+            height: dims.height,
+        }
+    }
+
+    /// Returns the view that frames the same world region as the
+    /// saved view, on a screen with the given dimensions.
+    pub fn view_for_dims<D: Into<ScreenDims>>(&self, screen_dims: D) -> View {
+        let dims = screen_dims.into();
+
+        if self.width <= 0.0
+            || self.height <= 0.0
+            || dims.width <= 0.0
+            || dims.height <= 0.0
+        {
+            return View {
+                center: self.center,
+                scale: self.scale,
+            };
+        }
+
+        let world_width = self.width * self.scale;
+        let world_height = self.height * self.scale;
+
+        let scale = (world_width / dims.width).max(world_height / dims.height);
+
+        View {
+            center: self.center,
+            scale,
+        }
+    }
+
+    pub fn save_file<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    pub fn load_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let view = serde_json::from_reader(std::io::BufReader::new(file))?;
+        Ok(view)
+    }
+}
+
+/// A saved view together with the file name of the GFA it's a view
+/// of, which can be encoded as a compact string and shared.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewLink {
+    pub gfa: String,
+    pub view: SavedView,
+}
+
+impl ViewLink {
+    const PREFIX: &'static str = "gfaestus:";
+
+    /// Creates a link using only the file name of `gfa_path`, so that
+    /// the link matches the same GFA in another directory.
+    pub fn new<P: AsRef<std::path::Path>>(
+        gfa_path: P,
+        view: SavedView,
+    ) -> Self {
+        Self {
+            gfa: Self::gfa_name(gfa_path),
+            view,
+        }
+    }
+
+    fn gfa_name<P: AsRef<std::path::Path>>(gfa_path: P) -> String {
+        let path = gfa_path.as_ref();
+        path.file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Returns true if the link was created from a GFA with the same
+    /// file name as `gfa_path`.
+    pub fn matches_gfa<P: AsRef<std::path::Path>>(&self, gfa_path: P) -> bool {
+        self.gfa == Self::gfa_name(gfa_path)
+    }
+
+    pub fn encode(&self) -> Result<String> {
+        let json = serde_json::to_vec(self)?;
+        let encoded = base64::encode_config(json, base64::URL_SAFE_NO_PAD);
+        Ok(format!("{}{}", Self::PREFIX, encoded))
+    }
+
+    pub fn decode(text: &str) -> Result<Self> {
+        let text = text.trim();
+        let encoded = text.strip_prefix(Self::PREFIX).ok_or_else(|| {
+            anyhow::anyhow!("View link must start with \"{}\"", Self::PREFIX)
+        })?;
+
+        let json = base64::decode_config(encoded, base64::URL_SAFE_NO_PAD)?;
+        let link = serde_json::from_slice(&json)?;
+        Ok(link)
+    }
+}
+
 #[inline]
 pub fn mat4_to_array(matrix: &glm::Mat4) -> [[f32; 4]; 4] {
     let s = glm::value_ptr(matrix);
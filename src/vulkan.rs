@@ -4,6 +4,7 @@ pub mod debug;
 pub mod draw_system;
 pub mod render_pass;
 pub mod texture;
+pub mod timestamps;
 
 pub mod msg;
 
@@ -116,7 +117,9 @@ impl GfaestusVk {
         };
 
         log::debug!("Created Vulkan entry");
-        let instance = create_instance(&entry, &window)?;
+        let validation = debug::validation_requested(args.validation);
+        let (instance, validation) =
+            create_instance(&entry, Some(&window), validation)?;
         log::debug!("Created Vulkan instance");
 
         let surface = Surface::new(&entry, &instance);
@@ -125,13 +128,71 @@ impl GfaestusVk {
         }?;
         log::debug!("Created window surface");
 
-        let debug_utils = debug::setup_debug_utils(&entry, &instance);
+        let result = Self::from_instance(
+            args,
+            entry,
+            instance,
+            validation,
+            surface,
+            Some(surface_khr),
+            [800, 600],
+        )?;
+
+        Ok((result, event_loop, window))
+    }
+
+    /// Creates a context without a window or swapchain, for
+    /// rendering frames that are only read back to the host, e.g.
+    /// with `--screenshot`. The frames are drawn to the `frame`
+    /// image of the offscreen attachment, with the size given by
+    /// `--size`.
+    pub fn new_headless(args: &Args) -> Result<Self> {
+        log::debug!("Initializing headless GfaestusVk context");
+        let entry = unsafe { Entry::new() }?;
+
+        let validation = debug::validation_requested(args.validation);
+        let (instance, validation) = create_instance(&entry, None, validation)?;
+        log::debug!("Created Vulkan instance");
+
+        // never used to create a surface, but `VkContext` keeps one
+        let surface = Surface::new(&entry, &instance);
+
+        Self::from_instance(
+            args,
+            entry,
+            instance,
+            validation,
+            surface,
+            None,
+            args.size.unwrap_or([800, 600]),
+        )
+    }
+
+    /// Sets up the device and everything rendering needs; without a
+    /// surface, the frames are drawn to an offscreen image instead
+    /// of a swapchain.
+    fn from_instance(
+        args: &Args,
+        entry: Entry,
+        instance: ash::Instance,
+        validation: bool,
+        surface: Surface,
+        surface_khr: Option<vk::SurfaceKHR>,
+        [width, height]: [u32; 2],
+    ) -> Result<Self> {
+        let debug_utils =
+            debug::setup_debug_utils(&entry, &instance, validation);
+
+        let gpu_index = match args.gpu {
+            Some(index) => Some(index),
+            None => gpu_index_from_env()?,
+        };
 
         let (physical_device, graphics_ix, present_ix, compute_ix) =
             choose_physical_device(
                 &instance,
-                &surface,
-                surface_khr,
+                surface_khr.map(|khr| (&surface, khr)),
+                gpu_index,
                 args.force_graphics_device.as_deref(),
             )?;
 
@@ -142,6 +203,7 @@ impl GfaestusVk {
                 graphics_ix,
                 present_ix,
                 compute_ix,
+                validation,
             )?;
 
         let allocator_create_info = vk_mem::AllocatorCreateInfo {
@@ -161,21 +223,26 @@ impl GfaestusVk {
             instance,
             debug_utils,
             surface,
-            surface_khr,
+            surface_khr.unwrap_or_else(vk::SurfaceKHR::null),
             physical_device,
             device,
         )?;
 
-        let width = 800u32;
-        let height = 600u32;
-
         let (swapchain, swapchain_khr, swapchain_props, images) =
-            create_swapchain_and_images(
-                &vk_context,
-                graphics_ix,
-                present_ix,
-                [width, height],
-            )?;
+            if surface_khr.is_some() {
+                create_swapchain_and_images(
+                    &vk_context,
+                    graphics_ix,
+                    present_ix,
+                    [width, height],
+                )?
+            } else {
+                let swapchain =
+                    Swapchain::new(vk_context.instance(), vk_context.device());
+                let props = SwapchainProperties::headless([width, height]);
+                (swapchain, vk::SwapchainKHR::null(), props, Vec::new())
+            };
+
         let swapchain_image_views = create_swapchain_image_views(
             vk_context.device(),
             &images,
@@ -213,22 +280,32 @@ impl GfaestusVk {
             render_passes.id_format,
         )?;
 
-        let offscreen_attachment = OffscreenAttachment::new(
-            &vk_context,
-            transient_command_pool,
-            graphics_queue,
-            swapchain_props,
-        )?;
+        let offscreen_attachment = if surface_khr.is_some() {
+            OffscreenAttachment::new(
+                &vk_context,
+                transient_command_pool,
+                graphics_queue,
+                swapchain_props,
+            )?
+        } else {
+            OffscreenAttachment::new_headless(
+                &vk_context,
+                transient_command_pool,
+                graphics_queue,
+                swapchain_props,
+            )?
+        };
 
-        let framebuffers = swapchain_image_views
-            .iter()
+        let framebuffers = offscreen_attachment
+            .target_views(&swapchain_image_views)
+            .into_iter()
             .map(|view| {
                 render_passes
                     .framebuffers(
                         vk_context.device(),
                         &node_attachments,
                         &offscreen_attachment,
-                        *view,
+                        view,
                         swapchain_props,
                     )
                     .unwrap()
@@ -279,7 +356,25 @@ impl GfaestusVk {
             "Offscreen Color Attachment",
         )?;
 
-        Ok((result, event_loop, window))
+        result.set_debug_object_name(
+            result.offscreen_attachment.blur.image,
+            "Offscreen Blur Attachment",
+        )?;
+
+        if let Some(frame) = result.offscreen_attachment.frame.as_ref() {
+            result.set_debug_object_name(
+                frame.image,
+                "Offscreen Frame Attachment",
+            )?;
+        }
+
+        Ok(result)
+    }
+
+    /// True if the context was created with `new_headless`, i.e.
+    /// frames are drawn with `draw_offscreen_frame`
+    pub fn is_headless(&self) -> bool {
+        self.offscreen_attachment.frame.is_some()
     }
 
     pub fn swapchain_dims(&self) -> ScreenDims {
@@ -295,6 +390,39 @@ impl GfaestusVk {
         &self.vk_context
     }
 
+    /// Set the MSAA sample count, falling back to the nearest count
+    /// supported by the device, and return the count that was chosen.
+    ///
+    /// Only takes effect after the swapchain has been recreated, and
+    /// the pipelines that use multisampling must be rebuilt as well.
+    pub fn set_msaa_samples(
+        &mut self,
+        samples: vk::SampleCountFlags,
+    ) -> vk::SampleCountFlags {
+        let supported = self.vk_context.supported_sample_counts();
+
+        let log2 = |count: u32| 31 - count.max(1).leading_zeros() as i32;
+        let requested = log2(samples.as_raw());
+
+        let chosen = (0..7)
+            .map(|exp| vk::SampleCountFlags::from_raw(1 << exp))
+            .filter(|count| supported.contains(*count))
+            .min_by_key(|count| (log2(count.as_raw()) - requested).abs())
+            .unwrap_or(vk::SampleCountFlags::TYPE_1);
+
+        if chosen != samples {
+            log::warn!(
+                "MSAA sample count {} not supported, using {}",
+                samples.as_raw(),
+                chosen.as_raw()
+            );
+        }
+
+        self.msaa_samples = chosen;
+
+        chosen
+    }
+
     pub fn draw_frame_from<F>(
         &mut self,
         window_size: [u32; 2],
@@ -401,6 +529,130 @@ impl GfaestusVk {
         Ok(false)
     }
 
+    /// Records a frame with `commands` into the offscreen frame
+    /// image of a headless context, and waits on the frame's fence,
+    /// so the image holds the finished frame when this returns.
+    pub fn draw_offscreen_frame<F>(&mut self, commands: F) -> Result<()>
+    where
+        F: FnOnce(&Device, vk::CommandBuffer, &Framebuffers),
+    {
+        if !self.is_headless() {
+            anyhow::bail!("Offscreen frames require a headless context");
+        }
+
+        let sync_objects = self.in_flight_frames.next().unwrap();
+
+        let in_flight_fence = sync_objects.fence;
+        let wait_fences = [in_flight_fence];
+
+        let device = self.vk_context.device();
+
+        unsafe { device.wait_for_fences(&wait_fences, true, std::u64::MAX) }?;
+        unsafe { device.reset_fences(&wait_fences) }?;
+
+        // a headless context has a single framebuffer set, for the
+        // offscreen frame image
+        let framebuffers = &self.framebuffers[0];
+
+        let cmd_buf = self.execute_one_time_commands_semaphores(
+            device,
+            self.command_pool,
+            self.graphics_queue,
+            &[],
+            &[],
+            &[],
+            in_flight_fence,
+            |cmd_buf| {
+                commands(device, cmd_buf, framebuffers);
+            },
+        )?;
+
+        unsafe { device.wait_for_fences(&wait_fences, true, std::u64::MAX) }?;
+
+        unsafe {
+            device.free_command_buffers(self.command_pool, &[cmd_buf]);
+        };
+
+        Ok(())
+    }
+
+    /// Copies the offscreen frame image of a headless context to
+    /// host memory, returning the pixels as tightly packed RGBA8
+    /// rows. Should be called after `draw_offscreen_frame`.
+    pub fn download_offscreen_frame(&self) -> Result<Vec<u8>> {
+        use vk::BufferUsageFlags as Usage;
+        use vk::ImageLayout as Layout;
+        use vk::MemoryPropertyFlags as MemPropFlags;
+
+        let frame = self.offscreen_attachment.frame.as_ref().ok_or_else(
+            || anyhow::anyhow!("Only headless contexts have a frame image"),
+        )?;
+
+        let device = self.vk_context.device();
+
+        let image = frame.image;
+        let extent = self.swapchain_props.extent;
+
+        let size = (extent.width * extent.height * 4) as vk::DeviceSize;
+
+        let (buffer, memory, _) = self.create_buffer(
+            size,
+            Usage::TRANSFER_DST,
+            MemPropFlags::HOST_VISIBLE | MemPropFlags::HOST_COHERENT,
+        )?;
+
+        Self::transition_image(
+            device,
+            self.transient_command_pool,
+            self.graphics_queue,
+            image,
+            Layout::COLOR_ATTACHMENT_OPTIMAL,
+            Layout::TRANSFER_SRC_OPTIMAL,
+        )?;
+
+        Self::copy_image_to_buffer(
+            device,
+            self.transient_command_pool,
+            self.graphics_queue,
+            image,
+            buffer,
+            extent,
+        )?;
+
+        Self::transition_image(
+            device,
+            self.transient_command_pool,
+            self.graphics_queue,
+            image,
+            Layout::TRANSFER_SRC_OPTIMAL,
+            Layout::COLOR_ATTACHMENT_OPTIMAL,
+        )?;
+
+        let mut pixels = vec![0u8; size as usize];
+
+        unsafe {
+            let data_ptr = device.map_memory(
+                memory,
+                0,
+                size,
+                vk::MemoryMapFlags::empty(),
+            )?;
+
+            let slice = std::slice::from_raw_parts(
+                data_ptr as *const u8,
+                size as usize,
+            );
+            pixels.copy_from_slice(slice);
+
+            device.unmap_memory(memory);
+
+            device.destroy_buffer(buffer, None);
+            device.free_memory(memory, None);
+        }
+
+        Ok(pixels)
+    }
+
     pub fn wait_gpu_idle(&self) -> Result<()> {
         let res = unsafe { self.vk_context.device().device_wait_idle() }?;
         Ok(res)
@@ -621,6 +873,25 @@ impl GfaestusVk {
                     vk::PipelineStageFlags::BOTTOM_OF_PIPE,
                     vk::PipelineStageFlags::FRAGMENT_SHADER,
                 ),
+                (
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                ) => (
+                    vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                    vk::AccessFlags::TRANSFER_READ,
+                    vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                    vk::PipelineStageFlags::TRANSFER,
+                ),
+                (
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                ) => (
+                    vk::AccessFlags::TRANSFER_READ,
+                    vk::AccessFlags::COLOR_ATTACHMENT_READ
+                        | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                    vk::PipelineStageFlags::TRANSFER,
+                    vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                ),
                 (vk::ImageLayout::UNDEFINED, vk::ImageLayout::GENERAL) => (
                     vk::AccessFlags::empty(),
                     vk::AccessFlags::COLOR_ATTACHMENT_READ
@@ -1233,6 +1504,10 @@ impl GfaestusVk {
         &mut self,
         dimensions: Option<[u32; 2]>,
     ) -> Result<()> {
+        if self.is_headless() {
+            anyhow::bail!("Headless contexts have no swapchain to recreate");
+        }
+
         self.wait_gpu_idle()?;
 
         self.cleanup_swapchain();
@@ -1287,6 +1562,11 @@ impl GfaestusVk {
             "Offscreen Color Attachment",
         )?;
 
+        self.set_debug_object_name(
+            offscreen_attachment.blur.image,
+            "Offscreen Blur Attachment",
+        )?;
+
         let framebuffers = swapchain_image_views
             .iter()
             .map(|view| {
@@ -1445,6 +1725,21 @@ pub struct SwapchainProperties {
     pub format: vk::SurfaceFormatKHR,
 }
 
+impl SwapchainProperties {
+    /// The properties used in place of a swapchain's by headless
+    /// contexts; the format is RGBA so frames can be saved directly
+    fn headless([width, height]: [u32; 2]) -> Self {
+        Self {
+            extent: vk::Extent2D { width, height },
+            present_mode: vk::PresentModeKHR::FIFO,
+            format: vk::SurfaceFormatKHR {
+                format: vk::Format::R8G8B8A8_UNORM,
+                color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            },
+        }
+    }
+}
+
 struct SwapchainSupportDetails {
     capabilities: vk::SurfaceCapabilitiesKHR,
     formats: Vec<vk::SurfaceFormatKHR>,
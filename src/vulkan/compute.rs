@@ -8,11 +8,13 @@ use anyhow::Result;
 use super::GfaestusVk;
 
 pub mod edges;
+pub mod node_cull;
 pub mod node_motion;
 pub mod path_view;
 pub mod selection;
 
 pub use edges::*;
+pub use node_cull::*;
 pub use node_motion::*;
 pub use selection::*;
 
@@ -72,7 +74,7 @@ impl ComputeManager {
     }
 
     pub fn free_fence(&mut self, fence_id: usize, block: bool) -> Result<()> {
-        let fence = *self.fences.get(&fence_id).unwrap();
+        let fence = self.fences.remove(&fence_id).unwrap();
 
         if block {
             let fences = [fence];
@@ -80,7 +82,7 @@ impl ComputeManager {
                 unsafe { self.device.wait_for_fences(&fences, true, 0) }?;
         }
 
-        let cmd_buf = *self.command_buffers.get(&fence_id).unwrap();
+        let cmd_buf = self.command_buffers.remove(&fence_id).unwrap();
 
         unsafe {
             let cmd_bufs = [cmd_buf];
@@ -155,6 +157,28 @@ impl ComputeManager {
 
         Ok(fence_id)
     }
+
+    /// Waits for any dispatches that haven't been freed yet, then
+    /// destroys their fences and the command pool.
+    pub fn destroy(&mut self) {
+        let fences = self.fences.values().copied().collect::<Vec<_>>();
+
+        unsafe {
+            if !fences.is_empty() {
+                let _ = self.device.wait_for_fences(&fences, true, u64::MAX);
+            }
+
+            for fence in fences {
+                self.device.destroy_fence(fence, None);
+            }
+
+            self.device
+                .destroy_command_pool(self.compute_cmd_pool, None);
+        }
+
+        self.fences.clear();
+        self.command_buffers.clear();
+    }
 }
 
 pub struct ComputePipeline {
@@ -234,6 +258,19 @@ impl ComputePipeline {
         })
     }
 
+    /// Destroys the pipeline, its layout, and the descriptor pool.
+    /// The descriptor set layout can be shared between pipelines, so
+    /// it's left to the owner to destroy.
+    pub fn destroy(&self) {
+        unsafe {
+            self.device.destroy_pipeline(self.pipeline, None);
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+    }
+
     pub(crate) fn create_pipeline(
         device: &Device,
         pipeline_layout: vk::PipelineLayout,
@@ -0,0 +1,529 @@
+use ash::version::DeviceV1_0;
+use ash::{vk, Device};
+
+use nalgebra_glm as glm;
+
+use anyhow::Result;
+
+#[allow(unused_imports)]
+use log::{debug, error, info, trace, warn};
+
+use crate::vulkan::{draw_system::nodes::NodeVertices, GfaestusVk};
+
+use super::ComputePipeline;
+
+/// Compute prepass for the node draw that compacts the vertex
+/// indices of the nodes that overlap the viewport into an index
+/// buffer, and writes the matching indexed indirect draw command.
+///
+/// The indices are the original vertex indices, so the node IDs
+/// derived from `gl_VertexIndex` in the node shaders, and thus the
+/// node ID buffer, are unchanged by the culling.
+pub struct NodeCulling {
+    compute_pipeline: ComputePipeline,
+
+    descriptor_set: vk::DescriptorSet,
+
+    pub(crate) index_buffer: vk::Buffer,
+    index_memory: vk::DeviceMemory,
+
+    pub(crate) indirect_buffer: vk::Buffer,
+    indirect_memory: vk::DeviceMemory,
+
+    /// The index count of the draw command is copied here by each
+    /// cull pass, so it can be read on the host once the frame is done
+    readback_buffer: vk::Buffer,
+    readback_memory: vk::DeviceMemory,
+
+    visible_node_count: usize,
+
+    /// The node vertex buffer bound in the descriptor set
+    vertex_buffer: vk::Buffer,
+
+    node_count: usize,
+    vertices_per_node: usize,
+}
+
+impl NodeCulling {
+    /// Size of `VkDrawIndexedIndirectCommand`
+    const DRAW_CMD_SIZE: vk::DeviceSize = 20;
+
+    /// Size of the index count, the first field of the draw command
+    const READBACK_SIZE: vk::DeviceSize = 4;
+
+    pub fn new(app: &GfaestusVk, vertices: &NodeVertices) -> Result<Self> {
+        let device = app.vk_context().device();
+
+        let node_count = vertices.node_count();
+        let vertices_per_node = vertices.vertices_per_node();
+
+        let desc_set_layout = Self::create_descriptor_set_layout(device)?;
+
+        let pipeline_layout = {
+            use vk::ShaderStageFlags as Flags;
+
+            let pc_range = vk::PushConstantRange::builder()
+                .stage_flags(Flags::COMPUTE)
+                .offset(0)
+                .size(CullPushConstants::SIZE as u32)
+                .build();
+
+            let pc_ranges = [pc_range];
+
+            let layouts = [desc_set_layout];
+
+            let layout_info = vk::PipelineLayoutCreateInfo::builder()
+                .set_layouts(&layouts)
+                .push_constant_ranges(&pc_ranges)
+                .build();
+
+            unsafe { device.create_pipeline_layout(&layout_info, None) }
+        }?;
+
+        let pool_sizes = [vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::STORAGE_BUFFER,
+            descriptor_count: 3,
+        }];
+
+        let compute_pipeline = ComputePipeline::new_with_pool_size(
+            device,
+            desc_set_layout,
+            &pool_sizes,
+            pipeline_layout,
+            crate::include_shader!("compute/node_cull.comp.spv"),
+        )?;
+
+        let descriptor_sets = {
+            let layouts = vec![desc_set_layout];
+
+            let alloc_info = vk::DescriptorSetAllocateInfo::builder()
+                .descriptor_pool(compute_pipeline.descriptor_pool)
+                .set_layouts(&layouts)
+                .build();
+
+            unsafe { device.allocate_descriptor_sets(&alloc_info) }
+        }?;
+
+        let index_size = (vertices.vertex_count.max(1)
+            * std::mem::size_of::<u32>())
+            as vk::DeviceSize;
+
+        let (index_buffer, index_memory, _) = app.create_buffer(
+            index_size,
+            vk::BufferUsageFlags::STORAGE_BUFFER
+                | vk::BufferUsageFlags::INDEX_BUFFER,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        )?;
+        app.set_debug_object_name(index_buffer, "Culled Node Index Buffer")?;
+
+        let (indirect_buffer, indirect_memory, _) = app.create_buffer(
+            Self::DRAW_CMD_SIZE,
+            vk::BufferUsageFlags::STORAGE_BUFFER
+                | vk::BufferUsageFlags::INDIRECT_BUFFER
+                | vk::BufferUsageFlags::TRANSFER_DST
+                | vk::BufferUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+        )?;
+        app.set_debug_object_name(
+            indirect_buffer,
+            "Node Indirect Draw Buffer",
+        )?;
+
+        let (readback_buffer, readback_memory, _) = app.create_buffer(
+            Self::READBACK_SIZE,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            vk::MemoryPropertyFlags::HOST_VISIBLE
+                | vk::MemoryPropertyFlags::HOST_COHERENT,
+        )?;
+        app.set_debug_object_name(
+            readback_buffer,
+            "Node Culling Readback Buffer",
+        )?;
+
+        let culling = Self {
+            compute_pipeline,
+
+            descriptor_set: descriptor_sets[0],
+
+            index_buffer,
+            index_memory,
+
+            indirect_buffer,
+            indirect_memory,
+
+            readback_buffer,
+            readback_memory,
+
+            visible_node_count: 0,
+
+            vertex_buffer: vertices.buffer(),
+
+            node_count,
+            vertices_per_node,
+        };
+
+        culling.write_descriptor_set(vertices);
+
+        Ok(culling)
+    }
+
+    /// Whether the culling buffers and descriptor set match the
+    /// current node vertices; if not, it must be recreated
+    pub fn matches_vertices(&self, vertices: &NodeVertices) -> bool {
+        self.vertex_buffer == vertices.buffer()
+            && self.node_count == vertices.node_count()
+    }
+
+    /// The number of nodes drawn by the cull pass that was last read
+    /// back with `read_visible_node_count`
+    pub fn visible_node_count(&self) -> usize {
+        self.visible_node_count
+    }
+
+    /// Read back the number of nodes drawn by the latest cull pass.
+    /// Must only be called once the frame that recorded the pass has
+    /// finished executing, as the readback buffer is written by it.
+    pub fn read_visible_node_count(&mut self) -> Result<()> {
+        let device = &self.compute_pipeline.device;
+
+        let index_count = unsafe {
+            let data_ptr = device.map_memory(
+                self.readback_memory,
+                0,
+                Self::READBACK_SIZE,
+                vk::MemoryMapFlags::empty(),
+            )?;
+
+            let index_count = *(data_ptr as *const u32);
+
+            device.unmap_memory(self.readback_memory);
+
+            index_count
+        };
+
+        self.visible_node_count = index_count as usize / self.vertices_per_node;
+
+        Ok(())
+    }
+
+    /// Record the cull pass, including the barriers that order it
+    /// after the previous draw that read the index and indirect
+    /// buffers, and make them available to the following draw, and
+    /// the copy of the index count to the readback buffer. Must be
+    /// recorded outside a render pass.
+    pub fn cull_cmd(
+        &self,
+        cmd_buf: vk::CommandBuffer,
+        view_transform: glm::Mat4,
+        margin: f32,
+    ) -> Result<()> {
+        let device = &self.compute_pipeline.device;
+
+        let draw_cmd: [u32; 5] = [0, 1, 0, 0, 0];
+        let draw_cmd_bytes = draw_cmd
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect::<Vec<u8>>();
+
+        unsafe {
+            // the previous draw must be done reading the draw command
+            // and indices before they're overwritten
+            let barrier = vk::MemoryBarrier::builder()
+                .src_access_mask(
+                    vk::AccessFlags::INDIRECT_COMMAND_READ
+                        | vk::AccessFlags::INDEX_READ,
+                )
+                .dst_access_mask(
+                    vk::AccessFlags::TRANSFER_WRITE
+                        | vk::AccessFlags::SHADER_WRITE,
+                )
+                .build();
+
+            device.cmd_pipeline_barrier(
+                cmd_buf,
+                vk::PipelineStageFlags::DRAW_INDIRECT
+                    | vk::PipelineStageFlags::VERTEX_INPUT,
+                vk::PipelineStageFlags::TRANSFER
+                    | vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &[barrier],
+                &[],
+                &[],
+            );
+
+            device.cmd_update_buffer(
+                cmd_buf,
+                self.indirect_buffer,
+                0,
+                &draw_cmd_bytes,
+            );
+
+            let barrier = vk::MemoryBarrier::builder()
+                .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                .dst_access_mask(
+                    vk::AccessFlags::SHADER_READ
+                        | vk::AccessFlags::SHADER_WRITE,
+                )
+                .build();
+
+            device.cmd_pipeline_barrier(
+                cmd_buf,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &[barrier],
+                &[],
+                &[],
+            );
+        }
+
+        unsafe {
+            device.cmd_bind_pipeline(
+                cmd_buf,
+                vk::PipelineBindPoint::COMPUTE,
+                self.compute_pipeline.pipeline,
+            );
+
+            let desc_sets = [self.descriptor_set];
+
+            let null = [];
+            device.cmd_bind_descriptor_sets(
+                cmd_buf,
+                vk::PipelineBindPoint::COMPUTE,
+                self.compute_pipeline.pipeline_layout,
+                0,
+                &desc_sets[0..=0],
+                &null,
+            );
+        };
+
+        let push_constants = CullPushConstants {
+            view_transform,
+            margin,
+            node_count: self.node_count as u32,
+            vertices_per_node: self.vertices_per_node as u32,
+        };
+
+        let pc_bytes = push_constants.bytes();
+
+        unsafe {
+            device.cmd_push_constants(
+                cmd_buf,
+                self.compute_pipeline.pipeline_layout,
+                vk::ShaderStageFlags::COMPUTE,
+                0,
+                &pc_bytes,
+            )
+        };
+
+        let x_group_count = {
+            let div = self.node_count / 256;
+            let rem = self.node_count % 256;
+
+            let mut count = div;
+            if rem > 0 {
+                count += 1;
+            }
+            count as u32
+        };
+
+        unsafe {
+            device.cmd_dispatch(cmd_buf, x_group_count, 1, 1);
+
+            let barrier = vk::MemoryBarrier::builder()
+                .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+                .dst_access_mask(
+                    vk::AccessFlags::INDIRECT_COMMAND_READ
+                        | vk::AccessFlags::INDEX_READ
+                        | vk::AccessFlags::TRANSFER_READ,
+                )
+                .build();
+
+            device.cmd_pipeline_barrier(
+                cmd_buf,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::DRAW_INDIRECT
+                    | vk::PipelineStageFlags::VERTEX_INPUT
+                    | vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[barrier],
+                &[],
+                &[],
+            );
+
+            let region = vk::BufferCopy::builder()
+                .src_offset(0)
+                .dst_offset(0)
+                .size(Self::READBACK_SIZE)
+                .build();
+
+            device.cmd_copy_buffer(
+                cmd_buf,
+                self.indirect_buffer,
+                self.readback_buffer,
+                &[region],
+            );
+
+            let barrier = vk::MemoryBarrier::builder()
+                .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+                .dst_access_mask(vk::AccessFlags::HOST_READ)
+                .build();
+
+            device.cmd_pipeline_barrier(
+                cmd_buf,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::HOST,
+                vk::DependencyFlags::empty(),
+                &[barrier],
+                &[],
+                &[],
+            );
+        }
+
+        Ok(())
+    }
+
+    fn write_descriptor_set(&self, vertices: &NodeVertices) {
+        let buffers =
+            [vertices.buffer(), self.index_buffer, self.indirect_buffer];
+
+        let buf_infos = buffers
+            .iter()
+            .map(|&buffer| {
+                [vk::DescriptorBufferInfo::builder()
+                    .buffer(buffer)
+                    .offset(0)
+                    .range(vk::WHOLE_SIZE)
+                    .build()]
+            })
+            .collect::<Vec<_>>();
+
+        let desc_writes = buf_infos
+            .iter()
+            .enumerate()
+            .map(|(binding, buf_info)| {
+                vk::WriteDescriptorSet::builder()
+                    .dst_set(self.descriptor_set)
+                    .dst_binding(binding as u32)
+                    .dst_array_element(0)
+                    .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                    .buffer_info(buf_info)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        unsafe {
+            self.compute_pipeline
+                .device
+                .update_descriptor_sets(&desc_writes, &[])
+        };
+    }
+
+    fn layout_binding() -> [vk::DescriptorSetLayoutBinding; 3] {
+        use vk::ShaderStageFlags as Stages;
+
+        let binding = |ix: u32| {
+            vk::DescriptorSetLayoutBinding::builder()
+                .binding(ix)
+                .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                .descriptor_count(1)
+                .stage_flags(Stages::COMPUTE)
+                .build()
+        };
+
+        // vertices, indices, draw command
+        [binding(0), binding(1), binding(2)]
+    }
+
+    fn create_descriptor_set_layout(
+        device: &Device,
+    ) -> Result<vk::DescriptorSetLayout> {
+        let bindings = Self::layout_binding();
+
+        let layout_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(&bindings)
+            .build();
+
+        let layout =
+            unsafe { device.create_descriptor_set_layout(&layout_info, None) }?;
+
+        Ok(layout)
+    }
+
+    pub fn destroy(&mut self) {
+        let device = &self.compute_pipeline.device;
+
+        unsafe {
+            device.destroy_buffer(self.index_buffer, None);
+            device.free_memory(self.index_memory, None);
+
+            device.destroy_buffer(self.indirect_buffer, None);
+            device.free_memory(self.indirect_memory, None);
+
+            device.destroy_buffer(self.readback_buffer, None);
+            device.free_memory(self.readback_memory, None);
+
+            device.destroy_descriptor_set_layout(
+                self.compute_pipeline.descriptor_set_layout,
+                None,
+            );
+            device.destroy_descriptor_pool(
+                self.compute_pipeline.descriptor_pool,
+                None,
+            );
+            device.destroy_pipeline(self.compute_pipeline.pipeline, None);
+            device.destroy_pipeline_layout(
+                self.compute_pipeline.pipeline_layout,
+                None,
+            );
+        }
+
+        self.index_buffer = vk::Buffer::null();
+        self.index_memory = vk::DeviceMemory::null();
+
+        self.indirect_buffer = vk::Buffer::null();
+        self.indirect_memory = vk::DeviceMemory::null();
+
+        self.readback_buffer = vk::Buffer::null();
+        self.readback_memory = vk::DeviceMemory::null();
+    }
+}
+
+struct CullPushConstants {
+    view_transform: glm::Mat4,
+    margin: f32,
+    node_count: u32,
+    vertices_per_node: u32,
+}
+
+impl CullPushConstants {
+    const SIZE: usize = 76;
+
+    #[inline]
+    fn bytes(&self) -> [u8; Self::SIZE] {
+        use crate::view;
+
+        let mut bytes = [0u8; Self::SIZE];
+
+        let view_transform_array = view::mat4_to_array(&self.view_transform);
+
+        let mut offset = 0;
+
+        let mut add_bytes = |b: [u8; 4]| {
+            bytes[offset..offset + 4].copy_from_slice(&b);
+            offset += 4;
+        };
+
+        for row in view_transform_array.iter() {
+            for val in row.iter() {
+                add_bytes(val.to_ne_bytes());
+            }
+        }
+
+        add_bytes(self.margin.to_ne_bytes());
+        add_bytes(self.node_count.to_ne_bytes());
+        add_bytes(self.vertices_per_node.to_ne_bytes());
+
+        bytes
+    }
+}
@@ -2,7 +2,14 @@ use crate::geometry::Point;
 use ash::version::DeviceV1_0;
 use ash::{vk, Device};
 
+use handlegraph::{
+    handle::{Direction, Handle, NodeId},
+    handlegraph::*,
+    packedgraph::PackedGraph,
+};
+
 use anyhow::Result;
+use rustc_hash::FxHashMap;
 
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
@@ -239,6 +246,19 @@ impl NodeTranslation {
 
         Ok(layout)
     }
+
+    pub fn destroy(&self) {
+        let device = &self.compute_pipeline.device;
+
+        self.compute_pipeline.destroy();
+
+        unsafe {
+            device.destroy_descriptor_set_layout(
+                self.compute_pipeline.descriptor_set_layout,
+                None,
+            );
+        }
+    }
 }
 
 pub struct DeltaPushConstants {
@@ -274,3 +294,454 @@ impl DeltaPushConstants {
         bytes
     }
 }
+
+/// Iterative force-directed refinement of the node layout. Each
+/// iteration moves every node toward a position where the distances
+/// to its neighbors approach `spring_length`.
+///
+/// Nodes are addressed by their index in the layout, which is also
+/// their index in the node vertex buffer, so node IDs don't need to
+/// be contiguous, and every vertex of a node is moved, for both node
+/// renderers.
+pub struct LayoutRelaxation {
+    compute_pipeline: ComputePipeline,
+
+    descriptor_set: vk::DescriptorSet,
+
+    prev_nodes_buf: vk::Buffer,
+    prev_nodes_alloc: vk_mem::Allocation,
+
+    offsets_buf: vk::Buffer,
+    offsets_alloc: vk_mem::Allocation,
+
+    neighbors_buf: vk::Buffer,
+    neighbors_alloc: vk_mem::Allocation,
+
+    pub spring_length: f32,
+    pub step_size: f32,
+
+    node_count: usize,
+    vertices_per_node: usize,
+}
+
+impl LayoutRelaxation {
+    /// `node_ids` must be in the same order as the nodes in
+    /// `vertices`, as given by the layout.
+    pub fn new(
+        app: &GfaestusVk,
+        graph: &PackedGraph,
+        node_ids: &[NodeId],
+        vertices: &NodeVertices,
+    ) -> Result<Self> {
+        let device = app.vk_context().device();
+
+        let node_count = node_ids.len();
+        let vertices_per_node = vertices.vertices_per_node();
+
+        let desc_set_layout = Self::create_descriptor_set_layout(device)?;
+
+        let pipeline_layout = {
+            use vk::ShaderStageFlags as Flags;
+
+            let pc_range = vk::PushConstantRange::builder()
+                .stage_flags(Flags::COMPUTE)
+                .offset(0)
+                .size(RelaxPushConstants::SIZE as u32)
+                .build();
+
+            let pc_ranges = [pc_range];
+
+            let layouts = [desc_set_layout];
+
+            let layout_info = vk::PipelineLayoutCreateInfo::builder()
+                .set_layouts(&layouts)
+                .push_constant_ranges(&pc_ranges)
+                .build();
+
+            unsafe { device.create_pipeline_layout(&layout_info, None) }
+        }?;
+
+        let pool_sizes = [vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::STORAGE_BUFFER,
+            descriptor_count: 4,
+        }];
+
+        let compute_pipeline = ComputePipeline::new_with_pool_size(
+            device,
+            desc_set_layout,
+            &pool_sizes,
+            pipeline_layout,
+            crate::include_shader!("compute/layout_relax.comp.spv"),
+        )?;
+
+        let descriptor_sets = {
+            let layouts = vec![desc_set_layout];
+
+            let alloc_info = vk::DescriptorSetAllocateInfo::builder()
+                .descriptor_pool(compute_pipeline.descriptor_pool)
+                .set_layouts(&layouts)
+                .build();
+
+            unsafe { device.allocate_descriptor_sets(&alloc_info) }
+        }?;
+
+        let (offsets, neighbors) = Self::adjacency(graph, node_ids);
+
+        let usage = vk::BufferUsageFlags::STORAGE_BUFFER;
+        let memory_usage = vk_mem::MemoryUsage::GpuOnly;
+
+        let (offsets_buf, offsets_alloc, _) =
+            app.create_buffer_with_data(usage, memory_usage, false, &offsets)?;
+        app.set_debug_object_name(offsets_buf, "Layout Adjacency Offsets")?;
+
+        let (neighbors_buf, neighbors_alloc, _) = app.create_buffer_with_data(
+            usage,
+            memory_usage,
+            false,
+            &neighbors,
+        )?;
+        app.set_debug_object_name(neighbors_buf, "Layout Adjacency Neighbors")?;
+
+        // each vertex is two floats
+        let prev_nodes = vec![0.0f32; node_count * vertices_per_node * 2];
+
+        let (prev_nodes_buf, prev_nodes_alloc, _) = app
+            .create_buffer_with_data(
+                usage | vk::BufferUsageFlags::TRANSFER_DST,
+                memory_usage,
+                false,
+                &prev_nodes,
+            )?;
+        app.set_debug_object_name(prev_nodes_buf, "Layout Previous Nodes")?;
+
+        Ok(Self {
+            compute_pipeline,
+
+            descriptor_set: descriptor_sets[0],
+
+            prev_nodes_buf,
+            prev_nodes_alloc,
+
+            offsets_buf,
+            offsets_alloc,
+
+            neighbors_buf,
+            neighbors_alloc,
+
+            spring_length: 100.0,
+            step_size: 0.1,
+
+            node_count,
+            vertices_per_node,
+        })
+    }
+
+    /// The graph adjacency in compressed sparse row form, using the
+    /// nodes' indices in `node_ids` and ignoring edge orientation
+    fn adjacency(
+        graph: &PackedGraph,
+        node_ids: &[NodeId],
+    ) -> (Vec<u32>, Vec<u32>) {
+        let node_count = node_ids.len();
+
+        let indices: FxHashMap<NodeId, u32> = node_ids
+            .iter()
+            .enumerate()
+            .map(|(ix, &id)| (id, ix as u32))
+            .collect();
+
+        let mut offsets: Vec<u32> = Vec::with_capacity(node_count + 1);
+        let mut neighbors: Vec<u32> = Vec::new();
+
+        for (ix, &id) in node_ids.iter().enumerate() {
+            offsets.push(neighbors.len() as u32);
+
+            let handle = Handle::pack(id, false);
+
+            let mut adjacent = graph
+                .neighbors(handle, Direction::Left)
+                .chain(graph.neighbors(handle, Direction::Right))
+                .filter_map(|other| indices.get(&other.id()).copied())
+                .filter(|&other| other as usize != ix)
+                .collect::<Vec<_>>();
+
+            adjacent.sort();
+            adjacent.dedup();
+
+            neighbors.extend(adjacent);
+        }
+
+        offsets.push(neighbors.len() as u32);
+
+        // empty storage buffers aren't allowed
+        if neighbors.is_empty() {
+            neighbors.push(0);
+        }
+
+        (offsets, neighbors)
+    }
+
+    /// Submit `steps` relaxation iterations, returning the ID of the
+    /// fence to poll before reading the updated node positions.
+    pub fn relax_nodes(
+        &self,
+        comp_manager: &mut ComputeManager,
+        vertices: &NodeVertices,
+        steps: usize,
+    ) -> Result<usize> {
+        self.write_descriptor_set(vertices);
+
+        let fence_id = comp_manager.dispatch_with(|_device, cmd_buf| {
+            for _ in 0..steps {
+                self.relax_cmd(cmd_buf, vertices).unwrap();
+            }
+        })?;
+
+        Ok(fence_id)
+    }
+
+    fn relax_cmd(
+        &self,
+        cmd_buf: vk::CommandBuffer,
+        vertices: &NodeVertices,
+    ) -> Result<()> {
+        let device = &self.compute_pipeline.device;
+
+        let floats = self.node_count * self.vertices_per_node * 2;
+        let nodes_size =
+            (floats * std::mem::size_of::<f32>()) as vk::DeviceSize;
+
+        let copy_region = vk::BufferCopy::builder()
+            .src_offset(0)
+            .dst_offset(0)
+            .size(nodes_size)
+            .build();
+
+        unsafe {
+            device.cmd_copy_buffer(
+                cmd_buf,
+                vertices.buffer(),
+                self.prev_nodes_buf,
+                &[copy_region],
+            );
+        }
+
+        Self::memory_barrier(
+            device,
+            cmd_buf,
+            (
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_WRITE,
+            ),
+            (
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::AccessFlags::SHADER_READ,
+            ),
+        );
+
+        unsafe {
+            device.cmd_bind_pipeline(
+                cmd_buf,
+                vk::PipelineBindPoint::COMPUTE,
+                self.compute_pipeline.pipeline,
+            )
+        };
+
+        unsafe {
+            let desc_sets = [self.descriptor_set];
+
+            let null = [];
+            device.cmd_bind_descriptor_sets(
+                cmd_buf,
+                vk::PipelineBindPoint::COMPUTE,
+                self.compute_pipeline.pipeline_layout,
+                0,
+                &desc_sets[0..=0],
+                &null,
+            );
+        };
+
+        let push_constants = RelaxPushConstants {
+            node_count: self.node_count as u32,
+            vertices_per_node: self.vertices_per_node as u32,
+            spring_length: self.spring_length,
+            step_size: self.step_size,
+        };
+        let pc_bytes = push_constants.bytes();
+
+        unsafe {
+            use vk::ShaderStageFlags as Flags;
+            device.cmd_push_constants(
+                cmd_buf,
+                self.compute_pipeline.pipeline_layout,
+                Flags::COMPUTE,
+                0,
+                &pc_bytes,
+            )
+        };
+
+        let x_group_count = {
+            let div = self.node_count / 256;
+            let rem = self.node_count % 256;
+
+            let mut count = div;
+            if rem > 0 {
+                count += 1;
+            }
+            count as u32
+        };
+
+        unsafe { device.cmd_dispatch(cmd_buf, x_group_count, 1, 1) };
+
+        // the next iteration copies the node positions again
+        Self::memory_barrier(
+            device,
+            cmd_buf,
+            (
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::AccessFlags::SHADER_WRITE,
+            ),
+            (
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_READ,
+            ),
+        );
+
+        Ok(())
+    }
+
+    fn memory_barrier(
+        device: &Device,
+        cmd_buf: vk::CommandBuffer,
+        src: (vk::PipelineStageFlags, vk::AccessFlags),
+        dst: (vk::PipelineStageFlags, vk::AccessFlags),
+    ) {
+        let barrier = vk::MemoryBarrier::builder()
+            .src_access_mask(src.1)
+            .dst_access_mask(dst.1)
+            .build();
+
+        let memory_barriers = [barrier];
+
+        unsafe {
+            device.cmd_pipeline_barrier(
+                cmd_buf,
+                src.0,
+                dst.0,
+                vk::DependencyFlags::empty(),
+                &memory_barriers,
+                &[],
+                &[],
+            );
+        }
+    }
+
+    fn write_descriptor_set(&self, vertices: &NodeVertices) {
+        let buffers = [
+            vertices.buffer(),
+            self.prev_nodes_buf,
+            self.offsets_buf,
+            self.neighbors_buf,
+        ];
+
+        let buf_infos = buffers
+            .iter()
+            .map(|&buffer| {
+                [vk::DescriptorBufferInfo::builder()
+                    .buffer(buffer)
+                    .offset(0)
+                    .range(vk::WHOLE_SIZE)
+                    .build()]
+            })
+            .collect::<Vec<_>>();
+
+        let desc_writes = buf_infos
+            .iter()
+            .enumerate()
+            .map(|(binding, buf_info)| {
+                vk::WriteDescriptorSet::builder()
+                    .dst_set(self.descriptor_set)
+                    .dst_binding(binding as u32)
+                    .dst_array_element(0)
+                    .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                    .buffer_info(buf_info)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        unsafe {
+            self.compute_pipeline
+                .device
+                .update_descriptor_sets(&desc_writes, &[])
+        };
+    }
+
+    fn create_descriptor_set_layout(
+        device: &Device,
+    ) -> Result<vk::DescriptorSetLayout> {
+        use vk::ShaderStageFlags as Stages;
+
+        let bindings = (0..4)
+            .map(|binding| {
+                vk::DescriptorSetLayoutBinding::builder()
+                    .binding(binding)
+                    .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                    .descriptor_count(1)
+                    .stage_flags(Stages::COMPUTE)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+
+        let layout_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(&bindings)
+            .build();
+
+        let layout =
+            unsafe { device.create_descriptor_set_layout(&layout_info, None) }?;
+
+        Ok(layout)
+    }
+
+    pub fn destroy(&mut self, app: &GfaestusVk) -> Result<()> {
+        app.allocator
+            .destroy_buffer(self.prev_nodes_buf, &self.prev_nodes_alloc)?;
+        app.allocator
+            .destroy_buffer(self.offsets_buf, &self.offsets_alloc)?;
+        app.allocator
+            .destroy_buffer(self.neighbors_buf, &self.neighbors_alloc)?;
+
+        self.compute_pipeline.destroy();
+
+        unsafe {
+            app.vk_context().device().destroy_descriptor_set_layout(
+                self.compute_pipeline.descriptor_set_layout,
+                None,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+pub struct RelaxPushConstants {
+    node_count: u32,
+    vertices_per_node: u32,
+    spring_length: f32,
+    step_size: f32,
+}
+
+impl RelaxPushConstants {
+    pub const SIZE: usize = 16;
+
+    #[inline]
+    pub fn bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+
+        bytes[0..4].copy_from_slice(&self.node_count.to_ne_bytes());
+        bytes[4..8].copy_from_slice(&self.vertices_per_node.to_ne_bytes());
+        bytes[8..12].copy_from_slice(&self.spring_length.to_ne_bytes());
+        bytes[12..16].copy_from_slice(&self.step_size.to_ne_bytes());
+
+        bytes
+    }
+}
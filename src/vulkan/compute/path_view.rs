@@ -975,7 +975,7 @@ impl PathViewRenderer {
                         )
                         .unwrap();
                     }
-                    OverlayKind::Value => {
+                    OverlayKind::Value | OverlayKind::Category => {
                         self.dispatch_cmd_val(
                             cmd_buf,
                             app,
@@ -1200,6 +1200,30 @@ impl PathViewRenderer {
 
         Ok(layout)
     }
+
+    /// Destroys the pipelines, buffer, and output image. Takes `&self`
+    /// as the renderer is shared with the GUI; it must not be used
+    /// afterward.
+    pub fn destroy(&self, app: &GfaestusVk) -> Result<()> {
+        let device = app.vk_context().device();
+
+        self.rgb_pipeline.destroy();
+        self.val_pipeline.destroy();
+
+        unsafe {
+            device.destroy_descriptor_pool(self.descriptor_pool, None);
+            device
+                .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+        }
+
+        app.allocator
+            .destroy_buffer(self.path_buffer, &self.path_allocation)?;
+
+        let mut output_image = self.output_image;
+        output_image.destroy(device);
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -1336,7 +1360,9 @@ impl Path1DLayout {
         }
     }
 
-    pub fn new(graph: &PackedGraph) -> Self {
+    pub fn new(graph_query: &GraphQuery) -> Self {
+        let graph = graph_query.graph();
+
         let nodes = {
             let mut ns = graph.handles().map(|h| h.id()).collect::<Vec<_>>();
             ns.sort();
@@ -1359,7 +1385,7 @@ impl Path1DLayout {
         for node in nodes {
             let handle = Handle::pack(node, false);
 
-            let len = graph.node_len(handle);
+            let len = graph_query.node_len(handle);
 
             node_offsets.push(total_len);
 
@@ -244,6 +244,19 @@ impl GpuSelection {
 
         Ok(layout)
     }
+
+    pub fn destroy(&mut self, device: &Device) {
+        self.selection_buffer.destroy(device);
+
+        self.compute_pipeline.destroy();
+
+        unsafe {
+            device.destroy_descriptor_set_layout(
+                self.compute_pipeline.descriptor_set_layout,
+                None,
+            );
+        }
+    }
 }
 
 pub struct RectPushConstants {
@@ -283,6 +283,15 @@ impl VkContext {
         })
     }
 
+    /// Return the sample counts supported for color attachments.
+    pub fn supported_sample_counts(&self) -> vk::SampleCountFlags {
+        let props = unsafe {
+            self.instance
+                .get_physical_device_properties(self.physical_device)
+        };
+        props.limits.framebuffer_color_sample_counts
+    }
+
     /// Return the maximim sample count supported.
     pub fn get_max_usable_sample_count(&self) -> vk::SampleCountFlags {
         let props = unsafe {
@@ -315,7 +324,10 @@ impl Drop for VkContext {
     fn drop(&mut self) {
         unsafe {
             self.device.destroy_device(None);
-            self.surface.destroy_surface(self.surface_khr, None);
+            // headless contexts have no surface
+            if self.surface_khr != vk::SurfaceKHR::null() {
+                self.surface.destroy_surface(self.surface_khr, None);
+            }
             if let Some((report, callback)) = self.debug_utils.take() {
                 report.destroy_debug_utils_messenger(callback, None);
             }
@@ -10,11 +10,17 @@ use ash::extensions::ext::DebugUtils;
 #[allow(unused_imports)]
 use log::{debug, error, info, trace, warn};
 
+/// Whether the validation layers are enabled when neither the
+/// environment variable nor the command line flag asks for them
 #[cfg(debug_assertions)]
 pub const ENABLE_VALIDATION_LAYERS: bool = true;
 #[cfg(not(debug_assertions))]
 pub const ENABLE_VALIDATION_LAYERS: bool = false;
 
+/// Set to 1 to enable the validation layers, or 0 to disable them
+/// even in debug builds
+pub const VALIDATION_ENV_VAR: &str = "GFAESTUS_VALIDATION";
+
 const REQUIRED_LAYERS: [&str; 1] = ["VK_LAYER_KHRONOS_validation"];
 
 unsafe extern "system" fn vulkan_debug_utils_callback(
@@ -123,6 +129,26 @@ unsafe extern "system" fn vulkan_debug_utils_callback(
     vk::FALSE
 }
 
+/// Whether the validation layers should be enabled, given the
+/// `--validation` command line flag. `GFAESTUS_VALIDATION` takes
+/// precedence over the flag if it's set to a recognized value.
+pub fn validation_requested(cli_flag: bool) -> bool {
+    let env_value = std::env::var(VALIDATION_ENV_VAR).ok();
+
+    match env_value.as_deref().map(str::trim) {
+        Some("1") | Some("true") => true,
+        Some("0") | Some("false") => false,
+        Some(other) => {
+            warn!(
+                "Ignoring unrecognized value {:?} for {}, expected 1 or 0",
+                other, VALIDATION_ENV_VAR
+            );
+            cli_flag || ENABLE_VALIDATION_LAYERS
+        }
+        None => cli_flag || ENABLE_VALIDATION_LAYERS,
+    }
+}
+
 /// Get the pointers to the validation layers names.
 /// Also return the corresponding `CString` to avoid dangling pointers.
 pub fn get_layer_names_and_pointers() -> (Vec<CString>, Vec<*const c_char>) {
@@ -137,37 +163,58 @@ pub fn get_layer_names_and_pointers() -> (Vec<CString>, Vec<*const c_char>) {
     (layer_names, layer_names_ptrs)
 }
 
-/// Check if the required validation set in `REQUIRED_LAYERS`
-/// are supported by the Vulkan instance.
+/// Check if the required validation set in `REQUIRED_LAYERS`, and
+/// the debug utils extension, are supported by the Vulkan instance.
 ///
-/// # Panics
-///
-/// Panic if at least one on the layer is not supported.
-pub fn check_validation_layer_support(entry: &Entry) {
+/// Logs a warning and returns `false` if anything is missing.
+pub fn check_validation_layer_support(entry: &Entry) -> bool {
+    let layers = match entry.enumerate_instance_layer_properties() {
+        Ok(layers) => layers,
+        Err(err) => {
+            warn!("Could not enumerate instance layers: {:?}", err);
+            return false;
+        }
+    };
+
     for required in REQUIRED_LAYERS.iter() {
-        let found = entry
-            .enumerate_instance_layer_properties()
-            .unwrap()
-            .iter()
-            .any(|layer| {
-                let name = unsafe { CStr::from_ptr(layer.layer_name.as_ptr()) };
-                let name =
-                    name.to_str().expect("Failed to get layer name pointer");
-                required == &name
-            });
+        let found = layers.iter().any(|layer| {
+            let name = unsafe { CStr::from_ptr(layer.layer_name.as_ptr()) };
+            name.to_str().map(|name| required == &name).unwrap_or(false)
+        });
 
         if !found {
-            panic!("Validation layer not supported: {}", required);
+            warn!(
+                "Validation layer not supported: {}, is the Vulkan SDK installed?",
+                required
+            );
+            return false;
         }
     }
+
+    let extensions = entry
+        .enumerate_instance_extension_properties()
+        .unwrap_or_default();
+
+    let debug_utils = extensions.iter().any(|ext| {
+        let name = unsafe { CStr::from_ptr(ext.extension_name.as_ptr()) };
+        name == DebugUtils::name()
+    });
+
+    if !debug_utils {
+        warn!("Instance extension not supported: {:?}", DebugUtils::name());
+        return false;
+    }
+
+    true
 }
 
 /// Setup the DebugUtils messenger if validation layers are enabled.
 pub fn setup_debug_utils(
     entry: &Entry,
     instance: &Instance,
+    validation: bool,
 ) -> Option<(DebugUtils, vk::DebugUtilsMessengerEXT)> {
-    if !ENABLE_VALIDATION_LAYERS {
+    if !validation {
         return None;
     }
 
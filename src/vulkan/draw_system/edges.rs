@@ -20,7 +20,7 @@ use std::ffi::CString;
 use super::create_shader_module;
 use super::Vertex;
 
-use super::nodes::NodePushConstants;
+use super::nodes::{NodeFocusMode, NodePushConstants};
 use crate::vulkan::render_pass::Framebuffers;
 use crate::vulkan::{draw_system::nodes::NodeVertices, GfaestusVk};
 
@@ -256,7 +256,7 @@ impl EdgeRenderer {
                         | Flags::FRAGMENT,
                 )
                 .offset(0)
-                .size(84)
+                .size(NodePushConstants::SIZE)
                 .build();
 
             let pc_ranges = [pc_range];
@@ -310,6 +310,64 @@ impl EdgeRenderer {
         (pipeline, layout)
     }
 
+    /// Create the edge pipeline matching the renderer configuration,
+    /// using the current render pass and MSAA sample count
+    fn create_edge_pipeline(
+        app: &GfaestusVk,
+        layouts: &[vk::DescriptorSetLayout],
+    ) -> Result<(vk::Pipeline, vk::PipelineLayout)> {
+        let device = app.vk_context().device();
+
+        let msaa_samples = app.msaa_samples;
+        let render_pass = app.render_passes.edges;
+
+        let renderer_config = app.vk_context().renderer_config;
+
+        let pipeline = match renderer_config.edges {
+            crate::vulkan::context::EdgeRendererType::TessellationIsolines => {
+                Self::create_isoline_pipeline(
+                    device,
+                    msaa_samples,
+                    render_pass,
+                    layouts,
+                )
+            }
+            crate::vulkan::context::EdgeRendererType::TessellationQuads => {
+                Self::create_quad_pipeline(
+                    device,
+                    msaa_samples,
+                    render_pass,
+                    layouts,
+                )
+            }
+            crate::vulkan::context::EdgeRendererType::Disabled => {
+                anyhow::bail!("Tried to create a Disabled edge renderer!");
+            }
+        };
+
+        Ok(pipeline)
+    }
+
+    /// Rebuild the pipeline, e.g. after the MSAA sample count and
+    /// render passes have changed
+    pub fn recreate_pipeline(&mut self, app: &GfaestusVk) -> Result<()> {
+        let layouts = [self.descriptor_set_layout];
+
+        let (pipeline, pipeline_layout) =
+            Self::create_edge_pipeline(app, &layouts)?;
+
+        unsafe {
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device.destroy_pipeline(self.pipeline, None);
+        }
+
+        self.pipeline = pipeline;
+        self.pipeline_layout = pipeline_layout;
+
+        Ok(())
+    }
+
     pub fn new(
         app: &GfaestusVk,
         graph: &PackedGraph,
@@ -318,9 +376,6 @@ impl EdgeRenderer {
         let vk_context = app.vk_context();
         let device = app.vk_context().device();
 
-        let msaa_samples = app.msaa_samples;
-        let render_pass = app.render_passes.edges;
-
         let ubo = EdgesUBOBuffer::new(app)?;
 
         let desc_set_layout = Self::create_descriptor_set_layout(device)?;
@@ -381,27 +436,8 @@ impl EdgeRenderer {
         let renderer_config = vk_context.renderer_config;
         let wide_lines = renderer_config.supported_features.wide_lines;
 
-        let (pipeline, pipeline_layout) = match renderer_config.edges {
-            crate::vulkan::context::EdgeRendererType::TessellationIsolines => {
-                Self::create_isoline_pipeline(
-                    device,
-                    msaa_samples,
-                    render_pass,
-                    &layouts,
-                )
-            }
-            crate::vulkan::context::EdgeRendererType::TessellationQuads => {
-                Self::create_quad_pipeline(
-                    device,
-                    msaa_samples,
-                    render_pass,
-                    &layouts,
-                )
-            }
-            crate::vulkan::context::EdgeRendererType::Disabled => {
-                anyhow::bail!("Tried to create a Disabled edge renderer!");
-            }
-        };
+        let (pipeline, pipeline_layout) =
+            Self::create_edge_pipeline(app, &layouts)?;
 
         let edge_index_buffer =
             EdgeIndices::new_with_components(app, graph, layout)?;
@@ -530,7 +566,9 @@ impl EdgeRenderer {
             viewport_dims,
             view,
             node_width,
+            0.0,
             7,
+            NodeFocusMode::Normal,
         );
 
         let pc_bytes = push_constants.bytes();
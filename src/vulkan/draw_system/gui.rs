@@ -310,6 +310,7 @@ impl GuiPipeline {
         let device = &self.device;
 
         unsafe {
+            device.destroy_descriptor_pool(self.descriptor_pool, None);
             device.destroy_descriptor_set_layout(
                 self.descriptor_set_layout,
                 None,
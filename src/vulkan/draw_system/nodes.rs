@@ -10,6 +10,7 @@ use nalgebra_glm as glm;
 use anyhow::*;
 
 use crate::view::View;
+use crate::vulkan::compute::NodeCulling;
 use crate::vulkan::context::NodeRendererType;
 use crate::vulkan::GfaestusVk;
 use crate::{geometry::Point, vulkan::texture::GradientTexture};
@@ -17,13 +18,25 @@ use crate::{geometry::Point, vulkan::texture::GradientTexture};
 use crate::vulkan::render_pass::Framebuffers;
 
 pub mod base;
+pub mod lod;
 pub mod overlay;
 pub mod vertices;
 
 pub use base::*;
+pub use lod::*;
 pub use overlay::*;
 pub use vertices::*;
 
+/// The resources and placement of a graph layer drawn on top of the
+/// main graph in the node pass, see `crate::app::layers`
+pub struct LayerDraw<'a> {
+    pub vertices: &'a NodeVertices,
+    pub selection_set: vk::DescriptorSet,
+    pub offset: Point,
+    pub tint: rgb::RGBA<f32>,
+    pub id_offset: u32,
+}
+
 pub struct NodePipelines {
     pub pipelines: OverlayPipelines,
 
@@ -31,6 +44,13 @@ pub struct NodePipelines {
 
     pub vertices: NodeVertices,
 
+    /// Created on the first culled draw, and recreated whenever the
+    /// node vertices are replaced
+    culling: Option<NodeCulling>,
+    culled_last_draw: bool,
+
+    lod_last_draw: bool,
+
     device: Device,
 
     renderer_type: NodeRendererType,
@@ -61,6 +81,11 @@ impl NodePipelines {
             vertices,
             selection_descriptors,
 
+            culling: None,
+            culled_last_draw: false,
+
+            lod_last_draw: false,
+
             device: device.clone(),
 
             renderer_type,
@@ -71,17 +96,98 @@ impl NodePipelines {
         &self.device
     }
 
+    /// Rebuild the node pipelines, required after the MSAA sample
+    /// count has changed
+    pub fn recreate_pipelines(&mut self, app: &GfaestusVk) -> Result<()> {
+        self.pipelines.recreate_pipelines(
+            app,
+            self.renderer_type,
+            self.selection_descriptors.layout,
+        )
+    }
+
     pub fn has_overlay(&self) -> bool {
         self.pipelines.overlay_set_id.is_some()
     }
 
+    /// Whether the last draw used the reduced level of detail
+    pub fn lod_active(&self) -> bool {
+        self.lod_last_draw
+    }
+
+    /// The number of nodes that passed the most recent culling
+    /// pass, or were drawn at the reduced level of detail, or `None`
+    /// if the last draw was neither
+    pub fn visible_node_count(&self) -> Option<usize> {
+        if self.lod_last_draw {
+            return self.vertices.lod().map(|lod| lod.node_count());
+        }
+
+        if !self.culled_last_draw {
+            return None;
+        }
+        let culling = self.culling.as_ref()?;
+        Some(culling.visible_node_count())
+    }
+
+    /// Read back the results of the latest culling pass, if the last
+    /// draw was culled. Must only be called once the frame that drew
+    /// it has finished executing.
+    pub fn read_cull_results(&mut self) -> Result<()> {
+        if !self.culled_last_draw {
+            return Ok(());
+        }
+
+        if let Some(culling) = self.culling.as_mut() {
+            culling.read_visible_node_count()?;
+        }
+
+        Ok(())
+    }
+
+    /// Create or recreate the culling pass resources if they don't
+    /// match the current node vertices. Must be called before a
+    /// culled `draw` whenever the vertices may have been replaced.
+    pub fn prepare_culling(&mut self, app: &GfaestusVk) -> Result<()> {
+        if !self.vertices.has_vertices() {
+            return Ok(());
+        }
+
+        if let Some(mut culling) = self.culling.take() {
+            if culling.matches_vertices(&self.vertices) {
+                self.culling = Some(culling);
+                return Ok(());
+            }
+            culling.destroy();
+        }
+
+        self.culling = Some(NodeCulling::new(app, &self.vertices)?);
+
+        Ok(())
+    }
+
+    /// If `cull` is true, and `prepare_culling` has been called since
+    /// the vertices were last uploaded, nodes outside the viewport
+    /// are discarded by a compute pass recorded before the render
+    /// pass, and the rest are drawn with an indexed indirect draw
+    ///
+    /// If `lod_threshold` is set, and the level of detail grid cells
+    /// are smaller than that many pixels at the current view, only
+    /// the representative node of each cell is drawn, without culling
+    ///
+    /// The `layers` are drawn in order after the main graph, in full
     pub fn draw(
         &mut self,
+        cull: bool,
+        lod_threshold: Option<f32>,
+        layers: &[LayerDraw<'_>],
         cmd_buf: vk::CommandBuffer,
         render_pass: vk::RenderPass,
         framebuffers: &Framebuffers,
         viewport_dims: [f32; 2],
         node_width: f32,
+        min_node_px: f32,
+        focus_mode: NodeFocusMode,
         view: View,
         offset: Point,
         background_color: rgb::RGB<f32>,
@@ -90,6 +196,41 @@ impl NodePipelines {
     ) -> Result<()> {
         self.pipelines.write_overlay(overlay_id, color_scheme)?;
 
+        let push_constants = NodePushConstants::new(
+            [offset.x, offset.y],
+            viewport_dims,
+            view,
+            node_width,
+            min_node_px,
+            7,
+            focus_mode,
+        );
+
+        let vertices = &self.vertices;
+
+        let lod = vertices.lod().filter(|lod| {
+            lod_threshold.map_or(false, |px| lod.is_active(view, px))
+        });
+
+        let culling = self
+            .culling
+            .as_ref()
+            .filter(|c| cull && lod.is_none() && c.matches_vertices(vertices));
+
+        self.culled_last_draw = culling.is_some();
+        self.lod_last_draw = lod.is_some();
+
+        if let Some(culling) = culling {
+            // the node quads are widened in clip space by the shaders,
+            // so pad the viewport bounds by the widest node
+            let max_dim = viewport_dims[0].max(viewport_dims[1]);
+            let margin = (2.0 * node_width / (view.scale * max_dim)
+                * self.vertices.max_width_factor())
+            .max(2.0 * min_node_px / max_dim);
+
+            culling.cull_cmd(cmd_buf, push_constants.view_transform, margin)?;
+        }
+
         let overlay = self.pipelines.overlays.get(&overlay_id).unwrap();
 
         let device = &self.pipelines.device;
@@ -140,8 +281,8 @@ impl NodePipelines {
 
         self.pipelines.bind_pipeline(device, cmd_buf, overlay.kind);
 
-        let vx_bufs = [self.vertices.vertex_buffer];
-        let offsets = [0];
+        let vx_bufs = [self.vertices.vertex_buffer, self.vertices.width_buffer];
+        let offsets = [0, 0];
 
         unsafe {
             device.cmd_bind_vertex_buffers(cmd_buf, 0, &vx_bufs, &offsets);
@@ -154,14 +295,6 @@ impl NodePipelines {
             self.selection_descriptors.descriptor_set,
         )?;
 
-        let push_constants = NodePushConstants::new(
-            [offset.x, offset.y],
-            viewport_dims,
-            view,
-            node_width,
-            7,
-        );
-
         let pc_bytes = push_constants.bytes();
 
         let layout = self.pipelines.pipeline_layout_kind(overlay.kind);
@@ -179,9 +312,106 @@ impl NodePipelines {
             device.cmd_push_constants(cmd_buf, layout, stages, 0, &pc_bytes)
         };
 
-        unsafe {
-            device.cmd_draw(cmd_buf, self.vertices.vertex_count as u32, 1, 0, 0)
-        };
+        if let Some(lod) = lod {
+            unsafe {
+                device.cmd_bind_index_buffer(
+                    cmd_buf,
+                    lod.index_buffer,
+                    0,
+                    vk::IndexType::UINT32,
+                );
+
+                device.cmd_draw_indexed(
+                    cmd_buf,
+                    lod.index_count() as u32,
+                    1,
+                    0,
+                    0,
+                    0,
+                );
+            }
+        } else if let Some(culling) = culling {
+            unsafe {
+                device.cmd_bind_index_buffer(
+                    cmd_buf,
+                    culling.index_buffer,
+                    0,
+                    vk::IndexType::UINT32,
+                );
+
+                device.cmd_draw_indexed_indirect(
+                    cmd_buf,
+                    culling.indirect_buffer,
+                    0,
+                    1,
+                    0,
+                );
+            }
+        } else {
+            unsafe {
+                device.cmd_draw(
+                    cmd_buf,
+                    self.vertices.vertex_count as u32,
+                    1,
+                    0,
+                    0,
+                )
+            };
+        }
+
+        for layer in layers {
+            if !layer.vertices.has_vertices() {
+                continue;
+            }
+
+            self.pipelines.bind_descriptor_sets(
+                device,
+                cmd_buf,
+                overlay_id,
+                layer.selection_set,
+            )?;
+
+            let layer_offset = offset + layer.offset;
+
+            let push_constants = NodePushConstants::new(
+                [layer_offset.x, layer_offset.y],
+                viewport_dims,
+                view,
+                node_width,
+                min_node_px,
+                7,
+                NodeFocusMode::Normal,
+            )
+            .with_layer(layer.id_offset, layer.tint);
+
+            let pc_bytes = push_constants.bytes();
+
+            unsafe {
+                use vk::ShaderStageFlags as Flags;
+
+                let mut stages = Flags::VERTEX | Flags::FRAGMENT;
+
+                if self.renderer_type == NodeRendererType::TessellationQuads {
+                    stages |= Flags::TESSELLATION_CONTROL
+                        | Flags::TESSELLATION_EVALUATION;
+                }
+
+                device
+                    .cmd_push_constants(cmd_buf, layout, stages, 0, &pc_bytes);
+
+                let vx_bufs =
+                    [layer.vertices.vertex_buffer, layer.vertices.width_buffer];
+                device.cmd_bind_vertex_buffers(cmd_buf, 0, &vx_bufs, &offsets);
+
+                device.cmd_draw(
+                    cmd_buf,
+                    layer.vertices.vertex_count as u32,
+                    1,
+                    0,
+                    0,
+                );
+            }
+        }
 
         // End render pass
         unsafe { device.cmd_end_render_pass(cmd_buf) };
@@ -190,15 +420,10 @@ impl NodePipelines {
     }
 
     pub fn destroy(&mut self, app: &super::super::GfaestusVk) {
-        let device = &self.device;
+        self.selection_descriptors.destroy(&self.device);
 
-        unsafe {
-            device.destroy_descriptor_set_layout(
-                self.selection_descriptors.layout,
-                None,
-            );
-            device
-                .destroy_descriptor_pool(self.selection_descriptors.pool, None);
+        if let Some(mut culling) = self.culling.take() {
+            culling.destroy();
         }
 
         self.vertices.destroy(app).unwrap();
@@ -216,7 +441,7 @@ pub struct SelectionDescriptors {
 }
 
 impl SelectionDescriptors {
-    fn new(
+    pub fn new(
         app: &GfaestusVk,
         buffer: vk::Buffer,
         image_count: u32,
@@ -286,6 +511,17 @@ impl SelectionDescriptors {
         })
     }
 
+    pub fn descriptor_set(&self) -> vk::DescriptorSet {
+        self.descriptor_set
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        unsafe {
+            device.destroy_descriptor_set_layout(self.layout, None);
+            device.destroy_descriptor_pool(self.pool, None);
+        }
+    }
+
     fn layout_binding() -> vk::DescriptorSetLayoutBinding {
         use vk::ShaderStageFlags as Stages;
 
@@ -314,6 +550,14 @@ impl SelectionDescriptors {
     }
 }
 
+/// Host-visible copy of the node ID attachment, used to find the
+/// nodes under the cursor or in a rectangle.
+///
+/// The node shaders write the IDs as exact integers to an `R32_UINT`
+/// attachment (or the smallest wider `*_UINT` format the device
+/// supports), so no precision is lost; 0 means no node. As the IDs
+/// are computed as signed integers in the vertex shaders, graphs can
+/// have at most `MAX_NODE_COUNT` nodes.
 pub struct NodeIdBuffer {
     pub buffer: vk::Buffer,
     memory: vk::DeviceMemory,
@@ -325,22 +569,44 @@ pub struct NodeIdBuffer {
 }
 
 impl NodeIdBuffer {
+    /// The largest number of nodes whose IDs can be read back
+    pub const MAX_NODE_COUNT: usize = i32::MAX as usize;
+
+    /// The number of `u32`s each pixel takes; the ID is stored in
+    /// the first
+    fn texel_len(&self) -> usize {
+        (self.elem_size as usize / 4).max(1)
+    }
+
+    /// Collect the unique node IDs in the given pixel ranges, clamped
+    /// to the buffer dimensions, from a single mapping of the buffer.
     pub fn read_rect(
         &self,
         device: &Device,
         x_range: RangeInclusive<u32>,
         y_range: RangeInclusive<u32>,
     ) -> FxHashSet<NodeId> {
-        let min_x = (*x_range.start()).max(0);
+        let mut values: FxHashSet<NodeId> = FxHashSet::default();
+
+        if self.width == 0 || self.height == 0 {
+            return values;
+        }
+
+        let min_x = *x_range.start();
         let max_x = (*x_range.end()).min(self.width - 1);
 
-        let min_y = (*y_range.start()).max(0);
+        let min_y = *y_range.start();
         let max_y = (*y_range.end()).min(self.height - 1);
 
-        let mut values: FxHashSet<NodeId> = FxHashSet::default();
+        if min_x > max_x || min_y > max_y {
+            return values;
+        }
+
+        let texel_len = self.texel_len();
+        let row_stride = self.width as usize * texel_len;
 
         let rows = min_y..=max_y;
-        let row_width = (max_x - min_x) as usize;
+        let row_width = (max_x - min_x + 1) as usize;
 
         unsafe {
             let data_ptr = device
@@ -353,18 +619,22 @@ impl NodeIdBuffer {
                 .unwrap();
 
             for y in rows {
-                let row_start = ((y * self.width) + min_x) as usize;
+                let row_start =
+                    y as usize * row_stride + min_x as usize * texel_len;
                 let val_ptr = (data_ptr as *const u32).add(row_start);
 
-                let slice = std::slice::from_raw_parts(val_ptr, row_width);
+                let slice =
+                    std::slice::from_raw_parts(val_ptr, row_width * texel_len);
 
-                values.extend(slice.iter().filter_map(|&id| {
-                    if id == 0 {
-                        None
-                    } else {
-                        Some(NodeId::from(id as u64))
-                    }
-                }));
+                values.extend(slice.iter().step_by(texel_len).filter_map(
+                    |&id| {
+                        if id == 0 {
+                            None
+                        } else {
+                            Some(NodeId::from(id as u64))
+                        }
+                    },
+                ));
             }
 
             device.unmap_memory(self.memory);
@@ -398,10 +668,13 @@ impl NodeIdBuffer {
                 (y + o).clamp(0, (self.height - 1) as i32) as u32
             };
 
-            let to_ix =
-                |x: u32, y: u32| -> usize { (y * self.width + x) as usize };
+            let texel_len = self.texel_len();
+
+            let to_ix = |x: u32, y: u32| -> usize {
+                (y as usize * self.width as usize + x as usize) * texel_len
+            };
 
-            let index = (y * self.width + x) as usize;
+            let index = to_ix(x, y);
 
             let ix_l = to_ix(x_offset(x, -1), y);
             let ix_r = to_ix(x_offset(x, 1), y);
@@ -493,7 +766,9 @@ impl NodeIdBuffer {
         width: u32,
         height: u32,
     ) -> Result<()> {
-        if self.width * self.height == width * height {
+        // the dimensions must match, not just the size, or the
+        // pixels would be read with the wrong row stride
+        if self.width == width && self.height == height {
             return Ok(());
         }
 
@@ -522,23 +797,61 @@ impl NodeIdBuffer {
     }
 }
 
+/// How the nodes outside the selection are drawn, to focus on the
+/// selected subgraph. Only the visible color is affected; every node
+/// is still written to the node ID buffer, so hidden nodes can be
+/// hovered and selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u32)]
+pub enum NodeFocusMode {
+    Normal = 0,
+    DimOthers = 1,
+    HideOthers = 2,
+}
+
+impl std::default::Default for NodeFocusMode {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+impl NodeFocusMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeFocusMode::Normal => "Normal",
+            NodeFocusMode::DimOthers => "Dim others",
+            NodeFocusMode::HideOthers => "Hide others",
+        }
+    }
+}
+
 pub struct NodePushConstants {
     view_transform: glm::Mat4,
     node_width: f32,
     scale: f32,
     viewport_dims: [f32; 2],
     texture_period: u32,
+    /// The minimum node width in pixels, regardless of scale; 0.0
+    /// disables it
+    min_node_px: f32,
+    focus_mode: NodeFocusMode,
+    /// Added to the node IDs written to the node ID buffer, so the
+    /// nodes of each graph layer have their own range of IDs
+    id_offset: u32,
+    /// If the alpha is nonzero, the nodes are drawn with this color
+    /// instead of the overlay
+    tint: rgb::RGBA<f32>,
 }
 
 impl NodePushConstants {
+    /// The transform from world coordinates to clip space used by
+    /// the node shaders
     #[inline]
-    pub fn new(
+    pub fn view_transform(
         offset: [f32; 2],
         viewport_dims: [f32; 2],
         view: crate::view::View,
-        node_width: f32,
-        texture_period: u32,
-    ) -> Self {
+    ) -> glm::Mat4 {
         use crate::view;
 
         let model_mat = glm::mat4(
@@ -553,7 +866,20 @@ impl NodePushConstants {
 
         let viewport_mat = view::viewport_scale(width, height);
 
-        let matrix = viewport_mat * view_mat * model_mat;
+        viewport_mat * view_mat * model_mat
+    }
+
+    #[inline]
+    pub fn new(
+        offset: [f32; 2],
+        viewport_dims: [f32; 2],
+        view: crate::view::View,
+        node_width: f32,
+        min_node_px: f32,
+        texture_period: u32,
+        focus_mode: NodeFocusMode,
+    ) -> Self {
+        let matrix = Self::view_transform(offset, viewport_dims, view);
 
         Self {
             view_transform: matrix,
@@ -561,14 +887,29 @@ impl NodePushConstants {
             viewport_dims,
             scale: view.scale,
             texture_period,
+            min_node_px,
+            focus_mode,
+            id_offset: 0,
+            tint: rgb::RGBA::new(0.0, 0.0, 0.0, 0.0),
         }
     }
 
+    /// Set the node ID offset and tint used to draw a graph layer
     #[inline]
-    pub fn bytes(&self) -> [u8; 84] {
+    pub fn with_layer(mut self, id_offset: u32, tint: rgb::RGBA<f32>) -> Self {
+        self.id_offset = id_offset;
+        self.tint = tint;
+        self
+    }
+
+    /// The size of the push constant range in bytes
+    pub const SIZE: u32 = 112;
+
+    #[inline]
+    pub fn bytes(&self) -> [u8; Self::SIZE as usize] {
         use crate::view;
 
-        let mut bytes = [0u8; 84];
+        let mut bytes = [0u8; Self::SIZE as usize];
 
         let view_transform_array = view::mat4_to_array(&self.view_transform);
 
@@ -605,6 +946,34 @@ impl NodePushConstants {
             offset += 1;
         }
 
+        let px_bytes = self.min_node_px.to_ne_bytes();
+        for i in 0..4 {
+            bytes[offset] = px_bytes[i];
+            offset += 1;
+        }
+
+        let mode_bytes = (self.focus_mode as u32).to_ne_bytes();
+        for i in 0..4 {
+            bytes[offset] = mode_bytes[i];
+            offset += 1;
+        }
+
+        let id_offset_bytes = self.id_offset.to_ne_bytes();
+        for i in 0..4 {
+            bytes[offset] = id_offset_bytes[i];
+            offset += 1;
+        }
+
+        // the vec4 is 16-byte aligned, which offset already is
+        let tint = self.tint;
+        for &f in [tint.r, tint.g, tint.b, tint.a].iter() {
+            let f_bytes = f.to_ne_bytes();
+            for i in 0..4 {
+                bytes[offset] = f_bytes[i];
+                offset += 1;
+            }
+        }
+
         bytes
     }
 }
@@ -7,6 +7,7 @@ use std::ffi::CString;
 use anyhow::*;
 
 use super::super::{create_shader_module, Vertex};
+use super::NodePushConstants;
 use crate::vulkan::context::NodeRendererType;
 use crate::vulkan::GfaestusVk;
 
@@ -97,6 +98,25 @@ impl NodePipelineConfig {
     }
 }
 
+/// The per-vertex node width factors are stored in a separate
+/// buffer, bound to binding 1
+fn width_binding_desc() -> vk::VertexInputBindingDescription {
+    vk::VertexInputBindingDescription::builder()
+        .binding(1)
+        .stride(std::mem::size_of::<f32>() as u32)
+        .input_rate(vk::VertexInputRate::VERTEX)
+        .build()
+}
+
+fn width_attribute_desc() -> vk::VertexInputAttributeDescription {
+    vk::VertexInputAttributeDescription::builder()
+        .binding(1)
+        .location(1)
+        .format(vk::Format::R32_SFLOAT)
+        .offset(0)
+        .build()
+}
+
 pub(crate) fn create_node_pipeline(
     app: &GfaestusVk,
     renderer_type: NodeRendererType,
@@ -116,8 +136,11 @@ pub(crate) fn create_node_pipeline(
         &entry_point,
     )?;
 
-    let vert_binding_descs = [Vertex::get_binding_desc()];
-    let vert_attr_descs = Vertex::get_attribute_descs();
+    let vert_binding_descs = [Vertex::get_binding_desc(), width_binding_desc()];
+    let vert_attr_descs = {
+        let [pos_desc] = Vertex::get_attribute_descs();
+        [pos_desc, width_attribute_desc()]
+    };
     let vert_input_info = vk::PipelineVertexInputStateCreateInfo::builder()
         .vertex_binding_descriptions(&vert_binding_descs)
         .vertex_attribute_descriptions(&vert_attr_descs)
@@ -224,7 +247,7 @@ pub(crate) fn create_node_pipeline(
         let pc_range = vk::PushConstantRange::builder()
             .stage_flags(stage_flags)
             .offset(0)
-            .size(84)
+            .size(NodePushConstants::SIZE)
             .build();
 
         let pc_ranges = [pc_range];
@@ -0,0 +1,152 @@
+use ash::vk;
+
+use anyhow::*;
+use rustc_hash::FxHashMap;
+
+use crate::vulkan::{draw_system::Vertex, GfaestusVk};
+
+/// A reduced set of nodes to draw in place of the full graph when
+/// zoomed out far enough that many nodes share each pixel.
+///
+/// The layout is binned into a square grid, and each occupied cell
+/// is represented by its longest node. The representatives are
+/// drawn with an indexed draw using their original vertex indices,
+/// so node IDs, overlays, and the selection work as in a full draw.
+pub struct NodeLod {
+    pub(crate) index_buffer: vk::Buffer,
+    allocation: vk_mem::Allocation,
+
+    index_count: usize,
+    node_count: usize,
+
+    /// The width and height of a grid cell, in world units
+    cell_size: f32,
+}
+
+impl NodeLod {
+    /// The number of grid cells along the longer side of the layout
+    pub const GRID_CELLS: usize = 1024;
+
+    /// Bin the nodes into the grid and upload the index buffer of
+    /// the representatives. `vertices` holds two vertices per node,
+    /// as passed to `NodeVertices::upload_vertices`.
+    ///
+    /// Returns `None` if there are no nodes.
+    pub fn new(
+        app: &GfaestusVk,
+        vertices: &[Vertex],
+        vertices_per_node: usize,
+    ) -> Result<Option<Self>> {
+        let node_count = vertices.len() / 2;
+
+        if node_count == 0 {
+            return Ok(None);
+        }
+
+        let mut min = [std::f32::MAX; 2];
+        let mut max = [std::f32::MIN; 2];
+
+        for v in vertices {
+            for i in 0..2 {
+                min[i] = min[i].min(v.position[i]);
+                max[i] = max[i].max(v.position[i]);
+            }
+        }
+
+        let extent = (max[0] - min[0]).max(max[1] - min[1]);
+        let cell_size = (extent / Self::GRID_CELLS as f32).max(1.0);
+
+        let cell_of = |p: [f32; 2]| -> (u32, u32) {
+            let x = ((p[0] - min[0]) / cell_size) as u32;
+            let y = ((p[1] - min[1]) / cell_size) as u32;
+            (x, y)
+        };
+
+        // the longest node in each cell, by its midpoint
+        let mut cells: FxHashMap<(u32, u32), (usize, f32)> =
+            FxHashMap::default();
+
+        for (node, pair) in vertices.chunks_exact(2).enumerate() {
+            let p = pair[0].position;
+            let q = pair[1].position;
+
+            let mid = [(p[0] + q[0]) * 0.5, (p[1] + q[1]) * 0.5];
+            let len = (q[0] - p[0]).hypot(q[1] - p[1]);
+
+            let entry = cells.entry(cell_of(mid)).or_insert((node, len));
+            if len > entry.1 {
+                *entry = (node, len);
+            }
+        }
+
+        let mut nodes = cells
+            .values()
+            .map(|&(node, _)| node as u32)
+            .collect::<Vec<_>>();
+        nodes.sort_unstable();
+
+        let per_node = vertices_per_node as u32;
+
+        let indices = nodes
+            .iter()
+            .flat_map(|&node| (0..per_node).map(move |i| node * per_node + i))
+            .collect::<Vec<u32>>();
+
+        let usage = vk::BufferUsageFlags::INDEX_BUFFER
+            | vk::BufferUsageFlags::TRANSFER_DST;
+        let memory_usage = vk_mem::MemoryUsage::GpuOnly;
+
+        let (index_buffer, allocation, _) =
+            app.create_buffer_with_data(usage, memory_usage, false, &indices)?;
+
+        app.set_debug_object_name(index_buffer, "Node LOD Index Buffer")?;
+
+        log::debug!(
+            "Node LOD: {} of {} nodes, cell size {}",
+            nodes.len(),
+            node_count,
+            cell_size
+        );
+
+        Ok(Some(Self {
+            index_buffer,
+            allocation,
+
+            index_count: indices.len(),
+            node_count: nodes.len(),
+
+            cell_size,
+        }))
+    }
+
+    pub fn index_count(&self) -> usize {
+        self.index_count
+    }
+
+    /// The number of nodes drawn at this level of detail
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// Whether the grid cells are smaller than `threshold_px` pixels
+    /// at the scale of `view`
+    pub fn is_active(
+        &self,
+        view: crate::view::View,
+        threshold_px: f32,
+    ) -> bool {
+        self.cell_size / view.scale < threshold_px
+    }
+
+    pub fn destroy(&mut self, app: &GfaestusVk) -> Result<()> {
+        app.allocator
+            .destroy_buffer(self.index_buffer, &self.allocation)?;
+
+        self.index_buffer = vk::Buffer::null();
+        self.allocation = vk_mem::Allocation::null();
+        self.index_count = 0;
+        self.node_count = 0;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use ash::version::DeviceV1_0;
 use ash::{vk, Device};
+use handlegraph::handle::NodeId;
 use rustc_hash::FxHashMap;
 
 use anyhow::*;
@@ -51,11 +52,35 @@ impl OverlayPipelines {
         })
     }
 
-    pub fn destroy(&self, allocator: &vk_mem::Allocator) -> Result<()> {
+    /// Rebuild the node pipelines, e.g. after the MSAA sample count
+    /// and render passes have changed
+    pub(super) fn recreate_pipelines(
+        &mut self,
+        app: &GfaestusVk,
+        renderer_type: NodeRendererType,
+        selection_set_layout: vk::DescriptorSetLayout,
+    ) -> Result<()> {
+        self.pipeline_rgb.recreate_pipeline(
+            app,
+            renderer_type,
+            selection_set_layout,
+        )?;
+        self.pipeline_value.recreate_pipeline(
+            app,
+            renderer_type,
+            selection_set_layout,
+        )?;
+        Ok(())
+    }
+
+    pub fn destroy(&mut self, allocator: &vk_mem::Allocator) -> Result<()> {
         self.pipeline_rgb.destroy();
         self.pipeline_value.destroy();
-        for overlay in self.overlays.values() {
+        for overlay in self.overlays.values_mut() {
             allocator.destroy_buffer(overlay.buffer, &overlay.alloc)?;
+            if let Some(palette) = overlay.palette.as_mut() {
+                palette.texture.destroy(&self.device);
+            }
         }
         Ok(())
     }
@@ -78,11 +103,12 @@ impl OverlayPipelines {
                     vk::PipelineBindPoint::GRAPHICS,
                     self.pipeline_rgb.pipeline,
                 ),
-                OverlayKind::Value => device.cmd_bind_pipeline(
-                    cmd_buf,
-                    vk::PipelineBindPoint::GRAPHICS,
-                    self.pipeline_value.pipeline,
-                ),
+                OverlayKind::Value | OverlayKind::Category => device
+                    .cmd_bind_pipeline(
+                        cmd_buf,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        self.pipeline_value.pipeline,
+                    ),
             }
         };
     }
@@ -93,7 +119,9 @@ impl OverlayPipelines {
     ) -> vk::PipelineLayout {
         match overlay_kind {
             OverlayKind::RGB => self.pipeline_rgb.pipeline_layout,
-            OverlayKind::Value => self.pipeline_value.pipeline_layout,
+            OverlayKind::Value | OverlayKind::Category => {
+                self.pipeline_value.pipeline_layout
+            }
         }
     }
 
@@ -115,6 +143,16 @@ impl OverlayPipelines {
                 self.pipeline_value
                     .write_active_overlay(color_scheme, overlay)?;
             }
+            OverlayKind::Category => {
+                // categorical overlays are drawn with the value
+                // pipeline, using their own palette as the lookup
+                // texture instead of the current gradient
+                let palette = overlay.palette.as_ref().ok_or(anyhow!(
+                    "Categorical overlay {} is missing its palette",
+                    overlay_id
+                ))?;
+                self.pipeline_value.write_active_overlay(palette, overlay)?;
+            }
         }
 
         self.overlay_set_id = Some(overlay_id);
@@ -140,7 +178,7 @@ impl OverlayPipelines {
                     let layout = self.pipeline_rgb.pipeline_layout;
                     (sets, layout)
                 }
-                OverlayKind::Value => {
+                OverlayKind::Value | OverlayKind::Category => {
                     let sets =
                         [self.pipeline_value.overlay_set, selection_descriptor];
                     let layout = self.pipeline_value.pipeline_layout;
@@ -176,6 +214,18 @@ impl OverlayPipelines {
         overlays
     }
 
+    /// The ID of the first overlay with the given name and kind
+    pub fn find_overlay(&self, name: &str, kind: OverlayKind) -> Option<usize> {
+        self.overlay_names()
+            .into_iter()
+            .find(|(_, k, n)| *k == kind && *n == name)
+            .map(|(id, _, _)| id)
+    }
+
+    pub fn overlay_mut(&mut self, overlay_id: usize) -> Option<&mut Overlay> {
+        self.overlays.get_mut(&overlay_id)
+    }
+
     pub fn create_overlay(&mut self, overlay: Overlay) -> usize {
         let overlay_id = self.next_overlay_id;
         self.next_overlay_id += 1;
@@ -285,6 +335,32 @@ impl OverlayPipelineValue {
         )
     }
 
+    /// Rebuild the pipeline, keeping the descriptor sets
+    pub(super) fn recreate_pipeline(
+        &mut self,
+        app: &GfaestusVk,
+        renderer_type: NodeRendererType,
+        selection_set_layout: vk::DescriptorSetLayout,
+    ) -> Result<()> {
+        let (pipeline, pipeline_layout) = Self::create_pipeline(
+            app,
+            renderer_type,
+            self.descriptor_set_layout,
+            selection_set_layout,
+        )?;
+
+        unsafe {
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device.destroy_pipeline(self.pipeline, None);
+        }
+
+        self.pipeline = pipeline;
+        self.pipeline_layout = pipeline_layout;
+
+        Ok(())
+    }
+
     pub(super) fn new(
         app: &GfaestusVk,
         renderer_type: NodeRendererType,
@@ -434,6 +510,32 @@ impl OverlayPipelineRGB {
         )
     }
 
+    /// Rebuild the pipeline, keeping the descriptor sets
+    pub(super) fn recreate_pipeline(
+        &mut self,
+        app: &GfaestusVk,
+        renderer_type: NodeRendererType,
+        selection_set_layout: vk::DescriptorSetLayout,
+    ) -> Result<()> {
+        let (pipeline, pipeline_layout) = Self::create_pipeline(
+            app,
+            renderer_type,
+            self.descriptor_set_layout,
+            selection_set_layout,
+        )?;
+
+        unsafe {
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device.destroy_pipeline(self.pipeline, None);
+        }
+
+        self.pipeline = pipeline;
+        self.pipeline_layout = pipeline_layout;
+
+        Ok(())
+    }
+
     pub(super) fn new(
         app: &GfaestusVk,
         renderer_type: NodeRendererType,
@@ -529,6 +631,13 @@ pub struct Overlay {
 
     pub buffer_view: Option<vk::BufferView>,
 
+    /// Lookup texture for categorical overlays
+    palette: Option<GradientTexture>,
+    palette_size: usize,
+
+    /// The number of nodes the buffer has room for
+    node_count: usize,
+
     host_visible: bool,
 }
 
@@ -568,10 +677,83 @@ impl Overlay {
 
             buffer_view: None,
 
+            palette: None,
+            palette_size: 0,
+
+            node_count,
+
             host_visible: true,
         })
     }
 
+    /// Create a new categorical overlay, using `palette` as the
+    /// category colors. The per-node values are stored as texture
+    /// coordinates into the palette, so this uses the same buffer
+    /// layout as value overlays.
+    pub fn new_empty_category(
+        name: &str,
+        app: &GfaestusVk,
+        node_count: usize,
+        palette: &[rgb::RGB<f32>],
+    ) -> Result<Self> {
+        if palette.is_empty() {
+            bail!("Categorical overlay palette can't be empty");
+        }
+
+        let mut overlay = Self::new_empty_value(name, app, node_count)?;
+
+        let obj_name = format!("Overlay (Category) - {}", name);
+        app.set_debug_object_name(overlay.buffer, &obj_name)?;
+
+        let palette_texture = GradientTexture::from_palette(
+            app,
+            app.transient_command_pool,
+            app.graphics_queue,
+            palette,
+        )?;
+
+        overlay.kind = OverlayKind::Category;
+        overlay.palette = Some(palette_texture);
+        overlay.palette_size = palette.len();
+
+        Ok(overlay)
+    }
+
+    /// Replace the palette of a categorical overlay, without touching
+    /// the per-node categories. If the palette size changes, the
+    /// categories must be rewritten afterward, as they're stored as
+    /// texture coordinates into the palette.
+    pub fn set_palette(
+        &mut self,
+        app: &GfaestusVk,
+        palette: &[rgb::RGB<f32>],
+    ) -> Result<()> {
+        if !matches!(self.kind, OverlayKind::Category) {
+            bail!("Tried to set the palette of a {:?} overlay", self.kind);
+        }
+
+        if palette.is_empty() {
+            bail!("Categorical overlay palette can't be empty");
+        }
+
+        let palette_texture = GradientTexture::from_palette(
+            app,
+            app.transient_command_pool,
+            app.graphics_queue,
+            palette,
+        )?;
+
+        // the old palette may still be used by a frame in flight
+        app.wait_gpu_idle()?;
+
+        if let Some(mut old) = self.palette.replace(palette_texture) {
+            old.texture.destroy(app.vk_context().device());
+        }
+        self.palette_size = palette.len();
+
+        Ok(())
+    }
+
     pub fn new_empty_rgb(
         name: &str,
         app: &GfaestusVk,
@@ -612,6 +794,11 @@ impl Overlay {
 
             buffer_view: Some(buffer_view),
 
+            palette: None,
+            palette_size: 0,
+
+            node_count,
+
             host_visible: true,
         })
     }
@@ -626,27 +813,91 @@ impl Overlay {
     where
         I: IntoIterator<Item = (handlegraph::handle::NodeId, f32)>,
     {
-        if matches!(self.kind, OverlayKind::RGB) {
+        if !matches!(self.kind, OverlayKind::Value) {
             return Err(anyhow!(
-                "Tried to update RGB overlay with single-channel colors"
+                "Tried to update {:?} overlay with single-channel colors",
+                self.kind
             ));
         }
 
+        self.write_values(new_values);
+
+        Ok(())
+    }
+
+    /// Update the categories for a host-visible categorical overlay
+    /// by providing a set of node IDs and new categories. Categories
+    /// past the end of the palette wrap around.
+    pub fn update_category_overlay<I>(
+        &mut self,
+        new_categories: I,
+    ) -> Result<()>
+    where
+        I: IntoIterator<Item = (handlegraph::handle::NodeId, u32)>,
+    {
+        if !matches!(self.kind, OverlayKind::Category) {
+            return Err(anyhow!(
+                "Tried to update {:?} overlay with categories",
+                self.kind
+            ));
+        }
+
+        let size = self.palette_size;
+
+        self.write_values(new_categories.into_iter().map(|(node, cat)| {
+            let ix = (cat as usize) % size;
+            (node, (ix as f32 + 0.5) / size as f32)
+        }));
+
+        Ok(())
+    }
+
+    /// Set every node of a host-visible value overlay to `value`,
+    /// e.g. before writing sparse data
+    pub fn fill_value_overlay(&mut self, value: f32) -> Result<()> {
+        let nodes = (1..=self.node_count as u64).map(NodeId::from);
+        self.update_value_overlay(nodes.map(|node| (node, value)))
+    }
+
+    /// Set every node of a host-visible RGB overlay to `color`, e.g.
+    /// before writing sparse data
+    pub fn fill_rgb_overlay(&mut self, color: rgb::RGBA<f32>) -> Result<()> {
+        let nodes = (1..=self.node_count as u64).map(NodeId::from);
+        self.update_rgb_overlay(nodes.map(|node| (node, color)))
+    }
+
+    /// The buffer index of `node`, or `None` if the node is outside
+    /// the overlay
+    fn node_index(&self, node: NodeId) -> Option<usize> {
+        let ix = (node.0 as usize).checked_sub(1)?;
+        if ix < self.node_count {
+            Some(ix)
+        } else {
+            None
+        }
+    }
+
+    fn write_values<I>(&mut self, new_values: I)
+    where
+        I: IntoIterator<Item = (handlegraph::handle::NodeId, f32)>,
+    {
         assert!(self.host_visible);
 
         unsafe {
             let ptr = self.alloc_info.get_mapped_data();
 
             for (node, value) in new_values.into_iter() {
+                let ix = match self.node_index(node) {
+                    Some(ix) => ix,
+                    None => continue,
+                };
+
                 let val_ptr = ptr as *mut f32;
-                let ix = (node.0 - 1) as usize;
 
                 let val_ptr = (val_ptr.add(ix)) as *mut f32;
                 val_ptr.write(value);
             }
         }
-
-        Ok(())
     }
 
     /// Update the colors for a host-visible overlay by providing a
@@ -659,9 +910,10 @@ impl Overlay {
     where
         I: IntoIterator<Item = (handlegraph::handle::NodeId, rgb::RGBA<f32>)>,
     {
-        if matches!(self.kind, OverlayKind::Value) {
+        if !matches!(self.kind, OverlayKind::RGB) {
             return Err(anyhow!(
-                "Tried to update single-channel overlay with RGB colors"
+                "Tried to update {:?} overlay with RGB colors",
+                self.kind
             ));
         }
 
@@ -671,8 +923,12 @@ impl Overlay {
             let ptr = self.alloc_info.get_mapped_data();
 
             for (node, color) in new_colors.into_iter() {
+                let ix = match self.node_index(node) {
+                    Some(ix) => ix,
+                    None => continue,
+                };
+
                 let val_ptr = ptr as *mut u32;
-                let ix = (node.0 - 1) as usize;
 
                 let val_ptr = (val_ptr.add(ix)) as *mut u8;
                 val_ptr.write((color.r * 255.0) as u8);
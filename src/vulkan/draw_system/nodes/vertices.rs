@@ -6,6 +6,8 @@ use crate::vulkan::{
     context::NodeRendererType, draw_system::Vertex, GfaestusVk,
 };
 
+use super::NodeLod;
+
 pub struct NodeVertices {
     pub(crate) vertex_count: usize,
 
@@ -14,6 +16,25 @@ pub struct NodeVertices {
     allocation: vk_mem::Allocation,
     allocation_info: Option<vk_mem::AllocationInfo>,
 
+    /// One width factor per vertex, multiplied with the global node
+    /// width in the node shaders
+    pub(crate) width_buffer: vk::Buffer,
+
+    width_allocation: vk_mem::Allocation,
+    width_allocation_info: Option<vk_mem::AllocationInfo>,
+
+    /// The largest uploaded width factor, used to pad the bounds
+    /// when culling nodes outside the viewport
+    max_width_factor: f32,
+
+    /// The uploaded width factors, one per node, kept so the node
+    /// widths can be reproduced outside the shaders
+    width_factors: Vec<f32>,
+
+    /// The reduced set of nodes drawn when zoomed out, rebuilt
+    /// whenever the vertices are uploaded
+    lod: Option<NodeLod>,
+
     renderer_type: NodeRendererType,
 }
 
@@ -31,10 +52,117 @@ impl NodeVertices {
             allocation,
             allocation_info,
 
+            width_buffer: vk::Buffer::null(),
+            width_allocation: vk_mem::Allocation::null(),
+            width_allocation_info: None,
+
+            max_width_factor: 1.0,
+
+            width_factors: Vec::new(),
+
+            lod: None,
+
             renderer_type,
         }
     }
 
+    pub fn vertices_per_node(&self) -> usize {
+        match self.renderer_type {
+            NodeRendererType::VertexOnly => 6,
+            NodeRendererType::TessellationQuads => 2,
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.vertex_count / self.vertices_per_node()
+    }
+
+    pub fn max_width_factor(&self) -> f32 {
+        self.max_width_factor
+    }
+
+    /// The width factor of each node, indexed by node ID - 1, or an
+    /// empty slice if no widths have been uploaded
+    pub fn width_factors(&self) -> &[f32] {
+        &self.width_factors
+    }
+
+    pub fn lod(&self) -> Option<&NodeLod> {
+        self.lod.as_ref()
+    }
+
+    pub fn has_widths(&self) -> bool {
+        self.width_allocation_info.is_some()
+    }
+
+    fn destroy_widths(&mut self, app: &GfaestusVk) -> Result<()> {
+        if self.has_widths() {
+            app.allocator
+                .destroy_buffer(self.width_buffer, &self.width_allocation)?;
+
+            self.width_buffer = vk::Buffer::null();
+            self.width_allocation = vk_mem::Allocation::null();
+            self.width_allocation_info = None;
+        }
+
+        self.width_factors.clear();
+
+        Ok(())
+    }
+
+    /// Upload the per-node width factors, indexed by node ID - 1. A
+    /// factor of 1.0 draws the node with the global node width.
+    ///
+    /// Each factor is repeated for every vertex of its node, so
+    /// `widths` must contain one entry per node.
+    pub fn upload_widths(
+        &mut self,
+        app: &GfaestusVk,
+        widths: &[f32],
+    ) -> Result<()> {
+        let node_count = self.node_count();
+
+        if widths.len() != node_count {
+            bail!(
+                "Expected {} node widths, but got {}",
+                node_count,
+                widths.len()
+            );
+        }
+
+        self.destroy_widths(app)?;
+
+        let usage = vk::BufferUsageFlags::VERTEX_BUFFER
+            | vk::BufferUsageFlags::TRANSFER_DST;
+        let memory_usage = vk_mem::MemoryUsage::GpuOnly;
+
+        let per_node = self.vertices_per_node();
+
+        let vertex_widths = widths
+            .iter()
+            .flat_map(|&w| std::iter::repeat(w).take(per_node))
+            .collect::<Vec<f32>>();
+
+        let (buffer, allocation, allocation_info) = app
+            .create_buffer_with_data(
+                usage,
+                memory_usage,
+                false,
+                &vertex_widths,
+            )?;
+
+        app.set_debug_object_name(buffer, "Node Width Buffer")?;
+
+        self.width_buffer = buffer;
+        self.width_allocation = allocation;
+        self.width_allocation_info = Some(allocation_info);
+
+        self.max_width_factor = widths.iter().copied().fold(1.0, f32::max);
+        self.width_factors = widths.to_vec();
+
+        Ok(())
+    }
+
     pub fn buffer(&self) -> vk::Buffer {
         self.vertex_buffer
     }
@@ -55,6 +183,12 @@ impl NodeVertices {
             self.vertex_count = 0;
         }
 
+        if let Some(mut lod) = self.lod.take() {
+            lod.destroy(app)?;
+        }
+
+        self.destroy_widths(app)?;
+
         Ok(())
     }
 
@@ -156,12 +290,18 @@ impl NodeVertices {
     ) -> Result<()> {
         match self.renderer_type {
             NodeRendererType::VertexOnly => {
-                self.upload_quad_vertices(app, vertices)
+                self.upload_quad_vertices(app, vertices)?;
             }
             NodeRendererType::TessellationQuads => {
-                self.upload_line_vertices(app, vertices)
+                self.upload_line_vertices(app, vertices)?;
             }
         }
+
+        self.lod = NodeLod::new(app, vertices, self.vertices_per_node())?;
+
+        // the input is always two vertices per node
+        let widths = vec![1.0f32; vertices.len() / 2];
+        self.upload_widths(app, &widths)
     }
 
     pub fn download_vertices(
@@ -213,7 +353,9 @@ impl NodeVertices {
 
             let val_ptr = mapped_ptr as *const crate::universe::Node;
 
-            let sel_slice = std::slice::from_raw_parts(val_ptr, node_count);
+            // each `Node` is two vertices
+            let len = node_count * self.vertices_per_node() / 2;
+            let sel_slice = std::slice::from_raw_parts(val_ptr, len);
 
             match self.renderer_type {
                 NodeRendererType::TessellationQuads => {
@@ -7,7 +7,7 @@ use anyhow::Result;
 
 use super::create_shader_module;
 
-use crate::vulkan::render_pass::Framebuffers;
+use crate::vulkan::render_pass::{Framebuffers, OffscreenAttachment};
 use crate::vulkan::{texture::Texture, GfaestusVk};
 
 pub struct SelectionOutlineEdgePipeline {
@@ -121,6 +121,8 @@ impl SelectionOutlineEdgePipeline {
         render_pass: vk::RenderPass,
         framebuffers: &Framebuffers,
         viewport_dims: [f32; 2],
+        style: OutlineStyle,
+        scale_factor: f32,
     ) -> Result<()> {
         let clear_values = {
             [vk::ClearValue {
@@ -175,7 +177,11 @@ impl SelectionOutlineEdgePipeline {
             );
         };
 
-        let push_constants = PushConstants::new(viewport_dims, true);
+        // the edge detection doesn't use the radius, but both passes
+        // get the same push constants, so they sample the same pixel
+        // coordinates at any scale factor
+        let push_constants =
+            PushConstants::new(viewport_dims, true, style.scaled(scale_factor));
 
         let pc_bytes = push_constants.bytes();
 
@@ -249,47 +255,52 @@ impl SelectionOutlineEdgePipeline {
             descriptor_set_layout,
             crate::include_shader!("post/post.vert.spv"),
             crate::include_shader!("post/post_edge.frag.spv"),
+            true,
         )
     }
 }
 
+/// Blurs the selection outline drawn by the edge detection pass with
+/// a separable gaussian kernel, first horizontally into the offscreen
+/// blur attachment, then vertically onto the frame
 pub struct SelectionOutlineBlurPipeline {
     descriptor_pool: vk::DescriptorPool,
     descriptor_set_layout: vk::DescriptorSetLayout,
-    descriptor_set: vk::DescriptorSet,
+    /// Samples the edge detection output, for the horizontal pass
+    source_set: vk::DescriptorSet,
+    /// Samples the horizontal pass output, for the vertical pass
+    intermediate_set: vk::DescriptorSet,
 
     pipeline_layout: vk::PipelineLayout,
-    pipeline: vk::Pipeline,
+    horizontal_pipeline: vk::Pipeline,
+    vertical_pipeline: vk::Pipeline,
 }
 
 impl SelectionOutlineBlurPipeline {
-    pub fn new(app: &GfaestusVk, image_count: u32) -> Result<Self> {
+    pub fn new(app: &GfaestusVk) -> Result<Self> {
         let vk_context = app.vk_context();
         let device = vk_context.device();
 
-        let render_pass = app.render_passes.selection_blur;
-        let offscreen_image = app.node_attachments.mask_resolve;
-
         let layout = Self::create_descriptor_set_layout(device)?;
 
         let descriptor_pool = {
             let pool_size = vk::DescriptorPoolSize {
                 ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
-                descriptor_count: image_count,
+                descriptor_count: 2,
             };
 
             let pool_sizes = [pool_size];
 
             let pool_info = vk::DescriptorPoolCreateInfo::builder()
                 .pool_sizes(&pool_sizes)
-                .max_sets(image_count)
+                .max_sets(2)
                 .build();
 
             unsafe { device.create_descriptor_pool(&pool_info, None) }
         }?;
 
         let descriptor_sets = {
-            let layouts = vec![layout];
+            let layouts = vec![layout, layout];
 
             let alloc_info = vk::DescriptorSetAllocateInfo::builder()
                 .descriptor_pool(descriptor_pool)
@@ -299,11 +310,62 @@ impl SelectionOutlineBlurPipeline {
             unsafe { device.allocate_descriptor_sets(&alloc_info) }
         }?;
 
-        for set in descriptor_sets.iter() {
+        // the horizontal pass draws to an image like the one the edge
+        // detection draws to, so it uses the same render pass, and
+        // must not blend with the cleared image
+        let (horizontal_pipeline, pipeline_layout) = create_pipeline(
+            device,
+            app.render_passes.selection_edge_detect,
+            layout,
+            crate::include_shader!("post/post.vert.spv"),
+            crate::include_shader!("post/post_blur.frag.spv"),
+            false,
+        );
+
+        let (vertical_pipeline, vertical_layout) = create_pipeline(
+            device,
+            app.render_passes.selection_blur,
+            layout,
+            crate::include_shader!("post/post.vert.spv"),
+            crate::include_shader!("post/post_blur.frag.spv"),
+            true,
+        );
+
+        // the layouts are identical, so one is enough
+        unsafe { device.destroy_pipeline_layout(vertical_layout, None) };
+
+        let mut pipeline = Self {
+            descriptor_pool,
+            descriptor_set_layout: layout,
+            source_set: descriptor_sets[0],
+            intermediate_set: descriptor_sets[1],
+            pipeline_layout,
+            horizontal_pipeline,
+            vertical_pipeline,
+        };
+
+        pipeline.write_descriptor_sets(device, &app.offscreen_attachment);
+
+        Ok(pipeline)
+    }
+
+    /// Update the descriptor sets to sample the current offscreen
+    /// attachments, after they've been recreated
+    pub fn write_descriptor_sets(
+        &mut self,
+        device: &Device,
+        offscreen: &OffscreenAttachment,
+    ) {
+        let sets = [
+            (self.source_set, offscreen.color),
+            (self.intermediate_set, offscreen.blur),
+        ];
+
+        for (set, image) in sets.iter() {
             let image_info = vk::DescriptorImageInfo::builder()
                 .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-                .image_view(offscreen_image.view)
-                .sampler(offscreen_image.sampler.unwrap())
+                .image_view(image.view)
+                .sampler(image.sampler.unwrap())
                 .build();
             let image_infos = [image_info];
 
@@ -319,54 +381,104 @@ impl SelectionOutlineBlurPipeline {
 
             unsafe { device.update_descriptor_sets(&descriptor_writes, &[]) }
         }
-
-        let (pipeline, pipeline_layout) =
-            Self::create_pipeline(device, render_pass, layout);
-
-        Ok(Self {
-            descriptor_pool,
-            descriptor_set_layout: layout,
-            descriptor_set: descriptor_sets[0],
-            pipeline_layout,
-            pipeline,
-        })
     }
 
-    pub fn write_descriptor_set(
-        &mut self,
+    /// Draw both blur passes. `render_pass` is the edge detection
+    /// render pass, used for the horizontal pass, and `blur_pass` is
+    /// the selection blur render pass, used for the vertical pass.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &self,
         device: &Device,
-        new_image: Texture,
-    ) {
-        let image_info = vk::DescriptorImageInfo::builder()
-            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
-            .image_view(new_image.view)
-            .sampler(new_image.sampler.unwrap())
-            .build();
-        let image_infos = [image_info];
+        cmd_buf: vk::CommandBuffer,
+        render_pass: vk::RenderPass,
+        blur_pass: vk::RenderPass,
+        framebuffers: &Framebuffers,
+        intermediate_image: vk::Image,
+        viewport_dims: [f32; 2],
+        style: OutlineStyle,
+        scale_factor: f32,
+    ) -> Result<()> {
+        // the radius is set in logical pixels, but the outline is
+        // drawn at the physical resolution
+        let push_constants =
+            PushConstants::new(viewport_dims, true, style.scaled(scale_factor));
+
+        let horizontal_clear = [vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: [0.0, 0.0, 0.0, 0.0],
+            },
+        }];
+
+        self.draw_pass(
+            device,
+            cmd_buf,
+            render_pass,
+            framebuffers.selection_blur_horizontal,
+            &horizontal_clear,
+            self.horizontal_pipeline,
+            self.source_set,
+            viewport_dims,
+            push_constants.vertical(false),
+        );
 
-        let sampler_descriptor_write = vk::WriteDescriptorSet::builder()
-            .dst_set(self.descriptor_set)
-            .dst_binding(0)
-            .dst_array_element(0)
-            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
-            .image_info(&image_infos)
-            .build();
+        unsafe {
+            let image_memory_barrier = vk::ImageMemoryBarrier::builder()
+                .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ)
+                .old_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(intermediate_image)
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                })
+                .build();
 
-        let descriptor_writes = [sampler_descriptor_write];
+            device.cmd_pipeline_barrier(
+                cmd_buf,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::BY_REGION,
+                &[],
+                &[],
+                &[image_memory_barrier],
+            );
+        }
 
-        unsafe { device.update_descriptor_sets(&descriptor_writes, &[]) }
+        self.draw_pass(
+            device,
+            cmd_buf,
+            blur_pass,
+            framebuffers.selection_blur,
+            &[],
+            self.vertical_pipeline,
+            self.intermediate_set,
+            viewport_dims,
+            push_constants.vertical(true),
+        );
+
+        Ok(())
     }
 
-    pub fn draw(
+    #[allow(clippy::too_many_arguments)]
+    fn draw_pass(
         &self,
         device: &Device,
         cmd_buf: vk::CommandBuffer,
         render_pass: vk::RenderPass,
-        framebuffers: &Framebuffers,
+        framebuffer: vk::Framebuffer,
+        clear_values: &[vk::ClearValue],
+        pipeline: vk::Pipeline,
+        descriptor_set: vk::DescriptorSet,
         viewport_dims: [f32; 2],
-    ) -> Result<()> {
-        let clear_values = [];
-
+        push_constants: PushConstants,
+    ) {
         let extent = vk::Extent2D {
             width: viewport_dims[0] as u32,
             height: viewport_dims[1] as u32,
@@ -374,12 +486,12 @@ impl SelectionOutlineBlurPipeline {
 
         let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
             .render_pass(render_pass)
-            .framebuffer(framebuffers.selection_blur)
+            .framebuffer(framebuffer)
             .render_area(vk::Rect2D {
                 offset: vk::Offset2D { x: 0, y: 0 },
                 extent,
             })
-            .clear_values(&clear_values)
+            .clear_values(clear_values)
             .build();
 
         unsafe {
@@ -394,11 +506,11 @@ impl SelectionOutlineBlurPipeline {
             device.cmd_bind_pipeline(
                 cmd_buf,
                 vk::PipelineBindPoint::GRAPHICS,
-                self.pipeline,
+                pipeline,
             )
         };
 
-        let desc_sets = [self.descriptor_set];
+        let desc_sets = [descriptor_set];
 
         unsafe {
             let null = [];
@@ -412,8 +524,6 @@ impl SelectionOutlineBlurPipeline {
             );
         };
 
-        let push_constants = PushConstants::new(viewport_dims, true);
-
         let pc_bytes = push_constants.bytes();
 
         unsafe {
@@ -431,8 +541,6 @@ impl SelectionOutlineBlurPipeline {
 
         // End render pass
         unsafe { device.cmd_end_render_pass(cmd_buf) };
-
-        Ok(())
     }
 
     pub fn destroy(&self, device: &Device) {
@@ -443,7 +551,8 @@ impl SelectionOutlineBlurPipeline {
             );
             device.destroy_descriptor_pool(self.descriptor_pool, None);
 
-            device.destroy_pipeline(self.pipeline, None);
+            device.destroy_pipeline(self.horizontal_pipeline, None);
+            device.destroy_pipeline(self.vertical_pipeline, None);
             device.destroy_pipeline_layout(self.pipeline_layout, None);
         }
     }
@@ -474,20 +583,6 @@ impl SelectionOutlineBlurPipeline {
 
         Ok(layout)
     }
-
-    fn create_pipeline(
-        device: &Device,
-        render_pass: vk::RenderPass,
-        descriptor_set_layout: vk::DescriptorSetLayout,
-    ) -> (vk::Pipeline, vk::PipelineLayout) {
-        create_pipeline(
-            device,
-            render_pass,
-            descriptor_set_layout,
-            crate::include_shader!("post/post.vert.spv"),
-            crate::include_shader!("post/post_blur.frag.spv"),
-        )
-    }
 }
 
 fn create_pipeline(
@@ -496,6 +591,7 @@ fn create_pipeline(
     descriptor_set_layout: vk::DescriptorSetLayout,
     vert_shader: &[u8],
     frag_shader: &[u8],
+    blend: bool,
 ) -> (vk::Pipeline, vk::PipelineLayout) {
     let vert_src = {
         let mut cursor = std::io::Cursor::new(vert_shader);
@@ -572,7 +668,7 @@ fn create_pipeline(
     let color_blend_attachment =
         vk::PipelineColorBlendAttachmentState::builder()
             .color_write_mask(vk::ColorComponentFlags::all())
-            .blend_enable(true)
+            .blend_enable(blend)
             .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
             .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
             .color_blend_op(vk::BlendOp::ADD)
@@ -598,7 +694,7 @@ fn create_pipeline(
         let pc_range = vk::PushConstantRange::builder()
             .stage_flags(Flags::VERTEX | Flags::FRAGMENT)
             .offset(0)
-            .size(12)
+            .size(PushConstants::SIZE as u32)
             .build();
 
         let pc_ranges = [pc_range];
@@ -645,15 +741,60 @@ fn create_pipeline(
     (pipeline, layout)
 }
 
+/// The color and blur radius of the selection outline, passed to
+/// both outline passes as push constants
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutlineStyle {
+    pub color: rgb::RGBA<f32>,
+
+    /// Radius of the blur kernel, in logical pixels
+    pub radius: f32,
+}
+
+impl OutlineStyle {
+    pub const MAX_RADIUS: f32 = 8.0;
+
+    /// The largest radius the blur shader supports, after scaling
+    pub const MAX_SCALED_RADIUS: f32 = 16.0;
+
+    /// The style with the blur radius scaled by the window's DPI
+    /// scale factor, so that the outline keeps the same apparent
+    /// thickness on high- and low-DPI displays
+    pub fn scaled(self, scale_factor: f32) -> Self {
+        let radius = (self.radius * scale_factor).min(Self::MAX_SCALED_RADIUS);
+        Self { radius, ..self }
+    }
+}
+
+impl std::default::Default for OutlineStyle {
+    fn default() -> Self {
+        Self {
+            color: rgb::RGBA::new(1.0, 1.0, 1.0, 1.0),
+            radius: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct PushConstants {
     width: f32,
     height: f32,
     enabled: bool,
+    style: OutlineStyle,
+    vertical: bool,
 }
 
 impl PushConstants {
+    /// The color starts at offset 16 to match the std430 alignment
+    /// of the `vec4` in the shaders
+    pub const SIZE: usize = 40;
+
     #[inline]
-    pub fn new(viewport_dims: [f32; 2], enabled: bool) -> Self {
+    pub fn new(
+        viewport_dims: [f32; 2],
+        enabled: bool,
+        style: OutlineStyle,
+    ) -> Self {
         let width = viewport_dims[0];
         let height = viewport_dims[1];
 
@@ -661,26 +802,44 @@ impl PushConstants {
             width,
             height,
             enabled,
+            style,
+            vertical: false,
         }
     }
 
+    /// Set the direction of the blur pass the constants are for
     #[inline]
-    pub fn bytes(&self) -> [u8; 12] {
-        let mut bytes = [0u8; 12];
+    pub fn vertical(self, vertical: bool) -> Self {
+        Self { vertical, ..self }
+    }
+
+    #[inline]
+    pub fn bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
 
         {
             let mut offset = 0;
 
-            let mut add_float = |f: f32| {
+            let mut add_float = |offset: &mut usize, f: f32| {
                 let f_bytes = f.to_ne_bytes();
                 for i in 0..4 {
-                    bytes[offset] = f_bytes[i];
-                    offset += 1;
+                    bytes[*offset] = f_bytes[i];
+                    *offset += 1;
                 }
             };
 
-            add_float(self.width);
-            add_float(self.height);
+            add_float(&mut offset, self.width);
+            add_float(&mut offset, self.height);
+
+            offset = 16;
+
+            let color = self.style.color;
+            add_float(&mut offset, color.r);
+            add_float(&mut offset, color.g);
+            add_float(&mut offset, color.b);
+            add_float(&mut offset, color.a);
+
+            add_float(&mut offset, self.style.radius);
         }
 
         if self.enabled {
@@ -689,6 +848,53 @@ impl PushConstants {
             bytes[11] = 0;
         }
 
+        if self.vertical {
+            bytes[39] = 1;
+        }
+
         bytes
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn float_at(bytes: &[u8], offset: usize) -> f32 {
+        let mut f_bytes = [0u8; 4];
+        f_bytes.copy_from_slice(&bytes[offset..offset + 4]);
+        f32::from_ne_bytes(f_bytes)
+    }
+
+    #[test]
+    fn outline_passes_agree_at_fractional_scale_factors() {
+        let dims = [1707.0, 960.0];
+        let style = OutlineStyle {
+            radius: 3.0,
+            ..OutlineStyle::default()
+        };
+
+        for &scale_factor in [1.0, 1.25, 1.5, 1.75, 2.0, 6.0].iter() {
+            let constants =
+                PushConstants::new(dims, true, style.scaled(scale_factor));
+
+            let edge = constants.bytes();
+            let horizontal = constants.vertical(false).bytes();
+            let vertical = constants.vertical(true).bytes();
+
+            // the passes only differ in the blur direction
+            assert_eq!(edge[..36], horizontal[..36]);
+            assert_eq!(edge[..36], vertical[..36]);
+            assert_eq!(horizontal[36..], [0, 0, 0, 0]);
+            assert_ne!(vertical[36..], [0, 0, 0, 0]);
+
+            assert_eq!(float_at(&edge, 0), dims[0]);
+            assert_eq!(float_at(&edge, 4), dims[1]);
+
+            let radius = float_at(&edge, 32);
+            let expected = (style.radius * scale_factor)
+                .min(OutlineStyle::MAX_SCALED_RADIUS);
+            assert_eq!(radius, expected);
+        }
+    }
+}
@@ -69,8 +69,9 @@ pub(super) fn instance_extensions(entry: &Entry) -> Result<InstanceExtensions> {
 
 pub(super) fn create_instance(
     entry: &Entry,
-    window: &Window,
-) -> Result<Instance> {
+    window: Option<&Window>,
+    validation: bool,
+) -> Result<(Instance, bool)> {
     log::debug!("Creating instance");
     let app_name = CString::new("Gfaestus")?;
 
@@ -82,15 +83,32 @@ pub(super) fn create_instance(
         .api_version(vk::make_version(1, 0, 0))
         .build();
 
-    let extension_names =
-        ash_window::enumerate_required_extensions(window).unwrap();
+    // headless instances don't need any surface extensions
+    let extension_names = match window {
+        Some(window) => {
+            ash_window::enumerate_required_extensions(window).unwrap()
+        }
+        None => Vec::new(),
+    };
     log::debug!("Enumerated required instance extensions");
     let mut extension_names = extension_names
         .iter()
         .map(|ext| ext.as_ptr())
         .collect::<Vec<_>>();
 
-    if super::debug::ENABLE_VALIDATION_LAYERS {
+    let validation = if validation {
+        let supported = check_validation_layer_support(&entry);
+        if supported {
+            log::info!("Enabling Vulkan validation layers");
+        } else {
+            log::warn!("Continuing without Vulkan validation layers");
+        }
+        supported
+    } else {
+        false
+    };
+
+    if validation {
         extension_names.push(DebugUtils::name().as_ptr());
     }
 
@@ -105,8 +123,7 @@ pub(super) fn create_instance(
         .application_info(&app_info)
         .enabled_extension_names(&extension_names);
 
-    if super::debug::ENABLE_VALIDATION_LAYERS {
-        check_validation_layer_support(&entry);
+    if validation {
         instance_create_info =
             instance_create_info.enabled_layer_names(&layer_names_ptrs);
     }
@@ -119,13 +136,14 @@ pub(super) fn create_instance(
     let instance =
         unsafe { entry.create_instance(&instance_create_info, None) }?;
 
-    Ok(instance)
+    Ok((instance, validation))
 }
 
+/// Without a surface, i.e. when headless, nothing is presented, so
+/// the graphics queue family is used as the present family.
 pub(super) fn find_queue_families(
     instance: &Instance,
-    surface: &Surface,
-    surface_khr: vk::SurfaceKHR,
+    surface: Option<(&Surface, vk::SurfaceKHR)>,
     device: vk::PhysicalDevice,
 ) -> Result<(Option<u32>, Option<u32>, Option<u32>)> {
     let mut graphics_ix: Option<u32> = None;
@@ -151,13 +169,16 @@ pub(super) fn find_queue_families(
             compute_ix = Some(ix as u32);
         }
 
-        let supports_present = unsafe {
-            surface.get_physical_device_surface_support(
-                device,
-                ix as u32,
-                surface_khr,
-            )
-        }?;
+        let supports_present = match surface {
+            Some((surface, surface_khr)) => unsafe {
+                surface.get_physical_device_surface_support(
+                    device,
+                    ix as u32,
+                    surface_khr,
+                )
+            }?,
+            None => family.queue_flags.contains(vk::QueueFlags::GRAPHICS),
+        };
 
         if supports_present && present_ix.is_none() {
             present_ix = Some(ix as u32);
@@ -210,12 +231,11 @@ pub(super) fn required_device_extensions() -> [&'static CStr; 1] {
 
 pub(super) fn device_is_suitable(
     instance: &Instance,
-    surface: &Surface,
-    surface_khr: SurfaceKHR,
+    surface: Option<(&Surface, SurfaceKHR)>,
     device: vk::PhysicalDevice,
 ) -> Result<bool> {
     let (graphics_ix, present_ix, compute_ix) =
-        find_queue_families(instance, surface, surface_khr, device)?;
+        find_queue_families(instance, surface, device)?;
 
     if graphics_ix.is_none() || present_ix.is_none() || compute_ix.is_none() {
         error!("Device is missing a queue family");
@@ -226,10 +246,13 @@ pub(super) fn device_is_suitable(
         return Ok(false);
     }
 
-    let swapchain_adequate = {
-        let details =
-            SwapchainSupportDetails::new(device, surface, surface_khr)?;
-        !details.formats.is_empty() && !details.present_modes.is_empty()
+    let swapchain_adequate = match surface {
+        Some((surface, surface_khr)) => {
+            let details =
+                SwapchainSupportDetails::new(device, surface, surface_khr)?;
+            !details.formats.is_empty() && !details.present_modes.is_empty()
+        }
+        None => true,
     };
 
     if !swapchain_adequate {
@@ -240,69 +263,135 @@ pub(super) fn device_is_suitable(
     device_supports_features(instance, device)
 }
 
+/// Set to a device index to pick the graphics device, like `--gpu`
+pub(super) const GPU_ENV_VAR: &str = "GFAESTUS_GPU";
+
+pub(super) fn gpu_index_from_env() -> Result<Option<usize>> {
+    match std::env::var(GPU_ENV_VAR) {
+        Ok(value) => {
+            let index = value.trim().parse::<usize>().map_err(|_| {
+                anyhow::anyhow!(
+                    "Could not parse {}={:?} as a device index",
+                    GPU_ENV_VAR,
+                    value
+                )
+            })?;
+            Ok(Some(index))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Lower is preferred when picking a device automatically
+fn device_type_rank(device_type: vk::PhysicalDeviceType) -> usize {
+    match device_type {
+        vk::PhysicalDeviceType::DISCRETE_GPU => 0,
+        vk::PhysicalDeviceType::INTEGRATED_GPU => 1,
+        vk::PhysicalDeviceType::VIRTUAL_GPU => 2,
+        vk::PhysicalDeviceType::CPU => 3,
+        _ => 4,
+    }
+}
+
 pub(super) fn choose_physical_device(
     instance: &Instance,
-    surface: &Surface,
-    surface_khr: vk::SurfaceKHR,
+    surface: Option<(&Surface, vk::SurfaceKHR)>,
+    force_index: Option<usize>,
     force_device: Option<&str>,
 ) -> Result<(vk::PhysicalDevice, u32, u32, u32)> {
     let devices = unsafe { instance.enumerate_physical_devices() }?;
 
     log::debug!("Enumerating physical devices");
 
-    let (_, device) = if let Some(preferred_device) = force_device {
+    let device_props = devices
+        .iter()
+        .map(|&dev| unsafe { instance.get_physical_device_properties(dev) })
+        .collect::<Vec<_>>();
+
+    let device_list = device_props
+        .iter()
+        .enumerate()
+        .map(|(ix, props)| {
+            let name = unsafe { CStr::from_ptr(props.device_name.as_ptr()) };
+            format!("  {} - {:?} ({:?})", ix, name, props.device_type)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    log::debug!("Available devices:\n{}", device_list);
+
+    let device = if let Some(index) = force_index {
+        log::warn!("Attempting to force use of device {}", index);
+
+        let device = match devices.get(index) {
+            Some(&device) => device,
+            None => anyhow::bail!(
+                "Requested graphics device {} out of range, \
+                 available devices:\n{}",
+                index,
+                device_list
+            ),
+        };
+
+        if !device_is_suitable(instance, surface, device)? {
+            anyhow::bail!(
+                "Requested graphics device {} is not suitable, \
+                 available devices:\n{}",
+                index,
+                device_list
+            );
+        }
+
+        device
+    } else if let Some(preferred_device) = force_device {
         log::warn!("Attempting to force use of device {}", preferred_device);
 
         let device_name = CString::new(preferred_device)?;
 
-        let device = devices
+        let (_, device) = devices
             .into_iter()
             .enumerate()
-            .find(|(_ix, dev)| {
+            .find(|(ix, dev)| {
                 let name = unsafe {
-                    let props = instance.get_physical_device_properties(*dev);
-                    CStr::from_ptr(props.device_name.as_ptr())
+                    CStr::from_ptr(device_props[*ix].device_name.as_ptr())
                 };
                 (name == device_name.as_c_str())
-                    && device_is_suitable(instance, surface, surface_khr, *dev)
+                    && device_is_suitable(instance, surface, *dev)
                         .unwrap()
             })
             .expect("No suitable physical device found!");
 
         device
     } else {
-        for (ix, device) in devices.iter().enumerate() {
-            unsafe {
-                let props = instance.get_physical_device_properties(*device);
-                log::debug!(
-                    "Device {} - {:?}",
-                    ix,
-                    CStr::from_ptr(props.device_name.as_ptr())
-                );
-            }
-        }
-
-        devices
+        // prefer discrete GPUs over integrated, and keep the
+        // enumeration order otherwise
+        let (_, device) = devices
             .into_iter()
             .enumerate()
-            .find(|(_ix, dev)| {
-                device_is_suitable(instance, surface, surface_khr, *dev)
+            .filter(|(_ix, dev)| {
+                device_is_suitable(instance, surface, *dev)
                     .unwrap()
             })
-            .expect("No suitable physical device found!")
+            .min_by_key(|(ix, _dev)| {
+                device_type_rank(device_props[*ix].device_type)
+            })
+            .expect("No suitable physical device found!");
+
+        device
     };
 
     let properties = unsafe { instance.get_physical_device_properties(device) };
 
     unsafe {
         info!(
-            "Selected physical device: {:?}",
-            CStr::from_ptr(properties.device_name.as_ptr())
+            "Selected physical device: {:?} ({:?})",
+            CStr::from_ptr(properties.device_name.as_ptr()),
+            properties.device_type
         );
     }
 
     let (graphics_ix, present_ix, compute_ix) =
-        find_queue_families(instance, surface, surface_khr, device)?;
+        find_queue_families(instance, surface, device)?;
     log::debug!(
         "Found queue families; graphics: {:?}, present: {:?}, compute: {:?}",
         graphics_ix,
@@ -346,7 +435,7 @@ pub(super) fn create_swapchain_and_images(
         preferred
     };
 
-    if super::debug::ENABLE_VALIDATION_LAYERS {
+    if log::log_enabled!(log::Level::Trace) {
         trace!(
             "Creating swapchain.\n\tFormat: {:?}\n\tColorSpace: {:?}\n\tPresentMode: {:?}\n\tExtent: {:?}\n\tImageCount: {:?}",
             props.format.format,
@@ -421,6 +510,7 @@ pub(super) fn create_logical_device(
     graphics_ix: u32,
     present_ix: u32,
     compute_ix: u32,
+    validation: bool,
 ) -> Result<(Device, vk::Queue, vk::Queue, vk::Queue)> {
     let queue_priorities = [1.0f32];
 
@@ -472,7 +562,7 @@ pub(super) fn create_logical_device(
         .enabled_extension_names(&device_extensions_ptrs)
         .enabled_features(&device_features);
 
-    if super::debug::ENABLE_VALIDATION_LAYERS {
+    if validation {
         device_create_info_builder =
             device_create_info_builder.enabled_layer_names(&layer_names_ptrs);
     }
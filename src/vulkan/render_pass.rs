@@ -22,6 +22,7 @@ pub struct Framebuffers {
     pub edges: vk::Framebuffer,
     pub selection_edge_detect: vk::Framebuffer,
     pub selection_blur: vk::Framebuffer,
+    pub selection_blur_horizontal: vk::Framebuffer,
     pub gui: vk::Framebuffer,
 }
 
@@ -37,6 +38,10 @@ impl Framebuffers {
             self.selection_blur,
             "Selection Border Blur Framebuffer",
         )?;
+        app.set_debug_object_name(
+            self.selection_blur_horizontal,
+            "Selection Border Horizontal Blur Framebuffer",
+        )?;
         app.set_debug_object_name(self.gui, "GUI Framebuffer")?;
 
         Ok(())
@@ -48,6 +53,7 @@ impl Framebuffers {
             device.destroy_framebuffer(self.edges, None);
             device.destroy_framebuffer(self.selection_edge_detect, None);
             device.destroy_framebuffer(self.selection_blur, None);
+            device.destroy_framebuffer(self.selection_blur_horizontal, None);
             device.destroy_framebuffer(self.gui, None);
         }
     }
@@ -66,6 +72,12 @@ pub struct NodeAttachments {
 
 pub struct OffscreenAttachment {
     pub color: Texture,
+    /// The selection outline after the horizontal blur pass, which
+    /// the vertical blur pass reads
+    pub blur: Texture,
+    /// The image frames are drawn to in place of a swapchain image,
+    /// when there's no window
+    pub frame: Option<Texture>,
 }
 
 impl OffscreenAttachment {
@@ -85,7 +97,54 @@ impl OffscreenAttachment {
             format,
         )?;
 
-        Ok(Self { color })
+        let blur = Self::color(
+            vk_context,
+            command_pool,
+            queue,
+            swapchain_props,
+            format,
+        )?;
+
+        Ok(Self {
+            color,
+            blur,
+            frame: None,
+        })
+    }
+
+    /// Like `new`, but also creates the `frame` image, with the
+    /// format and size in `swapchain_props`
+    pub fn new_headless(
+        vk_context: &VkContext,
+        command_pool: vk::CommandPool,
+        queue: vk::Queue,
+        swapchain_props: SwapchainProperties,
+    ) -> Result<Self> {
+        let mut attachment =
+            Self::new(vk_context, command_pool, queue, swapchain_props)?;
+
+        let frame = Self::frame(
+            vk_context,
+            command_pool,
+            queue,
+            swapchain_props,
+        )?;
+
+        attachment.frame = Some(frame);
+
+        Ok(attachment)
+    }
+
+    /// The image views to create framebuffers for; the frame image
+    /// if there is one, otherwise the swapchain images
+    pub fn target_views(
+        &self,
+        swapchain_image_views: &[vk::ImageView],
+    ) -> Vec<vk::ImageView> {
+        match self.frame.as_ref() {
+            Some(frame) => vec![frame.view],
+            None => swapchain_image_views.to_vec(),
+        }
     }
 
     pub fn recreate(
@@ -95,6 +154,8 @@ impl OffscreenAttachment {
         queue: vk::Queue,
         swapchain_props: SwapchainProperties,
     ) -> Result<()> {
+        let headless = self.frame.is_some();
+
         self.destroy(vk_context.device());
 
         let format = vk::Format::R8G8B8A8_UNORM;
@@ -107,11 +168,52 @@ impl OffscreenAttachment {
             format,
         )?;
 
+        self.blur = Self::color(
+            vk_context,
+            command_pool,
+            queue,
+            swapchain_props,
+            format,
+        )?;
+
+        if headless {
+            self.frame = Some(Self::frame(
+                vk_context,
+                command_pool,
+                queue,
+                swapchain_props,
+            )?);
+        }
+
         Ok(())
     }
 
     pub fn destroy(&mut self, device: &Device) {
         self.color.destroy(device);
+        self.blur.destroy(device);
+
+        if let Some(mut frame) = self.frame.take() {
+            frame.destroy(device);
+        }
+    }
+
+    fn frame(
+        vk_context: &VkContext,
+        command_pool: vk::CommandPool,
+        queue: vk::Queue,
+        swapchain_props: SwapchainProperties,
+    ) -> Result<Texture> {
+        Texture::create_attachment_image(
+            vk_context,
+            command_pool,
+            queue,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT
+                | vk::ImageUsageFlags::TRANSFER_SRC,
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            swapchain_props.extent,
+            swapchain_props.format.format,
+            None,
+        )
     }
 
     fn color(
@@ -607,6 +709,22 @@ impl RenderPasses {
             unsafe { device.create_framebuffer(&framebuffer_info, None) }
         }?;
 
+        // the horizontal blur pass draws to an image like the one the
+        // edge detection draws to, so it uses the same render pass
+        let selection_blur_horizontal = {
+            let attachments = [offscreen_attachment.blur.view];
+
+            let framebuffer_info = vk::FramebufferCreateInfo::builder()
+                .render_pass(self.selection_edge_detect)
+                .attachments(&attachments)
+                .width(extent.width)
+                .height(extent.height)
+                .layers(1)
+                .build();
+
+            unsafe { device.create_framebuffer(&framebuffer_info, None) }
+        }?;
+
         let selection_blur = {
             let attachments = [swapchain_image_view];
 
@@ -640,6 +758,7 @@ impl RenderPasses {
             edges,
             selection_edge_detect,
             selection_blur,
+            selection_blur_horizontal,
             gui,
         })
     }
@@ -96,6 +96,10 @@ impl Gradients_ {
         })
     }
 
+    pub fn destroy(&mut self, device: &Device) {
+        self.texture.destroy(device);
+    }
+
     pub const GRADIENT_NAMES: [GradientName; 38] = {
         use GradientName::*;
         [
@@ -143,6 +147,7 @@ impl Gradients_ {
 
 pub struct Gradients {
     gradients: HashMap<egui::TextureId, GradientTexture>,
+    reversed: HashMap<egui::TextureId, GradientTexture>,
 }
 
 impl Gradients {
@@ -151,6 +156,21 @@ impl Gradients {
         self.gradients.get(&key)
     }
 
+    /// Like `gradient`, but running from the end of the gradient to
+    /// the start if `reversed` is true
+    pub fn gradient_oriented(
+        &self,
+        name: GradientName,
+        reversed: bool,
+    ) -> Option<&GradientTexture> {
+        let key = name.texture_id();
+        if reversed {
+            self.reversed.get(&key)
+        } else {
+            self.gradients.get(&key)
+        }
+    }
+
     pub fn gradient_from_id(
         &self,
         texture_id: egui::TextureId,
@@ -210,6 +230,8 @@ impl Gradients {
     ) -> Result<Self> {
         let mut gradients: HashMap<egui::TextureId, GradientTexture> =
             HashMap::new();
+        let mut reversed: HashMap<egui::TextureId, GradientTexture> =
+            HashMap::new();
 
         for name in std::array::IntoIter::new(Self::GRADIENT_NAMES) {
             let gradient = name.gradient();
@@ -220,18 +242,57 @@ impl Gradients {
                 transition_queue,
                 gradient,
                 width,
+                false,
+            )?;
+
+            let reversed_texture = GradientTexture::new(
+                app,
+                command_pool,
+                transition_queue,
+                gradient,
+                width,
+                true,
             )?;
 
             let key = name.texture_id();
 
             gradients.insert(key, texture);
+            reversed.insert(key, reversed_texture);
+        }
+
+        Ok(Self {
+            gradients,
+            reversed,
+        })
+    }
+
+    pub fn destroy(&mut self, device: &Device) {
+        let textures = self
+            .gradients
+            .values_mut()
+            .chain(self.reversed.values_mut());
+
+        for gradient in textures {
+            gradient.texture.destroy(device);
         }
 
-        Ok(Self { gradients })
+        self.gradients.clear();
+        self.reversed.clear();
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 pub enum GradientName {
     Blues,
     BlueGreen,
@@ -377,6 +438,29 @@ impl GradientName {
     }
 }
 
+/// Sample `count` evenly spaced colors along `gradient`, from the
+/// end to the start if `reversed` is true. This is the color ramp
+/// uploaded by `GradientTexture::new`, so it can also be used for
+/// previews drawn on the CPU.
+pub fn sample_gradient(
+    gradient: Gradient,
+    count: usize,
+    reversed: bool,
+) -> Vec<rgb::RGB<f32>> {
+    (0..count)
+        .map(|i| {
+            let i = if reversed { count - 1 - i } else { i };
+            let (r, g, b) = gradient.eval_rational(i, count).as_tuple();
+
+            let r = (r as f32) / 255.0;
+            let g = (g as f32) / 255.0;
+            let b = (b as f32) / 255.0;
+
+            rgb::RGB::new(r, g, b)
+        })
+        .collect()
+}
+
 pub struct GradientTexture {
     pub texture: Texture1D,
 }
@@ -388,31 +472,39 @@ impl GradientTexture {
         transition_queue: vk::Queue,
         gradient: Gradient,
         width: usize,
+        reversed: bool,
     ) -> Result<Self> {
         assert!(
             width.is_power_of_two(),
             "GradientTexture width has to be a power of two"
         );
 
-        let mut colors: Vec<rgb::RGB<f32>> = Vec::with_capacity(width);
-
-        for i in 0..width {
-            let (r, g, b) = gradient.eval_rational(i, width).as_tuple();
+        let colors = sample_gradient(gradient, width, reversed);
 
-            let r = (r as f32) / 255.0;
-            let g = (g as f32) / 255.0;
-            let b = (b as f32) / 255.0;
-
-            let rgb_color = rgb::RGB::new(r, g, b);
+        let texture = Texture1D::create_from_colors(
+            app,
+            command_pool,
+            transition_queue,
+            &colors,
+        )?;
 
-            colors.push(rgb_color);
-        }
+        Ok(Self { texture })
+    }
 
+    /// Create a texture with one texel per palette entry, for
+    /// categorical overlays. Sampling at texel centers, i.e.
+    /// `(i + 0.5) / palette.len()`, returns the exact colors.
+    pub fn from_palette(
+        app: &GfaestusVk,
+        command_pool: vk::CommandPool,
+        transition_queue: vk::Queue,
+        palette: &[rgb::RGB<f32>],
+    ) -> Result<Self> {
         let texture = Texture1D::create_from_colors(
             app,
             command_pool,
             transition_queue,
-            &colors,
+            palette,
         )?;
 
         Ok(Self { texture })
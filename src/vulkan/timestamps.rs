@@ -0,0 +1,232 @@
+use ash::version::{DeviceV1_0, InstanceV1_0};
+use ash::{vk, Device};
+
+use anyhow::Result;
+
+use super::GfaestusVk;
+
+/// The render passes that are timed on the GPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuPass {
+    Nodes,
+    EdgeDetect,
+    Blur,
+    Gui,
+}
+
+impl GpuPass {
+    pub const COUNT: usize = 4;
+
+    pub const ALL: [GpuPass; Self::COUNT] = [
+        GpuPass::Nodes,
+        GpuPass::EdgeDetect,
+        GpuPass::Blur,
+        GpuPass::Gui,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GpuPass::Nodes => "Nodes",
+            GpuPass::EdgeDetect => "Outline edge detect",
+            GpuPass::Blur => "Outline blur",
+            GpuPass::Gui => "GUI",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// The GPU time of each pass in a frame, in milliseconds, or `None`
+/// for the passes that weren't drawn
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PassTimes(pub [Option<f32>; GpuPass::COUNT]);
+
+impl PassTimes {
+    pub fn get(&self, pass: GpuPass) -> Option<f32> {
+        self.0[pass.index()]
+    }
+}
+
+/// The number of frames of queries that are kept
+const FRAMES: usize = 3;
+
+/// Timestamp queries written around each pass of a frame.
+///
+/// Each frame uses its own range of queries, and the results of a
+/// frame are only read a couple of frames later, just before its
+/// queries are reused, and without waiting, so that reading the
+/// timings never stalls the GPU.
+pub struct PassTimestamps {
+    query_pool: vk::QueryPool,
+
+    /// Nanoseconds per timestamp tick
+    timestamp_period: f32,
+
+    /// The passes that were recorded in each frame's queries
+    recorded: [[bool; GpuPass::COUNT]; FRAMES],
+    current: usize,
+
+    /// Whether `begin_frame` was called since the last `end_frame`;
+    /// frames that weren't drawn don't use up any queries
+    begun: bool,
+}
+
+impl PassTimestamps {
+    const QUERIES_PER_FRAME: u32 = 2 * GpuPass::COUNT as u32;
+
+    /// Returns `None` if the device doesn't support timestamps on
+    /// the graphics queue.
+    pub fn new(app: &GfaestusVk) -> Result<Option<Self>> {
+        let vk_context = app.vk_context();
+
+        let props = unsafe {
+            vk_context
+                .instance()
+                .get_physical_device_properties(vk_context.physical_device())
+        };
+
+        if props.limits.timestamp_compute_and_graphics == vk::FALSE {
+            log::warn!("Device does not support timestamp queries");
+            return Ok(None);
+        }
+
+        let pool_info = vk::QueryPoolCreateInfo::builder()
+            .query_type(vk::QueryType::TIMESTAMP)
+            .query_count(Self::QUERIES_PER_FRAME * FRAMES as u32)
+            .build();
+
+        let device = vk_context.device();
+
+        let query_pool = unsafe { device.create_query_pool(&pool_info, None) }?;
+
+        Ok(Some(Self {
+            query_pool,
+            timestamp_period: props.limits.timestamp_period,
+
+            recorded: [[false; GpuPass::COUNT]; FRAMES],
+            current: 0,
+
+            begun: false,
+        }))
+    }
+
+    fn query_index(&self, frame: usize, pass: GpuPass, end: bool) -> u32 {
+        let base = frame as u32 * Self::QUERIES_PER_FRAME;
+        base + 2 * pass.index() as u32 + end as u32
+    }
+
+    /// Reset the current frame's queries; must be recorded outside
+    /// of any render pass, before the passes are timed
+    pub fn begin_frame(&mut self, device: &Device, cmd_buf: vk::CommandBuffer) {
+        let first = self.current as u32 * Self::QUERIES_PER_FRAME;
+
+        unsafe {
+            device.cmd_reset_query_pool(
+                cmd_buf,
+                self.query_pool,
+                first,
+                Self::QUERIES_PER_FRAME,
+            )
+        };
+
+        self.recorded[self.current] = [false; GpuPass::COUNT];
+        self.begun = true;
+    }
+
+    pub fn begin_pass(
+        &mut self,
+        device: &Device,
+        cmd_buf: vk::CommandBuffer,
+        pass: GpuPass,
+    ) {
+        let query = self.query_index(self.current, pass, false);
+
+        unsafe {
+            device.cmd_write_timestamp(
+                cmd_buf,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                self.query_pool,
+                query,
+            )
+        };
+    }
+
+    pub fn end_pass(
+        &mut self,
+        device: &Device,
+        cmd_buf: vk::CommandBuffer,
+        pass: GpuPass,
+    ) {
+        let query = self.query_index(self.current, pass, true);
+
+        unsafe {
+            device.cmd_write_timestamp(
+                cmd_buf,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                self.query_pool,
+                query,
+            )
+        };
+
+        self.recorded[self.current][pass.index()] = true;
+    }
+
+    /// Move on to the next frame's queries, and return the pass
+    /// times of the oldest recorded frame, if they're available
+    pub fn end_frame(&mut self, device: &Device) -> Option<PassTimes> {
+        if !self.begun {
+            return None;
+        }
+        self.begun = false;
+
+        self.current = (self.current + 1) % FRAMES;
+
+        // the queries that are about to be reused are the oldest
+        let oldest = self.current;
+
+        let recorded = self.recorded[oldest];
+
+        if !recorded.iter().any(|&r| r) {
+            return None;
+        }
+
+        let mut times = PassTimes::default();
+
+        for &pass in GpuPass::ALL.iter() {
+            if !recorded[pass.index()] {
+                continue;
+            }
+
+            let first = self.query_index(oldest, pass, false);
+
+            let mut ticks = [0u64; 2];
+
+            let result = unsafe {
+                device.get_query_pool_results(
+                    self.query_pool,
+                    first,
+                    2,
+                    &mut ticks,
+                    vk::QueryResultFlags::TYPE_64,
+                )
+            };
+
+            if result.is_ok() {
+                let [start, end] = ticks;
+                let nanos =
+                    end.saturating_sub(start) as f32 * self.timestamp_period;
+                times.0[pass.index()] = Some(nanos / 1_000_000.0);
+            }
+        }
+
+        self.recorded[oldest] = [false; GpuPass::COUNT];
+
+        Some(times)
+    }
+
+    pub fn destroy(&self, device: &Device) {
+        unsafe { device.destroy_query_pool(self.query_pool, None) };
+    }
+}